@@ -0,0 +1,106 @@
+// writes the public method table get_interface serves, read straight from the
+// #[near_bindgen] impl blocks so it cannot drift from the code
+use std::fmt::Write as _;
+use std::path::Path;
+use std::{env, fs};
+
+use quote::ToTokens;
+use syn::{FnArg, ImplItem, Item, Pat, ReturnType, Visibility};
+
+fn has_attr(attrs: &[syn::Attribute], name: &str) -> bool {
+    attrs.iter().any(|attr| attr.path.is_ident(name))
+}
+
+// `Option < U128 >` as the tokens print it, back to `Option<U128>`
+fn type_name(tokens: impl ToTokens) -> String {
+    let mut name = tokens.to_token_stream().to_string();
+    for (from, to) in [
+        (" < ", "<"),
+        ("< ", "<"),
+        (" >", ">"),
+        (" , ", ", "),
+        ("& ", "&"),
+    ] {
+        name = name.replace(from, to);
+    }
+    name
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed=src");
+    let mut files: Vec<_> = fs::read_dir("src")
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "rs"))
+        .collect();
+    files.sort();
+
+    let mut methods = String::new();
+    for path in files {
+        let file = syn::parse_file(&fs::read_to_string(&path).unwrap()).unwrap();
+        for item in file.items {
+            let item = match item {
+                Item::Impl(item) if has_attr(&item.attrs, "near_bindgen") => item,
+                _ => continue,
+            };
+            for impl_item in item.items {
+                let method = match impl_item {
+                    ImplItem::Method(method) if matches!(method.vis, Visibility::Public(_)) => {
+                        method
+                    }
+                    _ => continue,
+                };
+                let kind = if has_attr(&method.attrs, "init") {
+                    "init"
+                } else if has_attr(&method.attrs, "private") {
+                    "private"
+                } else {
+                    match method.sig.receiver() {
+                        Some(FnArg::Receiver(receiver)) if receiver.mutability.is_none() => "view",
+                        _ => "call",
+                    }
+                };
+                let mut args = String::new();
+                for input in &method.sig.inputs {
+                    let arg = match input {
+                        FnArg::Typed(arg) => arg,
+                        FnArg::Receiver(_) => continue,
+                    };
+                    // promise results, not part of the json arguments
+                    if arg.attrs.iter().any(|attr| {
+                        attr.path.is_ident("callback_result")
+                            || attr.path.is_ident("callback_unwrap")
+                    }) {
+                        continue;
+                    }
+                    let name = match &*arg.pat {
+                        Pat::Ident(ident) => ident.ident.to_string(),
+                        pat => type_name(pat),
+                    };
+                    write!(args, "({:?}, {:?}), ", name, type_name(&arg.ty)).unwrap();
+                }
+                let returns = match &method.sig.output {
+                    ReturnType::Default => "None".to_string(),
+                    ReturnType::Type(_, ty) => format!("Some({:?})", type_name(ty)),
+                };
+                writeln!(
+                    methods,
+                    "    MethodSpec {{ name: {:?}, kind: {:?}, payable: {}, args: &[{}], returns: {} }},",
+                    method.sig.ident.to_string(),
+                    kind,
+                    has_attr(&method.attrs, "payable"),
+                    args.trim_end_matches(", "),
+                    returns,
+                )
+                .unwrap();
+            }
+        }
+    }
+
+    let out = Path::new(&env::var("OUT_DIR").unwrap()).join("interface.rs");
+    fs::write(
+        out,
+        format!("pub const METHODS: &[MethodSpec] = &[\n{}];\n", methods),
+    )
+    .unwrap();
+}
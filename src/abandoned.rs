@@ -0,0 +1,157 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::Serialize;
+use near_sdk::{env, near_bindgen, require, AccountId};
+
+use crate::events::{AbandonedData, StakeEvent};
+use crate::time::{Duration, Timestamp};
+use crate::*;
+
+pub const MIN_ABANDON_TIMEOUT: Duration = Duration::from_secs(365 * 24 * 60 * 60);
+pub const MAX_ABANDON_BATCH: usize = 50;
+
+// withdrawable balance of a position that stayed deactivated past the timeout, moved
+// to the treasury. the account gets it back through restore_abandoned
+#[derive(BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AbandonedEntry {
+    pub id: u64,
+    pub account_id: AccountId,
+    pub amount: U128,
+    pub swept_at: Timestamp,
+    pub restored_at: Option<Timestamp>,
+}
+
+impl Contract {
+    // the amount a sweep would take now, zero when the position is not abandoned
+    fn internal_abandoned_amount(&self, account_id: &AccountId, stake_info: &StakeInfo) -> u128 {
+        let timeout = match self.abandon_timeout {
+            Some(timeout) => timeout,
+            None => return 0,
+        };
+        let abandoned = self.internal_position_state(stake_info) == PositionState::Deactivated
+            && Self::now() >= stake_info.state_changed_at + self.unbond_period + timeout
+            && !stake_info.pending_operation
+            && !self.frozen_accounts.contains_key(account_id);
+        if abandoned {
+            stake_info.withdrawable
+        } else {
+            0
+        }
+    }
+
+    pub(crate) fn internal_restore_abandoned(&mut self, entry_id: u64) {
+        let mut entry = self
+            .abandoned_entries
+            .get(&entry_id)
+            .unwrap_or_else(|| env::panic_str(&StakeError::UnknownAbandonedEntry));
+        require!(
+            entry.restored_at.is_none(),
+            StakeError::AbandonedEntryRestored
+        );
+        let amount = entry.amount.0;
+        require!(
+            amount <= self.protocol_fee_balance,
+            StakeError::InsufficientTreasury
+        );
+        let mut stake_info = self
+            .internal_load_stake_info(&entry.account_id)
+            .unwrap_or_else(|| env::panic_str(&StakeError::NotStaked));
+        self.protocol_fee_balance -= amount;
+        self.total_abandoned -= amount;
+        self.internal_credit_withdrawable(&mut stake_info, amount);
+        self.internal_save_stake_info(&entry.account_id, &stake_info);
+        entry.restored_at = Some(Self::now());
+        self.abandoned_entries.insert(&entry_id, &entry);
+        StakeEvent::AbandonedRestored(vec![AbandonedData {
+            entry_id,
+            account_id: entry.account_id,
+            amount: entry.amount,
+        }])
+        .emit();
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // None turns sweeping off, the timeout counts from the end of the unbond period
+    pub fn set_abandon_timeout(&mut self, timeout: Option<U64>) {
+        self.internal_assert_role(Role::Admin);
+        let timeout = timeout.map(|timeout| Duration::from_nanos(timeout.0));
+        require!(
+            timeout.is_none_or(|timeout| timeout >= MIN_ABANDON_TIMEOUT),
+            StakeError::AbandonTimeoutTooShort
+        );
+        self.internal_record_config_change("abandon_timeout", self.abandon_timeout, timeout);
+        self.abandon_timeout = timeout;
+    }
+
+    // permissionless so a keeper can run it, accounts that don't qualify are skipped.
+    // returns the ids of the entries it opened
+    pub fn sweep_abandoned(&mut self, account_ids: Vec<AccountId>) -> Vec<u64> {
+        require!(
+            account_ids.len() <= MAX_ABANDON_BATCH,
+            StakeError::BatchTooLarge
+        );
+        let mut events = vec![];
+        for account_id in account_ids {
+            self.internal_migrate_legacy(&account_id);
+            let mut stake_info = match self.internal_load_stake_info(&account_id) {
+                Some(stake_info) => stake_info,
+                None => continue,
+            };
+            let amount = self.internal_abandoned_amount(&account_id, &stake_info);
+            if amount == 0 {
+                continue;
+            }
+            stake_info.withdrawable -= amount;
+            self.total_withdrawable -= amount;
+            self.protocol_fee_balance += amount;
+            self.total_abandoned += amount;
+            self.internal_save_stake_info(&account_id, &stake_info);
+
+            self.abandoned_count += 1;
+            self.abandoned_entries.insert(
+                &self.abandoned_count,
+                &AbandonedEntry {
+                    id: self.abandoned_count,
+                    account_id: account_id.clone(),
+                    amount: U128(amount),
+                    swept_at: Self::now(),
+                    restored_at: None,
+                },
+            );
+            events.push(AbandonedData {
+                entry_id: self.abandoned_count,
+                account_id,
+                amount: U128(amount),
+            });
+        }
+        let entry_ids = events.iter().map(|event| event.entry_id).collect();
+        if !events.is_empty() {
+            StakeEvent::AbandonedSwept(events).emit();
+        }
+        entry_ids
+    }
+
+    // the account's way back, paid out of the treasury. goes through
+    // AdminAction::RestoreAbandoned once an admin set is configured
+    pub fn restore_abandoned(&mut self, entry_id: u64) {
+        self.internal_assert_owner();
+        self.internal_assert_no_confirmations();
+        self.internal_restore_abandoned(entry_id);
+    }
+
+    pub fn get_abandoned_entry(&self, entry_id: u64) -> Option<AbandonedEntry> {
+        self.abandoned_entries.get(&entry_id)
+    }
+
+    pub fn get_abandoned_amount(&self, account_id: AccountId) -> U128 {
+        U128(
+            self.internal_get_stake_info(&account_id)
+                .map_or(0, |stake_info| {
+                    self.internal_abandoned_amount(&account_id, &stake_info)
+                }),
+        )
+    }
+}
@@ -0,0 +1,51 @@
+use near_sdk::json_types::U128;
+use near_sdk::{assert_one_yocto, env, near_bindgen, require, AccountId, PromiseOrValue};
+
+use crate::*;
+
+impl Contract {
+    fn internal_assert_access_key_opt_in(&self) -> AccountId {
+        let account_id = env::predecessor_account_id();
+        require!(
+            self.access_key_accounts.contains(&account_id),
+            StakeError::AccessKeysNotEnabled
+        );
+        account_id
+    }
+}
+
+// variants for function call access keys, which can't attach the yocto. they are off
+// until the account turned them on from a full access key, and nothing they do sends
+// tokens out of the contract
+#[near_bindgen]
+impl Contract {
+    // always the yocto, a relayer or an exempt method can't stand in for the full key
+    #[payable]
+    pub fn set_access_key_opt_in(&mut self, enabled: bool) {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        if enabled {
+            self.access_key_accounts.insert(&account_id);
+        } else {
+            self.access_key_accounts.remove(&account_id);
+        }
+    }
+
+    // the principal always becomes withdrawable, withdrawing it still needs the yocto
+    pub fn unstake_token_no_deposit(&mut self, amount: U128, course_id: Option<String>) -> U128 {
+        let account_id = self.internal_assert_access_key_opt_in();
+        let received = self.internal_unstake(&account_id, amount.0, course_id, &account_id, false);
+        self.internal_process_queue(MAX_QUEUE_ACTIVATIONS);
+        self.internal_maybe_snapshot();
+        U128(received)
+    }
+
+    pub fn claim_reward_no_deposit(&mut self) -> PromiseOrValue<U128> {
+        let account_id = self.internal_assert_access_key_opt_in();
+        self.internal_claim_reward_verified(account_id)
+    }
+
+    pub fn is_access_key_opted_in(&self, account_id: AccountId) -> bool {
+        self.access_key_accounts.contains(&account_id)
+    }
+}
@@ -0,0 +1,70 @@
+use near_sdk::{near_bindgen, AccountId};
+
+use crate::*;
+
+impl Contract {
+    pub(crate) fn internal_check_can_stake(
+        &self,
+        account_id: &AccountId,
+    ) -> Result<(), StakeError> {
+        if self.paused {
+            return Err(StakeError::Paused);
+        }
+        if !self.internal_is_permitted(account_id) {
+            return Err(StakeError::AccountNotPermitted);
+        }
+        Ok(())
+    }
+
+    // denied accounts are always rejected, the allow list only applies once enabled
+    pub(crate) fn internal_is_permitted(&self, account_id: &AccountId) -> bool {
+        if self.deny_list.contains(account_id) {
+            return false;
+        }
+        !self.allow_list_enabled || self.allow_list.contains(account_id)
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn add_to_deny_list(&mut self, account_ids: Vec<AccountId>) {
+        self.internal_assert_role(Role::Admin);
+        for account_id in account_ids {
+            self.deny_list.insert(&account_id);
+        }
+    }
+
+    pub fn remove_from_deny_list(&mut self, account_ids: Vec<AccountId>) {
+        self.internal_assert_role(Role::Admin);
+        for account_id in account_ids {
+            self.deny_list.remove(&account_id);
+        }
+    }
+
+    pub fn add_to_allow_list(&mut self, account_ids: Vec<AccountId>) {
+        self.internal_assert_role(Role::Admin);
+        for account_id in account_ids {
+            self.allow_list.insert(&account_id);
+        }
+    }
+
+    pub fn remove_from_allow_list(&mut self, account_ids: Vec<AccountId>) {
+        self.internal_assert_role(Role::Admin);
+        for account_id in account_ids {
+            self.allow_list.remove(&account_id);
+        }
+    }
+
+    pub fn set_allow_list_enabled(&mut self, enabled: bool) {
+        self.internal_assert_role(Role::Admin);
+        self.allow_list_enabled = enabled;
+    }
+
+    pub fn is_denied(&self, account_id: AccountId) -> bool {
+        self.deny_list.contains(&account_id)
+    }
+
+    pub fn is_permitted(&self, account_id: AccountId) -> bool {
+        self.internal_is_permitted(&account_id)
+    }
+}
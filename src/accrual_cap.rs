@@ -0,0 +1,91 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{near_bindgen, require, AccountId};
+
+use crate::time::{Duration, Timestamp};
+use crate::*;
+
+// no account's reward grows by more than `amount` within one `period`, the excess is
+// deferred and released in the periods after
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AccrualCap {
+    pub amount: U128,
+    pub period: Duration,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DeferredRewardView {
+    pub deferred: U128,
+    pub accrued_in_window: U128,
+    pub window_ends_at: Option<Timestamp>,
+}
+
+impl Contract {
+    // how much of `accrued` plus what was deferred before goes into `reward` now
+    pub(crate) fn internal_cap_accrual(
+        &self,
+        stake_info: &mut StakeInfo,
+        accrued: u128,
+        now: Timestamp,
+    ) -> u128 {
+        let owed = stake_info.deferred_reward + accrued;
+        let cap = match &self.accrual_cap {
+            Some(cap) => cap,
+            None => {
+                stake_info.deferred_reward = 0;
+                return owed;
+            }
+        };
+        if now >= stake_info.accrual_window_start + cap.period {
+            stake_info.accrual_window_start = now;
+            stake_info.accrued_in_window = 0;
+        }
+        let released = owed.min(cap.amount.0.saturating_sub(stake_info.accrued_in_window));
+        stake_info.accrued_in_window += released;
+        stake_info.deferred_reward = owed - released;
+        released
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // None releases everything deferred on each account's next touch
+    pub fn set_accrual_cap(&mut self, accrual_cap: Option<AccrualCap>) {
+        self.internal_assert_role(Role::Admin);
+        if let Some(cap) = &accrual_cap {
+            require!(
+                cap.amount.0 > 0 && cap.period.as_nanos() > 0,
+                StakeError::InvalidAccrualCap
+            );
+        }
+        self.internal_record_config_change(
+            "accrual_cap",
+            self.accrual_cap.clone(),
+            accrual_cap.clone(),
+        );
+        self.accrual_cap = accrual_cap;
+    }
+
+    pub fn get_accrual_cap(&self) -> Option<AccrualCap> {
+        self.accrual_cap.clone()
+    }
+
+    // as if the account were touched now
+    pub fn get_deferred_reward(&self, account_id: AccountId) -> Option<DeferredRewardView> {
+        let mut stake_info = self.internal_get_stake_info(&account_id)?;
+        let now = Self::now();
+        let (accrued, _, _) = self.internal_accrual_at(&stake_info, now);
+        self.internal_cap_accrual(&mut stake_info, accrued, now);
+        Some(DeferredRewardView {
+            deferred: U128(stake_info.deferred_reward),
+            accrued_in_window: U128(stake_info.accrued_in_window),
+            window_ends_at: self
+                .accrual_cap
+                .as_ref()
+                .map(|cap| stake_info.accrual_window_start + cap.period),
+        })
+    }
+}
@@ -0,0 +1,45 @@
+use near_sdk::{near_bindgen, AccountId};
+
+use crate::*;
+
+impl Contract {
+    // frozen, or the target of an open slash proposal. group slashes hold the group's
+    // shares, not the members' own positions
+    fn internal_on_hold(&self, account_id: &AccountId) -> bool {
+        self.frozen_accounts.contains_key(account_id) || self.open_slashes.contains_key(account_id)
+    }
+
+    // settles the position up to now and then starts or ends its pause, called
+    // whenever a freeze or a slash proposal opens or closes. the time a position spent
+    // paused is dropped on resume, not paid late
+    pub(crate) fn internal_sync_accrual_pause(&mut self, account_id: &AccountId) {
+        let mut stake_info = match self.internal_touch(account_id) {
+            Some(stake_info) => stake_info,
+            None => return,
+        };
+        let held = self.pause_accrual_on_hold && self.internal_on_hold(account_id);
+        match (held, stake_info.paused_accrual_since) {
+            (true, None) => stake_info.paused_accrual_since = Some(Self::now()),
+            (false, Some(_)) => {
+                stake_info.paused_accrual_since = None;
+                self.internal_settle_marks(&mut stake_info);
+            }
+            _ => {}
+        }
+        self.internal_save_stake_info(account_id, &stake_info);
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // positions follow the new setting the next time a hold on them is placed or lifted
+    pub fn set_pause_accrual_on_hold(&mut self, enabled: bool) {
+        self.internal_assert_role(Role::Admin);
+        self.internal_record_config_change(
+            "pause_accrual_on_hold",
+            self.pause_accrual_on_hold,
+            enabled,
+        );
+        self.pause_accrual_on_hold = enabled;
+    }
+}
@@ -0,0 +1,104 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::Serialize;
+use near_sdk::{near_bindgen, AccountId};
+
+use crate::apr_history::DEFAULT_PAGE_LIMIT;
+use crate::ring_buffer::RingBuffer;
+use crate::*;
+
+pub const MAX_ACCOUNT_HISTORY: u64 = 100;
+pub const MAX_EVENTS_PAGE: u64 = 50;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub enum Activity {
+    Stake { amount: U128 },
+    Unstake { amount: U128 },
+    Claim { amount: U128 },
+    RewardAccrued { amount: U128 },
+    VoteReceived { rating: u8 },
+    Slashed { amount: U128 },
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ActivityEntry {
+    pub timestamp: U64,
+    pub activity: Activity,
+}
+
+// the n-th entry ever recorded for the account has seq n, starting at 1
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AccountEvent {
+    pub seq: U64,
+    #[serde(flatten)]
+    pub entry: ActivityEntry,
+}
+
+impl Contract {
+    pub(crate) fn internal_record_activity(&mut self, account_id: &AccountId, activity: Activity) {
+        let mut history = self.account_history.get(account_id).unwrap_or_else(|| {
+            RingBuffer::new(
+                StorageKey::account_history_entries(account_id),
+                MAX_ACCOUNT_HISTORY,
+            )
+        });
+        history.push(&ActivityEntry {
+            timestamp: Self::now().into(),
+            activity,
+        });
+        self.account_history.insert(account_id, &history);
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // oldest first, `from` is an offset into the kept entries
+    pub fn get_account_history(
+        &self,
+        account_id: AccountId,
+        from: Option<u64>,
+        limit: Option<u64>,
+    ) -> Vec<ActivityEntry> {
+        match self.account_history.get(&account_id) {
+            Some(history) => history.page(from.unwrap_or(0), limit.unwrap_or(DEFAULT_PAGE_LIMIT)),
+            None => vec![],
+        }
+    }
+
+    pub fn get_event_seq(&self, account_id: AccountId) -> U64 {
+        U64(self
+            .account_history
+            .get(&account_id)
+            .map_or(0, |history| history.pushed()))
+    }
+
+    // entries recorded after `seq`, oldest first. a first seq above `seq + 1` means
+    // the ones in between were overwritten before they were polled
+    pub fn get_events_since(
+        &self,
+        account_id: AccountId,
+        seq: U64,
+        limit: Option<u64>,
+    ) -> Vec<AccountEvent> {
+        let history = match self.account_history.get(&account_id) {
+            Some(history) => history,
+            None => return vec![],
+        };
+        let limit = limit.map_or(MAX_EVENTS_PAGE, |limit| limit.min(MAX_EVENTS_PAGE));
+        // seq of the oldest entry still kept, less one
+        let evicted = history.pushed() - history.len();
+        let from = seq.0.max(evicted) - evicted;
+        history
+            .page(from, limit)
+            .into_iter()
+            .zip(from..)
+            .map(|(entry, index)| AccountEvent {
+                seq: U64(evicted + index + 1),
+                entry,
+            })
+            .collect()
+    }
+}
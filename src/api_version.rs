@@ -0,0 +1,44 @@
+use near_sdk::json_types::{U128, U64};
+use near_sdk::{env, near_bindgen, AccountId};
+
+use crate::*;
+
+// 1 is the raw number views, 2 adds the v2_ methods that return JSON safe numbers and
+// None for unknown accounts, 3 adds the v3_ calls that return outcome structs
+pub const API_VERSION: u32 = 3;
+pub const SUPPORTED_API_VERSIONS: &[u32] = &[1, 2, 3];
+
+impl Contract {
+    // the old methods keep answering, they just say what to move to
+    pub(crate) fn internal_log_deprecated(method: &str, replacement: &str) {
+        env::log_str(&format!(
+            "DEPRECATED: {} is deprecated, use {}",
+            method, replacement
+        ));
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn api_version(&self) -> u32 {
+        API_VERSION
+    }
+
+    pub fn supported_api_versions(&self) -> Vec<u32> {
+        SUPPORTED_API_VERSIONS.to_vec()
+    }
+
+    pub fn v2_pending_reward(&self, account_id: AccountId) -> Option<U128> {
+        self.internal_get_stake_info(&account_id)
+            .map(|stake_info| U128(self.internal_pending_reward_at(&stake_info, Self::now())))
+    }
+
+    pub fn v2_get_staked_amount(&self, advisor_id: AccountId) -> Option<U128> {
+        self.internal_get_stake_info(&advisor_id)
+            .map(|stake_info| U128(stake_info.amount_staked))
+    }
+
+    pub fn v2_get_claim_nonce(&self, account_id: AccountId) -> U64 {
+        U64(self.claim_nonces.get(&account_id).unwrap_or(0))
+    }
+}
@@ -0,0 +1,315 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{I64, U128, U64};
+use near_sdk::serde::Serialize;
+use near_sdk::{env, near_bindgen, require, AccountId, Balance, Promise, ONE_NEAR};
+
+use crate::events::{AppealResolvedData, StakeEvent, VoteRecordedData};
+use crate::time::{Duration, Timestamp};
+use crate::*;
+
+pub const DEFAULT_APPEAL_BOND: Balance = ONE_NEAR;
+pub const DEFAULT_APPEAL_WINDOW: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+pub const MAX_APPEAL_ARBITERS: usize = 10;
+// a pending appeal no side carried by then can be expired, the bond goes back
+pub const DEFAULT_APPEAL_EXPIRY: Duration = Duration::from_secs(14 * 24 * 60 * 60);
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub enum AppealStatus {
+    Pending,
+    Reversed,
+    Rejected,
+    Expired,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Appeal {
+    pub bond: U128,
+    pub filed_at: Timestamp,
+    pub status: AppealStatus,
+    // arbiter stake when the appeal was filed, a side wins with more than half of it
+    pub total_weight: U128,
+    pub reverse_weight: U128,
+    pub uphold_weight: U128,
+    pub voted: Vec<AccountId>,
+}
+
+// a vote that moved an advisor's reputation, kept so its effect can be undone
+#[derive(BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct VoteRecord {
+    pub id: u64,
+    pub advisor_id: AccountId,
+    pub voter_id: AccountId,
+    pub rating: u8,
+    pub reputation_delta: I64,
+    pub votes_before: u8,
+    pub votes_after: u8,
+    pub cast_at: Timestamp,
+    pub appeal: Option<Appeal>,
+}
+
+impl Contract {
+    pub(crate) fn internal_record_vote(
+        &mut self,
+        advisor_id: &AccountId,
+        voter_id: &AccountId,
+        rating: u8,
+        reputation_delta: i64,
+        votes: (u8, u8),
+    ) {
+        let id = self.vote_record_count;
+        self.vote_record_count += 1;
+        self.vote_records.insert(
+            &id,
+            &VoteRecord {
+                id,
+                advisor_id: advisor_id.clone(),
+                voter_id: voter_id.clone(),
+                rating,
+                reputation_delta: I64(reputation_delta),
+                votes_before: votes.0,
+                votes_after: votes.1,
+                cast_at: Self::now(),
+                appeal: None,
+            },
+        );
+        self.internal_push_vote_delta(advisor_id, id, reputation_delta);
+        StakeEvent::VoteRecorded(vec![VoteRecordedData {
+            vote_id: id,
+            advisor_id: advisor_id.clone(),
+            rating,
+            reputation_delta: I64(reputation_delta),
+        }])
+        .emit();
+    }
+
+    fn internal_get_vote_record(&self, vote_id: u64) -> VoteRecord {
+        self.vote_records
+            .get(&vote_id)
+            .unwrap_or_else(|| env::panic_str(&StakeError::UnknownVote))
+    }
+
+    fn internal_arbiter_weight(&self, arbiter_id: &AccountId) -> u128 {
+        self.internal_get_stake_info(arbiter_id)
+            .map_or(0, |stake_info| stake_info.amount_staked)
+    }
+
+    // the stake an arbiter had when the appeal was filed, stake added later doesn't count
+    fn internal_snapshot_weight(&self, vote_id: u64, arbiter_id: &AccountId) -> u128 {
+        self.appeal_weights
+            .get(&vote_id)
+            .and_then(|weights| {
+                weights
+                    .into_iter()
+                    .find(|(account_id, _)| account_id == arbiter_id)
+            })
+            .map_or(0, |(_, weight)| weight.0)
+    }
+
+    // takes the vote's delta back off the current reputation and score, the apr follows
+    fn internal_reverse_vote(&mut self, record: &VoteRecord) {
+        let mut stake_info = self
+            .internal_touch(&record.advisor_id)
+            .unwrap_or_else(|| env::panic_str(&StakeError::AdvisorNotStaked));
+        let old_apr = stake_info.apr;
+        // a vote that already aged out of the horizon has nothing left to take back
+        let in_window = self.internal_forget_vote_delta(&record.advisor_id, record.id);
+        if in_window || self.vote_horizon.is_none() {
+            self.internal_add_reputation(&mut stake_info, -record.reputation_delta.0);
+        }
+        stake_info.apr = self.internal_apr_for_reputation(&stake_info);
+        stake_info.votes = if record.votes_after >= record.votes_before {
+            stake_info
+                .votes
+                .saturating_sub(record.votes_after - record.votes_before)
+        } else {
+            stake_info
+                .votes
+                .saturating_add(record.votes_before - record.votes_after)
+        };
+        if stake_info.apr != old_apr {
+            self.internal_update_reward_rate(
+                stake_info.amount_staked,
+                stake_info.boosted(old_apr),
+                stake_info.amount_staked,
+                stake_info.boosted(stake_info.apr),
+            );
+            self.internal_record_apr_change(
+                &record.advisor_id,
+                old_apr,
+                stake_info.apr,
+                AprChangeCause::Appeal { vote_id: record.id },
+            );
+        }
+        self.internal_save_stake_info(&record.advisor_id, &stake_info);
+        self.internal_push_rating(&record.advisor_id, stake_info.apr, stake_info.votes);
+    }
+
+    // a reversed vote returns the bond to the advisor, a rejected appeal forfeits it to the owner
+    fn internal_resolve_appeal(&mut self, record: &mut VoteRecord, status: AppealStatus) {
+        let appeal = record.appeal.as_mut().unwrap();
+        appeal.status = status;
+        let bond = appeal.bond.0;
+        self.appeal_weights.remove(&record.id);
+        if status == AppealStatus::Reversed {
+            self.internal_reverse_vote(record);
+            Promise::new(record.advisor_id.clone()).transfer(bond);
+        } else {
+            Promise::new(self.owner_id.clone()).transfer(bond);
+        }
+        StakeEvent::AppealResolved(vec![AppealResolvedData {
+            vote_id: record.id,
+            advisor_id: record.advisor_id.clone(),
+            reversed: status == AppealStatus::Reversed,
+        }])
+        .emit();
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    #[payable]
+    pub fn appeal_rating(&mut self, vote_id: u64) {
+        let mut record = self.internal_get_vote_record(vote_id);
+        require!(
+            record.advisor_id == env::predecessor_account_id(),
+            StakeError::NotVoteAdvisor
+        );
+        require!(record.appeal.is_none(), StakeError::AlreadyAppealed);
+        require!(
+            Self::now() < record.cast_at + self.appeal_window,
+            StakeError::AppealWindowClosed
+        );
+        require!(
+            env::attached_deposit() == self.appeal_bond,
+            StakeError::WrongAppealBond
+        );
+        let weights: Vec<(AccountId, U128)> = self
+            .appeal_arbiters
+            .iter()
+            .map(|arbiter_id| {
+                (
+                    arbiter_id.clone(),
+                    U128(self.internal_arbiter_weight(arbiter_id)),
+                )
+            })
+            .collect();
+        let total_weight: u128 = weights.iter().map(|(_, weight)| weight.0).sum();
+        require!(total_weight > 0, StakeError::NoAppealArbiters);
+        self.appeal_weights.insert(&vote_id, &weights);
+
+        record.appeal = Some(Appeal {
+            bond: U128(self.appeal_bond),
+            filed_at: Self::now(),
+            status: AppealStatus::Pending,
+            total_weight: U128(total_weight),
+            reverse_weight: U128(0),
+            uphold_weight: U128(0),
+            voted: vec![],
+        });
+        self.vote_records.insert(&vote_id, &record);
+    }
+
+    // arbiters vote with the stake they had at filing, the first side past half of the
+    // weight recorded then settles the appeal
+    pub fn vote_on_appeal(&mut self, vote_id: u64, reverse: bool) -> AppealStatus {
+        let arbiter_id = env::predecessor_account_id();
+        require!(
+            self.appeal_arbiters.contains(&arbiter_id),
+            StakeError::NotAppealArbiter
+        );
+        let weight = self.internal_snapshot_weight(vote_id, &arbiter_id);
+        let mut record = self.internal_get_vote_record(vote_id);
+        let appeal = record
+            .appeal
+            .as_mut()
+            .filter(|appeal| appeal.status == AppealStatus::Pending)
+            .unwrap_or_else(|| env::panic_str(&StakeError::AppealNotPending));
+        require!(
+            !appeal.voted.contains(&arbiter_id),
+            StakeError::AlreadyVotedOnAppeal
+        );
+        appeal.voted.push(arbiter_id);
+        let side = if reverse {
+            &mut appeal.reverse_weight
+        } else {
+            &mut appeal.uphold_weight
+        };
+        side.0 += weight;
+
+        let threshold = appeal.total_weight.0 / 2;
+        let status = if appeal.reverse_weight.0 > threshold {
+            AppealStatus::Reversed
+        } else if appeal.uphold_weight.0 > threshold {
+            AppealStatus::Rejected
+        } else {
+            AppealStatus::Pending
+        };
+        if status != AppealStatus::Pending {
+            self.internal_resolve_appeal(&mut record, status);
+        }
+        self.vote_records.insert(&vote_id, &record);
+        self.internal_maybe_snapshot();
+        status
+    }
+
+    // permissionless once the appeal outlived appeal_expiry undecided, the bond goes back
+    // to the advisor and the vote stands
+    pub fn expire_appeal(&mut self, vote_id: u64) {
+        let mut record = self.internal_get_vote_record(vote_id);
+        let appeal = record
+            .appeal
+            .as_mut()
+            .filter(|appeal| appeal.status == AppealStatus::Pending)
+            .unwrap_or_else(|| env::panic_str(&StakeError::AppealNotPending));
+        require!(
+            Self::now() >= appeal.filed_at + self.appeal_expiry,
+            StakeError::AppealNotExpired
+        );
+        appeal.status = AppealStatus::Expired;
+        Promise::new(record.advisor_id.clone()).transfer(appeal.bond.0);
+        self.appeal_weights.remove(&vote_id);
+        StakeEvent::AppealExpired(vec![AppealResolvedData {
+            vote_id,
+            advisor_id: record.advisor_id.clone(),
+            reversed: false,
+        }])
+        .emit();
+        self.vote_records.insert(&vote_id, &record);
+    }
+
+    pub fn set_appeal_expiry(&mut self, appeal_expiry: U64) {
+        self.internal_assert_role(Role::Admin);
+        require!(appeal_expiry.0 > 0, StakeError::InvalidAppealExpiry);
+        self.appeal_expiry = Duration::from_nanos(appeal_expiry.0);
+    }
+
+    pub fn set_appeal_arbiters(&mut self, arbiters: Vec<AccountId>) {
+        self.internal_assert_role(Role::Admin);
+        require!(
+            arbiters.len() <= MAX_APPEAL_ARBITERS,
+            StakeError::TooManyArbiters
+        );
+        self.appeal_arbiters = arbiters;
+    }
+
+    pub fn set_appeal_bond(&mut self, appeal_bond: U128) {
+        self.internal_assert_role(Role::Admin);
+        self.appeal_bond = appeal_bond.0;
+    }
+
+    pub fn set_appeal_window(&mut self, appeal_window: U64) {
+        self.internal_assert_role(Role::Admin);
+        self.appeal_window = Duration::from_nanos(appeal_window.0);
+    }
+
+    pub fn get_vote(&self, vote_id: u64) -> Option<VoteRecord> {
+        self.vote_records.get(&vote_id)
+    }
+
+    pub fn get_appeal_arbiters(&self) -> Vec<AccountId> {
+        self.appeal_arbiters.clone()
+    }
+}
@@ -0,0 +1,108 @@
+use near_sdk::json_types::{U128, U64};
+use near_sdk::{env, near_bindgen, require};
+
+use crate::math::{Rate, BPS_DENOMINATOR};
+use crate::time::Duration;
+use crate::*;
+
+pub const MAX_APR: Rate = Rate::percent(100);
+pub const MAX_VOTE_WEIGHT: i64 = 10;
+// vote steps a 1 to 5 rating moves the APR by
+pub const DEFAULT_VOTE_WEIGHTS: [i64; 5] = [-2, -1, 0, 1, 2];
+
+impl Contract {
+    pub(crate) fn internal_assert_apr_config(&self) {
+        require!(
+            self.min_apr <= self.max_apr && Rate::from_raw(self.max_apr) <= MAX_APR,
+            StakeError::InvalidAprBounds
+        );
+        require!(
+            self.default_apr <= self.max_apr,
+            StakeError::DefaultAprTooHigh
+        );
+        require!(
+            self.default_apr >= self.min_apr,
+            StakeError::DefaultAprTooLow
+        );
+    }
+
+    // new positions only have their votes recorded until both windows have passed
+    pub(crate) fn internal_in_vote_grace(&self, stake_info: &StakeInfo) -> bool {
+        Self::now() < stake_info.terms.opened_at + self.vote_grace_period
+            || stake_info.votes_received < self.vote_grace_votes
+    }
+
+    // reputation moves by the rating's weight scaled by the category's, the apr follows it
+    // and the vote score never goes below zero. returns the reputation added
+    pub(crate) fn internal_apply_vote(
+        &self,
+        stake_info: &mut StakeInfo,
+        rating: u8,
+        weight_bps: u16,
+    ) -> i64 {
+        let weight = *(rating as usize)
+            .checked_sub(1)
+            .and_then(|index| self.vote_weights.get(index))
+            .unwrap_or_else(|| env::panic_str(&StakeError::InvalidVote));
+        let delta = weight * REPUTATION_PER_WEIGHT * weight_bps as i64 / BPS_DENOMINATOR as i64;
+        self.internal_add_reputation(stake_info, delta);
+        stake_info.apr = self.internal_apr_for_reputation(stake_info);
+        let votes = weight.unsigned_abs().min(u8::MAX as u64) as u8;
+        if weight >= 0 {
+            stake_info.votes = stake_info.votes.saturating_add(votes);
+        } else {
+            stake_info.votes = stake_info.votes.saturating_sub(votes);
+        }
+        delta
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // only positions opened after the change start at the new APR
+    pub fn set_default_apr(&mut self, default_apr: U128) {
+        self.internal_assert_role(Role::Admin);
+        self.internal_record_config_change("default_apr", U128(self.default_apr), default_apr);
+        self.default_apr = default_apr.0;
+        self.internal_assert_apr_config();
+    }
+
+    // existing positions are clamped the next time a vote moves them
+    pub fn set_apr_bounds(&mut self, min_apr: U128, max_apr: U128) {
+        self.internal_assert_role(Role::Admin);
+        self.internal_record_config_change("min_apr", U128(self.min_apr), min_apr);
+        self.internal_record_config_change("max_apr", U128(self.max_apr), max_apr);
+        self.min_apr = min_apr.0;
+        self.max_apr = max_apr.0;
+        self.internal_assert_apr_config();
+    }
+
+    pub fn set_vote_step(&mut self, vote_step: U128) {
+        self.internal_assert_role(Role::Admin);
+        require!(
+            vote_step.0 > 0 && Rate::from_raw(vote_step.0) <= Rate::percent(1),
+            StakeError::InvalidVoteStep
+        );
+        self.internal_record_config_change("vote_step", U128(self.vote_step), vote_step);
+        self.vote_step = vote_step.0;
+    }
+
+    pub fn set_vote_grace(&mut self, grace_period: U64, grace_votes: u64) {
+        self.internal_assert_role(Role::Admin);
+        self.vote_grace_period = Duration::from_nanos(grace_period.0);
+        self.vote_grace_votes = grace_votes;
+    }
+
+    // weights for ratings 1 to 5, in vote steps
+    pub fn set_vote_weights(&mut self, vote_weights: [i64; 5]) {
+        self.internal_assert_role(Role::Admin);
+        require!(
+            vote_weights
+                .iter()
+                .all(|weight| weight.abs() <= MAX_VOTE_WEIGHT),
+            StakeError::VoteWeightTooHigh
+        );
+        self.internal_record_config_change("vote_weights", self.vote_weights, vote_weights);
+        self.vote_weights = vote_weights;
+    }
+}
@@ -0,0 +1,75 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::Serialize;
+use near_sdk::{near_bindgen, AccountId};
+
+use crate::ring_buffer::RingBuffer;
+use crate::*;
+
+pub const MAX_APR_HISTORY: u64 = 100;
+pub const DEFAULT_PAGE_LIMIT: u64 = 50;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub enum AprChangeCause {
+    Vote { rating: u8 },
+    Appeal { vote_id: u64 },
+    VotesExpired,
+    ReputationDecayed,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AprChange {
+    pub timestamp: U64,
+    pub old_apr: U128,
+    pub new_apr: U128,
+    pub cause: AprChangeCause,
+}
+
+impl Contract {
+    pub(crate) fn internal_record_apr_change(
+        &mut self,
+        advisor_id: &AccountId,
+        old_apr: u128,
+        new_apr: u128,
+        cause: AprChangeCause,
+    ) {
+        let mut history = self.apr_history.get(advisor_id).unwrap_or_else(|| {
+            RingBuffer::new(StorageKey::apr_history_entries(advisor_id), MAX_APR_HISTORY)
+        });
+        history.push(&AprChange {
+            timestamp: Self::now().into(),
+            old_apr: U128(old_apr),
+            new_apr: U128(new_apr),
+            cause,
+        });
+        self.apr_history.insert(advisor_id, &history);
+
+        self.total_apr = self.total_apr - old_apr + new_apr;
+        self.internal_on_lead_apr_change(advisor_id, new_apr);
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // oldest first, `from` is an offset into the kept entries
+    pub fn get_apr_history(
+        &self,
+        advisor_id: AccountId,
+        from: Option<u64>,
+        limit: Option<u64>,
+    ) -> Vec<AprChange> {
+        match self.apr_history.get(&advisor_id) {
+            Some(history) => history.page(from.unwrap_or(0), limit.unwrap_or(DEFAULT_PAGE_LIMIT)),
+            None => vec![],
+        }
+    }
+
+    pub fn get_average_apr(&self) -> U128 {
+        if self.total_stakers == 0 {
+            return U128(0);
+        }
+        U128(self.total_apr / self.total_stakers)
+    }
+}
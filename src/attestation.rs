@@ -0,0 +1,58 @@
+use near_sdk::json_types::U128;
+use near_sdk::{env, near_bindgen, AccountId, Gas, PromiseError, PromiseOrValue};
+
+use crate::*;
+
+pub const IS_VERIFIED_GAS: Gas = Gas(5_000_000_000_000);
+pub const ATTESTATION_CALLBACK_GAS: Gas = Gas(15_000_000_000_000);
+
+impl Contract {
+    // first deposits wait for the registry before a position is created
+    pub(crate) fn internal_needs_attestation(&self, account_id: &AccountId) -> bool {
+        self.attestation_registry.is_some() && self.internal_get_stake_info(account_id).is_none()
+    }
+
+    pub(crate) fn internal_stake_after_attestation(
+        &self,
+        account_id: AccountId,
+        amount: U128,
+    ) -> PromiseOrValue<U128> {
+        let registry = self.attestation_registry.clone().unwrap();
+        ext_attestation_registry::ext(registry)
+            .with_static_gas(IS_VERIFIED_GAS)
+            .is_verified(account_id.clone())
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(ATTESTATION_CALLBACK_GAS)
+                    .on_attestation_checked(account_id, amount),
+            )
+            .into()
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // returns the unused amount back to the token contract like ft_on_transfer
+    #[private]
+    pub fn on_attestation_checked(
+        &mut self,
+        account_id: AccountId,
+        amount: U128,
+        #[callback_result] verified: Result<bool, PromiseError>,
+    ) -> U128 {
+        if !verified.unwrap_or(false) {
+            return amount;
+        }
+        let accepted = self.internal_accept_up_to_cap(&account_id, amount.0);
+        if accepted > 0 {
+            self.internal_stake(account_id, accepted);
+            self.internal_note_inflow(accepted);
+        }
+        U128(amount.0 - accepted)
+    }
+
+    pub fn set_attestation_registry(&mut self, registry_id: Option<AccountId>) {
+        self.internal_assert_role(Role::Admin);
+        self.attestation_registry = registry_id;
+    }
+}
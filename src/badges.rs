@@ -0,0 +1,101 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{near_bindgen, require, AccountId};
+
+use crate::events::{BadgeChangedData, StakeEvent};
+use crate::time::{Duration, Timestamp};
+use crate::*;
+
+#[derive(
+    BorshDeserialize,
+    BorshSerialize,
+    Serialize,
+    Deserialize,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub enum Badge {
+    Bronze,
+    Silver,
+    Gold,
+    Platinum,
+}
+
+// an advisor holds the highest tier it meets every threshold of
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BadgeTier {
+    pub badge: Badge,
+    pub min_staked: U128,
+    pub min_tenure: Duration,
+    pub min_votes: u8,
+}
+
+impl Contract {
+    pub(crate) fn internal_badge_at(
+        &self,
+        stake_info: &StakeInfo,
+        now: Timestamp,
+    ) -> Option<Badge> {
+        let tenure = Duration::between(stake_info.terms.opened_at, now);
+        self.badge_tiers
+            .iter()
+            .rev()
+            .find(|tier| {
+                stake_info.amount_staked >= tier.min_staked.0
+                    && tenure >= tier.min_tenure
+                    && stake_info.votes >= tier.min_votes
+            })
+            .map(|tier| tier.badge)
+    }
+
+    // tenure alone doesn't write anything, a badge it earns shows up with the next save.
+    // returns the record to store when the badge moved
+    pub(crate) fn internal_refresh_badge(
+        &self,
+        account_id: &AccountId,
+        stake_info: &StakeInfo,
+    ) -> Option<StakeInfo> {
+        let badge = self.internal_badge_at(stake_info, Self::now());
+        if badge == stake_info.badge {
+            return None;
+        }
+        StakeEvent::BadgeChanged(vec![BadgeChangedData {
+            account_id: account_id.clone(),
+            old_badge: stake_info.badge,
+            new_badge: badge,
+        }])
+        .emit();
+        let mut stake_info = stake_info.clone();
+        stake_info.badge = badge;
+        Some(stake_info)
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // lowest tier first
+    pub fn set_badge_tiers(&mut self, tiers: Vec<BadgeTier>) {
+        self.internal_assert_role(Role::Admin);
+        require!(
+            tiers.windows(2).all(|pair| pair[0].badge < pair[1].badge),
+            StakeError::InvalidBadgeTiers
+        );
+        self.badge_tiers = tiers;
+    }
+
+    pub fn get_badge_tiers(&self) -> Vec<BadgeTier> {
+        self.badge_tiers.clone()
+    }
+
+    pub fn get_badge(&self, advisor_id: AccountId) -> Option<Badge> {
+        self.internal_get_stake_info(&advisor_id)
+            .and_then(|stake_info| self.internal_badge_at(&stake_info, Self::now()))
+    }
+}
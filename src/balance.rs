@@ -0,0 +1,154 @@
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::Serialize;
+use near_sdk::{env, near_bindgen, require, Gas, Promise, PromiseError};
+
+use crate::time::Duration;
+use crate::*;
+
+pub const BALANCE_CALLBACK_GAS: Gas = Gas(5_000_000_000_000);
+// a cached balance older than this is refetched by the next payout
+pub const MAX_TOKEN_BALANCE_AGE: Duration = Duration::from_secs(60 * 60);
+// left to the payout itself once the refetch is attached
+pub const BALANCE_REFRESH_GAS_RESERVE: Gas = Gas(30_000_000_000_000);
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TokenBalanceView {
+    pub balance: Option<U128>,
+    pub checked_at: U64,
+}
+
+// the stake token balance last reported by the token contract, moved along with the
+// transfers this contract sees until the next refresh
+impl Contract {
+    pub(crate) fn internal_set_token_balance(&mut self, balance: u128) {
+        self.token_balance = Some(balance);
+        self.token_balance_checked_at = Self::now();
+    }
+
+    pub(crate) fn internal_note_inflow(&mut self, amount: u128) {
+        self.total_inflows += amount;
+        if let Some(balance) = self.token_balance.as_mut() {
+            *balance += amount;
+        }
+    }
+
+    pub(crate) fn internal_note_outflow(&mut self, amount: u128) {
+        self.total_outflows += amount;
+        if let Some(balance) = self.token_balance.as_mut() {
+            *balance = balance.saturating_sub(amount);
+        }
+    }
+
+    // None when the cached balance covers every liability sweep counts. a payout is one of
+    // them until it leaves, so it needs no adding on top
+    pub(crate) fn internal_balance_error(&self) -> Option<StakeError> {
+        match self.token_balance {
+            None => Some(StakeError::TokenBalanceUnknown),
+            Some(balance) if self.internal_liabilities() > balance => {
+                Some(StakeError::InsufficientTokenBalance)
+            }
+            Some(_) => None,
+        }
+    }
+
+    // payouts fail closed until the balance was fetched once. a stale cache is still
+    // trusted and refetched in the background so the next payout sees a fresh one
+    pub(crate) fn internal_assert_balance_covers(&mut self) {
+        self.internal_maybe_refresh_token_balance();
+        let error = self.internal_balance_error();
+        require!(error.is_none(), error.as_deref().unwrap_or_default());
+    }
+
+    // at most once a block, and only when the call has the gas to spare
+    fn internal_maybe_refresh_token_balance(&mut self) {
+        let fresh = self.token_balance.is_some()
+            && Self::now() < self.token_balance_checked_at + MAX_TOKEN_BALANCE_AGE;
+        let left = env::prepaid_gas().0.saturating_sub(env::used_gas().0);
+        if fresh
+            || self.token_balance_refresh_block == env::block_height()
+            || left < FT_BALANCE_OF_GAS.0 + BALANCE_CALLBACK_GAS.0 + BALANCE_REFRESH_GAS_RESERVE.0
+        {
+            return;
+        }
+        self.token_balance_refresh_block = env::block_height();
+        self.refresh_token_balance();
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn refresh_token_balance(&mut self) -> Promise {
+        ext_ft_contract::ext(self.token_address.clone())
+            .with_static_gas(FT_BALANCE_OF_GAS)
+            .ft_balance_of(env::current_account_id())
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(BALANCE_CALLBACK_GAS)
+                    .on_token_balance(),
+            )
+    }
+
+    #[private]
+    pub fn on_token_balance(
+        &mut self,
+        #[callback_result] balance: Result<U128, PromiseError>,
+    ) -> Option<U128> {
+        let balance = balance.ok()?;
+        self.internal_set_token_balance(balance.0);
+        Some(balance)
+    }
+
+    pub fn get_token_balance(&self) -> TokenBalanceView {
+        TokenBalanceView {
+            balance: self.token_balance.map(U128),
+            checked_at: self.token_balance_checked_at.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    const AMOUNT: u128 = 100 * ONE_TOKEN;
+
+    fn unstake(contract: &mut Contract, account_id: &AccountId) {
+        call_with_yocto(account_id);
+        contract.unstake_token(U128(AMOUNT), None, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_TOKEN_BALANCE_UNKNOWN")]
+    fn payouts_fail_closed_until_the_balance_is_fetched() {
+        let mut contract = setup();
+        contract.token_balance = None;
+        let alice = account("alice");
+        stake(&mut contract, &alice, AMOUNT);
+        unstake(&mut contract, &alice);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INSUFFICIENT_TOKEN_BALANCE")]
+    fn a_payout_needs_every_liability_covered() {
+        let mut contract = setup();
+        fund_reward_pool(&mut contract, AMOUNT);
+        let alice = account("alice");
+        stake(&mut contract, &alice, AMOUNT);
+        // the stake alone is covered, the reward pool next to it is not
+        contract.internal_set_token_balance(AMOUNT);
+        unstake(&mut contract, &alice);
+    }
+
+    #[test]
+    fn a_covered_payout_goes_through() {
+        let mut contract = setup();
+        fund_reward_pool(&mut contract, AMOUNT);
+        let alice = account("alice");
+        stake(&mut contract, &alice, AMOUNT);
+        assert_eq!(contract.token_balance, Some(2 * AMOUNT));
+        unstake(&mut contract, &alice);
+        assert_eq!(contract.total_withdrawable, AMOUNT);
+    }
+}
@@ -0,0 +1,91 @@
+use near_sdk::json_types::U128;
+use near_sdk::serde::Serialize;
+use near_sdk::{env, near_bindgen, require, AccountId, Gas};
+
+use crate::*;
+
+pub const MAX_CLAIM_BATCH: usize = 50;
+// left for the claim in progress and for serializing the results
+pub const BATCH_CLAIM_GAS_RESERVE: Gas = Gas(15_000_000_000_000);
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BatchClaimItem {
+    pub account_id: AccountId,
+    pub claimed: U128,
+    pub error: Option<StakeError>,
+}
+
+// accounts past `processed` were not reached before gas ran low and can be sent again
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BatchClaimResult {
+    pub results: Vec<BatchClaimItem>,
+    pub processed: u32,
+    pub total_claimed: U128,
+}
+
+impl Contract {
+    // the checks internal_settle_claim would panic on, so one account can't sink the
+    // batch. the reward is estimated as of now before the position is touched
+    pub(crate) fn internal_claim_blocker(&self, account_id: &AccountId) -> Option<StakeError> {
+        let stake_info = match self.internal_get_stake_info(account_id) {
+            Some(stake_info) => stake_info,
+            None => return Some(StakeError::NotStaked),
+        };
+        if stake_info.pending_operation {
+            return Some(StakeError::OperationPending);
+        }
+        if self.frozen_accounts.contains_key(account_id) {
+            return Some(StakeError::AccountFrozen);
+        }
+        if Self::now() < self.internal_next_claim_at(&stake_info) {
+            return Some(StakeError::ClaimCooldown);
+        }
+        let reward = self.internal_pending_reward_at(&stake_info, Self::now());
+        if reward == 0 {
+            return Some(StakeError::NoReward);
+        }
+        self.internal_balance_error()
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // payroll run paid for by the platform, every claim lands in the advisor's own
+    // withdrawable balance
+    pub fn batch_claim_for(&mut self, accounts: Vec<AccountId>) -> BatchClaimResult {
+        self.internal_assert_role(Role::ClaimRelayer);
+        require!(accounts.len() <= MAX_CLAIM_BATCH, StakeError::BatchTooLarge);
+        let mut results = Vec::with_capacity(accounts.len());
+        let mut total_claimed = 0;
+        for account_id in accounts {
+            if env::prepaid_gas() - env::used_gas() < BATCH_CLAIM_GAS_RESERVE {
+                break;
+            }
+            let item = match self.internal_claim_blocker(&account_id) {
+                Some(error) => BatchClaimItem {
+                    account_id,
+                    claimed: U128(0),
+                    error: Some(error),
+                },
+                None => {
+                    let claimed = self.internal_claim_reward(&account_id, None);
+                    total_claimed += claimed;
+                    BatchClaimItem {
+                        account_id,
+                        claimed: U128(claimed),
+                        error: None,
+                    }
+                }
+            };
+            results.push(item);
+        }
+        self.internal_maybe_snapshot();
+        BatchClaimResult {
+            processed: results.len() as u32,
+            results,
+            total_claimed: U128(total_claimed),
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use near_sdk::json_types::U128;
+use near_sdk::serde::Serialize;
+use near_sdk::{near_bindgen, require};
+
+use crate::events::{BonusDistributedData, StakeEvent};
+use crate::math::{mul_div, RATE_PRECISION};
+use crate::*;
+
+// scale of bonus_per_share
+pub const BONUS_PRECISION: u128 = RATE_PRECISION;
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BonusView {
+    pub bonus_balance: U128,
+    pub bonus_per_share: U128,
+    pub distributions: u64,
+}
+
+impl Contract {
+    // bonus credited to a position since it last settled against the accumulator
+    pub(crate) fn internal_unsettled_bonus(&self, stake_info: &StakeInfo) -> u128 {
+        mul_div(
+            stake_info.amount_staked,
+            self.bonus_per_share - stake_info.bonus_per_share_paid,
+            BONUS_PRECISION,
+        )
+    }
+
+    // moves `total` into the reward pool and credits it to the accumulator, the caller
+    // takes it from wherever it was held. the part the floored per share increment
+    // can't express is dust
+    pub(crate) fn internal_distribute_to_stakers(&mut self, total: u128) {
+        let increment = mul_div(total, BONUS_PRECISION, self.total_staked);
+        let credited = mul_div(increment, self.total_staked, BONUS_PRECISION);
+        self.internal_record_dust(total - credited);
+        self.reward_pool += credited;
+        self.internal_update_global_accrual();
+        self.accrued_rewards += credited;
+        self.bonus_per_share += increment;
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // splits `total` from the funded bonus balance over everything staked right now,
+    // accounts pick their share up the next time they are touched
+    pub fn distribute_bonus(&mut self, total: U128) -> u64 {
+        self.internal_assert_role(Role::Treasurer);
+        let total = total.0;
+        require!(total > 0, StakeError::InvalidAmount);
+        require!(
+            total <= self.bonus_balance,
+            StakeError::InsufficientBonusBalance
+        );
+        require!(self.total_staked > 0, StakeError::NothingStaked);
+
+        self.bonus_balance -= total;
+        self.internal_distribute_to_stakers(total);
+        self.bonus_distributions += 1;
+
+        StakeEvent::BonusDistributed(vec![BonusDistributedData {
+            distribution_id: self.bonus_distributions,
+            amount: U128(total),
+            total_staked: U128(self.total_staked),
+        }])
+        .emit();
+        self.bonus_distributions
+    }
+
+    pub fn get_bonus(&self) -> BonusView {
+        BonusView {
+            bonus_balance: U128(self.bonus_balance),
+            bonus_per_share: U128(self.bonus_per_share),
+            distributions: self.bonus_distributions,
+        }
+    }
+}
@@ -0,0 +1,268 @@
+use near_contract_standards::non_fungible_token::Token;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::Serialize;
+use near_sdk::{env, near_bindgen, AccountId, Gas, Promise, PromiseError};
+
+use crate::math::{mul_div, BPS_DENOMINATOR};
+use crate::time::{Duration, Timestamp};
+use crate::*;
+
+pub const NFT_TOKEN_GAS: Gas = Gas(5_000_000_000_000);
+pub const BOOSTER_CALLBACK_GAS: Gas = Gas(20_000_000_000_000);
+pub const MAX_BOOST_BPS: u32 = 10_000;
+// claims that don't check ownership themselves drop a booster verified longer ago
+pub const BOOSTER_VERIFY_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Clone, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Booster {
+    pub nft_contract: AccountId,
+    pub token_id: String,
+    pub boost_bps: u32,
+    pub verified_at: Timestamp,
+}
+
+impl StakeInfo {
+    // apr raised by the registered booster, the governance tier and the loyalty step, what
+    // rewards actually accrue at
+    pub(crate) fn boosted(&self, apr: u128) -> u128 {
+        let boost_bps = self
+            .terms
+            .booster
+            .as_ref()
+            .map_or(0, |booster| booster.boost_bps);
+        let governance_bps = self
+            .terms
+            .governance
+            .as_ref()
+            .map_or(0, |governance| governance.bonus_bps);
+        let bonus_bps = boost_bps as u128 + governance_bps as u128 + self.loyalty_bps as u128;
+        if bonus_bps == 0 {
+            return apr;
+        }
+        mul_div(apr, BPS_DENOMINATOR + bonus_bps, BPS_DENOMINATOR)
+    }
+}
+
+impl Contract {
+    // swaps the booster without settling first, so the time since the last touch accrues
+    // at the new rate. dropping an unverified booster relies on that. a token boosts one
+    // account at a time, registering it elsewhere takes it from the previous holder
+    fn internal_set_booster(&mut self, account_id: &AccountId, booster: Option<Booster>) {
+        // the rate change below assumes the stake is already in the reward rate
+        self.internal_migrate_legacy(account_id);
+        let mut stake_info = match self.internal_load_stake_info(account_id) {
+            Some(stake_info) => stake_info,
+            None => return,
+        };
+        if let Some(old) = &stake_info.terms.booster {
+            let key = (old.nft_contract.clone(), old.token_id.clone());
+            if self.booster_holders.get(&key).as_ref() == Some(account_id) {
+                self.booster_holders.remove(&key);
+            }
+        }
+        if let Some(new) = &booster {
+            let key = (new.nft_contract.clone(), new.token_id.clone());
+            if let Some(holder) = self.booster_holders.insert(&key, account_id) {
+                if &holder != account_id {
+                    self.internal_set_booster(&holder, None);
+                }
+            }
+        }
+        let old_apr = stake_info.boosted(stake_info.apr);
+        stake_info.terms.booster = booster;
+        self.internal_update_reward_rate(
+            stake_info.amount_staked,
+            old_apr,
+            stake_info.amount_staked,
+            stake_info.boosted(stake_info.apr),
+        );
+        self.internal_save_stake_info(account_id, &stake_info);
+    }
+
+    pub(crate) fn internal_drop_stale_booster(&mut self, account_id: &AccountId) {
+        let stale = self
+            .internal_get_stake_info(account_id)
+            .and_then(|stake_info| stake_info.terms.booster)
+            .is_some_and(|booster| Self::now() >= booster.verified_at + BOOSTER_VERIFY_TTL);
+        if stale {
+            self.internal_set_booster(account_id, None);
+        }
+    }
+
+    pub(crate) fn internal_verify_booster(
+        &self,
+        account_id: AccountId,
+        nft_contract: AccountId,
+        token_id: String,
+        claim: bool,
+    ) -> Promise {
+        ext_nft::ext(nft_contract.clone())
+            .with_static_gas(NFT_TOKEN_GAS)
+            .nft_token(token_id.clone())
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(BOOSTER_CALLBACK_GAS)
+                    .on_booster_verified(account_id, nft_contract, token_id, claim),
+            )
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // the boost applies from the first touch after the token's owner is confirmed
+    pub fn register_booster(&mut self, nft_contract: AccountId, token_id: String) -> Promise {
+        let account_id = env::predecessor_account_id();
+        require!(
+            self.internal_get_stake_info(&account_id).is_some(),
+            StakeError::NotStaked
+        );
+        require!(
+            self.booster_contracts.contains_key(&nft_contract),
+            StakeError::BoosterNotSupported
+        );
+        self.internal_verify_booster(account_id, nft_contract, token_id, false)
+    }
+
+    pub fn unregister_booster(&mut self) {
+        self.internal_set_booster(&env::predecessor_account_id(), None);
+    }
+
+    #[private]
+    pub fn on_booster_verified(
+        &mut self,
+        account_id: AccountId,
+        nft_contract: AccountId,
+        token_id: String,
+        claim: bool,
+        #[callback_result] token: Result<Option<Token>, PromiseError>,
+    ) -> U128 {
+        let owned = matches!(token, Ok(Some(token)) if token.owner_id == account_id);
+        let boost_bps = self.booster_contracts.get(&nft_contract);
+        match (owned, boost_bps) {
+            (true, Some(boost_bps)) => {
+                // settle at the old rate, the new one only covers time after verification
+                if let Some(stake_info) = self.internal_touch(&account_id) {
+                    self.internal_save_stake_info(&account_id, &stake_info);
+                }
+                self.internal_set_booster(
+                    &account_id,
+                    Some(Booster {
+                        nft_contract,
+                        token_id,
+                        boost_bps,
+                        verified_at: Self::now(),
+                    }),
+                );
+            }
+            _ if claim => self.internal_set_booster(&account_id, None),
+            _ => {}
+        }
+        if !claim {
+            return U128(0);
+        }
+        let claimed = self.internal_claim_reward(&account_id, None);
+        self.internal_maybe_snapshot();
+        U128(claimed)
+    }
+
+    pub fn set_booster_contract(&mut self, nft_contract: AccountId, boost_bps: Option<u32>) {
+        self.internal_assert_role(Role::Admin);
+        match boost_bps {
+            Some(boost_bps) => {
+                require!(boost_bps <= MAX_BOOST_BPS, StakeError::InvalidBoost);
+                self.booster_contracts.insert(&nft_contract, &boost_bps);
+            }
+            None => {
+                self.booster_contracts.remove(&nft_contract);
+            }
+        }
+    }
+
+    pub fn get_booster(&self, account_id: AccountId) -> Option<Booster> {
+        self.internal_get_stake_info(&account_id)
+            .and_then(|stake_info| stake_info.terms.booster)
+    }
+
+    pub fn get_booster_holder(
+        &self,
+        nft_contract: AccountId,
+        token_id: String,
+    ) -> Option<AccountId> {
+        self.booster_holders.get(&(nft_contract, token_id))
+    }
+
+    pub fn get_booster_contract(&self, nft_contract: AccountId) -> Option<u32> {
+        self.booster_contracts.get(&nft_contract)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::legacy::LegacyStakeInfo;
+    use crate::math::Rate;
+    use crate::test_utils::*;
+
+    const AMOUNT: u128 = 1_000 * ONE_TOKEN;
+
+    // a record the first deployment wrote, not yet converted
+    fn legacy_staker(contract: &mut Contract, account_id: &AccountId) {
+        let legacy = LegacyStakeInfo::new(START as i64, AMOUNT, DEFAULT_APR);
+        contract.legacy_stake_info.insert(account_id, &legacy);
+        contract.total_staked += AMOUNT;
+        contract.total_stakers += 1;
+    }
+
+    #[test]
+    fn unregistering_migrates_a_legacy_record_first() {
+        let mut contract = setup();
+        let alice = account("alice");
+        legacy_staker(&mut contract, &alice);
+
+        call_as(&alice);
+        contract.unregister_booster();
+        assert!(contract.legacy_stake_info.get(&alice).is_none());
+        assert_eq!(
+            contract.reward_rate,
+            Rate::from_raw(DEFAULT_APR).annual_reward(AMOUNT)
+        );
+        assert_eq!(contract.total_apr, DEFAULT_APR);
+        // a later touch finds nothing left to migrate
+        let stake_info = contract.internal_touch(&alice).unwrap();
+        assert_eq!(stake_info.amount_staked, AMOUNT);
+        assert_eq!(
+            contract.reward_rate,
+            Rate::from_raw(DEFAULT_APR).annual_reward(AMOUNT)
+        );
+    }
+
+    #[test]
+    fn taking_over_a_booster_migrates_its_legacy_holder() {
+        let mut contract = setup();
+        let (alice, bob) = (account("alice"), account("bob"));
+        legacy_staker(&mut contract, &alice);
+        stake(&mut contract, &bob, AMOUNT);
+        let key = (account("nft"), "1".to_string());
+        contract.booster_holders.insert(&key, &alice);
+
+        contract.internal_set_booster(
+            &bob,
+            Some(Booster {
+                nft_contract: key.0.clone(),
+                token_id: key.1.clone(),
+                boost_bps: 1_000,
+                verified_at: Contract::now(),
+            }),
+        );
+        assert!(contract.legacy_stake_info.get(&alice).is_none());
+        assert_eq!(contract.booster_holders.get(&key), Some(bob.clone()));
+        let bob_info = contract.internal_get_stake_info(&bob).unwrap();
+        assert_eq!(
+            contract.reward_rate,
+            Rate::from_raw(DEFAULT_APR).annual_reward(AMOUNT)
+                + Rate::from_raw(bob_info.boosted(bob_info.apr)).annual_reward(AMOUNT)
+        );
+    }
+}
@@ -0,0 +1,114 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{near_bindgen, require};
+
+use crate::events::{BreakerTrippedData, StakeEvent};
+use crate::time::{Duration, Timestamp};
+use crate::*;
+
+// outflows are summed per bucket, a window is the buckets it spans
+pub const OUTFLOW_BUCKET: Duration = Duration::from_secs(15 * 60);
+pub const HOURLY_BUCKETS: u64 = 4;
+pub const DAILY_BUCKETS: u64 = 96;
+
+// limits on what unstakes and claims may move into withdrawable within the last hour
+// and day, going over either stops withdrawals until a pauser resets the breaker
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BreakerConfig {
+    pub hourly_limit: U128,
+    pub daily_limit: U128,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BreakerView {
+    pub config: Option<BreakerConfig>,
+    pub hourly_outflow: U128,
+    pub daily_outflow: U128,
+    pub tripped_at: Option<Timestamp>,
+}
+
+impl Contract {
+    fn internal_outflow_bucket(now: Timestamp) -> u64 {
+        now.as_nanos() / OUTFLOW_BUCKET.as_nanos()
+    }
+
+    fn internal_outflow_since(&self, buckets: u64, now: Timestamp) -> u128 {
+        let current = Self::internal_outflow_bucket(now);
+        self.outflow_buckets
+            .iter()
+            .filter(|(bucket, _)| *bucket + buckets > current)
+            .map(|(_, amount)| amount)
+            .sum()
+    }
+
+    pub(crate) fn internal_assert_breaker_closed(&self) {
+        require!(
+            self.breaker_tripped_at.is_none(),
+            StakeError::BreakerTripped
+        );
+    }
+
+    // called for every unstake and claim, the call that crosses a limit still completes
+    pub(crate) fn internal_record_outflow(&mut self, amount: u128) {
+        let config = match &self.breaker_config {
+            Some(config) => config.clone(),
+            None => return,
+        };
+        let now = Self::now();
+        let current = Self::internal_outflow_bucket(now);
+        self.outflow_buckets
+            .retain(|(bucket, _)| *bucket + DAILY_BUCKETS > current);
+        match self.outflow_buckets.last_mut() {
+            Some((bucket, total)) if *bucket == current => *total += amount,
+            _ => self.outflow_buckets.push((current, amount)),
+        }
+        if self.breaker_tripped_at.is_some() {
+            return;
+        }
+        let hourly_outflow = self.internal_outflow_since(HOURLY_BUCKETS, now);
+        let daily_outflow = self.internal_outflow_since(DAILY_BUCKETS, now);
+        if hourly_outflow > config.hourly_limit.0 || daily_outflow > config.daily_limit.0 {
+            self.breaker_tripped_at = Some(now);
+            StakeEvent::BreakerTripped(vec![BreakerTrippedData {
+                hourly_outflow: U128(hourly_outflow),
+                daily_outflow: U128(daily_outflow),
+                hourly_limit: config.hourly_limit,
+                daily_limit: config.daily_limit,
+            }])
+            .emit();
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn set_breaker_config(&mut self, breaker_config: Option<BreakerConfig>) {
+        self.internal_assert_role(Role::Admin);
+        self.internal_record_config_change(
+            "breaker_config",
+            self.breaker_config.clone(),
+            breaker_config.clone(),
+        );
+        self.breaker_config = breaker_config;
+    }
+
+    // the outflow seen so far is forgotten so the breaker does not trip again right away
+    pub fn reset_breaker(&mut self) {
+        self.internal_assert_role(Role::Pauser);
+        self.breaker_tripped_at = None;
+        self.outflow_buckets.clear();
+    }
+
+    pub fn get_breaker(&self) -> BreakerView {
+        let now = Self::now();
+        BreakerView {
+            config: self.breaker_config.clone(),
+            hourly_outflow: U128(self.internal_outflow_since(HOURLY_BUCKETS, now)),
+            daily_outflow: U128(self.internal_outflow_since(DAILY_BUCKETS, now)),
+            tripped_at: self.breaker_tripped_at,
+        }
+    }
+}
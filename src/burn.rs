@@ -0,0 +1,116 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{
+    env, is_promise_success, near_bindgen, require, AccountId, Gas, Promise, ONE_YOCTO,
+};
+
+use crate::*;
+
+pub const BURN_GAS: Gas = Gas(10_000_000_000_000);
+pub const BURN_CALLBACK_GAS: Gas = Gas(10_000_000_000_000);
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub enum BurnMode {
+    // for tokens without a burn method, e.g. a dead address nobody holds the key of
+    Transfer { receiver_id: AccountId },
+    // called on the token contract with `{"amount": "..."}` and one yocto
+    Method { method_name: String },
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BurnView {
+    pub mode: Option<BurnMode>,
+    pub burn_penalties: bool,
+    pub pending_burn: U128,
+    pub total_burned: U128,
+}
+
+impl Contract {
+    // slashed stake and unstake fees, burned instead of forfeited when burn_penalties is set
+    pub(crate) fn internal_collect_penalty(&mut self, amount: u128) -> bool {
+        if !self.burn_penalties || amount == 0 {
+            return false;
+        }
+        self.pending_burn += amount;
+        true
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn set_burn_config(&mut self, mode: Option<BurnMode>, burn_penalties: bool) {
+        self.internal_assert_role(Role::Admin);
+        // nothing may be routed to a burn that cannot run
+        require!(
+            mode.is_some()
+                || (!burn_penalties && self.forfeit_destination != ForfeitDestination::Burn),
+            StakeError::NoBurnMode
+        );
+        if let Some(BurnMode::Transfer { receiver_id }) = &mode {
+            Self::internal_assert_receiver(receiver_id);
+        }
+        self.burn_mode = mode;
+        self.burn_penalties = burn_penalties;
+    }
+
+    // a failed burn keeps the amount pending for the next attempt
+    pub fn execute_burn(&mut self) -> Promise {
+        self.internal_assert_role(Role::Treasurer);
+        self.internal_assert_transfers_up();
+        let mode = self
+            .burn_mode
+            .clone()
+            .unwrap_or_else(|| env::panic_str(&StakeError::NoBurnMode));
+        let amount = self.pending_burn;
+        require!(amount > 0, StakeError::NothingToBurn);
+        self.pending_burn = 0;
+        self.pending_outflows += amount;
+
+        let burn = match mode {
+            BurnMode::Transfer { receiver_id } => ext_ft_contract::ext(self.token_address.clone())
+                .with_static_gas(FT_TRANSFER_GAS)
+                .with_attached_deposit(ONE_YOCTO)
+                .ft_transfer(receiver_id, U128(amount), Some("burn".to_string())),
+            BurnMode::Method { method_name } => Promise::new(self.token_address.clone())
+                .function_call(
+                    method_name,
+                    near_sdk::serde_json::json!({ "amount": U128(amount) })
+                        .to_string()
+                        .into_bytes(),
+                    ONE_YOCTO,
+                    BURN_GAS,
+                ),
+        };
+        burn.then(
+            Self::ext(env::current_account_id())
+                .with_static_gas(BURN_CALLBACK_GAS)
+                .on_burn(U128(amount)),
+        )
+    }
+
+    // whatever the burn method returns is ignored, only success counts
+    #[private]
+    pub fn on_burn(&mut self, amount: U128) -> U128 {
+        self.internal_note_promise_result("on_burn", None, amount.0, is_promise_success());
+        self.pending_outflows -= amount.0;
+        if !is_promise_success() {
+            self.pending_burn += amount.0;
+            return U128(0);
+        }
+        self.internal_note_outflow(amount.0);
+        self.total_burned += amount.0;
+        amount
+    }
+
+    pub fn get_burn(&self) -> BurnView {
+        BurnView {
+            mode: self.burn_mode.clone(),
+            burn_penalties: self.burn_penalties,
+            pending_burn: U128(self.pending_burn),
+            total_burned: U128(self.total_burned),
+        }
+    }
+}
@@ -0,0 +1,295 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, require, AccountId};
+
+use crate::math::mul_div;
+use crate::time::{Duration, Timestamp};
+use crate::*;
+
+// scale of acc_reward_per_weight
+pub const CAMPAIGN_REWARD_PRECISION: u128 = 1_000_000_000_000_000_000_000_000;
+pub const MAX_CAMPAIGNS_PER_ACCOUNT: usize = 5;
+pub const MAX_CAMPAIGN_PAGE: u64 = 50;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub enum CampaignFilter {
+    Everyone,
+    // positions first opened once the campaign started
+    NewStakers,
+    MinStake { amount: U128 },
+}
+
+// streams `budget` evenly over start..end to the accounts that joined, weighted by
+// their current stake. paid from its own funding, apart from the advisor APR
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Campaign {
+    pub id: u64,
+    pub name: String,
+    pub start: Timestamp,
+    pub end: Timestamp,
+    pub budget: U128,
+    pub filter: CampaignFilter,
+    pub funded: U128,
+    pub emitted: U128,
+    pub claimed: U128,
+    pub participants: u64,
+    pub total_weight: U128,
+    pub acc_reward_per_weight: U128,
+    pub last_update: Timestamp,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct CampaignStake {
+    pub weight: u128,
+    pub reward_per_weight_paid: u128,
+    pub reward: u128,
+}
+
+impl Campaign {
+    // what accrued since the last update, never more than is funded. time without any
+    // weight joined is not streamed and stays with the funding
+    fn update(&mut self, now: Timestamp) {
+        let now = now.min(self.end);
+        if now <= self.last_update {
+            return;
+        }
+        let elapsed = Duration::between(self.last_update, now).as_nanos() as u128;
+        let length = Duration::between(self.start, self.end).as_nanos() as u128;
+        self.last_update = now;
+        if self.total_weight.0 == 0 {
+            return;
+        }
+        let emitted = mul_div(self.budget.0, elapsed, length).min(self.funded.0 - self.emitted.0);
+        let increment = mul_div(emitted, CAMPAIGN_REWARD_PRECISION, self.total_weight.0);
+        self.acc_reward_per_weight.0 += increment;
+        self.emitted.0 += mul_div(increment, self.total_weight.0, CAMPAIGN_REWARD_PRECISION);
+    }
+
+    fn settle(&self, stake: &mut CampaignStake) {
+        stake.reward += mul_div(
+            stake.weight,
+            self.acc_reward_per_weight.0 - stake.reward_per_weight_paid,
+            CAMPAIGN_REWARD_PRECISION,
+        );
+        stake.reward_per_weight_paid = self.acc_reward_per_weight.0;
+    }
+}
+
+impl Contract {
+    fn internal_get_campaign(&self, campaign_id: u64) -> Campaign {
+        self.campaigns
+            .get(&campaign_id)
+            .unwrap_or_else(|| env::panic_str(&StakeError::UnknownCampaign))
+    }
+
+    fn internal_campaign_eligible(&self, campaign: &Campaign, stake_info: &StakeInfo) -> bool {
+        match &campaign.filter {
+            CampaignFilter::Everyone => true,
+            CampaignFilter::NewStakers => stake_info.terms.first_staked_at >= campaign.start,
+            CampaignFilter::MinStake { amount } => stake_info.amount_staked >= amount.0,
+        }
+    }
+
+    pub(crate) fn internal_fund_campaign(
+        &mut self,
+        campaign_id: u64,
+        sender_id: &AccountId,
+        amount: u128,
+    ) -> Result<(), StakeError> {
+        if !self.internal_has_role(sender_id, Role::Treasurer) {
+            return Err(StakeError::MissingRole);
+        }
+        let mut campaign = self
+            .campaigns
+            .get(&campaign_id)
+            .ok_or(StakeError::UnknownCampaign)?;
+        if Self::now() >= campaign.end {
+            return Err(StakeError::CampaignEnded);
+        }
+        campaign.funded.0 += amount;
+        self.campaigns.insert(&campaign_id, &campaign);
+        self.total_campaign_reserve += amount;
+        Ok(())
+    }
+
+    // weights follow the stake, called whenever a saved position changed its amount
+    pub(crate) fn internal_sync_campaign_weights(&mut self, account_id: &AccountId, amount: u128) {
+        let campaign_ids = match self.account_campaigns.get(account_id) {
+            Some(campaign_ids) => campaign_ids,
+            None => return,
+        };
+        let now = Self::now();
+        for campaign_id in campaign_ids {
+            let key = (campaign_id, account_id.clone());
+            let (mut campaign, mut stake) = match (
+                self.campaigns.get(&campaign_id),
+                self.campaign_stakes.get(&key),
+            ) {
+                (Some(campaign), Some(stake)) => (campaign, stake),
+                _ => continue,
+            };
+            campaign.update(now);
+            campaign.settle(&mut stake);
+            campaign.total_weight.0 = campaign.total_weight.0 - stake.weight + amount;
+            stake.weight = amount;
+            self.campaigns.insert(&campaign_id, &campaign);
+            self.campaign_stakes.insert(&key, &stake);
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn create_campaign(
+        &mut self,
+        name: String,
+        start: Timestamp,
+        end: Timestamp,
+        budget: U128,
+        filter: CampaignFilter,
+    ) -> u64 {
+        self.internal_assert_role(Role::Admin);
+        require!(
+            end > start && end > Self::now() && budget.0 > 0,
+            StakeError::InvalidCampaign
+        );
+        self.campaign_count += 1;
+        let campaign = Campaign {
+            id: self.campaign_count,
+            name,
+            start,
+            end,
+            budget,
+            filter,
+            funded: U128(0),
+            emitted: U128(0),
+            claimed: U128(0),
+            participants: 0,
+            total_weight: U128(0),
+            acc_reward_per_weight: U128(0),
+            last_update: start,
+        };
+        self.campaigns.insert(&campaign.id, &campaign);
+        campaign.id
+    }
+
+    // funding nobody earned goes back to being surplus the treasurer can sweep
+    pub fn close_campaign(&mut self, campaign_id: u64) -> U128 {
+        self.internal_assert_role(Role::Treasurer);
+        let mut campaign = self.internal_get_campaign(campaign_id);
+        require!(Self::now() >= campaign.end, StakeError::CampaignNotEnded);
+        campaign.update(Self::now());
+        let leftover = campaign.funded.0 - campaign.emitted.0;
+        campaign.funded = campaign.emitted;
+        self.total_campaign_reserve -= leftover;
+        self.campaigns.insert(&campaign_id, &campaign);
+        U128(leftover)
+    }
+
+    pub fn join_campaign(&mut self, campaign_id: u64) {
+        let account_id = env::predecessor_account_id();
+        let stake_info = self
+            .internal_get_stake_info(&account_id)
+            .unwrap_or_else(|| env::panic_str(&StakeError::NotStaked));
+        let mut campaign = self.internal_get_campaign(campaign_id);
+        require!(Self::now() < campaign.end, StakeError::CampaignEnded);
+        require!(
+            self.internal_campaign_eligible(&campaign, &stake_info),
+            StakeError::NotEligibleForCampaign
+        );
+        let key = (campaign_id, account_id.clone());
+        require!(
+            !self.campaign_stakes.contains_key(&key),
+            StakeError::AlreadyInCampaign
+        );
+        let mut campaign_ids = self.account_campaigns.get(&account_id).unwrap_or_default();
+        require!(
+            campaign_ids.len() < MAX_CAMPAIGNS_PER_ACCOUNT,
+            StakeError::TooManyCampaigns
+        );
+
+        campaign.update(Self::now());
+        campaign.total_weight.0 += stake_info.amount_staked;
+        campaign.participants += 1;
+        self.campaign_stakes.insert(
+            &key,
+            &CampaignStake {
+                weight: stake_info.amount_staked,
+                reward_per_weight_paid: campaign.acc_reward_per_weight.0,
+                reward: 0,
+            },
+        );
+        self.campaigns.insert(&campaign_id, &campaign);
+        campaign_ids.push(campaign_id);
+        self.account_campaigns.insert(&account_id, &campaign_ids);
+    }
+
+    // credits withdrawable like every other payout, an ended campaign is left on claim
+    pub fn claim_campaign(&mut self, campaign_id: u64) -> U128 {
+        let account_id = env::predecessor_account_id();
+        let key = (campaign_id, account_id.clone());
+        let mut stake = self
+            .campaign_stakes
+            .get(&key)
+            .unwrap_or_else(|| env::panic_str(&StakeError::NotInCampaign));
+        let mut campaign = self.internal_get_campaign(campaign_id);
+        campaign.update(Self::now());
+        campaign.settle(&mut stake);
+        let reward = stake.reward;
+        require!(reward > 0, StakeError::NoReward);
+
+        stake.reward = 0;
+        campaign.claimed.0 += reward;
+        self.total_campaign_reserve -= reward;
+        let mut stake_info = self
+            .internal_load_stake_info(&account_id)
+            .unwrap_or_else(|| env::panic_str(&StakeError::NotStaked));
+        self.internal_credit_withdrawable(&mut stake_info, reward);
+        self.internal_save_stake_info(&account_id, &stake_info);
+
+        if Self::now() >= campaign.end {
+            campaign.total_weight.0 -= stake.weight;
+            self.campaign_stakes.remove(&key);
+            let mut campaign_ids = self.account_campaigns.get(&account_id).unwrap_or_default();
+            campaign_ids.retain(|id| *id != campaign_id);
+            self.account_campaigns.insert(&account_id, &campaign_ids);
+        } else {
+            self.campaign_stakes.insert(&key, &stake);
+        }
+        self.campaigns.insert(&campaign_id, &campaign);
+        U128(reward)
+    }
+
+    // stats are brought forward to now without writing anything
+    pub fn get_campaign(&self, campaign_id: u64) -> Option<Campaign> {
+        self.campaigns.get(&campaign_id).map(|mut campaign| {
+            campaign.update(Self::now());
+            campaign
+        })
+    }
+
+    pub fn get_campaigns(&self, from_index: Option<U64>, limit: Option<U64>) -> Vec<Campaign> {
+        let from = from_index.map_or(1, |index| index.0.max(1));
+        let limit = limit.map_or(MAX_CAMPAIGN_PAGE, |limit| limit.0.min(MAX_CAMPAIGN_PAGE));
+        (from..=self.campaign_count)
+            .take(limit as usize)
+            .filter_map(|campaign_id| self.get_campaign(campaign_id))
+            .collect()
+    }
+
+    pub fn get_campaign_reward(&self, campaign_id: u64, account_id: AccountId) -> U128 {
+        let (mut campaign, mut stake) = match (
+            self.campaigns.get(&campaign_id),
+            self.campaign_stakes.get(&(campaign_id, account_id)),
+        ) {
+            (Some(campaign), Some(stake)) => (campaign, stake),
+            _ => return U128(0),
+        };
+        campaign.update(Self::now());
+        campaign.settle(&mut stake);
+        U128(stake.reward)
+    }
+}
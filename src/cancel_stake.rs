@@ -0,0 +1,105 @@
+use near_sdk::json_types::{U128, U64};
+use near_sdk::{env, near_bindgen, require, AccountId};
+
+use crate::events::{StakeEvent, UnstakedData};
+use crate::time::Duration;
+use crate::*;
+
+pub const DEFAULT_STAKE_CANCEL_WINDOW: Duration = Duration::from_secs(60 * 60);
+
+impl Contract {
+    // only a position that nothing but its deposits happened to yet can be taken back
+    fn internal_is_cancellable(&self, stake_info: &StakeInfo) -> bool {
+        Self::now() < stake_info.terms.opened_at + self.stake_cancel_window
+            && stake_info.amount_staked > 0
+            && stake_info.lifetime_unstaked == 0
+            && stake_info.delegated == 0
+            && stake_info.grouped == 0
+            && stake_info.votes_received == 0
+            && stake_info.last_claimed_at.is_none()
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // makes every deposit of a position opened within the cancel window withdrawable again,
+    // without the unstake fee. whatever accrued on it is dropped rather than credited
+    #[payable]
+    pub fn cancel_recent_stake(&mut self) -> U128 {
+        self.internal_assert_deposit("cancel_recent_stake");
+        let account_id = env::predecessor_account_id();
+        self.internal_migrate_legacy(&account_id);
+        let mut stake_info = self
+            .internal_load_stake_info(&account_id)
+            .unwrap_or_else(|| env::panic_str(&StakeError::NotStaked));
+        Self::internal_assert_no_pending_operation(&stake_info);
+        self.internal_assert_not_frozen(&account_id);
+        self.internal_assert_no_open_slash(&account_id);
+        require!(
+            self.internal_is_cancellable(&stake_info),
+            StakeError::StakeNotCancellable
+        );
+        let amount = stake_info.amount_staked;
+        self.internal_assert_stake_free(&account_id, &stake_info, amount);
+        self.internal_assert_balance_covers();
+
+        // the global estimate already counted the dropped reward as owed
+        let (accrued, skim, fee) = self.internal_accrual_at(&stake_info, Self::now());
+        let dropped = accrued + skim + fee + stake_info.reward + stake_info.deferred_reward;
+        self.internal_update_global_accrual();
+        self.accrued_rewards = self.accrued_rewards.saturating_sub(dropped);
+        self.internal_settle_marks(&mut stake_info);
+        stake_info.reward = 0;
+        stake_info.deferred_reward = 0;
+
+        self.internal_shrink_course_stakes(&account_id, 0);
+        self.internal_update_reward_rate(
+            amount,
+            stake_info.boosted(stake_info.apr),
+            0,
+            stake_info.boosted(stake_info.apr),
+        );
+        stake_info.amount_staked = 0;
+        stake_info.lifetime_unstaked += amount;
+        stake_info.sync_streak(Self::now());
+        self.internal_credit_withdrawable(&mut stake_info, amount);
+        self.internal_record_outflow(amount);
+
+        self.total_staked -= amount;
+        self.total_volume_unstaked += amount;
+        self.internal_record_activity(
+            &account_id,
+            Activity::Unstake {
+                amount: U128(amount),
+            },
+        );
+        self.internal_notify_hooks(HookEvent::Unstake, &account_id, amount);
+        StakeEvent::Unstaked(vec![UnstakedData {
+            account_id: account_id.clone(),
+            amount: U128(amount),
+            fee: U128(0),
+            receiver_id: account_id.clone(),
+        }])
+        .emit();
+
+        self.internal_save_stake_info(&account_id, &stake_info);
+        self.internal_process_queue(MAX_QUEUE_ACTIVATIONS);
+        self.internal_maybe_snapshot();
+        U128(amount)
+    }
+
+    pub fn set_stake_cancel_window(&mut self, stake_cancel_window: U64) {
+        self.internal_assert_role(Role::Admin);
+        self.internal_record_config_change(
+            "stake_cancel_window",
+            U64(self.stake_cancel_window.as_nanos()),
+            stake_cancel_window,
+        );
+        self.stake_cancel_window = Duration::from_nanos(stake_cancel_window.0);
+    }
+
+    pub fn can_cancel_stake(&self, account_id: AccountId) -> bool {
+        self.internal_get_stake_info(&account_id)
+            .is_some_and(|stake_info| self.internal_is_cancellable(&stake_info))
+    }
+}
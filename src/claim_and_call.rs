@@ -0,0 +1,53 @@
+use near_sdk::json_types::U128;
+use near_sdk::{env, near_bindgen, AccountId, Gas, Promise, PromiseError, ONE_YOCTO};
+
+use crate::*;
+
+pub const FT_TRANSFER_CALL_GAS: Gas = Gas(50_000_000_000_000);
+pub const CLAIM_AND_CALL_CALLBACK_GAS: Gas = Gas(10_000_000_000_000);
+
+#[near_bindgen]
+impl Contract {
+    // claims the reward and forwards everything withdrawable to `receiver_contract`,
+    // the receiver sees this contract as the sender so `msg` has to name the beneficiary
+    #[payable]
+    pub fn claim_and_call(&mut self, receiver_contract: AccountId, msg: String) -> Promise {
+        self.internal_assert_deposit("claim_and_call");
+        self.internal_assert_transfers_up();
+        Self::internal_assert_receiver(&receiver_contract);
+        let account_id = env::predecessor_account_id();
+        self.internal_claim_reward(&account_id, None);
+        let amount = self.internal_start_outflow(&account_id);
+        self.internal_maybe_snapshot();
+
+        ext_ft_contract::ext(self.token_address.clone())
+            .with_static_gas(FT_TRANSFER_CALL_GAS)
+            .with_attached_deposit(ONE_YOCTO)
+            .ft_transfer_call(receiver_contract, U128(amount), None, msg)
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(CLAIM_AND_CALL_CALLBACK_GAS)
+                    .on_claim_and_call(account_id, U128(amount)),
+            )
+    }
+
+    // whatever the receiver refunded goes back to withdrawable, returns the amount it kept
+    #[private]
+    pub fn on_claim_and_call(
+        &mut self,
+        account_id: AccountId,
+        amount: U128,
+        #[callback_result] result: Result<U128, PromiseError>,
+    ) -> U128 {
+        self.internal_note_promise_result(
+            "on_claim_and_call",
+            Some(&account_id),
+            amount.0,
+            result.is_ok(),
+        );
+        let used = result.map_or(0, |used| used.0.min(amount.0));
+        self.internal_note_outflow(used);
+        self.internal_finish_outflow(&account_id, amount.0, amount.0 - used);
+        U128(used)
+    }
+}
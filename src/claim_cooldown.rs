@@ -0,0 +1,29 @@
+use near_sdk::json_types::U64;
+use near_sdk::{near_bindgen, AccountId};
+
+use crate::time::{Duration, Timestamp};
+use crate::*;
+
+impl Contract {
+    pub(crate) fn internal_next_claim_at(&self, stake_info: &StakeInfo) -> Timestamp {
+        match stake_info.last_claimed_at {
+            Some(last_claimed_at) => last_claimed_at + self.claim_cooldown,
+            None => Timestamp::default(),
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn set_claim_cooldown(&mut self, claim_cooldown: U64) {
+        self.internal_assert_role(Role::Admin);
+        self.claim_cooldown = Duration::from_nanos(claim_cooldown.0);
+    }
+
+    // zero when the account may claim right away
+    pub fn next_claim_at(&self, account_id: AccountId) -> Timestamp {
+        self.internal_get_stake_info(&account_id)
+            .map(|stake_info| self.internal_next_claim_at(&stake_info))
+            .unwrap_or_default()
+    }
+}
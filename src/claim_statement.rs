@@ -0,0 +1,81 @@
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::Serialize;
+use near_sdk::{near_bindgen, AccountId};
+
+use crate::time::Timestamp;
+use crate::*;
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct LockStatus {
+    pub state: PositionState,
+    pub pending_operation: bool,
+    pub open_slash: Option<U64>,
+    pub next_claim_at: Timestamp,
+    pub vesting_locked: U128,
+    pub vesting_claimable: U128,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ClaimStatement {
+    pub account_id: AccountId,
+    pub principal: U128,
+    // settled reward plus everything accrued since the last touch, after the insurance skim
+    pub unclaimed_reward: U128,
+    pub lifetime_claimed: U128,
+    pub withdrawable: U128,
+    pub apr: U128,
+    // apr rewards accrue at, booster and oracle modifier applied
+    pub effective_apr: U128,
+    pub boost_bps: u32,
+    pub apr_modifier_bps: u32,
+    pub apr_index: U128,
+    pub apr_index_paid: U128,
+    pub bonus_per_share: U128,
+    pub bonus_per_share_paid: U128,
+    pub lock: LockStatus,
+    pub as_of: Timestamp,
+}
+
+#[near_bindgen]
+impl Contract {
+    #[handle_result]
+    pub fn get_claim_statement(&self, account_id: AccountId) -> Result<ClaimStatement, StakeError> {
+        let stake_info = self
+            .internal_get_stake_info(&account_id)
+            .ok_or(StakeError::NotStaked)?;
+        let now = Self::now();
+        let vesting = self.get_vesting(account_id.clone());
+        Ok(ClaimStatement {
+            principal: U128(stake_info.amount_staked),
+            unclaimed_reward: U128(self.internal_pending_reward_at(&stake_info, now)),
+            lifetime_claimed: U128(stake_info.lifetime_rewards_claimed),
+            withdrawable: U128(stake_info.withdrawable),
+            apr: U128(stake_info.apr),
+            effective_apr: U128(
+                self.internal_apply_apr_modifier(stake_info.boosted(stake_info.apr)),
+            ),
+            boost_bps: stake_info
+                .terms
+                .booster
+                .as_ref()
+                .map_or(0, |booster| booster.boost_bps),
+            apr_modifier_bps: self.apr_modifier_bps,
+            apr_index: U128(self.internal_apr_index_at(now)),
+            apr_index_paid: U128(stake_info.apr_index_paid),
+            bonus_per_share: U128(self.bonus_per_share),
+            bonus_per_share_paid: U128(stake_info.bonus_per_share_paid),
+            lock: LockStatus {
+                state: self.internal_position_state(&stake_info),
+                pending_operation: stake_info.pending_operation,
+                open_slash: self.open_slashes.get(&account_id).map(U64),
+                next_claim_at: self.internal_next_claim_at(&stake_info),
+                vesting_locked: vesting.locked,
+                vesting_claimable: vesting.claimable,
+            },
+            as_of: now,
+            account_id,
+        })
+    }
+}
@@ -0,0 +1,133 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::Serialize;
+use near_sdk::{env, near_bindgen, require, AccountId};
+
+use crate::*;
+
+// what a whitelisted contract, e.g. a lending market, may hold of one account's stake.
+// the stake keeps earning, it just cannot leave the position while liened
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CollateralLien {
+    pub limit: U128,
+    pub locked: U128,
+}
+
+impl Contract {
+    pub(crate) fn internal_collateral_locked(&self, account_id: &AccountId) -> u128 {
+        self.collateral_locked.get(account_id).unwrap_or(0)
+    }
+
+    fn internal_set_collateral_locked(&mut self, account_id: &AccountId, locked: u128) {
+        if locked == 0 {
+            self.collateral_locked.remove(account_id);
+        } else {
+            self.collateral_locked.insert(account_id, &locked);
+        }
+    }
+
+    fn internal_assert_collateral_locker(&self, locker_id: &AccountId) {
+        require!(
+            self.collateral_lockers.contains(locker_id),
+            StakeError::NotCollateralLocker
+        );
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // the staker lets `locker_id` lien up to `limit`, a limit below what it already holds
+    // is refused. zero revokes once everything is released
+    #[payable]
+    pub fn approve_collateral_locker(&mut self, locker_id: AccountId, limit: U128) {
+        self.internal_assert_deposit("approve_collateral_locker");
+        self.internal_assert_collateral_locker(&locker_id);
+        let key = (env::predecessor_account_id(), locker_id);
+        let mut lien = self.collateral_liens.get(&key).unwrap_or(CollateralLien {
+            limit: U128(0),
+            locked: U128(0),
+        });
+        require!(limit.0 >= lien.locked.0, StakeError::LienLimitBelowLocked);
+        if limit.0 == 0 {
+            self.collateral_liens.remove(&key);
+            return;
+        }
+        lien.limit = limit;
+        self.collateral_liens.insert(&key, &lien);
+    }
+
+    pub fn lock_collateral(&mut self, account_id: AccountId, amount: U128) {
+        let locker_id = env::predecessor_account_id();
+        self.internal_assert_collateral_locker(&locker_id);
+        require!(amount.0 > 0, StakeError::InvalidAmount);
+        let key = (account_id.clone(), locker_id);
+        let mut lien = self
+            .collateral_liens
+            .get(&key)
+            .unwrap_or_else(|| env::panic_str(&StakeError::LockerNotApproved));
+        require!(
+            lien.locked.0 + amount.0 <= lien.limit.0,
+            StakeError::LienLimitExceeded
+        );
+        let stake_info = self
+            .internal_get_stake_info(&account_id)
+            .unwrap_or_else(|| env::panic_str(&StakeError::NotStaked));
+        let locked = self.internal_collateral_locked(&account_id);
+        require!(
+            stake_info.amount_staked
+                >= self.internal_session_locked(&account_id) + locked + amount.0,
+            StakeError::InsufficientStake
+        );
+        lien.locked.0 += amount.0;
+        self.collateral_liens.insert(&key, &lien);
+        self.internal_set_collateral_locked(&account_id, locked + amount.0);
+    }
+
+    // a locker may always release, even after it left the whitelist
+    pub fn release_collateral(&mut self, account_id: AccountId, amount: U128) {
+        let key = (account_id.clone(), env::predecessor_account_id());
+        let mut lien = self
+            .collateral_liens
+            .get(&key)
+            .unwrap_or_else(|| env::panic_str(&StakeError::LockerNotApproved));
+        require!(
+            amount.0 > 0 && amount.0 <= lien.locked.0,
+            StakeError::InvalidAmount
+        );
+        lien.locked.0 -= amount.0;
+        self.collateral_liens.insert(&key, &lien);
+        let locked = self.internal_collateral_locked(&account_id) - amount.0;
+        self.internal_set_collateral_locked(&account_id, locked);
+    }
+
+    pub fn add_collateral_lockers(&mut self, locker_ids: Vec<AccountId>) {
+        self.internal_assert_owner();
+        for locker_id in locker_ids {
+            self.collateral_lockers.insert(&locker_id);
+        }
+    }
+
+    pub fn remove_collateral_lockers(&mut self, locker_ids: Vec<AccountId>) {
+        self.internal_assert_owner();
+        for locker_id in locker_ids {
+            self.collateral_lockers.remove(&locker_id);
+        }
+    }
+
+    pub fn is_collateral_locker(&self, locker_id: AccountId) -> bool {
+        self.collateral_lockers.contains(&locker_id)
+    }
+
+    pub fn get_collateral_lien(
+        &self,
+        account_id: AccountId,
+        locker_id: AccountId,
+    ) -> Option<CollateralLien> {
+        self.collateral_liens.get(&(account_id, locker_id))
+    }
+
+    pub fn get_collateral_locked(&self, account_id: AccountId) -> U128 {
+        U128(self.internal_collateral_locked(&account_id))
+    }
+}
@@ -0,0 +1,107 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::Serialize;
+use near_sdk::{env, near_bindgen, require, AccountId};
+
+use crate::events::{CommissionChangedData, CommissionClaimedData, StakeEvent};
+use crate::time::{Duration, Timestamp};
+use crate::*;
+
+// at least this long, and never shorter than the unbond period, so delegators can be
+// out before a raise applies to them
+pub const COMMISSION_CHANGE_DELAY: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PendingCommission {
+    pub commission_bps: u16,
+    pub effective_at: Timestamp,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CommissionView {
+    pub commission_bps: u16,
+    pub pending_commission: Option<PendingCommission>,
+    pub unclaimed: U128,
+    pub claimed: U128,
+}
+
+impl Contract {
+    // the pool accrues at the old rate up to the first update once the delay passed
+    pub(crate) fn internal_apply_pending_commission(pool: &mut DelegationPool, now: Timestamp) {
+        if let Some(pending) = pool.pending_commission.clone() {
+            if now >= pending.effective_at {
+                pool.commission_bps = pending.commission_bps;
+                pool.pending_commission = None;
+            }
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // a cut applies right away, a raise only after the delay. a new call replaces
+    // whatever was scheduled
+    pub fn set_commission(&mut self, commission_bps: u16) -> Option<PendingCommission> {
+        let lead_id = env::predecessor_account_id();
+        require!(
+            commission_bps <= MAX_COMMISSION_BPS,
+            StakeError::CommissionTooHigh
+        );
+        let mut pool = self.internal_get_delegation_pool(&lead_id);
+        let now = Self::now();
+        self.internal_update_delegation_pool(&mut pool, now);
+        let old_commission_bps = pool.commission_bps;
+        let pending = if commission_bps <= pool.commission_bps {
+            pool.commission_bps = commission_bps;
+            None
+        } else {
+            Some(PendingCommission {
+                commission_bps,
+                effective_at: now + COMMISSION_CHANGE_DELAY.max(self.unbond_period),
+            })
+        };
+        pool.pending_commission = pending.clone();
+        self.delegation_pools.insert(&lead_id, &pool);
+        StakeEvent::CommissionChanged(vec![CommissionChangedData {
+            lead_id,
+            old_commission_bps,
+            new_commission_bps: commission_bps,
+            effective_at: pending.as_ref().map_or(now, |pending| pending.effective_at),
+        }])
+        .emit();
+        pending
+    }
+
+    // paid out of the reward pool like any claimed reward
+    #[payable]
+    pub fn claim_commission(&mut self) -> U128 {
+        self.internal_assert_deposit("claim_commission");
+        let lead_id = env::predecessor_account_id();
+        let mut pool = self.internal_get_delegation_pool(&lead_id);
+        self.internal_update_delegation_pool(&mut pool, Self::now());
+        let commission = pool.commission.0;
+        require!(commission > 0, StakeError::NoReward);
+        pool.commission = U128(0);
+        pool.commission_claimed.0 += commission;
+        self.delegation_pools.insert(&lead_id, &pool);
+        self.internal_pay_delegation_reward(&lead_id, commission);
+        StakeEvent::CommissionClaimed(vec![CommissionClaimedData {
+            lead_id,
+            amount: U128(commission),
+        }])
+        .emit();
+        U128(commission)
+    }
+
+    pub fn get_commission(&self, lead_id: AccountId) -> Option<CommissionView> {
+        let pool = self.get_delegation_pool(lead_id)?;
+        Some(CommissionView {
+            commission_bps: pool.commission_bps,
+            pending_commission: pool.pending_commission,
+            unclaimed: pool.commission,
+            claimed: pool.commission_claimed,
+        })
+    }
+}
@@ -0,0 +1,54 @@
+use near_sdk::json_types::U64;
+use near_sdk::{env, near_bindgen, require};
+
+use crate::math::{mul_div, Rate, BPS_DENOMINATOR, RATE_PRECISION};
+use crate::time::{Duration, NANOS_PER_YEAR};
+use crate::*;
+
+pub const DEFAULT_COMPOUND_PERIOD: Duration = Duration::from_secs(24 * 60 * 60);
+
+impl Contract {
+    // reward on stake plus unclaimed reward compounded once per full period of
+    // `index_growth`, the part of a period left over accrues simple interest
+    pub(crate) fn internal_compounded_reward(
+        &self,
+        stake_info: &StakeInfo,
+        index_growth: u128,
+    ) -> u128 {
+        let elapsed = index_growth / BPS_DENOMINATOR;
+        let period = self.compound_period.as_nanos() as u128;
+        let periods = (elapsed / period).min(u32::MAX as u128) as u32;
+        let remainder = elapsed - periods as u128 * period;
+
+        let apr = Rate::from_raw(stake_info.boosted(stake_info.apr));
+        let growth = apr.growth(self.compound_period, periods);
+        let base = stake_info.amount_staked + stake_info.reward;
+        let grown = mul_div(base, growth, RATE_PRECISION);
+        let tail = mul_div(apr.annual_reward(grown), remainder, NANOS_PER_YEAR as u128);
+        grown + tail - base
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // settles the position with the current mode before switching
+    pub fn set_compounding(&mut self, enabled: bool) {
+        let account_id = env::predecessor_account_id();
+        let mut stake_info = self
+            .internal_touch(&account_id)
+            .unwrap_or_else(|| env::panic_str(&StakeError::NotStaked));
+        stake_info.terms.compounding = enabled;
+        self.internal_save_stake_info(&account_id, &stake_info);
+    }
+
+    pub fn set_compound_period(&mut self, compound_period: U64) {
+        self.internal_assert_role(Role::Admin);
+        require!(compound_period.0 > 0, StakeError::InvalidCompoundPeriod);
+        self.compound_period = Duration::from_nanos(compound_period.0);
+    }
+
+    pub fn is_compounding(&self, account_id: AccountId) -> bool {
+        self.internal_get_stake_info(&account_id)
+            .is_some_and(|stake_info| stake_info.terms.compounding)
+    }
+}
@@ -0,0 +1,51 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::Serialize;
+use near_sdk::{env, near_bindgen, AccountId};
+
+use crate::time::Timestamp;
+use crate::*;
+
+pub const MAX_CONFIG_HISTORY: u64 = 100;
+
+// old and new are the json of the value, so one log holds every kind of setting
+#[derive(BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ConfigChange {
+    pub field: String,
+    pub old: String,
+    pub new: String,
+    pub by: AccountId,
+    pub at: Timestamp,
+}
+
+impl Contract {
+    // a setter called with the value already in place records nothing
+    pub(crate) fn internal_record_config_change<T: Serialize>(
+        &mut self,
+        field: &str,
+        old: T,
+        new: T,
+    ) {
+        let old = near_sdk::serde_json::to_string(&old).unwrap();
+        let new = near_sdk::serde_json::to_string(&new).unwrap();
+        if old == new {
+            return;
+        }
+        self.config_history.push(&ConfigChange {
+            field: field.to_string(),
+            old,
+            new,
+            by: env::predecessor_account_id(),
+            at: Self::now(),
+        });
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // oldest first, `from` is an offset into the kept entries
+    pub fn get_config_history(&self, from: Option<u64>, limit: Option<u64>) -> Vec<ConfigChange> {
+        self.config_history
+            .page(from.unwrap_or(0), limit.unwrap_or(DEFAULT_PAGE_LIMIT))
+    }
+}
@@ -0,0 +1,215 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{Base58CryptoHash, U128, U64};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, require, AccountId, PromiseOrValue};
+
+use crate::time::{Duration, Timestamp};
+use crate::*;
+
+pub const DEFAULT_ACTION_TTL: Duration = Duration::from_secs(3 * 24 * 60 * 60);
+pub const MAX_ACTION_ADMINS: usize = 10;
+// long enough for admins in other time zones to get to a proposal
+pub const MIN_ACTION_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+// actions that move or confiscate funds, once an admin set is configured they only
+// run after `confirmation_threshold` of its members confirmed them
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub enum AdminAction {
+    Slash {
+        advisor: AccountId,
+        amount: U128,
+        evidence_uri: String,
+    },
+    Sweep {
+        token_id: AccountId,
+        amount: U128,
+        receiver_id: AccountId,
+    },
+    StageUpgrade {
+        code_hash: Base58CryptoHash,
+    },
+    SetAdmins {
+        admins: Vec<AccountId>,
+        threshold: u32,
+    },
+    AdjustRewards {
+        adjustments: Vec<RewardAdjustment>,
+    },
+    RestoreAbandoned {
+        entry_id: u64,
+    },
+    // the arbiter decides contested slashes, so picking it is as sensitive as a slash
+    SetArbiter {
+        arbiter_id: Option<AccountId>,
+    },
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PendingAction {
+    pub id: u64,
+    pub action: AdminAction,
+    pub confirmations: Vec<AccountId>,
+    pub proposed_at: Timestamp,
+    pub expires_at: Timestamp,
+}
+
+impl Contract {
+    // the single key flow only stays open until an admin set exists
+    pub(crate) fn internal_assert_no_confirmations(&self) {
+        require!(
+            self.action_admins.is_empty(),
+            StakeError::ConfirmationRequired
+        );
+    }
+
+    fn internal_assert_action_admin(&self) -> AccountId {
+        let account_id = env::predecessor_account_id();
+        require!(
+            self.action_admins.contains(&account_id),
+            StakeError::NotActionAdmin
+        );
+        account_id
+    }
+
+    // a repeated admin could never confirm twice, so it would only make the threshold
+    // unreachable
+    fn internal_set_action_admins(&mut self, admins: Vec<AccountId>, threshold: u32) {
+        let unique = admins
+            .iter()
+            .enumerate()
+            .all(|(index, admin)| !admins[..index].contains(admin));
+        require!(
+            unique
+                && admins.len() <= MAX_ACTION_ADMINS
+                && threshold as usize <= admins.len()
+                && (threshold > 0 || admins.is_empty()),
+            StakeError::InvalidAdminSet
+        );
+        self.action_admins = admins;
+        self.confirmation_threshold = threshold;
+    }
+
+    fn internal_execute_action(&mut self, action: AdminAction) -> PromiseOrValue<bool> {
+        match action {
+            AdminAction::Slash {
+                advisor,
+                amount,
+                evidence_uri,
+            } => {
+                self.internal_propose_slash(advisor, amount, evidence_uri);
+            }
+            AdminAction::Sweep {
+                token_id,
+                amount,
+                receiver_id,
+            } => {
+                return PromiseOrValue::Promise(self.internal_sweep(token_id, amount, receiver_id));
+            }
+            AdminAction::StageUpgrade { code_hash } => {
+                self.internal_stage_upgrade(code_hash);
+            }
+            AdminAction::SetAdmins { admins, threshold } => {
+                self.internal_set_action_admins(admins, threshold)
+            }
+            AdminAction::AdjustRewards { adjustments } => self.internal_adjust_rewards(adjustments),
+            AdminAction::RestoreAbandoned { entry_id } => self.internal_restore_abandoned(entry_id),
+            AdminAction::SetArbiter { arbiter_id } => self.arbiter_id = arbiter_id,
+        }
+        PromiseOrValue::Value(true)
+    }
+
+    fn internal_new_pending_action(
+        &mut self,
+        action: AdminAction,
+        confirmations: Vec<AccountId>,
+    ) -> PendingAction {
+        self.action_count += 1;
+        let now = Self::now();
+        PendingAction {
+            id: self.action_count,
+            action,
+            confirmations,
+            proposed_at: now,
+            expires_at: now + self.action_ttl,
+        }
+    }
+
+    // for actions the contract raises on its own, e.g. the slash of a failed session,
+    // every admin confirmation is still outstanding
+    pub(crate) fn internal_queue_action(&mut self, action: AdminAction) -> u64 {
+        let pending = self.internal_new_pending_action(action, vec![]);
+        self.pending_actions.insert(&pending.id, &pending);
+        pending.id
+    }
+
+    // runs the action once enough members confirmed, false while it still waits
+    fn internal_confirm(&mut self, pending: PendingAction) -> PromiseOrValue<bool> {
+        if (pending.confirmations.len() as u32) < self.confirmation_threshold {
+            self.pending_actions.insert(&pending.id, &pending);
+            return PromiseOrValue::Value(false);
+        }
+        self.pending_actions.remove(&pending.id);
+        self.internal_execute_action(pending.action)
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // the proposer's own confirmation is counted right away
+    #[payable]
+    pub fn propose_action(&mut self, action: AdminAction) -> U64 {
+        self.internal_assert_deposit("propose_action");
+        let account_id = self.internal_assert_action_admin();
+        let pending = self.internal_new_pending_action(action, vec![account_id]);
+        let action_id = pending.id;
+        // with a threshold of one the action runs straight from the proposal
+        let _ = self.internal_confirm(pending);
+        U64(action_id)
+    }
+
+    #[payable]
+    pub fn confirm_action(&mut self, action_id: U64) -> PromiseOrValue<bool> {
+        self.internal_assert_deposit("confirm_action");
+        let account_id = self.internal_assert_action_admin();
+        let mut pending = self
+            .pending_actions
+            .get(&action_id.0)
+            .unwrap_or_else(|| env::panic_str(&StakeError::UnknownAction));
+        // an expired action is dropped instead of confirmed
+        if Self::now() >= pending.expires_at {
+            self.pending_actions.remove(&action_id.0);
+            return PromiseOrValue::Value(false);
+        }
+        require!(
+            !pending.confirmations.contains(&account_id),
+            StakeError::AlreadyConfirmed
+        );
+        pending.confirmations.push(account_id);
+        self.internal_confirm(pending)
+    }
+
+    // the owner bootstraps the set once, changes after that go through `SetAdmins`
+    pub fn set_action_admins(&mut self, admins: Vec<AccountId>, threshold: u32) {
+        self.internal_assert_owner();
+        self.internal_assert_no_confirmations();
+        self.internal_set_action_admins(admins, threshold);
+    }
+
+    pub fn set_action_ttl(&mut self, action_ttl: U64) {
+        self.internal_assert_role(Role::Admin);
+        let action_ttl = Duration::from_nanos(action_ttl.0);
+        require!(action_ttl >= MIN_ACTION_TTL, StakeError::InvalidActionTtl);
+        self.internal_record_config_change("action_ttl", self.action_ttl, action_ttl);
+        self.action_ttl = action_ttl;
+    }
+
+    pub fn get_pending_action(&self, action_id: U64) -> Option<PendingAction> {
+        self.pending_actions.get(&action_id.0)
+    }
+
+    pub fn get_action_admins(&self) -> (Vec<AccountId>, u32) {
+        (self.action_admins.clone(), self.confirmation_threshold)
+    }
+}
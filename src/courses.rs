@@ -0,0 +1,156 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::Serialize;
+use near_sdk::{env, near_bindgen, require, AccountId};
+
+use crate::*;
+
+pub const MAX_COURSES_PER_ADVISOR: usize = 20;
+pub const MAX_COURSE_ID_LEN: usize = 64;
+
+// part of amount_staked bonded to one course, the rest of the position is unassigned
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CourseStake {
+    pub course_id: String,
+    pub amount_staked: U128,
+    pub rating_sum: u64,
+    pub rating_count: u64,
+}
+
+impl Contract {
+    pub(crate) fn internal_check_course_stake(
+        &self,
+        account_id: &AccountId,
+        course_id: &str,
+    ) -> Result<(), StakeError> {
+        if course_id.is_empty() || course_id.len() > MAX_COURSE_ID_LEN {
+            return Err(StakeError::InvalidCourseId);
+        }
+        let courses = self.course_stakes.get(account_id).unwrap_or_default();
+        if courses.len() >= MAX_COURSES_PER_ADVISOR
+            && !courses.iter().any(|course| course.course_id == course_id)
+        {
+            return Err(StakeError::TooManyCourses);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn internal_add_course_stake(
+        &mut self,
+        account_id: &AccountId,
+        course_id: String,
+        amount: u128,
+    ) {
+        if let Err(error) = self.internal_check_course_stake(account_id, &course_id) {
+            env::panic_str(&error);
+        }
+        let mut courses = self.course_stakes.get(account_id).unwrap_or_default();
+        match courses
+            .iter_mut()
+            .find(|course| course.course_id == course_id)
+        {
+            Some(course) => course.amount_staked = U128(course.amount_staked.0 + amount),
+            None => {
+                courses.push(CourseStake {
+                    course_id,
+                    amount_staked: U128(amount),
+                    rating_sum: 0,
+                    rating_count: 0,
+                });
+            }
+        }
+        self.course_stakes.insert(account_id, &courses);
+    }
+
+    // unstakes without a course only come out of the unassigned part
+    pub(crate) fn internal_remove_course_stake(
+        &mut self,
+        account_id: &AccountId,
+        stake_info: &StakeInfo,
+        course_id: Option<String>,
+        amount: u128,
+    ) {
+        let mut courses = self.course_stakes.get(account_id).unwrap_or_default();
+        let course_id = match course_id {
+            Some(course_id) => course_id,
+            None => {
+                let assigned: u128 = courses.iter().map(|course| course.amount_staked.0).sum();
+                require!(
+                    stake_info.amount_staked - assigned >= amount,
+                    StakeError::StakeBondedToCourses
+                );
+                return;
+            }
+        };
+        let course = courses
+            .iter_mut()
+            .find(|course| course.course_id == course_id)
+            .unwrap_or_else(|| env::panic_str(&StakeError::UnknownCourse));
+        require!(
+            course.amount_staked.0 >= amount,
+            StakeError::InsufficientStake
+        );
+        course.amount_staked = U128(course.amount_staked.0 - amount);
+        courses.retain(|course| course.amount_staked.0 > 0 || course.rating_count > 0);
+        if courses.is_empty() {
+            self.course_stakes.remove(account_id);
+        } else {
+            self.course_stakes.insert(account_id, &courses);
+        }
+    }
+
+    // takes a stake reduction out of the unassigned part first, then course by course
+    pub(crate) fn internal_shrink_course_stakes(
+        &mut self,
+        account_id: &AccountId,
+        amount_staked: u128,
+    ) {
+        let mut courses = match self.course_stakes.get(account_id) {
+            Some(courses) => courses,
+            None => return,
+        };
+        let assigned: u128 = courses.iter().map(|course| course.amount_staked.0).sum();
+        let mut excess = assigned.saturating_sub(amount_staked);
+        for course in courses.iter_mut() {
+            let cut = excess.min(course.amount_staked.0);
+            course.amount_staked = U128(course.amount_staked.0 - cut);
+            excess -= cut;
+        }
+        self.course_stakes.insert(account_id, &courses);
+    }
+
+    pub(crate) fn internal_record_course_vote(
+        &mut self,
+        advisor_id: &AccountId,
+        course_id: String,
+        rating: u8,
+    ) {
+        let mut courses = self.course_stakes.get(advisor_id).unwrap_or_default();
+        let course = courses
+            .iter_mut()
+            .find(|course| course.course_id == course_id)
+            .unwrap_or_else(|| env::panic_str(&StakeError::UnknownCourse));
+        course.rating_sum += rating as u64;
+        course.rating_count += 1;
+        self.course_stakes.insert(advisor_id, &courses);
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn get_course_stakes(&self, account_id: AccountId) -> Vec<CourseStake> {
+        self.course_stakes.get(&account_id).unwrap_or_default()
+    }
+
+    pub fn get_course_stake(
+        &self,
+        account_id: AccountId,
+        course_id: String,
+    ) -> Option<CourseStake> {
+        self.course_stakes
+            .get(&account_id)?
+            .into_iter()
+            .find(|course| course.course_id == course_id)
+    }
+}
@@ -0,0 +1,131 @@
+use near_contract_standards::storage_management::StorageBalance;
+use near_sdk::{env, near_bindgen, require, AccountId, Gas, Promise, PromiseError};
+
+use crate::events::{StakeEvent, TransferHealthData};
+use crate::time::Timestamp;
+use crate::*;
+
+pub const DEFAULT_TRANSFER_FAILURE_THRESHOLD: u32 = 3;
+pub const STORAGE_CHECK_GAS: Gas = Gas(5_000_000_000_000);
+pub const STORAGE_CHECK_CALLBACK_GAS: Gas = Gas(5_000_000_000_000);
+
+impl Contract {
+    // called by the callbacks of plain ft_transfers of the staked token. after
+    // `transfer_failure_threshold` failures in a row new transfers stop, withdrawals wait
+    // in the outbox. any successful transfer lifts it again
+    pub(crate) fn internal_note_token_transfer(&mut self, receiver_id: &AccountId, success: bool) {
+        if success {
+            self.consecutive_transfer_failures = 0;
+            if self.transfers_degraded_since.take().is_some() {
+                StakeEvent::TransfersResumed(vec![TransferHealthData {
+                    consecutive_failures: 0,
+                }])
+                .emit();
+            }
+            return;
+        }
+        // a receiver without storage on the token fails the transfer too, that is only
+        // counted once the token confirmed the receiver is registered
+        let left = env::prepaid_gas().0.saturating_sub(env::used_gas().0);
+        if left < STORAGE_CHECK_GAS.0 + STORAGE_CHECK_CALLBACK_GAS.0 {
+            return;
+        }
+        ext_ft_contract::ext(self.token_address.clone())
+            .with_static_gas(STORAGE_CHECK_GAS)
+            .storage_balance_of(receiver_id.clone())
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(STORAGE_CHECK_CALLBACK_GAS)
+                    .with_unused_gas_weight(0)
+                    .on_transfer_failure_checked(receiver_id.clone()),
+            );
+    }
+
+    fn internal_count_transfer_failure(&mut self) {
+        self.consecutive_transfer_failures += 1;
+        if self.transfers_degraded_since.is_none()
+            && self.consecutive_transfer_failures >= self.transfer_failure_threshold
+        {
+            self.transfers_degraded_since = Some(Self::now());
+            StakeEvent::TransfersDegraded(vec![TransferHealthData {
+                consecutive_failures: self.consecutive_transfer_failures,
+            }])
+            .emit();
+        }
+    }
+
+    pub(crate) fn internal_transfers_degraded(&self) -> bool {
+        self.transfers_degraded_since.is_some()
+    }
+
+    // for the paths that transfer directly instead of through the outbox
+    pub(crate) fn internal_assert_transfers_up(&self) {
+        require!(
+            !self.internal_transfers_degraded(),
+            StakeError::TransfersDegraded
+        );
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // a token that can't answer the check counts against itself
+    #[private]
+    pub fn on_transfer_failure_checked(
+        &mut self,
+        receiver_id: AccountId,
+        #[callback_result] result: Result<Option<StorageBalance>, PromiseError>,
+    ) {
+        if let Ok(None) = result {
+            env::log_str(&format!(
+                "transfer to unregistered {} not counted",
+                receiver_id
+            ));
+            return;
+        }
+        self.internal_count_transfer_failure();
+    }
+
+    pub fn set_transfer_failure_threshold(&mut self, threshold: u32) {
+        self.internal_assert_role(Role::Admin);
+        require!(threshold > 0, StakeError::InvalidFailureThreshold);
+        self.internal_record_config_change(
+            "transfer_failure_threshold",
+            self.transfer_failure_threshold,
+            threshold,
+        );
+        self.transfer_failure_threshold = threshold;
+    }
+
+    // sends the oldest outbox transfer while degraded, its success ends degraded mode.
+    // skip_head moves the oldest one to the back first, for a head that keeps failing
+    // on its own receiver
+    pub fn probe_transfers(&mut self, skip_head: Option<bool>) -> Promise {
+        self.internal_assert_role(Role::Pauser);
+        require!(self.internal_transfers_degraded(), StakeError::NotDegraded);
+        if skip_head.unwrap_or(false) && self.outbox_tail - self.outbox_head > 1 {
+            let head = self.internal_pop_outbox().unwrap();
+            self.internal_enqueue_transfer(head);
+        }
+        let transfer = self
+            .internal_pop_outbox()
+            .unwrap_or_else(|| env::panic_str(&StakeError::NothingToProbe));
+        self.internal_issue_transfer(transfer)
+    }
+
+    // for a token that is known to be back, queued withdrawals go out with the next flush
+    pub fn reset_transfers_degraded(&mut self) {
+        self.internal_assert_role(Role::Pauser);
+        self.consecutive_transfer_failures = 0;
+        if self.transfers_degraded_since.take().is_some() {
+            StakeEvent::TransfersResumed(vec![TransferHealthData {
+                consecutive_failures: 0,
+            }])
+            .emit();
+        }
+    }
+
+    pub fn get_transfers_degraded_since(&self) -> Option<Timestamp> {
+        self.transfers_degraded_since
+    }
+}
@@ -0,0 +1,90 @@
+use ed25519_dalek::{PublicKey as Ed25519PublicKey, Signature, Verifier};
+use near_sdk::borsh::{self, BorshSerialize};
+use near_sdk::json_types::{Base64VecU8, U128};
+use near_sdk::{env, near_bindgen, require, AccountId, CurveType, PublicKey};
+
+use crate::*;
+
+// the payload an account signs with its registered key, borsh encoded
+#[derive(BorshSerialize)]
+pub struct ClaimPayload {
+    pub contract_id: AccountId,
+    pub account_id: AccountId,
+    pub amount: u128,
+    pub nonce: u64,
+}
+
+pub(crate) fn verify_ed25519(public_key: &PublicKey, message: &[u8], signature: &[u8]) -> bool {
+    let public_key = match Ed25519PublicKey::from_bytes(&public_key.as_bytes()[1..]) {
+        Ok(public_key) => public_key,
+        Err(_) => return false,
+    };
+    match Signature::from_bytes(signature) {
+        Ok(signature) => public_key.verify(message, &signature).is_ok(),
+        Err(_) => false,
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    #[payable]
+    pub fn set_claim_key(&mut self, public_key: Option<PublicKey>) {
+        self.internal_assert_deposit("set_claim_key");
+        let account_id = env::predecessor_account_id();
+        match public_key {
+            Some(public_key) => {
+                require!(
+                    public_key.curve_type() == CurveType::ED25519,
+                    StakeError::UnsupportedKeyType
+                );
+                self.claim_keys.insert(&account_id, &public_key);
+            }
+            None => {
+                self.claim_keys.remove(&account_id);
+            }
+        }
+    }
+
+    // anyone can relay a claim the account signed, nonces have to increase
+    pub fn claim_with_signature(
+        &mut self,
+        account_id: AccountId,
+        amount: U128,
+        nonce: u64,
+        signature: Base64VecU8,
+    ) -> U128 {
+        let public_key = self
+            .claim_keys
+            .get(&account_id)
+            .unwrap_or_else(|| env::panic_str(&StakeError::NoClaimKey));
+        require!(
+            nonce > self.claim_nonces.get(&account_id).unwrap_or(0),
+            StakeError::NonceUsed
+        );
+        let payload = ClaimPayload {
+            contract_id: env::current_account_id(),
+            account_id: account_id.clone(),
+            amount: amount.0,
+            nonce,
+        };
+        require!(
+            verify_ed25519(&public_key, &payload.try_to_vec().unwrap(), &signature.0),
+            StakeError::InvalidSignature
+        );
+        self.claim_nonces.insert(&account_id, &nonce);
+
+        let claimed = self.internal_claim_reward(&account_id, Some(amount.0));
+        self.internal_maybe_snapshot();
+        U128(claimed)
+    }
+
+    pub fn get_claim_key(&self, account_id: AccountId) -> Option<PublicKey> {
+        self.claim_keys.get(&account_id)
+    }
+
+    // api version 1
+    pub fn get_claim_nonce(&self, account_id: AccountId) -> u64 {
+        Self::internal_log_deprecated("get_claim_nonce", "v2_get_claim_nonce");
+        self.claim_nonces.get(&account_id).unwrap_or(0)
+    }
+}
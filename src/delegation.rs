@@ -0,0 +1,489 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::Serialize;
+use near_sdk::{env, near_bindgen, require, AccountId};
+
+use crate::math::{mul_div, Rate, BPS_DENOMINATOR};
+use crate::time::{Timestamp, NANOS_PER_YEAR};
+use crate::*;
+
+// scale of acc_reward_per_token
+pub const DELEGATION_REWARD_PRECISION: u128 = 1_000_000_000_000_000_000_000_000;
+pub const MAX_COMMISSION_BPS: u16 = 5_000;
+
+// a lead advisor's pool. delegated stake earns at the lead's apr, which its votes keep
+// moving, and the lead keeps commission_bps of what it earns in `commission`
+#[derive(BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DelegationPool {
+    pub lead_id: AccountId,
+    pub commission_bps: u16,
+    pub apr: U128,
+    pub total_delegated: U128,
+    pub delegators: u64,
+    pub acc_reward_per_token: U128,
+    pub apr_index_paid: U128,
+    pub commission: U128,
+    pub commission_claimed: U128,
+    pub pending_commission: Option<PendingCommission>,
+}
+
+// stake moved out of the delegator's own position, it comes back through withdrawable
+// once undelegated and unbonded
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Delegation {
+    pub lead_id: AccountId,
+    pub amount: u128,
+    pub reward_per_token_paid: u128,
+    pub reward: u128,
+    pub unbonding: u128,
+    pub unbonding_since: Timestamp,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DelegationView {
+    pub lead_id: AccountId,
+    pub amount: U128,
+    pub reward: U128,
+    pub unbonding: U128,
+    pub unbonded_at: Option<Timestamp>,
+}
+
+impl DelegationPool {
+    fn settle(&self, delegation: &mut Delegation) {
+        delegation.reward += mul_div(
+            delegation.amount,
+            self.acc_reward_per_token.0 - delegation.reward_per_token_paid,
+            DELEGATION_REWARD_PRECISION,
+        );
+        delegation.reward_per_token_paid = self.acc_reward_per_token.0;
+    }
+}
+
+impl Contract {
+    pub(crate) fn internal_get_delegation_pool(&self, lead_id: &AccountId) -> DelegationPool {
+        self.delegation_pools
+            .get(lead_id)
+            .unwrap_or_else(|| env::panic_str(&StakeError::NoDelegationPool))
+    }
+
+    fn internal_get_delegation(&self, account_id: &AccountId) -> Delegation {
+        self.delegations
+            .get(account_id)
+            .unwrap_or_else(|| env::panic_str(&StakeError::NotDelegated))
+    }
+
+    // folds what a token earned at the pool's apr since the last update into the
+    // accumulator, net of the commission which accrues to the lead on the whole pool
+    pub(crate) fn internal_update_delegation_pool(
+        &self,
+        pool: &mut DelegationPool,
+        now: Timestamp,
+    ) {
+        let apr_index = self.internal_apr_index_at(now);
+        let index_growth = apr_index - pool.apr_index_paid.0;
+        let gross = mul_div(
+            Rate::from_raw(pool.apr.0).annual_reward(DELEGATION_REWARD_PRECISION),
+            index_growth,
+            BPS_DENOMINATOR * NANOS_PER_YEAR as u128,
+        );
+        let commission = mul_div(gross, pool.commission_bps as u128, BPS_DENOMINATOR);
+        pool.acc_reward_per_token.0 += gross - commission;
+        pool.commission.0 += mul_div(
+            pool.total_delegated.0,
+            commission,
+            DELEGATION_REWARD_PRECISION,
+        );
+        pool.apr_index_paid = U128(apr_index);
+        Self::internal_apply_pending_commission(pool, now);
+    }
+
+    fn internal_delegation_touch(&self, account_id: &AccountId) -> (DelegationPool, Delegation) {
+        let mut delegation = self.internal_get_delegation(account_id);
+        let mut pool = self.internal_get_delegation_pool(&delegation.lead_id);
+        self.internal_update_delegation_pool(&mut pool, Self::now());
+        pool.settle(&mut delegation);
+        (pool, delegation)
+    }
+
+    // every apr change of a lead goes through internal_record_apr_change, the pool accrues
+    // at the old apr up to here
+    pub(crate) fn internal_on_lead_apr_change(&mut self, lead_id: &AccountId, new_apr: u128) {
+        let mut pool = match self.delegation_pools.get(lead_id) {
+            Some(pool) => pool,
+            None => return,
+        };
+        self.internal_update_delegation_pool(&mut pool, Self::now());
+        self.internal_update_reward_rate(
+            pool.total_delegated.0,
+            pool.apr.0,
+            pool.total_delegated.0,
+            new_apr,
+        );
+        pool.apr = U128(new_apr);
+        self.delegation_pools.insert(lead_id, &pool);
+    }
+
+    // takes what a slash could not find in the position, unbonding stake first as it no
+    // longer earns. returns how much it took
+    pub(crate) fn internal_slash_delegation(
+        &mut self,
+        account_id: &AccountId,
+        amount: u128,
+    ) -> u128 {
+        if amount == 0 || !self.delegations.contains_key(account_id) {
+            return 0;
+        }
+        let (mut pool, mut delegation) = self.internal_delegation_touch(account_id);
+        let from_unbonding = amount.min(delegation.unbonding);
+        delegation.unbonding -= from_unbonding;
+        let from_pool = (amount - from_unbonding).min(delegation.amount);
+        self.internal_update_reward_rate(
+            pool.total_delegated.0,
+            pool.apr.0,
+            pool.total_delegated.0 - from_pool,
+            pool.apr.0,
+        );
+        pool.total_delegated.0 -= from_pool;
+        delegation.amount -= from_pool;
+        if delegation.amount == 0 && delegation.unbonding == 0 && delegation.reward == 0 {
+            pool.delegators -= 1;
+            self.delegations.remove(account_id);
+        } else {
+            self.delegations.insert(account_id, &delegation);
+        }
+        self.delegation_pools.insert(&delegation.lead_id, &pool);
+        from_unbonding + from_pool
+    }
+
+    pub(crate) fn internal_pay_delegation_reward(&mut self, account_id: &AccountId, reward: u128) {
+        self.internal_assert_balance_covers();
+        self.internal_pay_reward(reward);
+        let mut stake_info = self
+            .internal_touch(account_id)
+            .unwrap_or_else(|| env::panic_str(&StakeError::NotStaked));
+        stake_info.lifetime_rewards_claimed += reward;
+        self.internal_release_reward(account_id, &mut stake_info, reward);
+        self.internal_record_outflow(reward);
+        self.internal_save_stake_info(account_id, &stake_info);
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // later commission changes go through set_commission
+    pub fn open_delegation_pool(&mut self, commission_bps: u16) {
+        let lead_id = env::predecessor_account_id();
+        require!(
+            commission_bps <= MAX_COMMISSION_BPS,
+            StakeError::CommissionTooHigh
+        );
+        require!(
+            !self.delegation_pools.contains_key(&lead_id),
+            StakeError::DelegationPoolExists
+        );
+        require!(
+            !self.delegations.contains_key(&lead_id),
+            StakeError::AlreadyDelegated
+        );
+        let stake_info = self
+            .internal_get_stake_info(&lead_id)
+            .unwrap_or_else(|| env::panic_str(&StakeError::NotStaked));
+        require!(
+            self.internal_position_state(&stake_info) == PositionState::Active,
+            StakeError::PositionNotActive
+        );
+        self.delegation_pools.insert(
+            &lead_id,
+            &DelegationPool {
+                lead_id: lead_id.clone(),
+                commission_bps,
+                apr: U128(stake_info.apr),
+                total_delegated: U128(0),
+                delegators: 0,
+                acc_reward_per_token: U128(0),
+                apr_index_paid: U128(self.internal_apr_index_at(Self::now())),
+                commission: U128(0),
+                commission_claimed: U128(0),
+                pending_commission: None,
+            },
+        );
+    }
+
+    // moves `amount` of the caller's own stake into the lead's pool, one lead at a time
+    #[payable]
+    pub fn delegate(&mut self, lead_id: AccountId, amount: U128) {
+        self.internal_assert_deposit("delegate");
+        self.internal_assert_not_paused();
+        let account_id = env::predecessor_account_id();
+        let amount = amount.0;
+        require!(amount > 0, StakeError::InvalidAmount);
+        require!(
+            !self.delegation_pools.contains_key(&account_id),
+            StakeError::LeadCannotDelegate
+        );
+        let mut pool = self.internal_get_delegation_pool(&lead_id);
+        self.internal_update_delegation_pool(&mut pool, Self::now());
+        let mut delegation = match self.delegations.get(&account_id) {
+            Some(delegation) => {
+                require!(delegation.lead_id == lead_id, StakeError::AlreadyDelegated);
+                delegation
+            }
+            None => {
+                pool.delegators += 1;
+                Delegation {
+                    lead_id: lead_id.clone(),
+                    amount: 0,
+                    reward_per_token_paid: pool.acc_reward_per_token.0,
+                    reward: 0,
+                    unbonding: 0,
+                    unbonding_since: Self::now(),
+                }
+            }
+        };
+        pool.settle(&mut delegation);
+
+        let mut stake_info = self
+            .internal_touch(&account_id)
+            .unwrap_or_else(|| env::panic_str(&StakeError::NotStaked));
+        Self::internal_assert_no_pending_operation(&stake_info);
+        self.internal_assert_no_open_slash(&account_id);
+        self.internal_assert_not_frozen(&account_id);
+        require!(
+            stake_info.amount_staked >= amount,
+            StakeError::InsufficientStake
+        );
+        self.internal_assert_stake_free(&account_id, &stake_info, amount);
+        self.internal_remove_course_stake(&account_id, &stake_info, None, amount);
+        self.internal_update_reward_rate(
+            stake_info.amount_staked,
+            stake_info.boosted(stake_info.apr),
+            stake_info.amount_staked - amount,
+            stake_info.boosted(stake_info.apr),
+        );
+        stake_info.amount_staked -= amount;
+        stake_info.delegated += amount;
+        stake_info.sync_streak(Self::now());
+        self.internal_save_stake_info(&account_id, &stake_info);
+
+        self.internal_update_reward_rate(
+            pool.total_delegated.0,
+            pool.apr.0,
+            pool.total_delegated.0 + amount,
+            pool.apr.0,
+        );
+        pool.total_delegated.0 += amount;
+        delegation.amount += amount;
+        self.delegation_pools.insert(&lead_id, &pool);
+        self.delegations.insert(&account_id, &delegation);
+    }
+
+    // stops earning right away, the amount can be withdrawn once the unbond period passed.
+    // undelegating again restarts the period for everything unbonding
+    #[payable]
+    pub fn undelegate(&mut self, amount: U128) {
+        self.internal_assert_deposit("undelegate");
+        let account_id = env::predecessor_account_id();
+        self.internal_assert_no_open_slash(&account_id);
+        self.internal_assert_not_frozen(&account_id);
+        let amount = amount.0;
+        let (mut pool, mut delegation) = self.internal_delegation_touch(&account_id);
+        require!(amount > 0, StakeError::InvalidAmount);
+        require!(
+            amount <= delegation.amount,
+            StakeError::InsufficientDelegation
+        );
+        self.internal_update_reward_rate(
+            pool.total_delegated.0,
+            pool.apr.0,
+            pool.total_delegated.0 - amount,
+            pool.apr.0,
+        );
+        pool.total_delegated.0 -= amount;
+        delegation.amount -= amount;
+        delegation.unbonding += amount;
+        delegation.unbonding_since = Self::now();
+        self.delegation_pools.insert(&delegation.lead_id, &pool);
+        self.delegations.insert(&account_id, &delegation);
+    }
+
+    #[payable]
+    pub fn withdraw_undelegated(&mut self) -> U128 {
+        self.internal_assert_deposit("withdraw_undelegated");
+        let account_id = env::predecessor_account_id();
+        self.internal_assert_no_open_slash(&account_id);
+        self.internal_assert_not_frozen(&account_id);
+        let (mut pool, mut delegation) = self.internal_delegation_touch(&account_id);
+        let amount = delegation.unbonding;
+        require!(amount > 0, StakeError::NothingUnbonding);
+        require!(
+            Self::now() >= delegation.unbonding_since + self.unbond_period,
+            StakeError::StillUnbonding
+        );
+        let mut stake_info = self
+            .internal_touch(&account_id)
+            .unwrap_or_else(|| env::panic_str(&StakeError::NotStaked));
+        self.internal_assert_balance_covers();
+        stake_info.delegated -= amount;
+        stake_info.lifetime_unstaked += amount;
+        self.internal_credit_withdrawable(&mut stake_info, amount);
+        self.internal_record_outflow(amount);
+        self.internal_save_stake_info(&account_id, &stake_info);
+        self.total_staked -= amount;
+        self.total_volume_unstaked += amount;
+
+        delegation.unbonding = 0;
+        if delegation.amount == 0 && delegation.reward == 0 {
+            pool.delegators -= 1;
+            self.delegations.remove(&account_id);
+        } else {
+            self.delegations.insert(&account_id, &delegation);
+        }
+        self.delegation_pools.insert(&delegation.lead_id, &pool);
+        self.internal_process_queue(MAX_QUEUE_ACTIVATIONS);
+        U128(amount)
+    }
+
+    // paid out of the reward pool like the position's own reward
+    #[payable]
+    pub fn claim_delegation_reward(&mut self) -> U128 {
+        self.internal_assert_deposit("claim_delegation_reward");
+        let account_id = env::predecessor_account_id();
+        let (mut pool, mut delegation) = self.internal_delegation_touch(&account_id);
+        let reward = delegation.reward;
+        require!(reward > 0, StakeError::NoReward);
+        delegation.reward = 0;
+        if delegation.amount == 0 && delegation.unbonding == 0 {
+            pool.delegators -= 1;
+            self.delegations.remove(&account_id);
+        } else {
+            self.delegations.insert(&account_id, &delegation);
+        }
+        self.delegation_pools.insert(&delegation.lead_id, &pool);
+        self.internal_pay_delegation_reward(&account_id, reward);
+        U128(reward)
+    }
+
+    pub fn get_delegation_pool(&self, lead_id: AccountId) -> Option<DelegationPool> {
+        self.delegation_pools.get(&lead_id).map(|mut pool| {
+            self.internal_update_delegation_pool(&mut pool, Self::now());
+            pool
+        })
+    }
+
+    pub fn get_delegation(&self, account_id: AccountId) -> Option<DelegationView> {
+        self.delegations.get(&account_id)?;
+        let (_, delegation) = self.internal_delegation_touch(&account_id);
+        Some(DelegationView {
+            lead_id: delegation.lead_id,
+            amount: U128(delegation.amount),
+            reward: U128(delegation.reward),
+            unbonding: U128(delegation.unbonding),
+            unbonded_at: (delegation.unbonding > 0)
+                .then(|| delegation.unbonding_since + self.unbond_period),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    const AMOUNT: u128 = 100 * ONE_TOKEN;
+
+    // bob keeps 40 in his position, 40 in alice's pool and 20 undelegating
+    fn setup_delegated() -> Contract {
+        let mut contract = setup();
+        stake(&mut contract, &account("alice"), AMOUNT);
+        call_as(&account("alice"));
+        contract.open_delegation_pool(1_000);
+        stake(&mut contract, &account("bob"), AMOUNT);
+        call_with_yocto(&account("bob"));
+        contract.delegate(account("alice"), U128(60 * ONE_TOKEN));
+        call_with_yocto(&account("bob"));
+        contract.undelegate(U128(20 * ONE_TOKEN));
+        contract
+    }
+
+    fn slash(contract: &mut Contract, advisor: &AccountId, amount: u128) -> u128 {
+        call_as(&owner());
+        let proposal_id =
+            contract.propose_slash(advisor.clone(), U128(amount), "ipfs://evidence".to_string());
+        advance(DEFAULT_CHALLENGE_PERIOD);
+        contract.finalize_slash(proposal_id).0
+    }
+
+    #[test]
+    fn a_slash_reaches_delegated_stake() {
+        let mut contract = setup_delegated();
+        let bob = account("bob");
+        assert_eq!(slash(&mut contract, &bob, 90 * ONE_TOKEN), 90 * ONE_TOKEN);
+
+        let stake_info = contract.internal_get_stake_info(&bob).unwrap();
+        assert_eq!(stake_info.amount_staked, 0);
+        assert_eq!(stake_info.delegated, 10 * ONE_TOKEN);
+        let delegation = contract.get_delegation(bob.clone()).unwrap();
+        assert_eq!(delegation.unbonding.0, 0);
+        assert_eq!(delegation.amount.0, 10 * ONE_TOKEN);
+        let pool = contract.get_delegation_pool(account("alice")).unwrap();
+        assert_eq!(pool.total_delegated.0, 10 * ONE_TOKEN);
+        assert_eq!(contract.total_staked, AMOUNT + 10 * ONE_TOKEN);
+    }
+
+    #[test]
+    fn a_slash_larger_than_everything_takes_everything() {
+        let mut contract = setup_delegated();
+        let bob = account("bob");
+        assert_eq!(slash(&mut contract, &bob, 2 * AMOUNT), AMOUNT);
+        assert_eq!(contract.internal_get_stake_info(&bob).unwrap().delegated, 0);
+        // what the pool earned during the challenge period stays claimable
+        let delegation = contract.get_delegation(bob.clone()).unwrap();
+        assert_eq!(delegation.amount.0 + delegation.unbonding.0, 0);
+        assert!(delegation.reward.0 > 0);
+        let pool = contract.get_delegation_pool(account("alice")).unwrap();
+        assert_eq!(pool.total_delegated.0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_OPEN_SLASH_PROPOSAL")]
+    fn an_open_slash_blocks_undelegate() {
+        let mut contract = setup_delegated();
+        call_as(&owner());
+        contract.propose_slash(account("bob"), U128(ONE_TOKEN), String::new());
+        call_with_yocto(&account("bob"));
+        contract.undelegate(U128(ONE_TOKEN));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_OPEN_SLASH_PROPOSAL")]
+    fn an_open_slash_blocks_withdraw_undelegated() {
+        let mut contract = setup_delegated();
+        advance(DEFAULT_UNBOND_PERIOD);
+        call_as(&owner());
+        contract.propose_slash(account("bob"), U128(ONE_TOKEN), String::new());
+        call_with_yocto(&account("bob"));
+        contract.withdraw_undelegated();
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_ACCOUNT_FROZEN")]
+    fn a_frozen_account_cannot_withdraw_undelegated() {
+        let mut contract = setup_delegated();
+        advance(DEFAULT_UNBOND_PERIOD);
+        call_as(&owner());
+        contract.freeze_account(account("bob"), "hold".to_string());
+        call_with_yocto(&account("bob"));
+        contract.withdraw_undelegated();
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of exactly 1 yoctoNEAR")]
+    fn withdraw_undelegated_asks_for_a_yocto() {
+        let mut contract = setup_delegated();
+        advance(DEFAULT_UNBOND_PERIOD);
+        call_as(&account("bob"));
+        contract.withdraw_undelegated();
+    }
+}
@@ -0,0 +1,99 @@
+use near_sdk::{assert_one_yocto, env, near_bindgen, require};
+
+use crate::*;
+
+// every method that asks for one yocto, so a typo cannot switch off nothing
+pub const DEPOSIT_METHODS: [&str; 34] = [
+    "approve_collateral_locker",
+    "cancel_queue_entry",
+    "cancel_recent_stake",
+    "cancel_stake_intent",
+    "claim_and_call",
+    "claim_bonus",
+    "claim_commission",
+    "claim_delegation_reward",
+    "claim_group_reward",
+    "claim_insurance",
+    "claim_lp_reward",
+    "claim_micro",
+    "claim_pool_reward",
+    "claim_reward",
+    "claim_vested",
+    "confirm_action",
+    "contribute_to_group",
+    "delegate",
+    "deploy_upgrade",
+    "exit",
+    "leave_group",
+    "migrate_to",
+    "propose_action",
+    "restake_into",
+    "set_claim_key",
+    "stake_from_deposit",
+    "storage_unregister",
+    "sweep",
+    "undelegate",
+    "unstake_from_pool",
+    "unstake_lp",
+    "unstake_token",
+    "withdraw",
+    "withdraw_undelegated",
+];
+
+impl Contract {
+    // the yocto proves a full access key signed, a whitelisted relayer is trusted instead
+    pub(crate) fn internal_assert_deposit(&self, method: &str) {
+        if self
+            .deposit_relayers
+            .contains(&env::predecessor_account_id())
+            || self.deposit_exempt.contains(&method.to_string())
+        {
+            return;
+        }
+        assert_one_yocto();
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn set_deposit_required(&mut self, method: String, required: bool) {
+        self.internal_assert_owner();
+        require!(
+            DEPOSIT_METHODS.contains(&method.as_str()),
+            StakeError::UnknownDepositMethod
+        );
+        let old = !self.deposit_exempt.contains(&method);
+        if required {
+            self.deposit_exempt.remove(&method);
+        } else {
+            self.deposit_exempt.insert(&method);
+        }
+        self.internal_record_config_change(&format!("deposit_required:{}", method), old, required);
+    }
+
+    pub fn add_deposit_relayers(&mut self, relayer_ids: Vec<AccountId>) {
+        self.internal_assert_owner();
+        for relayer_id in relayer_ids {
+            self.deposit_relayers.insert(&relayer_id);
+        }
+    }
+
+    pub fn remove_deposit_relayers(&mut self, relayer_ids: Vec<AccountId>) {
+        self.internal_assert_owner();
+        for relayer_id in relayer_ids {
+            self.deposit_relayers.remove(&relayer_id);
+        }
+    }
+
+    pub fn is_deposit_relayer(&self, relayer_id: AccountId) -> bool {
+        self.deposit_relayers.contains(&relayer_id)
+    }
+
+    pub fn is_deposit_required(&self, method: String) -> bool {
+        DEPOSIT_METHODS.contains(&method.as_str()) && !self.deposit_exempt.contains(&method)
+    }
+
+    pub fn get_deposit_exempt_methods(&self) -> Vec<String> {
+        self.deposit_exempt.to_vec()
+    }
+}
@@ -0,0 +1,81 @@
+use near_sdk::json_types::U128;
+use near_sdk::{env, near_bindgen, AccountId, Gas, Promise, PromiseError};
+
+use crate::*;
+
+pub const CREDIT_CALLBACK_GAS: Gas = Gas(10_000_000_000_000);
+
+// tokens sent with a plain ft_transfer, e.g. from an exchange, reach the contract without
+// a notification. the owner assigns them to the sender once the transfer is confirmed,
+// only out of the surplus over everything already owed, and the sender stakes them later
+#[near_bindgen]
+impl Contract {
+    pub fn credit_deposit(&mut self, account_id: AccountId, amount: U128) -> Promise {
+        self.internal_assert_role(Role::Treasurer);
+        require!(amount.0 > 0, StakeError::InvalidAmount);
+        ext_ft_contract::ext(self.token_address.clone())
+            .with_static_gas(FT_BALANCE_OF_GAS)
+            .ft_balance_of(env::current_account_id())
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(CREDIT_CALLBACK_GAS)
+                    .on_credit_balance(account_id, amount),
+            )
+    }
+
+    #[private]
+    pub fn on_credit_balance(
+        &mut self,
+        account_id: AccountId,
+        amount: U128,
+        #[callback_result] balance: Result<U128, PromiseError>,
+    ) -> U128 {
+        let balance = balance
+            .unwrap_or_else(|_| env::panic_str(&StakeError::BalanceFetchFailed))
+            .0;
+        self.internal_set_token_balance(balance);
+        let surplus = balance.saturating_sub(self.internal_liabilities());
+        require!(amount.0 <= surplus, StakeError::AmountExceedsSurplus);
+
+        let deposit = self.deposits.get(&account_id).unwrap_or(0) + amount.0;
+        self.deposits.insert(&account_id, &deposit);
+        self.total_deposits += amount.0;
+        U128(deposit)
+    }
+
+    // same checks as staking through ft_transfer_call, except that nothing can be refunded
+    #[payable]
+    pub fn stake_from_deposit(&mut self, amount: U128) {
+        self.internal_assert_deposit("stake_from_deposit");
+        let account_id = env::predecessor_account_id();
+        require!(amount.0 > 0, StakeError::InvalidAmount);
+        self.internal_assert_not_paused();
+        require!(
+            self.internal_is_permitted(&account_id),
+            StakeError::AccountNotPermitted
+        );
+        require!(
+            !self.internal_needs_attestation(&account_id),
+            StakeError::AttestationRequired
+        );
+        let deposit = self.deposits.get(&account_id).unwrap_or(0);
+        require!(amount.0 <= deposit, StakeError::InsufficientDeposit);
+        require!(
+            amount.0 <= self.internal_open_capacity(),
+            StakeError::TvlCapReached
+        );
+
+        if deposit == amount.0 {
+            self.deposits.remove(&account_id);
+        } else {
+            self.deposits.insert(&account_id, &(deposit - amount.0));
+        }
+        self.total_deposits -= amount.0;
+        self.internal_stake(account_id, amount.0);
+        self.internal_maybe_snapshot();
+    }
+
+    pub fn get_deposit(&self, account_id: AccountId) -> U128 {
+        U128(self.deposits.get(&account_id).unwrap_or(0))
+    }
+}
@@ -0,0 +1,73 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::Serialize;
+use near_sdk::{env, near_bindgen, AccountId};
+
+use crate::time::Timestamp;
+use crate::*;
+
+pub const MAX_FAILED_OPERATIONS: u64 = 100;
+
+// a callback that found its promise failed, whatever it restored is already back
+#[derive(BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FailedOperation {
+    pub callback: String,
+    pub account_id: Option<AccountId>,
+    pub amount: U128,
+    pub at: Timestamp,
+    pub prepaid_gas: U64,
+    pub used_gas: U64,
+}
+
+impl Contract {
+    // called first in every callback that resolves a transfer or push, with the
+    // gas-diagnostics feature each call is logged whether it failed or not
+    pub(crate) fn internal_note_promise_result(
+        &mut self,
+        callback: &str,
+        account_id: Option<&AccountId>,
+        amount: u128,
+        success: bool,
+    ) {
+        let (prepaid_gas, used_gas) = (env::prepaid_gas().0, env::used_gas().0);
+        #[cfg(feature = "gas-diagnostics")]
+        env::log_str(
+            &near_sdk::serde_json::json!({
+                "callback": callback,
+                "account_id": account_id,
+                "amount": U128(amount),
+                "success": success,
+                "prepaid_gas": U64(prepaid_gas),
+                "used_gas": U64(used_gas),
+            })
+            .to_string(),
+        );
+        if success {
+            return;
+        }
+        self.failed_operations.push(&FailedOperation {
+            callback: callback.to_string(),
+            account_id: account_id.cloned(),
+            amount: U128(amount),
+            at: Self::now(),
+            prepaid_gas: U64(prepaid_gas),
+            used_gas: U64(used_gas),
+        });
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // newest first
+    pub fn last_failed_operations(&self, limit: Option<u64>) -> Vec<FailedOperation> {
+        let limit = limit.map_or(MAX_FAILED_OPERATIONS, |limit| {
+            limit.min(MAX_FAILED_OPERATIONS)
+        });
+        let len = self.failed_operations.len();
+        (len.saturating_sub(limit)..len)
+            .rev()
+            .filter_map(|index| self.failed_operations.get(index))
+            .collect()
+    }
+}
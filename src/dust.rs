@@ -0,0 +1,28 @@
+use near_sdk::json_types::U128;
+use near_sdk::near_bindgen;
+
+use crate::*;
+
+// every reward division floors so a payout never exceeds what was set aside. where the
+// truncated remainder is known in token units it goes here instead of vanishing into
+// a balance, and stays a liability until folded back into the reward pool
+impl Contract {
+    pub(crate) fn internal_record_dust(&mut self, dust: u128) {
+        self.dust_accumulator += dust;
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn fold_dust(&mut self) -> U128 {
+        self.internal_assert_role(Role::Treasurer);
+        let dust = self.dust_accumulator;
+        self.dust_accumulator = 0;
+        self.reward_pool += dust;
+        U128(dust)
+    }
+
+    pub fn get_dust(&self) -> U128 {
+        U128(self.dust_accumulator)
+    }
+}
@@ -0,0 +1,49 @@
+use near_sdk::json_types::U128;
+use near_sdk::serde::Serialize;
+use near_sdk::{near_bindgen, AccountId};
+
+use crate::*;
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct EligibilityReport {
+    pub account_id: AccountId,
+    pub eligible: bool,
+    pub amount_staked: U128,
+    pub min_stake: U128,
+    pub meets_min_stake: bool,
+    pub state: Option<PositionState>,
+    pub votes: u8,
+    pub slashed: bool,
+    pub permitted: bool,
+}
+
+#[near_bindgen]
+impl Contract {
+    // everything the matching service needs to decide if an advisor can take students
+    pub fn is_eligible_advisor(&self, account_id: AccountId) -> EligibilityReport {
+        let stake_info = self.internal_get_stake_info(&account_id);
+        let amount_staked = stake_info.as_ref().map_or(0, |info| info.amount_staked);
+        let state = stake_info
+            .as_ref()
+            .map(|info| self.internal_position_state(info));
+        let meets_min_stake = amount_staked > 0 && amount_staked >= self.min_advisor_stake;
+        let permitted = self.internal_is_permitted(&account_id);
+        EligibilityReport {
+            eligible: meets_min_stake && permitted && state == Some(PositionState::Active),
+            amount_staked: U128(amount_staked),
+            min_stake: U128(self.min_advisor_stake),
+            meets_min_stake,
+            state,
+            votes: stake_info.as_ref().map_or(0, |info| info.votes),
+            slashed: state == Some(PositionState::Slashed),
+            permitted,
+            account_id,
+        }
+    }
+
+    pub fn set_min_advisor_stake(&mut self, min_stake: U128) {
+        self.internal_assert_role(Role::Admin);
+        self.min_advisor_stake = min_stake.0;
+    }
+}
@@ -0,0 +1,213 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::Serialize;
+use near_sdk::{env, near_bindgen, AccountId, Promise, PromiseError, ONE_YOCTO};
+
+use crate::time::Timestamp;
+use crate::*;
+
+// activations done on the back of an unstake, process_queue can do more
+pub const MAX_QUEUE_ACTIVATIONS: u64 = 3;
+pub const MAX_PROCESS_QUEUE_BATCH: u64 = 20;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct QueueEntry {
+    pub amount: U128,
+    pub position: U64,
+    pub joined_at: Timestamp,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct QueuePosition {
+    pub amount: U128,
+    // entries still ahead, cancelled ones included until the head passes them
+    pub ahead: U64,
+    pub joined_at: Timestamp,
+}
+
+// deposits waiting for room under max_total_staked, held in escrow without accruing
+// and staked first in first out as capacity frees up
+impl Contract {
+    pub(crate) fn internal_join_queue(
+        &mut self,
+        account_id: &AccountId,
+        amount: u128,
+    ) -> Result<(), StakeError> {
+        if self.internal_open_capacity() >= amount {
+            return Err(StakeError::CapacityAvailable);
+        }
+        if self.queue_entries.contains_key(account_id) {
+            return Err(StakeError::AlreadyQueued);
+        }
+        if self.internal_needs_attestation(account_id) {
+            return Err(StakeError::AttestationRequired);
+        }
+        self.internal_push_queue_entry(account_id, amount, Self::now());
+        Ok(())
+    }
+
+    fn internal_push_queue_entry(
+        &mut self,
+        account_id: &AccountId,
+        amount: u128,
+        joined_at: Timestamp,
+    ) {
+        let position = self.queue_tail;
+        self.queue_tail += 1;
+        self.queue_order.insert(&position, account_id);
+        self.queue_entries.insert(
+            account_id,
+            &QueueEntry {
+                amount: U128(amount),
+                position: U64(position),
+                joined_at,
+            },
+        );
+        self.total_queued += amount;
+    }
+
+    // stakes from the head of the queue while there is room, an entry larger than the room
+    // left is staked in part and keeps its place. accounts that may no longer stake are
+    // taken out of the order and can only cancel
+    // passed by the head without being staked, it waits for its account to cancel
+    pub(crate) fn internal_is_skipped(&self, entry: &QueueEntry) -> bool {
+        entry.position.0 < self.queue_head
+    }
+
+    pub(crate) fn internal_process_queue(&mut self, max_activations: u64) -> u64 {
+        self.internal_with_hooks_deferred(|contract| {
+            contract.internal_activate_queued(max_activations)
+        })
+    }
+
+    fn internal_activate_queued(&mut self, max_activations: u64) -> u64 {
+        let mut activations = 0;
+        while activations < max_activations && self.queue_head < self.queue_tail && !self.paused {
+            let capacity = self.internal_stake_capacity();
+            if capacity == 0 {
+                break;
+            }
+            let account_id = match self.queue_order.get(&self.queue_head) {
+                Some(account_id) => account_id,
+                None => {
+                    self.queue_head += 1;
+                    continue;
+                }
+            };
+            if !self.internal_is_permitted(&account_id) {
+                self.queue_order.remove(&self.queue_head);
+                self.queue_head += 1;
+                let entry = self.queue_entries.get(&account_id).unwrap();
+                self.total_skipped_queued += entry.amount.0;
+                continue;
+            }
+            let mut entry = self.queue_entries.get(&account_id).unwrap();
+            let amount = entry.amount.0.min(capacity);
+            entry.amount.0 -= amount;
+            self.total_queued -= amount;
+            if entry.amount.0 == 0 {
+                self.queue_entries.remove(&account_id);
+                self.queue_order.remove(&self.queue_head);
+                self.queue_head += 1;
+            } else {
+                self.queue_entries.insert(&account_id, &entry);
+            }
+            self.internal_stake(account_id, amount);
+            activations += 1;
+        }
+        activations
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn process_queue(&mut self, limit: Option<u64>) -> u64 {
+        let limit = limit
+            .unwrap_or(MAX_PROCESS_QUEUE_BATCH)
+            .min(MAX_PROCESS_QUEUE_BATCH);
+        let activations = self.internal_process_queue(limit);
+        self.internal_maybe_snapshot();
+        activations
+    }
+
+    #[payable]
+    pub fn cancel_queue_entry(&mut self) -> Promise {
+        self.internal_assert_deposit("cancel_queue_entry");
+        self.internal_assert_transfers_up();
+        let account_id = env::predecessor_account_id();
+        let entry = self
+            .queue_entries
+            .remove(&account_id)
+            .unwrap_or_else(|| env::panic_str(&StakeError::NotQueued));
+        self.queue_order.remove(&entry.position.0);
+        self.total_queued -= entry.amount.0;
+        if self.internal_is_skipped(&entry) {
+            self.total_skipped_queued -= entry.amount.0;
+        }
+        self.pending_outflows += entry.amount.0;
+
+        ext_ft_contract::ext(self.token_address.clone())
+            .with_static_gas(FT_TRANSFER_GAS)
+            .with_attached_deposit(ONE_YOCTO)
+            .ft_transfer(account_id.clone(), entry.amount, None)
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(WITHDRAW_CALLBACK_GAS)
+                    .on_cancel_queue_entry(account_id, entry.amount),
+            )
+    }
+
+    // a failed refund goes back to the end of the queue, or onto the entry of a rejoin
+    #[private]
+    pub fn on_cancel_queue_entry(
+        &mut self,
+        account_id: AccountId,
+        amount: U128,
+        #[callback_result] result: Result<(), PromiseError>,
+    ) -> U128 {
+        self.internal_note_promise_result(
+            "on_cancel_queue_entry",
+            Some(&account_id),
+            amount.0,
+            result.is_ok(),
+        );
+        self.internal_note_token_transfer(&account_id, result.is_ok());
+        self.pending_outflows -= amount.0;
+        if result.is_ok() {
+            self.internal_note_outflow(amount.0);
+            return amount;
+        }
+        match self.queue_entries.get(&account_id) {
+            Some(mut entry) => {
+                entry.amount.0 += amount.0;
+                self.queue_entries.insert(&account_id, &entry);
+                self.total_queued += amount.0;
+                if self.internal_is_skipped(&entry) {
+                    self.total_skipped_queued += amount.0;
+                }
+            }
+            None => self.internal_push_queue_entry(&account_id, amount.0, Self::now()),
+        }
+        U128(0)
+    }
+
+    pub fn get_queue_position(&self, account_id: AccountId) -> Option<QueuePosition> {
+        self.queue_entries
+            .get(&account_id)
+            .map(|entry| QueuePosition {
+                amount: entry.amount,
+                ahead: U64(entry.position.0.saturating_sub(self.queue_head)),
+                joined_at: entry.joined_at,
+            })
+    }
+
+    pub fn get_queue_length(&self) -> U64 {
+        U64(self.queue_tail - self.queue_head)
+    }
+
+    pub fn get_total_queued(&self) -> U128 {
+        U128(self.total_queued)
+    }
+}
@@ -0,0 +1,276 @@
+use std::ops::Deref;
+
+use near_sdk::serde::Serialize;
+
+macro_rules! stake_errors {
+    ($($variant:ident => $code:literal, $message:literal;)*) => {
+        #[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+        #[serde(crate = "near_sdk::serde")]
+        pub enum StakeError {
+            $($variant,)*
+        }
+
+        impl StakeError {
+            pub fn code(&self) -> &'static str {
+                match self {
+                    $(StakeError::$variant => $code,)*
+                }
+            }
+
+            // what the contract panics with, "<code>: <message>"
+            pub fn as_str(&self) -> &'static str {
+                match self {
+                    $(StakeError::$variant => concat!($code, ": ", $message),)*
+                }
+            }
+        }
+    };
+}
+
+stake_errors! {
+    NotStaked => "ERR_NOT_STAKED", "You didn't stake any tokens!";
+    InvalidAmount => "ERR_INVALID_AMOUNT", "Invalid amount";
+    AdvisorNotStaked => "ERR_ADVISOR_NOT_STAKED", "Advisor not stake any tokens!";
+    InsufficientStake => "ERR_INSUFFICIENT_STAKE", "You staked less token than amount";
+    UnknownCourse => "ERR_UNKNOWN_COURSE", "Unknown course!";
+    ProposalNotPending => "ERR_PROPOSAL_NOT_PENDING", "Proposal is not pending!";
+    InsufficientBonusBalance => "ERR_INSUFFICIENT_BONUS_BALANCE", "Bonus balance is insufficient!";
+    NoReward => "ERR_NO_REWARD", "You have no reward yet!";
+    VoteWeightTooHigh => "ERR_VOTE_WEIGHT_TOO_HIGH", "Vote weight is too high!";
+    UpgradeTimelocked => "ERR_UPGRADE_TIMELOCKED", "Upgrade timelock has not passed yet!";
+    UpgradeDelayDecrease => "ERR_UPGRADE_DELAY_DECREASE", "Upgrade delay can only be increased!";
+    UnsupportedToken => "ERR_UNSUPPORTED_TOKEN", "Unsupported token!";
+    UnstakeFeeTooHigh => "ERR_UNSTAKE_FEE_TOO_HIGH", "Unstake fee is too high!";
+    UnknownSlashProposal => "ERR_UNKNOWN_SLASH_PROPOSAL", "Unknown slash proposal!";
+    TooManyVestingEntries => "ERR_TOO_MANY_VESTING_ENTRIES", "Too many vesting schedules, call claim_vested first!";
+    BatchTooLarge => "ERR_BATCH_TOO_LARGE", "Too many records in one batch!";
+    TooManyCurvePoints => "ERR_TOO_MANY_CURVE_POINTS", "Too many curve points!";
+    TooManyCourses => "ERR_TOO_MANY_COURSES", "Too many courses!";
+    TooManyAccounts => "ERR_TOO_MANY_ACCOUNTS", "Too many accounts requested!";
+    NotWrappedNear => "ERR_NOT_WRAPPED_NEAR", "Stake token is not wrapped NEAR!";
+    InsufficientRewardPool => "ERR_INSUFFICIENT_REWARD_POOL", "Reward pool is insufficient!";
+    ProposalNotContested => "ERR_PROPOSAL_NOT_CONTESTED", "Proposal is not contested!";
+    ProposalClosed => "ERR_PROPOSAL_CLOSED", "Proposal is already closed!";
+    OperationPending => "ERR_OPERATION_PENDING", "Previous withdrawal is still in progress!";
+    PositionNotActive => "ERR_POSITION_NOT_ACTIVE", "Position is not active!";
+    OpenSlashProposal => "ERR_OPEN_SLASH_PROPOSAL", "Position has an open slash proposal!";
+    PositionNotReactivatable => "ERR_POSITION_NOT_REACTIVATABLE", "Position can not be reactivated!";
+    NotOracle => "ERR_NOT_ORACLE", "Only the oracle can report metrics and prices!";
+    NotArbiter => "ERR_NOT_ARBITER", "Only the arbiter can resolve!";
+    NotSlashedAdvisor => "ERR_NOT_SLASHED_ADVISOR", "Only the advisor can contest!";
+    NotOwner => "ERR_NOT_OWNER", "Only owner can call this method!";
+    UnsupportedKeyType => "ERR_UNSUPPORTED_KEY_TYPE", "Only ed25519 keys are supported!";
+    NothingToWithdraw => "ERR_NOTHING_TO_WITHDRAW", "Nothing to withdraw!";
+    NothingVested => "ERR_NOTHING_VESTED", "Nothing to claim yet!";
+    NotVesting => "ERR_NOT_VESTING", "Nothing is vesting!";
+    NothingStaked => "ERR_NOTHING_STAKED", "Nothing is staked!";
+    NonceUsed => "ERR_NONCE_USED", "Nonce was already used!";
+    NoStagedUpgrade => "ERR_NO_STAGED_UPGRADE", "No upgrade staged!";
+    NoStateToMigrate => "ERR_NO_STATE_TO_MIGRATE", "No state to migrate!";
+    NoClaimKey => "ERR_NO_CLAIM_KEY", "No claim key registered!";
+    NoBonusCampaign => "ERR_NO_BONUS_CAMPAIGN", "No bonus campaign!";
+    MathOverflow => "ERR_MATH_OVERFLOW", "Math overflow!";
+    InvalidVote => "ERR_INVALID_VOTE", "Invalid vote!";
+    InvalidVoteStep => "ERR_INVALID_VOTE_STEP", "Invalid vote step!";
+    InvalidVestingConfig => "ERR_INVALID_VESTING_CONFIG", "Invalid vesting config!";
+    InvalidSignature => "ERR_INVALID_SIGNATURE", "Invalid signature!";
+    InvalidMsg => "ERR_INVALID_MSG", "Invalid msg!";
+    InvalidMerkleProof => "ERR_INVALID_MERKLE_PROOF", "Invalid merkle proof!";
+    InvalidCourseId => "ERR_INVALID_COURSE_ID", "Invalid course id!";
+    InvalidCompoundingPeriods => "ERR_INVALID_COMPOUNDING_PERIODS", "Invalid compounding periods!";
+    InvalidCompoundPeriod => "ERR_INVALID_COMPOUND_PERIOD", "Invalid compound period!";
+    InvalidAprModifier => "ERR_INVALID_APR_MODIFIER", "Invalid APR modifier!";
+    InsuranceSkimTooHigh => "ERR_INSURANCE_SKIM_TOO_HIGH", "Insurance skim is too high!";
+    InsufficientInsuranceFund => "ERR_INSUFFICIENT_INSURANCE_FUND", "Insurance fund is insufficient!";
+    ImportFinished => "ERR_IMPORT_FINISHED", "Import is finished!";
+    AttestationRequired => "ERR_ATTESTATION_REQUIRED", "First stake has to go through ft_transfer_call!";
+    FaucetEmpty => "ERR_FAUCET_EMPTY", "Faucet is out of tokens!";
+    FaucetDisabled => "ERR_FAUCET_DISABLED", "Faucet is disabled!";
+    FaucetCooldown => "ERR_FAUCET_COOLDOWN", "Faucet cooldown has not passed yet!";
+    BalanceFetchFailed => "ERR_BALANCE_FETCH_FAILED", "Failed to fetch token balance!";
+    EvidenceUriTooLong => "ERR_EVIDENCE_URI_TOO_LONG", "Evidence uri is too long!";
+    DefaultAprTooHigh => "ERR_DEFAULT_APR_TOO_HIGH", "Default APR is too high!";
+    UnsortedCurve => "ERR_UNSORTED_CURVE", "Curve points must be sorted by metric!";
+    InsufficientTokenBalance => "ERR_INSUFFICIENT_TOKEN_BALANCE", "Contract token balance is insufficient!";
+    CodeHashMismatch => "ERR_CODE_HASH_MISMATCH", "Code does not match the staged upgrade!";
+    ClaimCooldown => "ERR_CLAIM_COOLDOWN", "Claim cooldown has not passed yet!";
+    ChallengeWindowClosed => "ERR_CHALLENGE_WINDOW_CLOSED", "Challenge window has passed!";
+    ChallengeWindowOpen => "ERR_CHALLENGE_WINDOW_OPEN", "Challenge window has not passed yet!";
+    BonusCampaignExhausted => "ERR_BONUS_CAMPAIGN_EXHAUSTED", "Bonus campaign is exhausted!";
+    BonusClaimed => "ERR_BONUS_CLAIMED", "Bonus already claimed!";
+    StakeBondedToCourses => "ERR_STAKE_BONDED_TO_COURSES", "Amount is bonded to courses!";
+    AmountExceedsReward => "ERR_AMOUNT_EXCEEDS_REWARD", "Amount exceeds your reward!";
+    AmountExceedsSurplus => "ERR_AMOUNT_EXCEEDS_SURPLUS", "Amount exceeds the unaccounted balance!";
+    AdvisorNotPermitted => "ERR_ADVISOR_NOT_PERMITTED", "Advisor is not allowed to receive votes!";
+    AccountNotPermitted => "ERR_ACCOUNT_NOT_PERMITTED", "Account is not permitted to stake!";
+    AprCurveNotSet => "ERR_APR_CURVE_NOT_SET", "APR curve is not set!";
+    InvalidOpId => "ERR_INVALID_OP_ID", "Op id is too long!";
+    NoStakeIntent => "ERR_NO_STAKE_INTENT", "No stake intent!";
+    InvalidIntentPeriod => "ERR_INVALID_INTENT_PERIOD", "Invalid intent period!";
+    NotKeeper => "ERR_NOT_KEEPER", "Only the keeper can execute intents!";
+    Paused => "ERR_PAUSED", "Contract is paused!";
+    NotPaused => "ERR_NOT_PAUSED", "Contract has to be paused first!";
+    HookAlreadyRegistered => "ERR_HOOK_ALREADY_REGISTERED", "Hook is already registered!";
+    TooManyHooks => "ERR_TOO_MANY_HOOKS", "Too many hooks registered!";
+    HookNotFound => "ERR_HOOK_NOT_FOUND", "Hook is not registered!";
+    InvalidTokenAddress => "ERR_INVALID_TOKEN_ADDRESS", "Invalid token address!";
+    InvalidAprBounds => "ERR_INVALID_APR_BOUNDS", "Invalid APR bounds!";
+    DefaultAprTooLow => "ERR_DEFAULT_APR_TOO_LOW", "Default APR is too low!";
+    InsufficientDeposit => "ERR_INSUFFICIENT_DEPOSIT", "Not enough unassigned deposit!";
+    UnknownVote => "ERR_UNKNOWN_VOTE", "Unknown vote!";
+    NotVoteAdvisor => "ERR_NOT_VOTE_ADVISOR", "Only the rated advisor can appeal!";
+    AlreadyAppealed => "ERR_ALREADY_APPEALED", "Vote was already appealed!";
+    AppealWindowClosed => "ERR_APPEAL_WINDOW_CLOSED", "Appeal window has closed!";
+    WrongAppealBond => "ERR_WRONG_APPEAL_BOND", "Attached deposit has to match the appeal bond!";
+    AppealNotPending => "ERR_APPEAL_NOT_PENDING", "Appeal is not pending!";
+    NoAppealArbiters => "ERR_NO_APPEAL_ARBITERS", "No staked appeal arbiters!";
+    NotAppealArbiter => "ERR_NOT_APPEAL_ARBITER", "Only an appeal arbiter can vote!";
+    AlreadyVotedOnAppeal => "ERR_ALREADY_VOTED_ON_APPEAL", "Arbiter already voted on this appeal!";
+    TooManyArbiters => "ERR_TOO_MANY_ARBITERS", "Too many appeal arbiters!";
+    BoosterNotSupported => "ERR_BOOSTER_NOT_SUPPORTED", "NFT contract is not a registered booster!";
+    InvalidBoost => "ERR_INVALID_BOOST", "Boost is too high!";
+    NoLpPool => "ERR_NO_LP_POOL", "LP pool is not configured!";
+    LpPoolInUse => "ERR_LP_POOL_IN_USE", "LP pool still holds stake!";
+    NotPlatform => "ERR_NOT_PLATFORM", "Only the platform contract can share revenue!";
+    TvlCapReached => "ERR_TVL_CAP_REACHED", "Amount exceeds the remaining staking capacity!";
+    AlreadyQueued => "ERR_ALREADY_QUEUED", "Account is already waiting in the queue!";
+    NotQueued => "ERR_NOT_QUEUED", "Account is not in the queue!";
+    CapacityAvailable => "ERR_CAPACITY_AVAILABLE", "Staking capacity is available, stake directly!";
+    MissingRole => "ERR_MISSING_ROLE", "Caller is missing the required role!";
+    ConfirmationRequired => "ERR_CONFIRMATION_REQUIRED", "Action must go through propose_action!";
+    NotActionAdmin => "ERR_NOT_ACTION_ADMIN", "Caller is not in the admin set!";
+    InvalidAdminSet => "ERR_INVALID_ADMIN_SET", "Invalid admin set or threshold!";
+    UnknownAction => "ERR_UNKNOWN_ACTION", "No pending action with this id!";
+    AlreadyConfirmed => "ERR_ALREADY_CONFIRMED", "Action already confirmed by caller!";
+    InvalidBadgeTiers => "ERR_INVALID_BADGE_TIERS", "Badge tiers must be listed lowest first!";
+    InvalidLoyaltyConfig => "ERR_INVALID_LOYALTY_CONFIG", "Invalid loyalty config!";
+    UnknownCampaign => "ERR_UNKNOWN_CAMPAIGN", "Unknown campaign!";
+    InvalidCampaign => "ERR_INVALID_CAMPAIGN", "Campaign needs a budget and an end after its start!";
+    CampaignEnded => "ERR_CAMPAIGN_ENDED", "Campaign has ended!";
+    CampaignNotEnded => "ERR_CAMPAIGN_NOT_ENDED", "Campaign has not ended yet!";
+    NotEligibleForCampaign => "ERR_NOT_ELIGIBLE_FOR_CAMPAIGN", "Account is not eligible for this campaign!";
+    AlreadyInCampaign => "ERR_ALREADY_IN_CAMPAIGN", "Account already joined this campaign!";
+    TooManyCampaigns => "ERR_TOO_MANY_CAMPAIGNS", "Account joined too many campaigns!";
+    NotInCampaign => "ERR_NOT_IN_CAMPAIGN", "Account did not join this campaign!";
+    NoDelegationPool => "ERR_NO_DELEGATION_POOL", "Lead has no delegation pool!";
+    DelegationPoolExists => "ERR_DELEGATION_POOL_EXISTS", "Delegation pool already open!";
+    CommissionTooHigh => "ERR_COMMISSION_TOO_HIGH", "Commission exceeds the maximum!";
+    AlreadyDelegated => "ERR_ALREADY_DELEGATED", "Account already delegates to another lead!";
+    LeadCannotDelegate => "ERR_LEAD_CANNOT_DELEGATE", "A pool lead cannot delegate!";
+    NotDelegated => "ERR_NOT_DELEGATED", "Account has no delegation!";
+    InsufficientDelegation => "ERR_INSUFFICIENT_DELEGATION", "Amount exceeds the delegation!";
+    NothingUnbonding => "ERR_NOTHING_UNBONDING", "Nothing is unbonding!";
+    StillUnbonding => "ERR_STILL_UNBONDING", "Unbond period has not passed yet!";
+    VaultNotApproved => "ERR_VAULT_NOT_APPROVED", "Vault is not approved!";
+    InvalidAccrualCap => "ERR_INVALID_ACCRUAL_CAP", "Accrual cap needs an amount and a period!";
+    BreakerTripped => "ERR_BREAKER_TRIPPED", "Withdrawals are halted until the breaker is reset!";
+    CoursePaymentsDisabled => "ERR_COURSE_PAYMENTS_DISABLED", "Course payments are not enabled!";
+    PaymentTooLow => "ERR_PAYMENT_TOO_LOW", "Payment is below the minimum!";
+    InvalidCoursePaymentConfig => "ERR_INVALID_COURSE_PAYMENT_CONFIG", "Pool share exceeds the payment!";
+    ProtocolFeeTooHigh => "ERR_PROTOCOL_FEE_TOO_HIGH", "Protocol fee exceeds the maximum!";
+    NoProtocolFeeAccount => "ERR_NO_PROTOCOL_FEE_ACCOUNT", "No protocol fee account set!";
+    NoProtocolFees => "ERR_NO_PROTOCOL_FEES", "No protocol fees to claim!";
+    NotPlatformCaller => "ERR_NOT_PLATFORM_CALLER", "Only the platform contract can manage sessions!";
+    InvalidSessionId => "ERR_INVALID_SESSION_ID", "Session id is too long!";
+    SessionExists => "ERR_SESSION_EXISTS", "Session already holds collateral!";
+    UnknownSession => "ERR_UNKNOWN_SESSION", "Unknown session!";
+    StakeLockedInSession => "ERR_STAKE_LOCKED_IN_SESSION", "Amount is locked as session collateral!";
+    InvalidSessionOutcome => "ERR_INVALID_SESSION_OUTCOME", "Slash share exceeds the collateral!";
+    MemoTooLong => "ERR_MEMO_TOO_LONG", "Memo is too long!";
+    NoBurnMode => "ERR_NO_BURN_MODE", "No burn mode set!";
+    NothingToBurn => "ERR_NOTHING_TO_BURN", "Nothing is pending burn!";
+    AccountFrozen => "ERR_ACCOUNT_FROZEN", "Account is frozen!";
+    AccountNotFrozen => "ERR_ACCOUNT_NOT_FROZEN", "Account is not frozen!";
+    FreezeReasonTooLong => "ERR_FREEZE_REASON_TOO_LONG", "Freeze reason is too long!";
+    StakeNotCancellable => "ERR_STAKE_NOT_CANCELLABLE", "Only a new position can be cancelled within the window!";
+    InvalidPrice => "ERR_INVALID_PRICE", "Price needs a short quote, a positive value and at most 24 decimals!";
+    UnknownQuote => "ERR_UNKNOWN_QUOTE", "No price reported for this quote!";
+    StalePrice => "ERR_STALE_PRICE", "Price is older than the maximum age!";
+    UnknownGroup => "ERR_UNKNOWN_GROUP", "Unknown group!";
+    NotGroupAdmin => "ERR_NOT_GROUP_ADMIN", "Only the group admin can manage members!";
+    NotGroupMember => "ERR_NOT_GROUP_MEMBER", "Not a member of this group!";
+    AlreadyGroupMember => "ERR_ALREADY_GROUP_MEMBER", "Already a member of this group!";
+    InsufficientShares => "ERR_INSUFFICIENT_SHARES", "Not enough group shares!";
+    GroupSlashPending => "ERR_GROUP_SLASH_PENDING", "A slash of this group is open!";
+    GroupWiped => "ERR_GROUP_WIPED", "Group was slashed to nothing!";
+    NotCollateralLocker => "ERR_NOT_COLLATERAL_LOCKER", "Contract is not whitelisted to lock collateral!";
+    LockerNotApproved => "ERR_LOCKER_NOT_APPROVED", "Account has not approved this locker!";
+    LienLimitExceeded => "ERR_LIEN_LIMIT_EXCEEDED", "Lien would exceed the approved limit!";
+    LienLimitBelowLocked => "ERR_LIEN_LIMIT_BELOW_LOCKED", "Limit is below what the locker holds!";
+    StakeLockedAsCollateral => "ERR_STAKE_LOCKED_AS_COLLATERAL", "Amount is locked as collateral!";
+    UnknownDepositMethod => "ERR_UNKNOWN_DEPOSIT_METHOD", "Method does not take a deposit!";
+    InvalidOutboxBudget => "ERR_INVALID_OUTBOX_BUDGET", "Outbox budget must be positive!";
+    CategoryOutOfScope => "ERR_CATEGORY_OUT_OF_SCOPE", "Relay cannot vote in this category!";
+    InvalidCategory => "ERR_INVALID_CATEGORY", "Invalid vote category!";
+    InvalidCategoryWeight => "ERR_INVALID_CATEGORY_WEIGHT", "Category weight exceeds the maximum!";
+    NoGovernanceToken => "ERR_NO_GOVERNANCE_TOKEN", "No governance token configured!";
+    InvalidGovernanceTiers => "ERR_INVALID_GOVERNANCE_TIERS", "Governance tiers must ascend by balance!";
+    AdjustReasonTooLong => "ERR_ADJUST_REASON_TOO_LONG", "Adjustment reason is too long!";
+    UnknownPartnerPool => "ERR_UNKNOWN_PARTNER_POOL", "Partner pool not found!";
+    NotPoolAdmin => "ERR_NOT_POOL_ADMIN", "Only the pool admin can do this!";
+    PartnerPoolPaused => "ERR_PARTNER_POOL_PAUSED", "Partner pool is not taking stakes!";
+    InvalidPoolName => "ERR_INVALID_POOL_NAME", "Invalid pool name!";
+    InvalidVoteMetadata => "ERR_INVALID_VOTE_METADATA", "Invalid vote metadata!";
+    VoteFeeRequired => "ERR_VOTE_FEE_REQUIRED", "Votes must be sent with the vote fee!";
+    VoteFeeDisabled => "ERR_VOTE_FEE_DISABLED", "Paid votes are not enabled!";
+    VoteFeeTooLow => "ERR_VOTE_FEE_TOO_LOW", "Attached amount is below the vote fee!";
+    StateTreeFull => "ERR_STATE_TREE_FULL", "State tree has no free leaf!";
+    InvalidPromotion => "ERR_INVALID_PROMOTION", "Invalid promotion!";
+    TooManyPromotions => "ERR_TOO_MANY_PROMOTIONS", "Too many promotions scheduled!";
+    UnknownPromotion => "ERR_UNKNOWN_PROMOTION", "Unknown promotion!";
+    InvalidSender => "ERR_INVALID_SENDER", "Sender cannot be this contract or the token contract!";
+    InvalidReceiver => "ERR_INVALID_RECEIVER", "Receiver cannot be this contract!";
+    InvalidInsurancePolicy => "ERR_INVALID_INSURANCE_POLICY", "Invalid insurance policy!";
+    NoInsurancePolicy => "ERR_NO_INSURANCE_POLICY", "No insurance policy is offered!";
+    UnknownInsuranceClaim => "ERR_UNKNOWN_INSURANCE_CLAIM", "Unknown insurance claim!";
+    InsuranceClaimNotPayable => "ERR_INSURANCE_CLAIM_NOT_PAYABLE", "Insurance claim is not approved or already paid!";
+    TimestampNotReplayable => "ERR_TIMESTAMP_NOT_REPLAYABLE", "Timestamp is before the last settlement!";
+    AbandonTimeoutTooShort => "ERR_ABANDON_TIMEOUT_TOO_SHORT", "Abandon timeout is too short!";
+    UnknownAbandonedEntry => "ERR_UNKNOWN_ABANDONED_ENTRY", "Unknown abandoned entry!";
+    AbandonedEntryRestored => "ERR_ABANDONED_ENTRY_RESTORED", "Abandoned entry was already restored!";
+    InsufficientTreasury => "ERR_INSUFFICIENT_TREASURY", "Treasury balance is insufficient!";
+    AccessKeysNotEnabled => "ERR_ACCESS_KEYS_NOT_ENABLED", "Enable the no-deposit methods from a full access key first!";
+    SuccessorNotApproved => "ERR_SUCCESSOR_NOT_APPROVED", "Successor contract is not whitelisted!";
+    InvalidVoteCurve => "ERR_INVALID_VOTE_CURVE", "Invalid vote curve!";
+    InvalidSponsorshipDuration => "ERR_INVALID_SPONSORSHIP_DURATION", "Sponsorship duration must be positive!";
+    LegacyPending => "ERR_LEGACY_PENDING", "Legacy records may still be unindexed!";
+    ReconcileOutOfOrder => "ERR_RECONCILE_OUT_OF_ORDER", "Reconcile has to continue where the last page ended!";
+    UsdPegActive => "ERR_USD_PEG_ACTIVE", "The USD peg drives the APR modifier!";
+    AprCurveSet => "ERR_APR_CURVE_SET", "Clear the APR curve first!";
+    InvalidUsdPeg => "ERR_INVALID_USD_PEG", "Invalid USD peg!";
+    TransfersDegraded => "ERR_TRANSFERS_DEGRADED", "Token transfers are failing, try again later!";
+    InvalidFailureThreshold => "ERR_INVALID_FAILURE_THRESHOLD", "Failure threshold must be positive!";
+    NotDegraded => "ERR_NOT_DEGRADED", "Transfers are not degraded!";
+    NothingToProbe => "ERR_NOTHING_TO_PROBE", "The outbox is empty, reset instead!";
+    InvalidMilestoneId => "ERR_INVALID_MILESTONE_ID", "Invalid milestone id!";
+    InsufficientMilestoneBudget => "ERR_INSUFFICIENT_MILESTONE_BUDGET", "Milestone budget is too low!";
+    AppealNotExpired => "ERR_APPEAL_NOT_EXPIRED", "The appeal has not expired yet!";
+    InvalidAppealExpiry => "ERR_INVALID_APPEAL_EXPIRY", "Appeal expiry must be positive!";
+    OpAlreadyProcessed => "ERR_OP_ALREADY_PROCESSED", "Op id was already processed!";
+    InvalidActionTtl => "ERR_INVALID_ACTION_TTL", "Action ttl is too short!";
+    InvalidChallengePeriod => "ERR_INVALID_CHALLENGE_PERIOD", "Challenge period is too short!";
+    StakeLocked => "ERR_STAKE_LOCKED", "Stake is locked in a position!";
+    UnknownPosition => "ERR_UNKNOWN_POSITION", "Unknown position!";
+    TooManyPositions => "ERR_TOO_MANY_POSITIONS", "Too many positions!";
+    InvalidLockDuration => "ERR_INVALID_LOCK_DURATION", "Invalid lock duration!";
+    InvalidPositionMerge => "ERR_INVALID_POSITION_MERGE", "Merge needs two or more distinct positions!";
+    StoragePayerMismatch => "ERR_STORAGE_PAYER_MISMATCH", "Storage deposit belongs to another payer!";
+    StorageInUse => "ERR_STORAGE_IN_USE", "Account still has records in use!";
+    TokenBalanceUnknown => "ERR_TOKEN_BALANCE_UNKNOWN", "Token balance was never fetched, call refresh_token_balance!";
+    InvariantViolated => "ERR_INVARIANT_VIOLATED", "Invariant violated";
+}
+
+// lets an error stand in for the message in `require!` and `env::panic_str`
+impl Deref for StakeError {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for StakeError {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
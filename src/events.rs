@@ -0,0 +1,249 @@
+use near_sdk::json_types::{I64, U128, U64};
+use near_sdk::serde::Serialize;
+use near_sdk::{env, AccountId};
+
+use crate::badges::Badge;
+use crate::time::Timestamp;
+
+pub const EVENT_STANDARD: &str = "spk-stake";
+pub const EVENT_VERSION: &str = "1.0.0";
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BonusDistributedData {
+    pub distribution_id: u64,
+    pub amount: U128,
+    pub total_staked: U128,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SlashData {
+    pub proposal_id: u64,
+    pub advisor_id: AccountId,
+    pub amount: U128,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RatingPushFailedData {
+    pub advisor_id: AccountId,
+    pub new_apr: U128,
+    pub votes: u8,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct VoteRecordedData {
+    pub vote_id: u64,
+    pub advisor_id: AccountId,
+    pub rating: u8,
+    pub reputation_delta: I64,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AppealResolvedData {
+    pub vote_id: u64,
+    pub advisor_id: AccountId,
+    pub reversed: bool,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RevenueSharedData {
+    pub epoch_height: U64,
+    pub amount: U128,
+    pub total_staked: U128,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StakePartiallyAcceptedData {
+    pub account_id: AccountId,
+    pub accepted: U128,
+    pub refunded: U128,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BadgeChangedData {
+    pub account_id: AccountId,
+    pub old_badge: Option<Badge>,
+    pub new_badge: Option<Badge>,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CommissionChangedData {
+    pub lead_id: AccountId,
+    pub old_commission_bps: u16,
+    pub new_commission_bps: u16,
+    pub effective_at: Timestamp,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CommissionClaimedData {
+    pub lead_id: AccountId,
+    pub amount: U128,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BreakerTrippedData {
+    pub hourly_outflow: U128,
+    pub daily_outflow: U128,
+    pub hourly_limit: U128,
+    pub daily_limit: U128,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct WithdrawnData {
+    pub account_id: AccountId,
+    pub amount: U128,
+    pub memo: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct UnstakedData {
+    pub account_id: AccountId,
+    pub amount: U128,
+    pub fee: U128,
+    pub receiver_id: AccountId,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AccountFrozenData {
+    pub account_id: AccountId,
+    pub reason: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TransferQueuedData {
+    pub account_id: AccountId,
+    pub receiver_id: AccountId,
+    pub amount: U128,
+    pub position: U64,
+}
+
+// one run of micro-claims, in place of an event per claim
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RewardStreamedData {
+    pub account_id: AccountId,
+    pub amount: U128,
+    pub claims: u32,
+    pub since: Timestamp,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RewardAdjustedData {
+    pub account_id: AccountId,
+    pub old_reward: U128,
+    pub new_reward: U128,
+    pub reason: String,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AbandonedData {
+    pub entry_id: u64,
+    pub account_id: AccountId,
+    pub amount: U128,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MilestoneRecordedData {
+    pub advisor_id: AccountId,
+    pub milestone_id: String,
+    pub bonus: U128,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TransferHealthData {
+    pub consecutive_failures: u32,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StorageDepositData {
+    pub account_id: AccountId,
+    pub payer_id: AccountId,
+    pub amount: U128,
+    pub bytes: U64,
+}
+
+// a counter reconcile found off and set to what the positions add up to
+#[derive(Serialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CounterPatchedData {
+    pub counter: String,
+    pub old: U128,
+    pub new: U128,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(tag = "event", content = "data", rename_all = "snake_case")]
+pub enum StakeEvent {
+    BonusDistributed(Vec<BonusDistributedData>),
+    SlashProposed(Vec<SlashData>),
+    SlashExecuted(Vec<SlashData>),
+    RatingPushFailed(Vec<RatingPushFailedData>),
+    VoteRecorded(Vec<VoteRecordedData>),
+    AppealResolved(Vec<AppealResolvedData>),
+    RevenueShared(Vec<RevenueSharedData>),
+    StakePartiallyAccepted(Vec<StakePartiallyAcceptedData>),
+    BadgeChanged(Vec<BadgeChangedData>),
+    CommissionChanged(Vec<CommissionChangedData>),
+    CommissionClaimed(Vec<CommissionClaimedData>),
+    BreakerTripped(Vec<BreakerTrippedData>),
+    Withdrawn(Vec<WithdrawnData>),
+    Unstaked(Vec<UnstakedData>),
+    AccountFrozen(Vec<AccountFrozenData>),
+    AccountUnfrozen(Vec<AccountFrozenData>),
+    TransferQueued(Vec<TransferQueuedData>),
+    RewardStreamed(Vec<RewardStreamedData>),
+    RewardAdjusted(Vec<RewardAdjustedData>),
+    AbandonedSwept(Vec<AbandonedData>),
+    AbandonedRestored(Vec<AbandonedData>),
+    CountersPatched(Vec<CounterPatchedData>),
+    TransfersDegraded(Vec<TransferHealthData>),
+    TransfersResumed(Vec<TransferHealthData>),
+    MilestoneRecorded(Vec<MilestoneRecordedData>),
+    AppealExpired(Vec<AppealResolvedData>),
+    StorageDeposited(Vec<StorageDepositData>),
+    StorageRefunded(Vec<StorageDepositData>),
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct EventLog<'a> {
+    standard: &'static str,
+    version: &'static str,
+    #[serde(flatten)]
+    event: &'a StakeEvent,
+}
+
+impl StakeEvent {
+    // nep-297 log line
+    pub fn emit(&self) {
+        let log = EventLog {
+            standard: EVENT_STANDARD,
+            version: EVENT_VERSION,
+            event: self,
+        };
+        env::log_str(&format!(
+            "EVENT_JSON:{}",
+            near_sdk::serde_json::to_string(&log).unwrap()
+        ));
+    }
+}
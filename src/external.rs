@@ -1,7 +1,84 @@
-use near_sdk::json_types::U128;
+use near_contract_standards::fungible_token::metadata::FungibleTokenMetadata;
+use near_contract_standards::non_fungible_token::{Token, TokenId};
+use near_contract_standards::storage_management::{StorageBalance, StorageBalanceBounds};
+use near_sdk::json_types::{U128, U64};
 use near_sdk::{ext_contract, AccountId};
 
+use crate::hooks::HookEvent;
+
 #[ext_contract(ext_ft_contract)]
 pub trait FungibleTokenCore {
     fn ft_transfer(&mut self, receiver_id: AccountId, amount: U128, memo: Option<String>);
+    fn ft_transfer_call(
+        &mut self,
+        receiver_id: AccountId,
+        amount: U128,
+        memo: Option<String>,
+        msg: String,
+    ) -> U128;
+    fn ft_balance_of(&self, account_id: AccountId) -> U128;
+    fn ft_metadata(&self) -> FungibleTokenMetadata;
+    // nep-145 on the token, a receiver has to be registered before ft_transfer to it
+    // can succeed
+    fn storage_deposit(
+        &mut self,
+        account_id: Option<AccountId>,
+        registration_only: Option<bool>,
+    ) -> StorageBalance;
+    fn storage_balance_of(&self, account_id: AccountId) -> Option<StorageBalance>;
+    fn storage_balance_bounds(&self) -> StorageBalanceBounds;
+}
+
+// the platform's course catalogue, course ids used in course stakes and votes come
+// from it
+#[ext_contract(ext_course_registry)]
+pub trait CourseRegistry {
+    fn is_course_active(&self, course_id: String) -> bool;
+    fn get_course_advisor(&self, course_id: String) -> Option<AccountId>;
+}
+
+// read side of the oracle feeding report_metric and report_price, for pulling a
+// value instead of waiting for the next push
+#[ext_contract(ext_oracle)]
+pub trait Oracle {
+    fn get_metric(&self) -> U128;
+    // price scaled by 10^decimals and the timestamp it was observed at
+    fn get_price(&self, quote: String) -> Option<(U128, u8, U64)>;
+}
+
+#[ext_contract(ext_attestation_registry)]
+pub trait AttestationRegistry {
+    fn is_verified(&self, account_id: AccountId) -> bool;
+}
+
+#[ext_contract(ext_wrap_near)]
+pub trait WrapNear {
+    fn near_deposit(&mut self);
+}
+
+#[ext_contract(ext_platform)]
+pub trait Platform {
+    fn on_rating_changed(&mut self, advisor_id: AccountId, new_apr: U128, votes: u8);
+}
+
+#[ext_contract(ext_stake_hook)]
+pub trait StakeHook {
+    fn on_stake_event(&mut self, event: HookEvent, account_id: AccountId, amount: U128);
+}
+
+#[ext_contract(ext_nft)]
+pub trait NonFungibleTokenCore {
+    fn nft_token(&self, token_id: TokenId) -> Option<Token>;
+}
+
+// multi fungible token shares, e.g. ref.finance pool shares
+#[ext_contract(ext_mft)]
+pub trait MultiFungibleToken {
+    fn mft_transfer(
+        &mut self,
+        token_id: String,
+        receiver_id: AccountId,
+        amount: U128,
+        memo: Option<String>,
+    );
 }
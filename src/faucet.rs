@@ -0,0 +1,78 @@
+use near_sdk::json_types::{U128, U64};
+use near_sdk::{env, near_bindgen, AccountId, Promise, PromiseError, ONE_YOCTO};
+
+use crate::time::{Duration, Timestamp};
+use crate::*;
+
+pub const DEFAULT_FAUCET_COOLDOWN: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[near_bindgen]
+impl Contract {
+    // testnet onboarding, sends `faucet_amount` of the stake token once per cooldown
+    pub fn faucet_claim(&mut self) -> Promise {
+        self.internal_assert_transfers_up();
+        let account_id = env::predecessor_account_id();
+        let amount = self.faucet_amount;
+        require!(amount > 0, StakeError::FaucetDisabled);
+        require!(self.faucet_balance >= amount, StakeError::FaucetEmpty);
+        let previous_claim = self.faucet_last_claim.get(&account_id);
+        if let Some(last_claim) = previous_claim {
+            require!(
+                Self::now() >= last_claim + self.faucet_cooldown,
+                StakeError::FaucetCooldown
+            );
+        }
+
+        self.faucet_balance -= amount;
+        self.pending_outflows += amount;
+        self.faucet_last_claim.insert(&account_id, &Self::now());
+
+        ext_ft_contract::ext(self.token_address.clone())
+            .with_static_gas(FT_TRANSFER_GAS)
+            .with_attached_deposit(ONE_YOCTO)
+            .ft_transfer(account_id.clone(), U128(amount), None)
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(FAUCET_CALLBACK_GAS)
+                    .on_faucet_claim(account_id, U128(amount), previous_claim),
+            )
+    }
+
+    #[private]
+    pub fn on_faucet_claim(
+        &mut self,
+        account_id: AccountId,
+        amount: U128,
+        previous_claim: Option<Timestamp>,
+        #[callback_result] result: Result<(), PromiseError>,
+    ) -> U128 {
+        self.internal_note_promise_result(
+            "on_faucet_claim",
+            Some(&account_id),
+            amount.0,
+            result.is_ok(),
+        );
+        self.internal_note_token_transfer(&account_id, result.is_ok());
+        self.pending_outflows -= amount.0;
+        if result.is_ok() {
+            self.internal_note_outflow(amount.0);
+            return amount;
+        }
+        self.faucet_balance += amount.0;
+        match previous_claim {
+            Some(last_claim) => self.faucet_last_claim.insert(&account_id, &last_claim),
+            None => self.faucet_last_claim.remove(&account_id),
+        };
+        U128(0)
+    }
+
+    pub fn set_faucet_config(&mut self, amount: U128, cooldown: U64) {
+        self.internal_assert_role(Role::Admin);
+        self.faucet_amount = amount.0;
+        self.faucet_cooldown = Duration::from_nanos(cooldown.0);
+    }
+
+    pub fn faucet_remaining(&self) -> U128 {
+        U128(self.faucet_balance)
+    }
+}
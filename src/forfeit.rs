@@ -0,0 +1,60 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{near_bindgen, require};
+
+use crate::*;
+
+// where stake and reward a position gives up ends
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub enum ForfeitDestination {
+    RewardPool,
+    // the protocol fee balance, claimed to the fee account
+    Treasury,
+    // held until execute_burn burns it
+    Burn,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ForfeitView {
+    pub destination: ForfeitDestination,
+    pub pending_burn: U128,
+    pub total_forfeited: U128,
+}
+
+impl Contract {
+    // every forfeiture goes through here
+    pub(crate) fn internal_forfeit(&mut self, amount: u128) {
+        if amount == 0 {
+            return;
+        }
+        match self.forfeit_destination {
+            ForfeitDestination::RewardPool => self.reward_pool += amount,
+            ForfeitDestination::Treasury => self.protocol_fee_balance += amount,
+            ForfeitDestination::Burn => self.pending_burn += amount,
+        }
+        self.total_forfeited += amount;
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn set_forfeit_destination(&mut self, destination: ForfeitDestination) {
+        self.internal_assert_role(Role::Admin);
+        require!(
+            destination != ForfeitDestination::Burn || self.burn_mode.is_some(),
+            StakeError::NoBurnMode
+        );
+        self.forfeit_destination = destination;
+    }
+
+    pub fn get_forfeit(&self) -> ForfeitView {
+        ForfeitView {
+            destination: self.forfeit_destination,
+            pending_burn: U128(self.pending_burn),
+            total_forfeited: U128(self.total_forfeited),
+        }
+    }
+}
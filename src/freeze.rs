@@ -0,0 +1,60 @@
+use near_sdk::{near_bindgen, require, AccountId};
+
+use crate::events::{AccountFrozenData, StakeEvent};
+use crate::*;
+
+pub const MAX_FREEZE_REASON_LEN: usize = 256;
+
+impl Contract {
+    // a frozen account keeps its stake and keeps accruing unless pause_accrual_on_hold
+    // is set, nothing leaves it
+    pub(crate) fn internal_assert_not_frozen(&self, account_id: &AccountId) {
+        require!(
+            !self.frozen_accounts.contains_key(account_id),
+            StakeError::AccountFrozen
+        );
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn freeze_account(&mut self, account_id: AccountId, reason: String) {
+        self.internal_assert_role(Role::Compliance);
+        require!(
+            reason.len() <= MAX_FREEZE_REASON_LEN,
+            StakeError::FreezeReasonTooLong
+        );
+        require!(
+            self.frozen_accounts.insert(&account_id, &reason).is_none(),
+            StakeError::AccountFrozen
+        );
+        self.internal_sync_accrual_pause(&account_id);
+        StakeEvent::AccountFrozen(vec![AccountFrozenData {
+            account_id,
+            reason: Some(reason),
+        }])
+        .emit();
+    }
+
+    pub fn unfreeze_account(&mut self, account_id: AccountId) {
+        self.internal_assert_role(Role::Compliance);
+        require!(
+            self.frozen_accounts.remove(&account_id).is_some(),
+            StakeError::AccountNotFrozen
+        );
+        self.internal_sync_accrual_pause(&account_id);
+        StakeEvent::AccountUnfrozen(vec![AccountFrozenData {
+            account_id,
+            reason: None,
+        }])
+        .emit();
+    }
+
+    pub fn is_frozen(&self, account_id: AccountId) -> bool {
+        self.frozen_accounts.contains_key(&account_id)
+    }
+
+    pub fn get_freeze_reason(&self, account_id: AccountId) -> Option<String> {
+        self.frozen_accounts.get(&account_id)
+    }
+}
@@ -0,0 +1,209 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, require, AccountId, Gas, Promise, PromiseError, PromiseOrValue};
+
+use crate::time::Timestamp;
+use crate::*;
+
+// a claim can go on to check the booster nft from here
+pub const GOVERNANCE_CALLBACK_GAS: Gas = Gas(50_000_000_000_000);
+pub const MAX_GOVERNANCE_TIERS: usize = 10;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct GovernanceTier {
+    pub min_balance: U128,
+    pub bonus_bps: u32,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Clone, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct GovernanceBoost {
+    pub balance: U128,
+    pub bonus_bps: u32,
+    pub verified_at: Timestamp,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct GovernanceConfig {
+    pub token_id: Option<AccountId>,
+    pub tiers: Vec<GovernanceTier>,
+}
+
+impl Contract {
+    // the highest tier the balance reaches, tiers are kept ascending
+    fn internal_governance_bonus_bps(&self, balance: u128) -> u32 {
+        self.governance_tiers
+            .iter()
+            .rev()
+            .find(|tier| balance >= tier.min_balance.0)
+            .map_or(0, |tier| tier.bonus_bps)
+    }
+
+    // like internal_set_booster the time since the last touch accrues at the new rate
+    fn internal_set_governance_boost(
+        &mut self,
+        account_id: &AccountId,
+        governance: Option<GovernanceBoost>,
+    ) {
+        let mut stake_info = match self.internal_get_stake_info(account_id) {
+            Some(stake_info) => stake_info,
+            None => return,
+        };
+        let old_apr = stake_info.boosted(stake_info.apr);
+        stake_info.terms.governance = governance;
+        self.internal_update_reward_rate(
+            stake_info.amount_staked,
+            old_apr,
+            stake_info.amount_staked,
+            stake_info.boosted(stake_info.apr),
+        );
+        self.internal_save_stake_info(account_id, &stake_info);
+    }
+
+    pub(crate) fn internal_drop_stale_governance(&mut self, account_id: &AccountId) {
+        let stale = self
+            .internal_get_stake_info(account_id)
+            .and_then(|stake_info| stake_info.terms.governance)
+            .is_some_and(|governance| {
+                self.governance_token.is_none()
+                    || Self::now() >= governance.verified_at + BOOSTER_VERIFY_TTL
+            });
+        if stale {
+            self.internal_set_governance_boost(account_id, None);
+        }
+    }
+
+    pub(crate) fn internal_verify_governance(
+        &self,
+        account_id: AccountId,
+        token_id: AccountId,
+        claim: bool,
+    ) -> Promise {
+        ext_ft_contract::ext(token_id)
+            .with_static_gas(FT_BALANCE_OF_GAS)
+            .ft_balance_of(account_id.clone())
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(GOVERNANCE_CALLBACK_GAS)
+                    .on_governance_verified(account_id, claim),
+            )
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // the bonus applies from the first touch after the balance is read
+    pub fn verify_governance_balance(&mut self) -> Promise {
+        let account_id = env::predecessor_account_id();
+        require!(
+            self.internal_get_stake_info(&account_id).is_some(),
+            StakeError::NotStaked
+        );
+        let token_id = self
+            .governance_token
+            .clone()
+            .unwrap_or_else(|| env::panic_str(&StakeError::NoGovernanceToken));
+        self.internal_verify_governance(account_id, token_id, false)
+    }
+
+    // a claim goes on to check the booster, if any, and then claims
+    #[private]
+    pub fn on_governance_verified(
+        &mut self,
+        account_id: AccountId,
+        claim: bool,
+        #[callback_result] balance: Result<U128, PromiseError>,
+    ) -> PromiseOrValue<U128> {
+        let current = self
+            .internal_get_stake_info(&account_id)
+            .and_then(|stake_info| stake_info.terms.governance);
+        match balance {
+            Ok(balance) if self.governance_token.is_some() => {
+                let bonus_bps = self.internal_governance_bonus_bps(balance.0);
+                let governance = (bonus_bps > 0).then(|| GovernanceBoost {
+                    balance,
+                    bonus_bps,
+                    verified_at: Self::now(),
+                });
+                // settle at the old rate when the bonus grows, a smaller one covers
+                // everything not settled yet
+                if bonus_bps > current.as_ref().map_or(0, |current| current.bonus_bps) {
+                    if let Some(stake_info) = self.internal_touch(&account_id) {
+                        self.internal_save_stake_info(&account_id, &stake_info);
+                    }
+                }
+                self.internal_set_governance_boost(&account_id, governance);
+            }
+            _ if claim => self.internal_set_governance_boost(&account_id, None),
+            _ => {}
+        }
+        if !claim {
+            return PromiseOrValue::Value(U128(0));
+        }
+        let booster = self
+            .internal_get_stake_info(&account_id)
+            .and_then(|stake_info| stake_info.terms.booster);
+        if let Some(booster) = booster {
+            return PromiseOrValue::Promise(self.internal_verify_booster(
+                account_id,
+                booster.nft_contract,
+                booster.token_id,
+                true,
+            ));
+        }
+        let claimed = self.internal_claim_reward(&account_id, None);
+        self.internal_maybe_snapshot();
+        PromiseOrValue::Value(U128(claimed))
+    }
+
+    // tiers ascending by min_balance, boosts already granted lapse on their next claim
+    // when the token is unset
+    pub fn set_governance_token(
+        &mut self,
+        token_id: Option<AccountId>,
+        tiers: Vec<GovernanceTier>,
+    ) {
+        self.internal_assert_role(Role::Admin);
+        require!(
+            tiers.len() <= MAX_GOVERNANCE_TIERS,
+            StakeError::InvalidGovernanceTiers
+        );
+        require!(
+            tiers
+                .windows(2)
+                .all(|pair| pair[0].min_balance.0 < pair[1].min_balance.0),
+            StakeError::InvalidGovernanceTiers
+        );
+        require!(
+            tiers.iter().all(|tier| tier.bonus_bps <= MAX_BOOST_BPS),
+            StakeError::InvalidBoost
+        );
+        self.internal_record_config_change(
+            "governance_token",
+            self.governance_token.clone(),
+            token_id.clone(),
+        );
+        self.internal_record_config_change(
+            "governance_tiers",
+            self.governance_tiers.clone(),
+            tiers.clone(),
+        );
+        self.governance_token = token_id;
+        self.governance_tiers = tiers;
+    }
+
+    pub fn get_governance_config(&self) -> GovernanceConfig {
+        GovernanceConfig {
+            token_id: self.governance_token.clone(),
+            tiers: self.governance_tiers.clone(),
+        }
+    }
+
+    pub fn get_governance_boost(&self, account_id: AccountId) -> Option<GovernanceBoost> {
+        self.internal_get_stake_info(&account_id)
+            .and_then(|stake_info| stake_info.terms.governance)
+    }
+}
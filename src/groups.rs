@@ -0,0 +1,365 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::Serialize;
+use near_sdk::{env, near_bindgen, require, AccountId};
+
+use crate::math::{mul_div, Rate, BPS_DENOMINATOR};
+use crate::time::{Timestamp, NANOS_PER_YEAR};
+use crate::*;
+
+// scale of acc_reward_per_share
+pub const GROUP_REWARD_PRECISION: u128 = 1_000_000_000_000_000_000_000_000;
+
+// a team position, e.g. a tutoring agency. members move part of their own stake in for
+// shares, rewards accrue per share at the group's apr and a slash lowers what every
+// share is worth, so both land pro-rata
+#[derive(BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Group {
+    pub group_id: u64,
+    pub admin_id: AccountId,
+    // the default apr when the group was created
+    pub apr: U128,
+    pub total_stake: U128,
+    pub total_shares: U128,
+    pub members: u64,
+    pub acc_reward_per_share: U128,
+    pub apr_index_paid: U128,
+    pub open_slash: Option<u64>,
+    pub created_at: Timestamp,
+}
+
+// kept from add_group_member until the member is removed, `principal` is the stake
+// moved in for the shares still held
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct GroupMember {
+    pub shares: u128,
+    pub principal: u128,
+    pub reward_per_share_paid: u128,
+    pub reward: u128,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct GroupMemberView {
+    pub shares: U128,
+    pub principal: U128,
+    pub value: U128,
+    pub reward: U128,
+}
+
+impl Group {
+    fn settle(&self, member: &mut GroupMember) {
+        member.reward += mul_div(
+            member.shares,
+            self.acc_reward_per_share.0 - member.reward_per_share_paid,
+            GROUP_REWARD_PRECISION,
+        );
+        member.reward_per_share_paid = self.acc_reward_per_share.0;
+    }
+
+    fn value_of(&self, shares: u128) -> u128 {
+        if self.total_shares.0 == 0 {
+            return 0;
+        }
+        mul_div(shares, self.total_stake.0, self.total_shares.0)
+    }
+}
+
+impl Contract {
+    pub(crate) fn internal_get_group(&self, group_id: u64) -> Group {
+        self.groups
+            .get(&group_id)
+            .unwrap_or_else(|| env::panic_str(&StakeError::UnknownGroup))
+    }
+
+    fn internal_get_group_member(&self, group_id: u64, account_id: &AccountId) -> GroupMember {
+        self.group_members
+            .get(&(group_id, account_id.clone()))
+            .unwrap_or_else(|| env::panic_str(&StakeError::NotGroupMember))
+    }
+
+    fn internal_assert_group_admin(group: &Group) {
+        require!(
+            group.admin_id == env::predecessor_account_id(),
+            StakeError::NotGroupAdmin
+        );
+    }
+
+    pub(crate) fn internal_update_group(&self, group: &mut Group, now: Timestamp) {
+        let apr_index = self.internal_apr_index_at(now);
+        if group.total_shares.0 > 0 {
+            let gross = mul_div(
+                Rate::from_raw(group.apr.0).annual_reward(group.total_stake.0),
+                apr_index - group.apr_index_paid.0,
+                BPS_DENOMINATOR * NANOS_PER_YEAR as u128,
+            );
+            group.acc_reward_per_share.0 +=
+                mul_div(gross, GROUP_REWARD_PRECISION, group.total_shares.0);
+        }
+        group.apr_index_paid = U128(apr_index);
+    }
+
+    fn internal_set_group_stake(&mut self, group: &mut Group, total_stake: u128) {
+        self.internal_update_reward_rate(
+            group.total_stake.0,
+            group.apr.0,
+            total_stake,
+            group.apr.0,
+        );
+        self.total_grouped = self.total_grouped - group.total_stake.0 + total_stake;
+        group.total_stake = U128(total_stake);
+    }
+
+    // burns `shares` and moves what they are worth back into the member's own position,
+    // the part of the principal a slash took is accounted as unstaked
+    fn internal_leave_group(
+        &mut self,
+        group: &mut Group,
+        member: &mut GroupMember,
+        account_id: &AccountId,
+        shares: u128,
+    ) -> u128 {
+        require!(group.open_slash.is_none(), StakeError::GroupSlashPending);
+        require!(
+            shares > 0 && shares <= member.shares,
+            StakeError::InsufficientShares
+        );
+        // the last shares out take whatever rounding left in the group
+        let value = if shares == group.total_shares.0 {
+            group.total_stake.0
+        } else {
+            group.value_of(shares)
+        };
+        let principal = mul_div(member.principal, shares, member.shares);
+        let total_stake = group.total_stake.0 - value;
+        self.internal_set_group_stake(group, total_stake);
+        group.total_shares.0 -= shares;
+        member.shares -= shares;
+        member.principal -= principal;
+
+        let mut stake_info = self
+            .internal_touch(account_id)
+            .unwrap_or_else(|| env::panic_str(&StakeError::NotStaked));
+        self.internal_update_reward_rate(
+            stake_info.amount_staked,
+            stake_info.boosted(stake_info.apr),
+            stake_info.amount_staked + value,
+            stake_info.boosted(stake_info.apr),
+        );
+        stake_info.amount_staked += value;
+        stake_info.grouped -= principal;
+        if principal >= value {
+            stake_info.lifetime_unstaked += principal - value;
+        } else {
+            stake_info.lifetime_staked += value - principal;
+        }
+        stake_info.sync_streak(Self::now());
+        self.internal_save_stake_info(account_id, &stake_info);
+        value
+    }
+
+    // slashed stake goes where an advisor's slashed stake goes
+    pub(crate) fn internal_slash_group(&mut self, group_id: u64, amount: u128) -> u128 {
+        let mut group = self.internal_get_group(group_id);
+        self.internal_update_group(&mut group, Self::now());
+        let amount = amount.min(group.total_stake.0);
+        let total_stake = group.total_stake.0 - amount;
+        self.internal_set_group_stake(&mut group, total_stake);
+        self.groups.insert(&group_id, &group);
+        self.total_staked -= amount;
+        if !self.internal_collect_penalty(amount) {
+            self.reward_pool += amount;
+        }
+        self.total_slashed += amount;
+        amount
+    }
+
+    pub(crate) fn internal_set_group_open_slash(&mut self, group_id: u64, proposal: Option<u64>) {
+        let mut group = self.internal_get_group(group_id);
+        group.open_slash = proposal;
+        self.groups.insert(&group_id, &group);
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn create_group(&mut self) -> u64 {
+        self.internal_assert_not_paused();
+        self.group_count += 1;
+        let group_id = self.group_count;
+        self.groups.insert(
+            &group_id,
+            &Group {
+                group_id,
+                admin_id: env::predecessor_account_id(),
+                apr: U128(self.default_apr),
+                total_stake: U128(0),
+                total_shares: U128(0),
+                members: 0,
+                acc_reward_per_share: U128(0),
+                apr_index_paid: U128(self.internal_apr_index_at(Self::now())),
+                open_slash: None,
+                created_at: Self::now(),
+            },
+        );
+        group_id
+    }
+
+    pub fn add_group_member(&mut self, group_id: u64, account_id: AccountId) {
+        let mut group = self.internal_get_group(group_id);
+        Self::internal_assert_group_admin(&group);
+        let key = (group_id, account_id);
+        require!(
+            !self.group_members.contains_key(&key),
+            StakeError::AlreadyGroupMember
+        );
+        self.group_members.insert(
+            &key,
+            &GroupMember {
+                shares: 0,
+                principal: 0,
+                reward_per_share_paid: group.acc_reward_per_share.0,
+                reward: 0,
+            },
+        );
+        group.members += 1;
+        self.groups.insert(&group_id, &group);
+    }
+
+    // hands the member's shares back as stake in their own position and pays out the
+    // reward they earned in the group
+    pub fn remove_group_member(&mut self, group_id: u64, account_id: AccountId) {
+        let mut group = self.internal_get_group(group_id);
+        Self::internal_assert_group_admin(&group);
+        self.internal_update_group(&mut group, Self::now());
+        let mut member = self.internal_get_group_member(group_id, &account_id);
+        group.settle(&mut member);
+        if member.shares > 0 {
+            let shares = member.shares;
+            self.internal_leave_group(&mut group, &mut member, &account_id, shares);
+        }
+        group.members -= 1;
+        self.groups.insert(&group_id, &group);
+        self.group_members.remove(&(group_id, account_id.clone()));
+        if member.reward > 0 {
+            self.internal_pay_delegation_reward(&account_id, member.reward);
+        }
+    }
+
+    // moves `amount` of the caller's own stake into the group, returns the shares minted
+    #[payable]
+    pub fn contribute_to_group(&mut self, group_id: u64, amount: U128) -> U128 {
+        self.internal_assert_deposit("contribute_to_group");
+        self.internal_assert_not_paused();
+        let account_id = env::predecessor_account_id();
+        let amount = amount.0;
+        require!(amount > 0, StakeError::InvalidAmount);
+        let mut group = self.internal_get_group(group_id);
+        require!(group.open_slash.is_none(), StakeError::GroupSlashPending);
+        // shares of a group slashed down to nothing are worth nothing, new stake would
+        // be diluted into them
+        require!(
+            group.total_shares.0 == 0 || group.total_stake.0 > 0,
+            StakeError::GroupWiped
+        );
+        self.internal_update_group(&mut group, Self::now());
+        let mut member = self.internal_get_group_member(group_id, &account_id);
+        group.settle(&mut member);
+
+        let mut stake_info = self
+            .internal_touch(&account_id)
+            .unwrap_or_else(|| env::panic_str(&StakeError::NotStaked));
+        Self::internal_assert_no_pending_operation(&stake_info);
+        self.internal_assert_no_open_slash(&account_id);
+        require!(
+            stake_info.amount_staked >= amount,
+            StakeError::InsufficientStake
+        );
+        self.internal_assert_stake_free(&account_id, &stake_info, amount);
+        self.internal_remove_course_stake(&account_id, &stake_info, None, amount);
+        self.internal_update_reward_rate(
+            stake_info.amount_staked,
+            stake_info.boosted(stake_info.apr),
+            stake_info.amount_staked - amount,
+            stake_info.boosted(stake_info.apr),
+        );
+        stake_info.amount_staked -= amount;
+        stake_info.grouped += amount;
+        stake_info.sync_streak(Self::now());
+        self.internal_save_stake_info(&account_id, &stake_info);
+
+        let shares = if group.total_shares.0 == 0 {
+            amount
+        } else {
+            mul_div(amount, group.total_shares.0, group.total_stake.0)
+        };
+        let total_stake = group.total_stake.0 + amount;
+        self.internal_set_group_stake(&mut group, total_stake);
+        group.total_shares.0 += shares;
+        member.shares += shares;
+        member.principal += amount;
+        self.groups.insert(&group_id, &group);
+        self.group_members.insert(&(group_id, account_id), &member);
+        U128(shares)
+    }
+
+    // every share when `shares` is None, returns the stake moved back into the position
+    #[payable]
+    pub fn leave_group(&mut self, group_id: u64, shares: Option<U128>) -> U128 {
+        self.internal_assert_deposit("leave_group");
+        let account_id = env::predecessor_account_id();
+        let mut group = self.internal_get_group(group_id);
+        self.internal_update_group(&mut group, Self::now());
+        let mut member = self.internal_get_group_member(group_id, &account_id);
+        group.settle(&mut member);
+        let shares = shares.map_or(member.shares, |shares| shares.0);
+        let value = self.internal_leave_group(&mut group, &mut member, &account_id, shares);
+        self.groups.insert(&group_id, &group);
+        self.group_members.insert(&(group_id, account_id), &member);
+        U128(value)
+    }
+
+    // paid out of the reward pool like the position's own reward
+    #[payable]
+    pub fn claim_group_reward(&mut self, group_id: u64) -> U128 {
+        self.internal_assert_deposit("claim_group_reward");
+        let account_id = env::predecessor_account_id();
+        let mut group = self.internal_get_group(group_id);
+        self.internal_update_group(&mut group, Self::now());
+        let mut member = self.internal_get_group_member(group_id, &account_id);
+        group.settle(&mut member);
+        let reward = member.reward;
+        require!(reward > 0, StakeError::NoReward);
+        member.reward = 0;
+        self.groups.insert(&group_id, &group);
+        self.group_members
+            .insert(&(group_id, account_id.clone()), &member);
+        self.internal_pay_delegation_reward(&account_id, reward);
+        U128(reward)
+    }
+
+    pub fn get_group(&self, group_id: u64) -> Option<Group> {
+        self.groups.get(&group_id).map(|mut group| {
+            self.internal_update_group(&mut group, Self::now());
+            group
+        })
+    }
+
+    pub fn get_group_member(
+        &self,
+        group_id: u64,
+        account_id: AccountId,
+    ) -> Option<GroupMemberView> {
+        let mut member = self.group_members.get(&(group_id, account_id))?;
+        let mut group = self.internal_get_group(group_id);
+        self.internal_update_group(&mut group, Self::now());
+        group.settle(&mut member);
+        Some(GroupMemberView {
+            shares: U128(member.shares),
+            principal: U128(member.principal),
+            value: U128(group.value_of(member.shares)),
+            reward: U128(member.reward),
+        })
+    }
+}
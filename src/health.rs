@@ -0,0 +1,48 @@
+use near_sdk::json_types::{U128, U64};
+use near_sdk::near_bindgen;
+use near_sdk::serde::Serialize;
+
+use crate::math::{mul_div, BPS_DENOMINATOR};
+use crate::*;
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Health {
+    pub version: String,
+    pub paused: bool,
+    pub import_finished: bool,
+    pub upgrade_staged: bool,
+    pub breaker_tripped: bool,
+    // token transfers kept failing, withdrawals wait in the outbox
+    pub transfers_degraded: bool,
+    pub consecutive_transfer_failures: u32,
+    pub reward_pool: U128,
+    pub outstanding_rewards: U128,
+    // reward pool over outstanding rewards, None while nothing is outstanding
+    pub solvency_bps: Option<U128>,
+    pub solvent: bool,
+    pub last_mutation_at: U64,
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn health(&self) -> Health {
+        let outstanding = self.accrued_rewards + self.internal_accrued_since_update(Self::now());
+        let solvency_bps = (outstanding > 0)
+            .then(|| U128(mul_div(self.reward_pool, BPS_DENOMINATOR, outstanding)));
+        Health {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            paused: self.paused,
+            import_finished: self.import_finished,
+            upgrade_staged: self.staged_upgrade.is_some(),
+            breaker_tripped: self.breaker_tripped_at.is_some(),
+            transfers_degraded: self.internal_transfers_degraded(),
+            consecutive_transfer_failures: self.consecutive_transfer_failures,
+            reward_pool: U128(self.reward_pool),
+            outstanding_rewards: U128(outstanding),
+            solvency_bps,
+            solvent: self.reward_pool >= outstanding,
+            last_mutation_at: self.last_mutation_at.into(),
+        }
+    }
+}
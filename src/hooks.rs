@@ -0,0 +1,136 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{near_bindgen, AccountId, Gas};
+
+use crate::*;
+
+pub const MAX_HOOKS: usize = 5;
+pub const HOOK_GAS: Gas = Gas(5_000_000_000_000);
+// left to the call that notifies, on top of what the hooks take
+pub const HOOK_GAS_RESERVE: Gas = Gas(50_000_000_000_000);
+pub const MAX_FLUSH_HOOKS_BATCH: u64 = 10;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(rename_all = "snake_case")]
+pub enum HookEvent {
+    Stake,
+    Unstake,
+    Slash,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct HookNotification {
+    pub event: HookEvent,
+    pub account_id: AccountId,
+    pub amount: U128,
+}
+
+impl Contract {
+    // callbacks and batches have no gas to spare for the hooks, their notifications wait
+    // for flush_hooks. so does any call too short on gas to cover every hook
+    fn internal_hooks_inline(&self) -> bool {
+        let needed = HOOK_GAS.0 * self.hooks.len() as u64 + HOOK_GAS_RESERVE.0;
+        !self.hooks_deferred
+            && env::predecessor_account_id() != env::current_account_id()
+            && env::prepaid_gas().0.saturating_sub(env::used_gas().0) >= needed
+    }
+
+    // fire and forget with a fixed gas budget, a failing hook never affects the caller
+    fn internal_fire_hooks(&self, notification: HookNotification) {
+        for hook in self.hooks.iter() {
+            ext_stake_hook::ext(hook.clone())
+                .with_static_gas(HOOK_GAS)
+                .with_unused_gas_weight(0)
+                .on_stake_event(
+                    notification.event,
+                    notification.account_id.clone(),
+                    notification.amount,
+                );
+        }
+    }
+
+    pub(crate) fn internal_notify_hooks(
+        &mut self,
+        event: HookEvent,
+        account_id: &AccountId,
+        amount: u128,
+    ) {
+        if self.hooks.is_empty() {
+            return;
+        }
+        let notification = HookNotification {
+            event,
+            account_id: account_id.clone(),
+            amount: U128(amount),
+        };
+        if self.internal_hooks_inline() {
+            self.internal_fire_hooks(notification);
+        } else {
+            self.hook_queue.insert(&self.hook_queue_tail, &notification);
+            self.hook_queue_tail += 1;
+        }
+    }
+
+    // for the batch paths, every account in `f` has its notifications queued
+    pub(crate) fn internal_with_hooks_deferred<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> T {
+        let deferred = std::mem::replace(&mut self.hooks_deferred, true);
+        let result = f(self);
+        self.hooks_deferred = deferred;
+        result
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn add_hook(&mut self, hook_id: AccountId) {
+        self.internal_assert_role(Role::Admin);
+        require!(
+            !self.hooks.contains(&hook_id),
+            StakeError::HookAlreadyRegistered
+        );
+        require!(self.hooks.len() < MAX_HOOKS, StakeError::TooManyHooks);
+        self.hooks.push(hook_id);
+    }
+
+    pub fn remove_hook(&mut self, hook_id: AccountId) {
+        self.internal_assert_role(Role::Admin);
+        let index = self
+            .hooks
+            .iter()
+            .position(|hook| hook == &hook_id)
+            .unwrap_or_else(|| env::panic_str(&StakeError::HookNotFound));
+        self.hooks.remove(index);
+    }
+
+    pub fn get_hooks(&self) -> Vec<AccountId> {
+        self.hooks.clone()
+    }
+
+    // permissionless, oldest first and only as far as the attached gas covers every hook.
+    // notifications queued while no hook was registered are dropped
+    pub fn flush_hooks(&mut self, limit: Option<u64>) -> u64 {
+        let limit = limit
+            .unwrap_or(MAX_FLUSH_HOOKS_BATCH)
+            .min(MAX_FLUSH_HOOKS_BATCH);
+        let per_notification = HOOK_GAS.0 * self.hooks.len() as u64;
+        let mut flushed = 0;
+        while flushed < limit && self.hook_queue_head < self.hook_queue_tail {
+            let left = env::prepaid_gas().0.saturating_sub(env::used_gas().0);
+            if left < per_notification + BATCH_CLAIM_GAS_RESERVE.0 {
+                break;
+            }
+            let notification = self.hook_queue.remove(&self.hook_queue_head).unwrap();
+            self.hook_queue_head += 1;
+            self.internal_fire_hooks(notification);
+            flushed += 1;
+        }
+        flushed
+    }
+
+    pub fn get_queued_hook_notifications(&self) -> U64 {
+        U64(self.hook_queue_tail - self.hook_queue_head)
+    }
+}
@@ -0,0 +1,143 @@
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, require, AccountId};
+
+use crate::*;
+
+pub const MAX_PROCESSED_OPS: u64 = 10_000;
+// a vote costs about 20 TGas of host calls with the indexes it moves and close to 50 TGas
+// when it also pushes the rating to the platform, a full batch has to leave room for
+// wasm execution, which nothing here has measured
+pub const MAX_VOTE_BATCH: usize = 4;
+pub const MAX_OP_ID_LEN: usize = 64;
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct VoteRequest {
+    pub advisor_id: AccountId,
+    pub learner_vote: u8,
+    pub course_id: Option<String>,
+    pub op_id: Option<String>,
+    pub category: Option<String>,
+    pub metadata: Option<VoteMetadata>,
+}
+
+impl Contract {
+    // runs `op` once per caller and op_id, only the newest MAX_PROCESSED_OPS ids are kept
+    pub(crate) fn internal_idempotent(
+        &mut self,
+        op_id: Option<String>,
+        op: impl FnOnce(&mut Self) -> u128,
+    ) -> u128 {
+        self.internal_idempotent_for(&env::predecessor_account_id(), op_id, op)
+    }
+
+    // for calls that arrive through the token, where the caller is the sender
+    pub(crate) fn internal_idempotent_for(
+        &mut self,
+        caller: &AccountId,
+        op_id: Option<String>,
+        op: impl FnOnce(&mut Self) -> u128,
+    ) -> u128 {
+        let op_id = match op_id {
+            Some(op_id) => op_id,
+            None => return op(self),
+        };
+        require!(op_id.len() <= MAX_OP_ID_LEN, StakeError::InvalidOpId);
+        let key = (caller.clone(), op_id);
+        if let Some(result) = self.processed_ops.get(&key) {
+            return result;
+        }
+        let result = op(self);
+        self.processed_ops.insert(&key, &result);
+        if let Some(evicted) = self.processed_op_ids.push(&key) {
+            self.processed_ops.remove(&evicted);
+        }
+        result
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // same as calling update_apr for each vote, returns the resulting aprs in order
+    pub fn batch_update_apr(&mut self, votes: Vec<VoteRequest>) -> Vec<U128> {
+        require!(votes.len() <= MAX_VOTE_BATCH, StakeError::BatchTooLarge);
+        self.internal_assert_vote_fee_off();
+        let voter_id = env::predecessor_account_id();
+        let results = votes
+            .into_iter()
+            .map(|vote| {
+                let category = self.internal_assert_vote_authority(vote.category);
+                U128(self.internal_idempotent(vote.op_id, |contract| {
+                    let counts =
+                        contract.internal_check_vote_tenure(&voter_id, vote.metadata.as_ref());
+                    contract.internal_update_apr(
+                        &vote.advisor_id,
+                        &voter_id,
+                        vote.learner_vote,
+                        vote.course_id,
+                        category,
+                        counts,
+                    )
+                }))
+            })
+            .collect();
+        self.internal_maybe_snapshot();
+        results
+    }
+
+    pub fn is_op_processed(&self, account_id: AccountId, op_id: String) -> bool {
+        self.processed_ops.contains_key(&(account_id, op_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    // host-call counters of a full batch over 100 stakers, regression ceilings rather than
+    // gas limits, see host_gas_of
+    const BATCH_HOST_GAS: u64 = 100 * TGAS;
+    const BATCH_WITH_PLATFORM_HOST_GAS: u64 = 220 * TGAS;
+
+    fn votes(advisors: &[AccountId]) -> Vec<VoteRequest> {
+        advisors
+            .iter()
+            .map(|advisor_id| VoteRequest {
+                advisor_id: advisor_id.clone(),
+                learner_vote: 5,
+                course_id: None,
+                op_id: None,
+                category: None,
+                metadata: None,
+            })
+            .collect()
+    }
+
+    fn full_batch_host_gas(platform: bool) -> u64 {
+        let mut contract = setup();
+        if platform {
+            call_as(&owner());
+            contract.set_platform_contract(Some(account("platform")));
+        }
+        let advisors = populate(&mut contract, "advisor", 100);
+        advance_secs(100);
+        let batch = votes(&advisors[..MAX_VOTE_BATCH]);
+        host_gas_of(&mut contract, &relay(), |contract| {
+            assert_eq!(contract.batch_update_apr(batch).len(), MAX_VOTE_BATCH);
+        })
+    }
+
+    #[test]
+    fn a_full_vote_batch_host_calls_stay_under_their_ceiling() {
+        let gas = full_batch_host_gas(false);
+        assert!(gas <= BATCH_HOST_GAS, "{} TGas", gas / TGAS);
+    }
+
+    #[test]
+    fn a_full_vote_batch_pushing_ratings_host_calls_stay_under_their_ceiling() {
+        let gas = full_batch_host_gas(true);
+        assert!(gas <= BATCH_WITH_PLATFORM_HOST_GAS, "{} TGas", gas / TGAS);
+    }
+}
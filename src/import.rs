@@ -0,0 +1,76 @@
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{near_bindgen, AccountId};
+
+use crate::*;
+
+pub const MAX_IMPORT_BATCH: usize = 50;
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ImportRecord {
+    pub account_id: AccountId,
+    pub amount_staked: U128,
+    pub reward: U128,
+    pub apr: Option<U128>,
+    pub votes: u8,
+}
+
+#[near_bindgen]
+impl Contract {
+    // seeds positions from the v1 contract, accounts already imported are skipped so
+    // a failed batch can simply be resent. returns how many records were applied
+    pub fn import_stakes(&mut self, records: Vec<ImportRecord>) -> u32 {
+        self.internal_assert_owner();
+        require!(!self.import_finished, StakeError::ImportFinished);
+        require!(records.len() <= MAX_IMPORT_BATCH, StakeError::BatchTooLarge);
+
+        let mut imported = 0;
+        for record in records {
+            if !self.imported_accounts.insert(&record.account_id) {
+                continue;
+            }
+            let amount = record.amount_staked.0;
+            match self.internal_touch(&record.account_id) {
+                Some(mut stake_info) => {
+                    self.internal_update_reward_rate(
+                        stake_info.amount_staked,
+                        stake_info.boosted(stake_info.apr),
+                        stake_info.amount_staked + amount,
+                        stake_info.boosted(stake_info.apr),
+                    );
+                    stake_info.amount_staked += amount;
+                    stake_info.lifetime_staked += amount;
+                    stake_info.reward += record.reward.0;
+                    self.internal_save_stake_info(&record.account_id, &stake_info);
+                }
+                None => {
+                    let apr = record.apr.map_or(self.default_apr, |apr| apr.0);
+                    let mut stake_info = StakeInfo::new(amount, apr);
+                    stake_info.reward = record.reward.0;
+                    stake_info.votes = record.votes;
+                    self.internal_settle_marks(&mut stake_info);
+                    self.internal_update_reward_rate(0, 0, amount, stake_info.apr);
+                    self.internal_save_stake_info(&record.account_id, &stake_info);
+                    self.total_stakers += 1;
+                    self.total_apr += stake_info.apr;
+                }
+            }
+            self.total_staked += amount;
+            self.accrued_rewards += record.reward.0;
+            imported += 1;
+        }
+        self.internal_maybe_snapshot();
+        imported
+    }
+
+    // permanently disables import_stakes
+    pub fn import_finished(&mut self) {
+        self.internal_assert_owner();
+        self.import_finished = true;
+    }
+
+    pub fn is_import_finished(&self) -> bool {
+        self.import_finished
+    }
+}
@@ -0,0 +1,230 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, require, AccountId};
+
+use crate::math::{mul_div, BPS_DENOMINATOR};
+use crate::time::Timestamp;
+use crate::*;
+
+pub const MAX_INSURANCE_BPS: u16 = 2_000;
+
+// terms for stakers who opt in, the premium is taken from their accrual on top of
+// the skim every position pays
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct InsurancePolicy {
+    pub premium_bps: u16,
+    // share of the slashed stake paid back
+    pub coverage_bps: u16,
+    pub max_payout: U128,
+}
+
+// opened when a slash of an insured position executes, it pays out once a slasher
+// confirmed the slash was not the advisor's fault
+#[derive(BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct InsuranceClaim {
+    pub proposal_id: u64,
+    pub account_id: AccountId,
+    pub slashed: U128,
+    pub approved: bool,
+    pub paid: Option<U128>,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct InsuranceView {
+    pub balance: U128,
+    pub skim_bps: u16,
+    pub total_contributed: U128,
+    pub total_paid: U128,
+    pub policy: Option<InsurancePolicy>,
+}
+
+impl Contract {
+    pub(crate) fn internal_insurance_skim(&self, accrued: u128) -> u128 {
+        mul_div(accrued, self.insurance_bps as u128, BPS_DENOMINATOR)
+    }
+
+    // moves the skim out of the reward pool, only as much as the pool can back
+    pub(crate) fn internal_contribute_insurance(&mut self, skim: u128) {
+        let skim = skim.min(self.reward_pool);
+        if skim == 0 {
+            return;
+        }
+        self.internal_update_global_accrual();
+        self.reward_pool -= skim;
+        self.accrued_rewards = self.accrued_rewards.saturating_sub(skim);
+        self.insurance_balance += skim;
+        self.total_insurance_contributed += skim;
+    }
+
+    pub(crate) fn internal_insurance_premium(&self, stake_info: &StakeInfo, accrued: u128) -> u128 {
+        match (&self.insurance_policy, stake_info.terms.insured_since) {
+            (Some(policy), Some(_)) => {
+                mul_div(accrued, policy.premium_bps as u128, BPS_DENOMINATOR)
+            }
+            _ => 0,
+        }
+    }
+
+    // only a slash proposed after the position opted in is covered
+    pub(crate) fn internal_open_insurance_claim(
+        &mut self,
+        account_id: &AccountId,
+        proposal_id: u64,
+        proposed_at: Timestamp,
+        slashed: u128,
+    ) {
+        let covered = self.insurance_policy.is_some()
+            && self
+                .internal_get_stake_info(account_id)
+                .and_then(|stake_info| stake_info.terms.insured_since)
+                .is_some_and(|insured_since| insured_since <= proposed_at);
+        if !covered || slashed == 0 {
+            return;
+        }
+        self.insurance_claims.insert(
+            &proposal_id,
+            &InsuranceClaim {
+                proposal_id,
+                account_id: account_id.clone(),
+                slashed: U128(slashed),
+                approved: false,
+                paid: None,
+            },
+        );
+    }
+
+    fn internal_get_insurance_claim(&self, proposal_id: u64) -> InsuranceClaim {
+        self.insurance_claims
+            .get(&proposal_id)
+            .unwrap_or_else(|| env::panic_str(&StakeError::UnknownInsuranceClaim))
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // covers a shortfall by crediting the account's withdrawable balance
+    pub fn pay_insurance_claim(&mut self, account_id: AccountId, amount: U128) {
+        self.internal_assert_role(Role::Treasurer);
+        require!(
+            amount.0 <= self.insurance_balance,
+            StakeError::InsufficientInsuranceFund
+        );
+        self.internal_migrate_legacy(&account_id);
+        let mut stake_info = self
+            .internal_load_stake_info(&account_id)
+            .unwrap_or_else(|| env::panic_str(&StakeError::NotStaked));
+        self.insurance_balance -= amount.0;
+        self.total_insurance_paid += amount.0;
+        self.internal_credit_withdrawable(&mut stake_info, amount.0);
+        self.internal_save_stake_info(&account_id, &stake_info);
+    }
+
+    pub fn set_insurance_bps(&mut self, insurance_bps: u16) {
+        self.internal_assert_role(Role::Admin);
+        require!(
+            insurance_bps <= MAX_INSURANCE_BPS,
+            StakeError::InsuranceSkimTooHigh
+        );
+        self.internal_record_config_change("insurance_bps", self.insurance_bps, insurance_bps);
+        self.insurance_bps = insurance_bps;
+    }
+
+    pub fn set_insurance_policy(&mut self, policy: Option<InsurancePolicy>) {
+        self.internal_assert_role(Role::Admin);
+        if let Some(policy) = &policy {
+            require!(
+                policy.premium_bps <= MAX_INSURANCE_BPS
+                    && policy.coverage_bps as u128 <= BPS_DENOMINATOR,
+                StakeError::InvalidInsurancePolicy
+            );
+        }
+        self.insurance_policy = policy;
+    }
+
+    // settles the position first so the premium only applies from here on, a position
+    // with an open slash can't change its cover
+    pub fn set_insured(&mut self, enabled: bool) {
+        let account_id = env::predecessor_account_id();
+        require!(
+            !enabled || self.insurance_policy.is_some(),
+            StakeError::NoInsurancePolicy
+        );
+        self.internal_assert_no_open_slash(&account_id);
+        let mut stake_info = self
+            .internal_touch(&account_id)
+            .unwrap_or_else(|| env::panic_str(&StakeError::NotStaked));
+        if enabled != stake_info.terms.insured_since.is_some() {
+            stake_info.terms.insured_since = enabled.then(Self::now);
+        }
+        self.internal_save_stake_info(&account_id, &stake_info);
+    }
+
+    pub fn approve_insurance_claim(&mut self, proposal_id: u64) {
+        self.internal_assert_role(Role::Slasher);
+        let mut claim = self.internal_get_insurance_claim(proposal_id);
+        claim.approved = true;
+        self.insurance_claims.insert(&proposal_id, &claim);
+    }
+
+    // credits withdrawable, capped by the policy in force now and by the fund
+    #[payable]
+    pub fn claim_insurance(&mut self, proposal_id: u64) -> U128 {
+        self.internal_assert_deposit("claim_insurance");
+        let account_id = env::predecessor_account_id();
+        let mut claim = self.internal_get_insurance_claim(proposal_id);
+        require!(
+            claim.account_id == account_id,
+            StakeError::UnknownInsuranceClaim
+        );
+        require!(
+            claim.approved && claim.paid.is_none(),
+            StakeError::InsuranceClaimNotPayable
+        );
+        let policy = self
+            .insurance_policy
+            .clone()
+            .unwrap_or_else(|| env::panic_str(&StakeError::NoInsurancePolicy));
+        let amount = mul_div(
+            claim.slashed.0,
+            policy.coverage_bps as u128,
+            BPS_DENOMINATOR,
+        )
+        .min(policy.max_payout.0)
+        .min(self.insurance_balance);
+        require!(amount > 0, StakeError::InsufficientInsuranceFund);
+
+        let mut stake_info = self
+            .internal_load_stake_info(&account_id)
+            .unwrap_or_else(|| env::panic_str(&StakeError::NotStaked));
+        self.insurance_balance -= amount;
+        self.total_insurance_paid += amount;
+        self.internal_credit_withdrawable(&mut stake_info, amount);
+        self.internal_save_stake_info(&account_id, &stake_info);
+        claim.paid = Some(U128(amount));
+        self.insurance_claims.insert(&proposal_id, &claim);
+        U128(amount)
+    }
+
+    pub fn get_insurance_claim(&self, proposal_id: u64) -> Option<InsuranceClaim> {
+        self.insurance_claims.get(&proposal_id)
+    }
+
+    pub fn is_insured(&self, account_id: AccountId) -> bool {
+        self.internal_get_stake_info(&account_id)
+            .is_some_and(|stake_info| stake_info.terms.insured_since.is_some())
+    }
+
+    pub fn get_insurance(&self) -> InsuranceView {
+        InsuranceView {
+            balance: U128(self.insurance_balance),
+            skim_bps: self.insurance_bps,
+            total_contributed: U128(self.total_insurance_contributed),
+            total_paid: U128(self.total_insurance_paid),
+            policy: self.insurance_policy.clone(),
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use near_sdk::near_bindgen;
+use near_sdk::serde::Serialize;
+
+use crate::*;
+
+// one #[near_bindgen] method as the build script read it, types as written in the source
+pub struct MethodSpec {
+    pub name: &'static str,
+    // view, call, private (callbacks and self calls) or init
+    pub kind: &'static str,
+    pub payable: bool,
+    pub args: &'static [(&'static str, &'static str)],
+    pub returns: Option<&'static str>,
+}
+
+include!(concat!(env!("OUT_DIR"), "/interface.rs"));
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ArgView {
+    pub name: &'static str,
+    #[serde(rename = "type")]
+    pub type_name: &'static str,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MethodView {
+    pub name: &'static str,
+    pub kind: &'static str,
+    pub payable: bool,
+    // one yocto unless the owner exempted the method, relayers skip it either way
+    pub deposit_required: bool,
+    pub args: Vec<ArgView>,
+    pub returns: Option<&'static str>,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct InterfaceView {
+    pub version: String,
+    pub methods: Vec<MethodView>,
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn get_interface(&self) -> InterfaceView {
+        InterfaceView {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            methods: METHODS
+                .iter()
+                .map(|method| MethodView {
+                    name: method.name,
+                    kind: method.kind,
+                    payable: method.payable,
+                    deposit_required: self.is_deposit_required(method.name.to_string()),
+                    args: method
+                        .args
+                        .iter()
+                        .map(|(name, type_name)| ArgView { name, type_name })
+                        .collect(),
+                    returns: method.returns,
+                })
+                .collect(),
+        }
+    }
+}
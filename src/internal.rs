@@ -0,0 +1,608 @@
+use crate::events::{StakeEvent, UnstakedData};
+use crate::math::{mul_div, reward_for_duration, Rate, BPS_DENOMINATOR};
+use crate::time::{Duration, Timestamp, NANOS_PER_YEAR};
+use crate::*;
+
+impl Contract {
+    // reward earned since the record was last touched less the insurance skim and the
+    // protocol fee, then the skim and the fee themselves
+    pub(crate) fn internal_accrual_at(
+        &self,
+        stake_info: &StakeInfo,
+        timestamp: Timestamp,
+    ) -> (u128, u128, u128) {
+        // a touch settles at the decayed apr, estimates have to as well
+        let decayed;
+        let stake_info = match self.internal_decayed_apr(stake_info, timestamp) {
+            Some(apr) if apr != stake_info.apr => {
+                decayed = StakeInfo {
+                    apr,
+                    ..stake_info.clone()
+                };
+                &decayed
+            }
+            _ => stake_info,
+        };
+        let index_growth = self
+            .internal_apr_index_at(timestamp)
+            .saturating_sub(stake_info.apr_index_paid);
+        let paused = stake_info.paused_accrual_since.is_some();
+        let pending_reward = if paused {
+            0
+        } else if stake_info.terms.compounding {
+            self.internal_compounded_reward(stake_info, index_growth)
+        } else {
+            mul_div(
+                Rate::from_raw(stake_info.boosted(stake_info.apr))
+                    .annual_reward(stake_info.amount_staked),
+                index_growth,
+                BPS_DENOMINATOR * NANOS_PER_YEAR as u128,
+            )
+        };
+        let accrued = pending_reward
+            + self.internal_unsettled_bonus(stake_info)
+            + if paused {
+                0
+            } else {
+                self.internal_promotion_reward(stake_info, timestamp)
+            };
+        let skim = self.internal_insurance_skim(accrued)
+            + self.internal_insurance_premium(stake_info, accrued);
+        let fee = self.internal_protocol_fee(accrued);
+        (accrued - skim - fee, skim, fee)
+    }
+
+    pub(crate) fn internal_pending_reward_at(
+        &self,
+        stake_info: &StakeInfo,
+        timestamp: Timestamp,
+    ) -> u128 {
+        self.internal_accrual_at(stake_info, timestamp).0 + stake_info.reward
+    }
+
+    // rolls the contract wide pending reward estimate forward to now, the estimate
+    // treats compounding positions as simple interest
+    pub(crate) fn internal_update_global_accrual(&mut self) {
+        let now = Self::now();
+        self.accrued_rewards += self.internal_accrued_since_update(now);
+        self.last_accrual_update = now;
+    }
+
+    pub(crate) fn internal_accrued_since_update(&self, now: Timestamp) -> u128 {
+        self.internal_apply_apr_modifier(reward_for_duration(
+            self.reward_rate,
+            Duration::between(self.last_accrual_update, now),
+        )) + self.internal_promotion_estimate(self.last_accrual_update, now)
+    }
+
+    // marks a record as settled against the accumulators as of now
+    pub(crate) fn internal_settle_marks(&self, stake_info: &mut StakeInfo) {
+        let now = Self::now();
+        stake_info.time_staked = now;
+        stake_info.apr_index_paid = self.internal_apr_index_at(now);
+        stake_info.bonus_per_share_paid = self.bonus_per_share;
+    }
+
+    pub(crate) fn internal_update_reward_rate(
+        &mut self,
+        old_amount: u128,
+        old_apr: u128,
+        new_amount: u128,
+        new_apr: u128,
+    ) {
+        self.internal_update_global_accrual();
+        self.reward_rate = self.reward_rate - Rate::from_raw(old_apr).annual_reward(old_amount)
+            + Rate::from_raw(new_apr).annual_reward(new_amount);
+    }
+
+    pub(crate) fn internal_pay_reward(&mut self, amount: u128) {
+        require!(
+            amount <= self.reward_pool,
+            StakeError::InsufficientRewardPool
+        );
+        self.internal_update_global_accrual();
+        self.reward_pool -= amount;
+        self.accrued_rewards = self.accrued_rewards.saturating_sub(amount);
+        self.total_rewards_paid += amount;
+    }
+}
+
+impl Contract {
+    // every write of a position goes through here
+    pub(crate) fn internal_save_stake_info(
+        &mut self,
+        account_id: &AccountId,
+        stake_info: &StakeInfo,
+    ) {
+        let refreshed = self.internal_refresh_badge(account_id, stake_info);
+        let stake_info = refreshed.as_ref().unwrap_or(stake_info);
+        let previous = self.stake_info.insert(account_id, stake_info);
+        self.internal_note_stake_write();
+        self.internal_store_stake_terms(account_id, stake_info);
+        self.internal_commit_state(account_id, stake_info);
+        if previous.is_none() {
+            self.staker_ids.insert(account_id);
+        }
+        if previous
+            .as_ref()
+            .map_or(0, |previous| previous.amount_staked)
+            != stake_info.amount_staked
+        {
+            self.internal_sync_campaign_weights(account_id, stake_info.amount_staked);
+        }
+        self.internal_update_leaderboards(account_id, previous.as_ref(), stake_info);
+        self.internal_update_market_index(account_id, previous.as_ref(), stake_info);
+        #[cfg(feature = "invariant-checks")]
+        self.internal_assert_invariants(account_id, stake_info);
+    }
+
+    // every entry point goes through here so pending reward is folded into `reward`
+    // before amount or apr change, the caller saves the returned record
+    pub(crate) fn internal_touch(&mut self, account_id: &AccountId) -> Option<StakeInfo> {
+        self.internal_migrate_legacy(account_id);
+        // before settling, so any touch stops a boost that is no longer verified
+        self.internal_drop_stale_booster(account_id);
+        let mut stake_info = self.internal_load_stake_info(account_id)?;
+        // the time since the last touch settles at the decayed apr
+        self.internal_decay_apr(account_id, &mut stake_info);
+        let now = Self::now();
+        let (accrued, skim, fee) = self.internal_accrual_at(&stake_info, now);
+        self.internal_contribute_insurance(skim);
+        self.internal_collect_protocol_fee(fee);
+        let accrued = self.internal_cap_accrual(&mut stake_info, accrued, now);
+        self.internal_note_accrual(&mut stake_info, accrued);
+        if accrued > 0 {
+            self.internal_record_activity(
+                account_id,
+                Activity::RewardAccrued {
+                    amount: U128(accrued),
+                },
+            );
+        }
+        stake_info.reward += accrued;
+        self.internal_settle_marks(&mut stake_info);
+        self.internal_expire_votes(account_id, &mut stake_info);
+        self.internal_refresh_loyalty(&mut stake_info);
+        Some(stake_info)
+    }
+
+    // claims `amount` of the account's reward, everything when None
+    pub(crate) fn internal_claim_reward(
+        &mut self,
+        account_id: &AccountId,
+        amount: Option<u128>,
+    ) -> u128 {
+        let reward = self.internal_settle_claim(account_id, amount);
+        self.internal_record_activity(
+            account_id,
+            Activity::Claim {
+                amount: U128(reward),
+            },
+        );
+        reward
+    }
+
+    // internal_claim_reward without the activity entry, micro-claims record theirs in bulk
+    pub(crate) fn internal_settle_claim(
+        &mut self,
+        account_id: &AccountId,
+        amount: Option<u128>,
+    ) -> u128 {
+        self.internal_drop_stale_governance(account_id);
+        let mut stake_info = self
+            .internal_touch(account_id)
+            .unwrap_or_else(|| env::panic_str(&StakeError::NotStaked));
+        Self::internal_assert_no_pending_operation(&stake_info);
+        self.internal_assert_not_frozen(account_id);
+        require!(
+            Self::now() >= self.internal_next_claim_at(&stake_info),
+            StakeError::ClaimCooldown
+        );
+
+        let reward = amount.unwrap_or(stake_info.reward);
+        require!(reward > 0, StakeError::NoReward);
+        require!(reward <= stake_info.reward, StakeError::AmountExceedsReward);
+        self.internal_assert_balance_covers();
+        stake_info.last_claimed_at = Some(Self::now());
+        self.internal_pay_sponsored_reward(account_id, reward);
+        stake_info.lifetime_rewards_claimed += reward;
+        stake_info.reward -= reward;
+        self.internal_release_reward(account_id, &mut stake_info, reward);
+        self.internal_record_outflow(reward);
+
+        self.internal_save_stake_info(account_id, &stake_info);
+        reward
+    }
+
+    // applies one learner vote and returns the advisor's apr afterwards
+    pub(crate) fn internal_update_apr(
+        &mut self,
+        advisor_id: &AccountId,
+        voter_id: &AccountId,
+        rating: u8,
+        course_id: Option<String>,
+        category: Option<String>,
+        counts: bool,
+    ) -> u128 {
+        self.internal_assert_not_paused();
+        let mut stake_info = self
+            .internal_touch(advisor_id)
+            .unwrap_or_else(|| env::panic_str(&StakeError::AdvisorNotStaked));
+        require!(
+            self.internal_is_permitted(advisor_id),
+            StakeError::AdvisorNotPermitted
+        );
+        let (old_apr, old_votes) = (stake_info.apr, stake_info.votes);
+        let mut reputation_delta = 0;
+        if counts && !self.internal_in_vote_grace(&stake_info) {
+            let weight_bps = self.internal_category_weight_bps(category.as_ref());
+            reputation_delta = self.internal_apply_vote(&mut stake_info, rating, weight_bps);
+        }
+        stake_info.votes_received += 1;
+        if let Some(count) = (rating as usize)
+            .checked_sub(1)
+            .and_then(|index| stake_info.rating_counts.get_mut(index))
+        {
+            *count = count.saturating_add(1);
+        }
+        self.internal_record_activity(advisor_id, Activity::VoteReceived { rating });
+        if let Some(course_id) = course_id {
+            self.internal_record_course_vote(advisor_id, course_id, rating);
+        }
+        if stake_info.apr != old_apr {
+            self.internal_update_reward_rate(
+                stake_info.amount_staked,
+                stake_info.boosted(old_apr),
+                stake_info.amount_staked,
+                stake_info.boosted(stake_info.apr),
+            );
+            self.internal_record_apr_change(
+                advisor_id,
+                old_apr,
+                stake_info.apr,
+                AprChangeCause::Vote { rating },
+            );
+        }
+        self.internal_save_stake_info(advisor_id, &stake_info);
+        if reputation_delta != 0 {
+            self.internal_record_vote(
+                advisor_id,
+                voter_id,
+                rating,
+                reputation_delta,
+                (old_votes, stake_info.votes),
+            );
+        }
+        if stake_info.apr != old_apr || stake_info.votes != old_votes {
+            self.internal_push_rating(advisor_id, stake_info.apr, stake_info.votes);
+        }
+        stake_info.apr
+    }
+
+    // moves `amount` of stake into withdrawable less the unstake fee, returns what was
+    // credited. a migration to a successor contract is no early exit and pays none
+    pub(crate) fn internal_unstake(
+        &mut self,
+        account_id: &AccountId,
+        amount: u128,
+        course_id: Option<String>,
+        receiver_id: &AccountId,
+        waive_fee: bool,
+    ) -> u128 {
+        let mut stake_info = self
+            .internal_touch(account_id)
+            .unwrap_or_else(|| env::panic_str(&StakeError::NotStaked));
+        Self::internal_assert_no_pending_operation(&stake_info);
+        self.internal_assert_not_frozen(account_id);
+        self.internal_assert_no_open_slash(account_id);
+        require!(
+            stake_info.amount_staked >= amount,
+            StakeError::InsufficientStake
+        );
+        require!(amount > 0, StakeError::InvalidAmount);
+        self.internal_assert_stake_free(account_id, &stake_info, amount);
+        self.internal_remove_course_stake(account_id, &stake_info, course_id, amount);
+        self.internal_update_reward_rate(
+            stake_info.amount_staked,
+            stake_info.boosted(stake_info.apr),
+            stake_info.amount_staked - amount,
+            stake_info.boosted(stake_info.apr),
+        );
+
+        stake_info.amount_staked -= amount;
+        stake_info.lifetime_unstaked += amount;
+        stake_info.sync_streak(Self::now());
+        let fee = if waive_fee {
+            0
+        } else {
+            self.internal_unstake_fee(account_id, amount)
+        };
+        self.internal_assert_balance_covers();
+        self.internal_collect_unstake_fee(fee);
+        self.internal_credit_withdrawable(&mut stake_info, amount - fee);
+        self.internal_record_outflow(amount - fee);
+
+        self.total_staked -= amount;
+        self.total_volume_unstaked += amount;
+        self.internal_record_activity(
+            account_id,
+            Activity::Unstake {
+                amount: U128(amount),
+            },
+        );
+        self.internal_notify_hooks(HookEvent::Unstake, account_id, amount);
+        StakeEvent::Unstaked(vec![UnstakedData {
+            account_id: account_id.clone(),
+            amount: U128(amount),
+            fee: U128(fee),
+            receiver_id: receiver_id.clone(),
+        }])
+        .emit();
+
+        self.internal_save_stake_info(account_id, &stake_info);
+        amount - fee
+    }
+
+    pub(crate) fn internal_stake(&mut self, _account_id: AccountId, _stake_amount: u128) {
+        let storage_before = env::storage_usage();
+        let created = match self.internal_touch(&_account_id) {
+            Some(mut stake_info) => {
+                self.internal_update_reward_rate(
+                    stake_info.amount_staked,
+                    stake_info.boosted(stake_info.apr),
+                    stake_info.amount_staked + _stake_amount,
+                    stake_info.boosted(stake_info.apr),
+                );
+                stake_info.amount_staked += _stake_amount;
+                stake_info.lifetime_staked += _stake_amount;
+                stake_info.sync_streak(Self::now());
+
+                self.internal_save_stake_info(&_account_id, &stake_info);
+                false
+            }
+            None => {
+                let apr = self.default_apr;
+                self.internal_update_reward_rate(0, 0, _stake_amount, apr);
+                let mut stake_info = StakeInfo::new(_stake_amount, apr);
+                self.internal_settle_marks(&mut stake_info);
+                self.internal_save_stake_info(&_account_id, &stake_info);
+                self.total_stakers += 1;
+                self.total_apr += apr;
+                true
+            }
+        };
+        self.total_staked += _stake_amount;
+        self.total_volume_staked += _stake_amount;
+        self.internal_record_activity(
+            &_account_id,
+            Activity::Stake {
+                amount: U128(_stake_amount),
+            },
+        );
+        self.internal_notify_hooks(HookEvent::Stake, &_account_id, _stake_amount);
+        if created {
+            let bytes = env::storage_usage().saturating_sub(storage_before);
+            self.internal_note_storage_created(&_account_id, bytes);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use crate::time::SECONDS_PER_YEAR;
+
+    const DAY: u64 = crate::time::SECONDS_PER_DAY;
+
+    fn year_share(amount: u128, apr: u128, secs: u64) -> u128 {
+        mul_div(
+            Rate::from_raw(apr).annual_reward(amount),
+            secs as u128,
+            SECONDS_PER_YEAR as u128,
+        )
+    }
+
+    #[test]
+    fn accrues_nothing_without_time() {
+        let mut contract = setup();
+        stake(&mut contract, &account("alice"), 1_000 * ONE_TOKEN);
+        assert_eq!(pending(&contract, &account("alice")), 0);
+    }
+
+    #[test]
+    fn accrues_over_known_durations() {
+        let mut contract = setup();
+        let alice = account("alice");
+        stake(&mut contract, &alice, 1_000 * ONE_TOKEN);
+
+        advance_secs(DAY);
+        assert_eq!(
+            pending(&contract, &alice),
+            year_share(1_000 * ONE_TOKEN, DEFAULT_APR, DAY)
+        );
+        advance_secs(SECONDS_PER_YEAR - DAY);
+        assert_eq!(pending(&contract, &alice), 50 * ONE_TOKEN);
+    }
+
+    #[test]
+    fn votes_move_the_apr_by_the_vote_step() {
+        let mut contract = setup();
+        let alice = account("alice");
+        stake(&mut contract, &alice, 1_000 * ONE_TOKEN);
+
+        assert_eq!(
+            vote(&mut contract, &alice, 5),
+            DEFAULT_APR + 2 * DEFAULT_VOTE_STEP
+        );
+        assert_eq!(
+            vote(&mut contract, &alice, 4),
+            DEFAULT_APR + 3 * DEFAULT_VOTE_STEP
+        );
+        assert_eq!(
+            vote(&mut contract, &alice, 3),
+            DEFAULT_APR + 3 * DEFAULT_VOTE_STEP
+        );
+        assert_eq!(
+            vote(&mut contract, &alice, 1),
+            DEFAULT_APR + DEFAULT_VOTE_STEP
+        );
+        assert_eq!(vote(&mut contract, &alice, 2), DEFAULT_APR);
+    }
+
+    #[test]
+    fn each_interval_accrues_at_the_apr_voted_before_it() {
+        let mut contract = setup();
+        call_as(&owner());
+        contract.set_reputation_half_life(0.into());
+        let alice = account("alice");
+        stake(&mut contract, &alice, 1_000 * ONE_TOKEN);
+
+        advance_secs(SECONDS_PER_YEAR / 2);
+        let raised = vote(&mut contract, &alice, 5);
+        advance_secs(SECONDS_PER_YEAR / 2);
+        assert_eq!(
+            pending(&contract, &alice),
+            25 * ONE_TOKEN + year_share(1_000 * ONE_TOKEN, raised, SECONDS_PER_YEAR / 2)
+        );
+        assert_eq!(pending(&contract, &alice), 51 * ONE_TOKEN);
+    }
+
+    // 1000 tokens at the default 5%, the reward is counted in nanoseconds against a
+    // year of seconds so one second earns 1/31536000 of 50 tokens, not 1e9 times that
+    #[test]
+    fn pins_rewards_for_known_intervals() {
+        let mut contract = setup();
+        let alice = account("alice");
+        stake(&mut contract, &alice, 1_000 * ONE_TOKEN);
+
+        let mut elapsed = 0;
+        for (secs, expected) in [
+            (1, 1_585_489_599_188),
+            (3_600, 5_707_762_557_077_625),
+            (DAY, 136_986_301_369_863_013),
+            (30 * DAY, 4_109_589_041_095_890_410),
+            (SECONDS_PER_YEAR, 50_000_000_000_000_000_000),
+            (2 * SECONDS_PER_YEAR, 100_000_000_000_000_000_000),
+        ] {
+            advance_secs(secs - elapsed);
+            elapsed = secs;
+            assert_eq!(pending(&contract, &alice), expected, "after {}s", secs);
+            assert_eq!(
+                reward_for_duration(50 * ONE_TOKEN, Duration::from_secs(secs)),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn settled_rewards_match_the_pinned_values() {
+        let mut contract = setup();
+        let alice = account("alice");
+        stake(&mut contract, &alice, 1_000 * ONE_TOKEN);
+
+        advance_secs(DAY);
+        let stake_info = contract.internal_touch(&alice).unwrap();
+        contract.internal_save_stake_info(&alice, &stake_info);
+        advance_secs(DAY);
+        let stake_info = contract.internal_touch(&alice).unwrap();
+        assert_eq!(stake_info.reward, 2 * 136_986_301_369_863_013);
+    }
+
+    #[test]
+    fn top_up_keeps_the_reward_earned_before_it() {
+        let mut contract = setup();
+        let alice = account("alice");
+        stake(&mut contract, &alice, 1_000 * ONE_TOKEN);
+
+        advance_secs(SECONDS_PER_YEAR / 2);
+        assert_eq!(stake(&mut contract, &alice, 1_000 * ONE_TOKEN), 0);
+        assert_eq!(staked(&contract, &alice), 2_000 * ONE_TOKEN);
+        assert_eq!(pending(&contract, &alice), 25 * ONE_TOKEN);
+
+        advance_secs(SECONDS_PER_YEAR / 2);
+        assert_eq!(pending(&contract, &alice), 75 * ONE_TOKEN);
+    }
+
+    #[test]
+    fn repeated_top_ups_in_one_block_lose_nothing() {
+        let mut contract = setup();
+        let alice = account("alice");
+        stake(&mut contract, &alice, 1_000 * ONE_TOKEN);
+        advance_secs(SECONDS_PER_YEAR);
+        for _ in 0..3 {
+            stake(&mut contract, &alice, ONE_TOKEN);
+        }
+        assert_eq!(pending(&contract, &alice), 50 * ONE_TOKEN);
+    }
+
+    #[test]
+    fn partial_unstake_keeps_the_reward_earned_before_it() {
+        let mut contract = setup();
+        let alice = account("alice");
+        stake(&mut contract, &alice, 1_000 * ONE_TOKEN);
+
+        advance_secs(SECONDS_PER_YEAR / 2);
+        call_with_yocto(&alice);
+        contract.unstake_token(U128(500 * ONE_TOKEN), None, None);
+        assert_eq!(staked(&contract, &alice), 500 * ONE_TOKEN);
+        assert_eq!(pending(&contract, &alice), 25 * ONE_TOKEN);
+
+        advance_secs(SECONDS_PER_YEAR / 2);
+        assert_eq!(
+            pending(&contract, &alice),
+            25 * ONE_TOKEN + 25 * ONE_TOKEN / 2
+        );
+    }
+
+    #[test]
+    fn the_estimate_matches_what_a_touch_settles_after_decay() {
+        let mut contract = setup();
+        let alice = account("alice");
+        stake(&mut contract, &alice, 1_000 * ONE_TOKEN);
+        vote(&mut contract, &alice, 5);
+
+        advance_secs(SECONDS_PER_YEAR);
+        let estimate = pending(&contract, &alice);
+        let stake_info = contract.internal_touch(&alice).unwrap();
+        assert!(stake_info.apr < DEFAULT_APR + 2 * DEFAULT_VOTE_STEP);
+        assert_eq!(stake_info.reward, estimate);
+    }
+
+    #[test]
+    fn the_mock_clock_drives_the_contract_clock() {
+        let _contract = setup();
+        assert_eq!(Contract::now().as_nanos(), START);
+        advance_secs(10);
+        assert_eq!(
+            Contract::now().as_nanos(),
+            START + 10 * crate::time::NANOS_PER_SECOND
+        );
+    }
+
+    // host-call counters with 100 stakers indexed, regression ceilings rather than gas
+    // limits, see host_gas_of
+    const CHECKPOINT_HOST_GAS: u64 = TGAS;
+    const TOUCH_HOST_GAS: u64 = 6 * TGAS;
+
+    #[test]
+    fn the_accumulator_update_host_calls_stay_under_their_ceiling() {
+        let mut contract = setup();
+        let stakers = populate(&mut contract, "staker", 100);
+        advance_secs(100);
+
+        let checkpoint = host_gas_of(&mut contract, &relay(), |contract| {
+            contract.internal_checkpoint_accrual();
+        });
+        assert!(
+            checkpoint <= CHECKPOINT_HOST_GAS,
+            "{} TGas",
+            checkpoint / TGAS
+        );
+        let touch = host_gas_of(&mut contract, &relay(), |contract| {
+            let stake_info = contract.internal_touch(&stakers[10]).unwrap();
+            contract.internal_save_stake_info(&stakers[10], &stake_info);
+        });
+        assert!(touch <= TOUCH_HOST_GAS, "{} TGas", touch / TGAS);
+    }
+}
@@ -0,0 +1,167 @@
+use near_sdk::json_types::U128;
+use near_sdk::serde::Serialize;
+use near_sdk::{env, near_bindgen, AccountId};
+
+use crate::math::{mul_div, BPS_DENOMINATOR};
+use crate::*;
+
+pub const MAX_LEADERBOARD_LIMIT: usize = 100;
+pub const MAX_WHALES_LIMIT: usize = 50;
+pub const CONCENTRATION_TOP: usize = 10;
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct LeaderboardEntry {
+    pub account_id: AccountId,
+    pub amount_staked: U128,
+    pub apr: U128,
+    pub votes: u8,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Whale {
+    pub account_id: AccountId,
+    pub amount_staked: U128,
+    // of total_staked
+    pub share_bps: u16,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Concentration {
+    pub total_staked: U128,
+    pub stakers: u64,
+    pub largest_share_bps: u16,
+    // held by the CONCENTRATION_TOP largest positions
+    pub top_staked: U128,
+    pub top_share_bps: u16,
+}
+
+impl Contract {
+    fn internal_share_bps(&self, amount: u128) -> u16 {
+        if self.total_staked == 0 {
+            return 0;
+        }
+        mul_div(amount, BPS_DENOMINATOR, self.total_staked) as u16
+    }
+
+    // keeps positions with stake indexed by amount and by vote score
+    pub(crate) fn internal_update_leaderboards(
+        &mut self,
+        account_id: &AccountId,
+        previous: Option<&StakeInfo>,
+        stake_info: &StakeInfo,
+    ) {
+        let (old_amount, old_votes) =
+            previous.map_or((0, 0), |info| (info.amount_staked, info.votes));
+        // each tree is only rewritten when its own key moved, a vote leaves the amount
+        // tree alone
+        let (was_listed, listed) = (old_amount > 0, stake_info.amount_staked > 0);
+        if old_amount != stake_info.amount_staked {
+            if was_listed {
+                self.stakers_by_amount
+                    .remove(&(old_amount, account_id.clone()));
+            }
+            if listed {
+                self.stakers_by_amount
+                    .insert(&(stake_info.amount_staked, account_id.clone()), &());
+            }
+        }
+        if (was_listed, old_votes) != (listed, stake_info.votes) {
+            if was_listed {
+                self.stakers_by_votes
+                    .remove(&(old_votes, account_id.clone()));
+            }
+            if listed {
+                self.stakers_by_votes
+                    .insert(&(stake_info.votes, account_id.clone()), &());
+            }
+        }
+    }
+
+    fn internal_leaderboard_entry(&self, account_id: AccountId) -> LeaderboardEntry {
+        let stake_info = self.internal_peek_stake_info(&account_id).unwrap();
+        LeaderboardEntry {
+            account_id,
+            amount_staked: U128(stake_info.amount_staked),
+            apr: U128(stake_info.apr),
+            votes: stake_info.votes,
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn get_top_stakers(&self, limit: Option<usize>) -> Vec<LeaderboardEntry> {
+        let limit = limit
+            .unwrap_or(MAX_LEADERBOARD_LIMIT)
+            .min(MAX_LEADERBOARD_LIMIT);
+        self.stakers_by_amount
+            .iter_rev()
+            .filter(|((_, account_id), _)| !self.hidden_from_listings.contains(account_id))
+            .take(limit)
+            .map(|((_, account_id), _)| self.internal_leaderboard_entry(account_id))
+            .collect()
+    }
+
+    pub fn get_top_rated(&self, limit: Option<usize>) -> Vec<LeaderboardEntry> {
+        let limit = limit
+            .unwrap_or(MAX_LEADERBOARD_LIMIT)
+            .min(MAX_LEADERBOARD_LIMIT);
+        self.stakers_by_votes
+            .iter_rev()
+            .filter(|((_, account_id), _)| !self.hidden_from_listings.contains(account_id))
+            .take(limit)
+            .map(|((_, account_id), _)| self.internal_leaderboard_entry(account_id))
+            .collect()
+    }
+
+    // largest positions first, read straight off the amount index. hidden accounts
+    // are left out here but still count in get_concentration
+    pub fn get_whales(&self, limit: Option<usize>) -> Vec<Whale> {
+        let limit = limit.unwrap_or(MAX_WHALES_LIMIT).min(MAX_WHALES_LIMIT);
+        self.stakers_by_amount
+            .iter_rev()
+            .filter(|((_, account_id), _)| !self.hidden_from_listings.contains(account_id))
+            .take(limit)
+            .map(|((amount, account_id), _)| Whale {
+                account_id,
+                amount_staked: U128(amount),
+                share_bps: self.internal_share_bps(amount),
+            })
+            .collect()
+    }
+
+    pub fn get_concentration(&self) -> Concentration {
+        let top: Vec<u128> = self
+            .stakers_by_amount
+            .iter_rev()
+            .take(CONCENTRATION_TOP)
+            .map(|((amount, _), _)| amount)
+            .collect();
+        let top_staked = top.iter().sum();
+        Concentration {
+            total_staked: U128(self.total_staked),
+            stakers: self.stakers_by_amount.len(),
+            largest_share_bps: self.internal_share_bps(top.first().copied().unwrap_or(0)),
+            top_staked: U128(top_staked),
+            top_share_bps: self.internal_share_bps(top_staked),
+        }
+    }
+
+    // keeps the account out of the listings above, totals and its own views still
+    // show it. everything stays readable from raw contract state
+    pub fn set_hide_from_listings(&mut self, hidden: bool) {
+        let account_id = env::predecessor_account_id();
+        if hidden {
+            self.hidden_from_listings.insert(&account_id);
+        } else {
+            self.hidden_from_listings.remove(&account_id);
+        }
+    }
+
+    pub fn is_hidden_from_listings(&self, account_id: AccountId) -> bool {
+        self.hidden_from_listings.contains(&account_id)
+    }
+}
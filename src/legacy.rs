@@ -0,0 +1,93 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::LookupMap;
+use near_sdk::{env, near_bindgen, AccountId};
+
+use crate::math::BPS_DENOMINATOR;
+use crate::time::Timestamp;
+use crate::*;
+
+// layout written by the first deployment, timestamps were stored as i64
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct LegacyStakeInfo {
+    time_staked: i64,
+    amount_staked: u128,
+    reward: u128,
+    apr: u128,
+    votes: u8,
+}
+
+#[cfg(test)]
+impl LegacyStakeInfo {
+    pub(crate) fn new(time_staked: i64, amount_staked: u128, apr: u128) -> Self {
+        LegacyStakeInfo {
+            time_staked,
+            amount_staked,
+            reward: 0,
+            apr,
+            votes: 0,
+        }
+    }
+}
+
+impl From<LegacyStakeInfo> for StakeInfo {
+    fn from(legacy: LegacyStakeInfo) -> Self {
+        let mut stake_info = StakeInfo::new(legacy.amount_staked, legacy.apr);
+        stake_info.time_staked = Timestamp::from_nanos(legacy.time_staked.max(0) as u64);
+        // legacy positions predate the apr modifier, so their index mark is plain time
+        stake_info.apr_index_paid = BPS_DENOMINATOR * stake_info.time_staked.as_nanos() as u128;
+        stake_info.reward = legacy.reward;
+        stake_info.votes = legacy.votes;
+        stake_info.terms.opened_at = stake_info.time_staked;
+        stake_info.terms.first_staked_at = stake_info.time_staked;
+        stake_info.terms.streak_started_at = Some(stake_info.time_staked);
+        stake_info
+    }
+}
+
+#[derive(BorshDeserialize)]
+struct OldContract {
+    token_address: AccountId,
+    total_stakers: u128,
+    total_staked: u128,
+    #[allow(dead_code)]
+    stake_info: LookupMap<AccountId, LegacyStakeInfo>,
+}
+
+impl Contract {
+    pub(crate) fn internal_get_stake_info(&self, account_id: &AccountId) -> Option<StakeInfo> {
+        self.internal_load_stake_info(account_id)
+            .or_else(|| self.legacy_stake_info.get(account_id).map(StakeInfo::from))
+    }
+
+    // records are converted the first time their account is touched, the aggregates
+    // only learn about a legacy position at that point
+    pub(crate) fn internal_migrate_legacy(&mut self, account_id: &AccountId) {
+        if let Some(legacy) = self.legacy_stake_info.remove(account_id) {
+            let stake_info = StakeInfo::from(legacy);
+            self.internal_update_reward_rate(0, 0, stake_info.amount_staked, stake_info.apr);
+            self.total_apr += stake_info.apr;
+            self.internal_save_stake_info(account_id, &stake_info);
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // the first deployment had no owner, without owner_id it would fall to the contract
+    // account itself
+    #[private]
+    #[init(ignore_state)]
+    pub fn migrate(owner_id: AccountId) -> Self {
+        let old: OldContract =
+            env::state_read().unwrap_or_else(|| env::panic_str(&StakeError::NoStateToMigrate));
+        let config = InitConfig {
+            owner_id: Some(owner_id),
+            ..Default::default()
+        };
+        let mut contract = Self::new(old.token_address, Some(config));
+        contract.total_stakers = old.total_stakers;
+        contract.total_staked = old.total_staked;
+        contract.legacy_pending = true;
+        contract
+    }
+}
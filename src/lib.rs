@@ -1,57 +1,906 @@
-use std::ops::Sub;
-
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::LookupMap;
-use near_sdk::json_types::U128;
+use near_sdk::collections::{LookupMap, LookupSet, TreeMap, UnorderedMap, UnorderedSet, Vector};
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::{
-    assert_one_yocto, env, near_bindgen, require, AccountId, BorshStorageKey, Gas, PanicOnDefault,
-    ONE_NEAR, ONE_YOCTO, PromiseOrValue,
+    env, near_bindgen, require, AccountId, CryptoHash, Gas, PanicOnDefault, PromiseOrValue,
+    PublicKey,
 };
 
 pub const FT_TRANSFER_GAS: Gas = Gas(10_000_000_000_000);
-pub const WITHDRAW_CALLBACK_GAS: Gas = Gas(10_000_000_000_000);
-pub const FAUCET_CALLBACK_GAS: Gas = Gas(10_000_000_000_000);
+// transfer callbacks cover the storage check of a failed transfer, see degraded.rs
+pub const WITHDRAW_CALLBACK_GAS: Gas = Gas(20_000_000_000_000);
+pub const FAUCET_CALLBACK_GAS: Gas = Gas(20_000_000_000_000);
 
-pub const POINT_ONE_TOKEN: u128 = 100_000_000_000_000_000_000_000; // 0.1 to 24 decimal
-pub const DEFAULT_APR: u128 = 5_000_000_000_000_000_000_000_000; // 5%
+pub const DEFAULT_VOTE_STEP: u128 = RATE_PRECISION / 10; // 0.1%
+pub const DEFAULT_APR: u128 = Rate::percent(5).raw();
 
+pub mod abandoned;
+pub mod access_keys;
+pub mod access_list;
+pub mod accrual_cap;
+pub mod accrual_pause;
+pub mod activity;
+pub mod api_version;
+pub mod appeals;
+pub mod apr_config;
+pub mod apr_history;
+pub mod attestation;
+pub mod badges;
+pub mod balance;
+pub mod batch_claim;
+pub mod bonus;
+pub mod boosters;
+pub mod breaker;
+pub mod burn;
+pub mod campaigns;
+pub mod cancel_stake;
+pub mod claim_and_call;
+pub mod claim_cooldown;
+pub mod claim_statement;
+pub mod collateral;
+pub mod commission;
+pub mod compounding;
+pub mod config_history;
+pub mod confirmations;
+pub mod courses;
+pub mod degraded;
+pub mod delegated_claim;
+pub mod delegation;
+pub mod deposit_policy;
+pub mod deposits;
+pub mod diagnostics;
+pub mod dust;
+pub mod eligibility;
+pub mod entry_queue;
+pub mod errors;
+pub mod events;
 pub mod external;
+pub mod faucet;
+pub mod forfeit;
+pub mod freeze;
+pub mod governance_boost;
+pub mod groups;
+pub mod health;
+pub mod hooks;
+pub mod idempotency;
+pub mod import;
+pub mod insurance;
+pub mod interface;
+mod internal;
+pub mod invariants;
+pub mod leaderboard;
+mod legacy;
+pub mod loyalty;
+pub mod lp_pool;
+pub mod market_index;
+pub mod math;
+pub mod merkle;
+pub mod micro_claims;
+pub mod migration;
+pub mod milestones;
+pub mod oracle;
+pub mod outbox;
+pub mod outcomes;
+mod owner;
+pub mod partner_pools;
+pub mod pause;
+pub mod pay_and_rate;
+pub mod ping;
+pub mod platform;
+pub mod position_state;
+pub mod positions;
+pub mod price_feed;
+pub mod projection;
+pub mod promotions;
+pub mod protocol_fee;
+pub mod prune;
+pub mod reconciliation;
+pub mod reputation;
+pub mod restake;
+pub mod revenue;
+pub mod reward_adjustments;
+pub mod ring_buffer;
+pub mod roles;
+pub mod sessions;
+pub mod slashing;
+pub mod snapshots;
+pub mod sponsorship;
+pub mod stake_intents;
+pub mod stake_terms;
+pub mod state_export;
+pub mod state_root;
+pub mod stats;
+pub mod storage_deposits;
+pub mod storage_keys;
+pub mod sunset;
+pub mod sweep;
+pub mod tax_report;
+#[cfg(test)]
+mod test_utils;
+pub mod time;
+pub mod token_metadata;
+pub mod tvl_cap;
+pub mod unstake_fee;
+pub mod upgrade;
+pub mod usd_peg;
+pub mod vesting;
+pub mod views;
+pub mod vote_categories;
+pub mod vote_curve;
+pub mod vote_fee;
+pub mod vote_horizon;
+pub mod vote_tenure;
+pub mod watchdog;
+pub mod withdraw;
+pub mod withdrawal_eta;
+pub mod wrap_near;
+pub use crate::abandoned::*;
+pub use crate::accrual_cap::*;
+pub use crate::activity::*;
+pub use crate::api_version::*;
+pub use crate::appeals::*;
+pub use crate::apr_config::*;
+pub use crate::apr_history::*;
+pub use crate::badges::*;
+pub use crate::balance::*;
+pub use crate::batch_claim::*;
+pub use crate::bonus::*;
+pub use crate::boosters::*;
+pub use crate::breaker::*;
+pub use crate::burn::*;
+pub use crate::campaigns::*;
+pub use crate::cancel_stake::*;
+pub use crate::claim_and_call::*;
+pub use crate::claim_statement::*;
+pub use crate::collateral::*;
+pub use crate::commission::*;
+pub use crate::compounding::*;
+pub use crate::config_history::*;
+pub use crate::confirmations::*;
+pub use crate::courses::*;
+pub use crate::degraded::*;
+pub use crate::delegated_claim::*;
+pub use crate::delegation::*;
+pub use crate::deposit_policy::*;
+pub use crate::deposits::*;
+pub use crate::diagnostics::*;
+pub use crate::eligibility::*;
+pub use crate::entry_queue::*;
+pub use crate::errors::*;
 pub use crate::external::*;
+pub use crate::faucet::*;
+pub use crate::forfeit::*;
+pub use crate::freeze::*;
+pub use crate::governance_boost::*;
+pub use crate::groups::*;
+pub use crate::health::*;
+pub use crate::hooks::*;
+pub use crate::idempotency::*;
+pub use crate::import::*;
+pub use crate::insurance::*;
+pub use crate::interface::*;
+pub use crate::invariants::*;
+pub use crate::leaderboard::*;
+pub use crate::legacy::*;
+pub use crate::loyalty::*;
+pub use crate::lp_pool::*;
+pub use crate::market_index::*;
+use crate::math::{Rate, RATE_PRECISION};
+pub use crate::merkle::*;
+pub use crate::micro_claims::*;
+pub use crate::migration::*;
+pub use crate::milestones::*;
+pub use crate::oracle::*;
+pub use crate::outbox::*;
+pub use crate::outcomes::*;
+pub use crate::partner_pools::*;
+pub use crate::pay_and_rate::*;
+pub use crate::ping::*;
+pub use crate::platform::*;
+pub use crate::position_state::*;
+pub use crate::positions::*;
+pub use crate::price_feed::*;
+pub use crate::projection::*;
+pub use crate::promotions::*;
+pub use crate::protocol_fee::*;
+pub use crate::prune::*;
+pub use crate::reconciliation::*;
+pub use crate::reputation::*;
+pub use crate::revenue::*;
+pub use crate::reward_adjustments::*;
+use crate::ring_buffer::RingBuffer;
+pub use crate::roles::*;
+pub use crate::sessions::*;
+pub use crate::slashing::*;
+pub use crate::snapshots::*;
+pub use crate::sponsorship::*;
+pub use crate::stake_intents::*;
+pub use crate::stake_terms::*;
+pub use crate::state_export::*;
+pub use crate::state_root::*;
+pub use crate::stats::*;
+pub use crate::storage_deposits::*;
+pub use crate::storage_keys::*;
+pub use crate::sweep::*;
+pub use crate::tax_report::*;
+use crate::time::{contract_clock, Clock, Duration, Timestamp};
+pub use crate::token_metadata::*;
+pub use crate::unstake_fee::*;
+pub use crate::upgrade::*;
+pub use crate::usd_peg::*;
+pub use crate::vesting::*;
+pub use crate::views::*;
+pub use crate::vote_categories::*;
+pub use crate::vote_curve::*;
+pub use crate::vote_fee::*;
+pub use crate::vote_horizon::*;
+pub use crate::vote_tenure::*;
+pub use crate::watchdog::*;
+pub use crate::withdraw::*;
+pub use crate::withdrawal_eta::*;
+pub use crate::wrap_near::*;
 
 #[near_bindgen]
-#[derive(BorshDeserialize, BorshSerialize)]
+#[derive(BorshDeserialize, BorshSerialize, Clone)]
 pub struct StakeInfo {
-    time_staked: i64,
+    time_staked: Timestamp,
     amount_staked: u128,
     reward: u128,
     apr: u128,
     votes: u8,
+    lifetime_staked: u128,
+    lifetime_unstaked: u128,
+    lifetime_rewards_accrued: u128,
+    lifetime_rewards_claimed: u128,
+    withdrawable: u128,
+    // set while a withdraw promise is in flight
+    pending_operation: bool,
+    state: PositionState,
+    state_changed_at: Timestamp,
+    last_claimed_at: Option<Timestamp>,
+    bonus_per_share_paid: u128,
+    apr_index_paid: u128,
+    votes_received: u64,
+    reputation: i64,
+    reputation_updated_at: Timestamp,
+    badge: Option<Badge>,
+    loyalty_bps: u32,
+    // moved into a lead's pool, still counted in total_staked until withdrawn
+    delegated: u128,
+    // accrual over the cap waits here, released on later touches
+    deferred_reward: u128,
+    accrual_window_start: Timestamp,
+    accrued_in_window: u128,
+    // votes received per rating, 1 to 5 stars
+    rating_counts: [u32; 5],
+    // moved into groups for shares, the principal still behind the shares held
+    grouped: u128,
+    // set while a freeze or slash dispute holds the position's accrual
+    paused_accrual_since: Option<Timestamp>,
+    // stored under `stake_terms`, the copy as loaded tells whether it needs a write
+    #[borsh_skip]
+    terms: StakeTerms,
+    #[borsh_skip]
+    stored_terms: Option<StakeTerms>,
+}
+
+impl StakeInfo {
+    pub(crate) fn new(amount_staked: u128, apr: u128) -> Self {
+        StakeInfo {
+            time_staked: Contract::now(),
+            amount_staked,
+            reward: 0,
+            apr,
+            votes: 0,
+            lifetime_staked: amount_staked,
+            lifetime_unstaked: 0,
+            lifetime_rewards_accrued: 0,
+            lifetime_rewards_claimed: 0,
+            withdrawable: 0,
+            pending_operation: false,
+            state: PositionState::Active,
+            state_changed_at: Contract::now(),
+            last_claimed_at: None,
+            bonus_per_share_paid: 0,
+            apr_index_paid: 0,
+            votes_received: 0,
+            reputation: 0,
+            reputation_updated_at: Contract::now(),
+            badge: None,
+            loyalty_bps: 0,
+            delegated: 0,
+            deferred_reward: 0,
+            accrual_window_start: Contract::now(),
+            accrued_in_window: 0,
+            rating_counts: [0; 5],
+            grouped: 0,
+            paused_accrual_since: None,
+            terms: StakeTerms {
+                opened_at: Contract::now(),
+                base_apr: apr,
+                compounding: false,
+                booster: None,
+                first_staked_at: Contract::now(),
+                streak_started_at: Some(Contract::now()),
+                governance: None,
+                insured_since: None,
+            },
+            stored_terms: None,
+        }
+    }
 }
 
 #[near_bindgen]
 #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
 pub struct Contract {
+    pub owner_id: AccountId,
     pub token_address: AccountId,
     pub total_stakers: u128,
     pub total_staked: u128,
+    pub legacy_stake_info: LookupMap<AccountId, LegacyStakeInfo>,
     pub stake_info: LookupMap<AccountId, StakeInfo>,
+    pub apr_history: LookupMap<AccountId, RingBuffer<AprChange>>,
+    pub total_apr: u128,
+    pub reward_pool: u128,
+    pub total_rewards_paid: u128,
+    // sum of every position's yearly reward, used to estimate outstanding rewards
+    pub reward_rate: u128,
+    pub accrued_rewards: u128,
+    pub last_accrual_update: Timestamp,
+    pub total_rewards_accrued: u128,
+    pub total_volume_staked: u128,
+    pub total_volume_unstaked: u128,
+    pub total_withdrawable: u128,
+    pub faucet_balance: u128,
+    pub faucet_amount: u128,
+    pub faucet_cooldown: Duration,
+    pub faucet_last_claim: LookupMap<AccountId, Timestamp>,
+    // tokens sent out by promises that have not resolved yet
+    pub pending_outflows: u128,
+    pub deny_list: LookupSet<AccountId>,
+    pub allow_list: LookupSet<AccountId>,
+    pub allow_list_enabled: bool,
+    pub attestation_registry: Option<AccountId>,
+    pub unbond_period: Duration,
+    pub min_advisor_stake: u128,
+    pub vesting_config: Option<VestingConfig>,
+    pub vesting: LookupMap<AccountId, Vec<VestingEntry>>,
+    pub total_vesting: u128,
+    pub claim_cooldown: Duration,
+    pub unstake_fee_bps: u16,
+    pub fee_exempt: LookupSet<AccountId>,
+    pub total_unstake_fees: u128,
+    pub imported_accounts: LookupSet<AccountId>,
+    pub import_finished: bool,
+    pub upgrade_delay: Duration,
+    pub staged_upgrade: Option<StagedUpgrade>,
+    pub staker_ids: UnorderedSet<AccountId>,
+    pub snapshots: RingBuffer<Snapshot>,
+    pub snapshot_interval: Duration,
+    pub last_snapshot_at: Timestamp,
+    pub stakers_by_amount: TreeMap<(u128, AccountId), ()>,
+    pub stakers_by_votes: TreeMap<(u8, AccountId), ()>,
+    pub bonus_balance: u128,
+    pub bonus_per_share: u128,
+    pub bonus_distributions: u64,
+    pub merkle_campaign: Option<MerkleCampaign>,
+    pub merkle_campaigns: u64,
+    pub claimed_leaves: LookupSet<Vec<u8>>,
+    pub claim_keys: LookupMap<AccountId, PublicKey>,
+    pub claim_nonces: LookupMap<AccountId, u64>,
+    pub oracle_id: Option<AccountId>,
+    pub apr_curve: Vec<CurvePoint>,
+    pub last_metric: Option<U128>,
+    pub apr_modifier_bps: u32,
+    pub apr_index: u128,
+    pub apr_index_updated_at: Timestamp,
+    pub course_stakes: LookupMap<AccountId, Vec<CourseStake>>,
+    pub arbiter_id: Option<AccountId>,
+    pub slash_challenge_period: Duration,
+    pub slash_proposals: LookupMap<u64, SlashProposal>,
+    pub slash_proposal_count: u64,
+    pub open_slashes: LookupMap<AccountId, u64>,
+    pub total_slashed: u128,
+    pub insurance_bps: u16,
+    pub insurance_balance: u128,
+    pub total_insurance_contributed: u128,
+    pub total_insurance_paid: u128,
+    pub compound_period: Duration,
+    pub default_apr: u128,
+    pub vote_step: u128,
+    pub vote_weights: [i64; 5],
+    pub vote_grace_period: Duration,
+    pub vote_grace_votes: u64,
+    pub account_history: LookupMap<AccountId, RingBuffer<ActivityEntry>>,
+    pub last_mutation_at: Timestamp,
+    pub token_balance: Option<u128>,
+    pub token_balance_checked_at: Timestamp,
+    pub processed_ops: LookupMap<(AccountId, String), u128>,
+    pub processed_op_ids: RingBuffer<(AccountId, String)>,
+    pub keeper_id: Option<AccountId>,
+    pub stake_intents: LookupMap<AccountId, StakeIntent>,
+    pub total_intent_balance: u128,
+    pub paused: bool,
+    pub platform_contract: Option<AccountId>,
+    pub hooks: Vec<AccountId>,
+    pub token_decimals: Option<u8>,
+    pub min_apr: u128,
+    pub max_apr: u128,
+    pub token_verification_pending: bool,
+    pub deposits: LookupMap<AccountId, u128>,
+    pub total_deposits: u128,
+    pub vote_records: LookupMap<u64, VoteRecord>,
+    pub vote_record_count: u64,
+    pub appeal_arbiters: Vec<AccountId>,
+    pub appeal_bond: u128,
+    pub appeal_window: Duration,
+    pub reputation_half_life: Duration,
+    pub booster_contracts: LookupMap<AccountId, u32>,
+    pub lp_pool: Option<LpPool>,
+    pub lp_stakes: LookupMap<AccountId, LpStake>,
+    pub total_revenue: u128,
+    pub revenue_epochs: LookupMap<u64, RevenueEpoch>,
+    pub max_total_staked: Option<u128>,
+    pub queue_entries: LookupMap<AccountId, QueueEntry>,
+    pub queue_order: LookupMap<u64, AccountId>,
+    pub queue_head: u64,
+    pub queue_tail: u64,
+    pub total_queued: u128,
+    pub roles: LookupSet<(Role, AccountId)>,
+    pub action_admins: Vec<AccountId>,
+    pub confirmation_threshold: u32,
+    pub pending_actions: LookupMap<u64, PendingAction>,
+    pub action_count: u64,
+    pub action_ttl: Duration,
+    pub vote_horizon: Option<Duration>,
+    pub vote_windows: LookupMap<AccountId, VoteWindow>,
+    pub stake_terms: LookupMap<AccountId, StakeTerms>,
+    pub dust_accumulator: u128,
+    pub badge_tiers: Vec<BadgeTier>,
+    pub loyalty_config: Option<LoyaltyConfig>,
+    pub campaigns: LookupMap<u64, Campaign>,
+    pub campaign_count: u64,
+    pub campaign_stakes: LookupMap<(u64, AccountId), CampaignStake>,
+    pub account_campaigns: LookupMap<AccountId, Vec<u64>>,
+    pub total_campaign_reserve: u128,
+    pub delegation_pools: LookupMap<AccountId, DelegationPool>,
+    pub delegations: LookupMap<AccountId, Delegation>,
+    pub approved_vaults: LookupSet<AccountId>,
+    pub accrual_cap: Option<AccrualCap>,
+    pub breaker_config: Option<BreakerConfig>,
+    // (bucket, amount) for the last day, oldest first
+    pub outflow_buckets: Vec<(u64, u128)>,
+    pub breaker_tripped_at: Option<Timestamp>,
+    pub course_payment_config: Option<CoursePaymentConfig>,
+    pub protocol_fee_bps: u16,
+    pub protocol_fee_account: Option<AccountId>,
+    pub protocol_fee_balance: u128,
+    pub total_protocol_fees: u128,
+    pub sessions: LookupMap<String, Session>,
+    pub session_locked: LookupMap<AccountId, u128>,
+    pub forfeit_destination: ForfeitDestination,
+    pub burn_mode: Option<BurnMode>,
+    pub burn_penalties: bool,
+    pub pending_burn: u128,
+    pub total_forfeited: u128,
+    pub total_burned: u128,
+    pub config_history: RingBuffer<ConfigChange>,
+    pub frozen_accounts: LookupMap<AccountId, String>,
+    pub stake_cancel_window: Duration,
+    pub stakers_by_apr: TreeMap<(u128, AccountId), ()>,
+    pub market_index: MarketIndex,
+    pub prices: LookupMap<String, PricePoint>,
+    pub max_price_age: Duration,
+    pub groups: LookupMap<u64, Group>,
+    pub group_count: u64,
+    pub group_members: LookupMap<(u64, AccountId), GroupMember>,
+    // what the groups' shares are worth, counted in total_staked
+    pub total_grouped: u128,
+    pub collateral_lockers: LookupSet<AccountId>,
+    // (account, locker)
+    pub collateral_liens: LookupMap<(AccountId, AccountId), CollateralLien>,
+    pub collateral_locked: LookupMap<AccountId, u128>,
+    // methods the owner let go without the yocto
+    pub deposit_exempt: UnorderedSet<String>,
+    pub deposit_relayers: LookupSet<AccountId>,
+    // paid from the reward pool to whoever pings first after the interval
+    pub ping_reward: u128,
+    pub ping_interval: Duration,
+    pub last_paid_ping: Timestamp,
+    pub total_ping_rewards: u128,
+    pub outbox: LookupMap<u64, OutboxTransfer>,
+    pub outbox_head: u64,
+    pub outbox_tail: u64,
+    // part of pending_outflows still waiting in the outbox
+    pub queued_outflows: u128,
+    pub outbox_budget: u32,
+    pub outbox_block: u64,
+    pub outbox_sent: u32,
+    // relays scoped to one course category or region
+    pub category_relays: LookupMap<AccountId, String>,
+    pub category_weights: UnorderedMap<String, u16>,
+    pub governance_token: Option<AccountId>,
+    pub governance_tiers: Vec<GovernanceTier>,
+    pub micro_claims: LookupMap<AccountId, MicroClaimTally>,
+    pub partner_pools: LookupMap<u64, PartnerPool>,
+    pub partner_pool_count: u64,
+    pub pool_stakes: LookupMap<(u64, AccountId), PoolStake>,
+    // held for the partner pools, kept out of every advisor total
+    pub total_partner_staked: u128,
+    pub total_partner_rewards: u128,
+    pub min_session_duration: Duration,
+    // per relay, votes kept from moving apr by min_session_duration
+    pub rejected_votes: LookupMap<AccountId, RejectedVoteStats>,
+    pub total_rejected_votes: u64,
+    pub vote_fee: u128,
+    // (level, index), level 0 are the leaves
+    pub state_nodes: LookupMap<(u8, u64), CryptoHash>,
+    pub state_leaf_index: LookupMap<AccountId, u64>,
+    pub state_leaf_accounts: LookupMap<u64, AccountId>,
+    pub state_leaf_count: u64,
+    pub state_root: CryptoHash,
+    pub promotions: Vec<Promotion>,
+    pub promotion_count: u64,
+    pub insurance_policy: Option<InsurancePolicy>,
+    // by slash proposal id
+    pub insurance_claims: LookupMap<u64, InsuranceClaim>,
+    pub pause_accrual_on_hold: bool,
+    pub abandon_timeout: Option<Duration>,
+    pub abandoned_entries: LookupMap<u64, AbandonedEntry>,
+    pub abandoned_count: u64,
+    // swept and not restored yet
+    pub total_abandoned: u128,
+    pub hidden_from_listings: LookupSet<AccountId>,
+    pub total_inflows: u128,
+    pub total_outflows: u128,
+    pub total_reward_funded: u128,
+    pub total_swept: u128,
+    // opted into the no-deposit variants
+    pub access_key_accounts: LookupSet<AccountId>,
+    pub failed_operations: RingBuffer<FailedOperation>,
+    // contracts migrate_to may forward positions to
+    pub migration_successors: LookupSet<AccountId>,
+    pub vote_curve: Vec<CurveSegment>,
+    pub sponsored_budgets: LookupMap<AccountId, SponsoredBudget>,
+    pub total_sponsored: u128,
+    pub sponsorship_duration: Duration,
+    // bumped on every position write, see ReconcileJob
+    pub stake_writes: u64,
+    pub reconcile_job: Option<ReconcileJob>,
+    // set by migrate until the owner confirms every old record was touched
+    pub legacy_pending: bool,
+    pub usd_peg: Option<UsdPeg>,
+    pub consecutive_transfer_failures: u32,
+    pub transfer_failure_threshold: u32,
+    pub transfers_degraded_since: Option<Timestamp>,
+    pub milestones: LookupMap<(AccountId, String), Milestone>,
+    // per advisor, in the order recorded
+    pub milestone_ids: LookupMap<AccountId, Vector<String>>,
+    pub milestone_budget: u128,
+    pub hook_queue: LookupMap<u64, HookNotification>,
+    pub hook_queue_head: u64,
+    pub hook_queue_tail: u64,
+    // which account a booster token currently boosts
+    pub booster_holders: LookupMap<(AccountId, String), AccountId>,
+    // arbiter stakes of each pending appeal, taken at filing
+    pub appeal_weights: LookupMap<u64, Vec<(AccountId, U128)>>,
+    pub appeal_expiry: Duration,
+    // part of total_queued in entries the queue head skipped
+    pub total_skipped_queued: u128,
+    pub token_balance_refresh_block: u64,
+    pub stake_positions: LookupMap<AccountId, Vec<StakePosition>>,
+    pub position_count: u64,
+    pub storage_deposits: LookupMap<AccountId, StorageDeposit>,
+    pub deferred_session_penalties: LookupMap<AccountId, DeferredSessionPenalty>,
+    // set for the length of a batch, never stored
+    #[borsh_skip]
+    pub hooks_deferred: bool,
+}
+
+// msg of ft_transfer_call, an empty msg stakes the tokens
+#[derive(Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub enum TransferMsg {
+    Stake,
+    StakeForCourse { course_id: String },
+    FundRewardPool,
+    FundFaucet,
+    FundBonus,
+    FundIntent,
+    FundLpRewards,
+    RevenueShare,
+    JoinQueue,
+    FundCampaign { campaign_id: u64 },
+    PayAndRate { advisor: AccountId, rating: u8 },
+    StakeInPool { pool_id: u64 },
+    FundPool { pool_id: u64 },
+    // a relayed vote carrying the vote fee, the transfer is added to the reward pool
+    Vote(PaidVote),
+    // enrollment funding for one advisor's rewards
+    SponsorAdvisor { advisor: AccountId },
+    FundMilestones,
+    // stakes into a running position of the sender and holds it at least `duration` longer
+    ExtendLock { position_id: u64, duration: U64 },
+    // stakes into a new position locked for `duration`
+    NewPosition { duration: U64 },
+    // stakes only while none of the sender's locks runs, otherwise refunds
+    RejectIfLocked,
 }
 
-#[derive(BorshDeserialize, BorshSerialize, BorshStorageKey)]
-pub enum StorageKey {
-    StakeInfoKey,
+// optional settings of `new`, anything left out keeps its default
+#[derive(Deserialize, Serialize, Default)]
+#[serde(crate = "near_sdk::serde")]
+pub struct InitConfig {
+    pub owner_id: Option<AccountId>,
+    pub default_apr: Option<U128>,
+    pub min_apr: Option<U128>,
+    pub max_apr: Option<U128>,
+    pub min_advisor_stake: Option<U128>,
+    pub unbond_period: Option<U64>,
+    // stakes stay refused until the token answered ft_metadata
+    pub verify_token: Option<bool>,
 }
 
 #[near_bindgen]
 impl Contract {
     #[init]
-    pub fn new(_token_address: AccountId) -> Self {
-        Contract {
+    pub fn new(_token_address: AccountId, config: Option<InitConfig>) -> Self {
+        let config = config.unwrap_or_default();
+        require!(
+            _token_address != env::current_account_id(),
+            StakeError::InvalidTokenAddress
+        );
+        let verify_token = config.verify_token.unwrap_or(false);
+        let mut contract = Contract {
+            owner_id: config.owner_id.unwrap_or_else(env::predecessor_account_id),
             token_address: _token_address,
             total_stakers: 0,
             total_staked: 0,
-            stake_info: LookupMap::new(StorageKey::StakeInfoKey),
+            legacy_stake_info: LookupMap::new(StorageKey::StakeInfoKey),
+            stake_info: LookupMap::new(StorageKey::StakeInfoV2),
+            apr_history: LookupMap::new(StorageKey::AprHistory),
+            total_apr: 0,
+            reward_pool: 0,
+            total_rewards_paid: 0,
+            reward_rate: 0,
+            accrued_rewards: 0,
+            last_accrual_update: Self::now(),
+            total_rewards_accrued: 0,
+            total_volume_staked: 0,
+            total_volume_unstaked: 0,
+            total_withdrawable: 0,
+            faucet_balance: 0,
+            faucet_amount: 0,
+            faucet_cooldown: DEFAULT_FAUCET_COOLDOWN,
+            faucet_last_claim: LookupMap::new(StorageKey::FaucetLastClaim),
+            pending_outflows: 0,
+            deny_list: LookupSet::new(StorageKey::DenyList),
+            allow_list: LookupSet::new(StorageKey::AllowList),
+            allow_list_enabled: false,
+            attestation_registry: None,
+            unbond_period: config
+                .unbond_period
+                .map_or(DEFAULT_UNBOND_PERIOD, Duration::from),
+            min_advisor_stake: config.min_advisor_stake.map_or(0, |stake| stake.0),
+            vesting_config: None,
+            vesting: LookupMap::new(StorageKey::Vesting),
+            total_vesting: 0,
+            claim_cooldown: Duration::default(),
+            unstake_fee_bps: 0,
+            fee_exempt: LookupSet::new(StorageKey::FeeExempt),
+            total_unstake_fees: 0,
+            imported_accounts: LookupSet::new(StorageKey::ImportedAccounts),
+            import_finished: false,
+            upgrade_delay: Duration::default(),
+            staged_upgrade: None,
+            staker_ids: UnorderedSet::new(StorageKey::StakerIds),
+            snapshots: RingBuffer::new(StorageKey::Snapshots, MAX_SNAPSHOTS),
+            snapshot_interval: DEFAULT_SNAPSHOT_INTERVAL,
+            last_snapshot_at: Timestamp::default(),
+            stakers_by_amount: TreeMap::new(StorageKey::StakersByAmount),
+            stakers_by_votes: TreeMap::new(StorageKey::StakersByVotes),
+            bonus_balance: 0,
+            bonus_per_share: 0,
+            bonus_distributions: 0,
+            merkle_campaign: None,
+            merkle_campaigns: 0,
+            claimed_leaves: LookupSet::new(StorageKey::ClaimedLeaves),
+            claim_keys: LookupMap::new(StorageKey::ClaimKeys),
+            claim_nonces: LookupMap::new(StorageKey::ClaimNonces),
+            oracle_id: None,
+            apr_curve: vec![],
+            last_metric: None,
+            apr_modifier_bps: NEUTRAL_APR_MODIFIER_BPS,
+            // starts out as plain time so marks taken before any modifier change line up
+            apr_index: math::BPS_DENOMINATOR * Self::now().as_nanos() as u128,
+            apr_index_updated_at: Self::now(),
+            course_stakes: LookupMap::new(StorageKey::CourseStakes),
+            arbiter_id: None,
+            slash_challenge_period: DEFAULT_CHALLENGE_PERIOD,
+            slash_proposals: LookupMap::new(StorageKey::SlashProposals),
+            slash_proposal_count: 0,
+            open_slashes: LookupMap::new(StorageKey::OpenSlashes),
+            total_slashed: 0,
+            insurance_bps: 0,
+            insurance_balance: 0,
+            total_insurance_contributed: 0,
+            total_insurance_paid: 0,
+            compound_period: DEFAULT_COMPOUND_PERIOD,
+            default_apr: config.default_apr.map_or(DEFAULT_APR, |apr| apr.0),
+            vote_step: DEFAULT_VOTE_STEP,
+            vote_weights: DEFAULT_VOTE_WEIGHTS,
+            vote_grace_period: Duration::from_nanos(0),
+            vote_grace_votes: 0,
+            account_history: LookupMap::new(StorageKey::AccountHistory),
+            last_mutation_at: Timestamp::default(),
+            token_balance: None,
+            token_balance_checked_at: Timestamp::default(),
+            processed_ops: LookupMap::new(StorageKey::ProcessedOps),
+            processed_op_ids: RingBuffer::new(StorageKey::ProcessedOpIds, MAX_PROCESSED_OPS),
+            keeper_id: None,
+            stake_intents: LookupMap::new(StorageKey::StakeIntents),
+            total_intent_balance: 0,
+            paused: false,
+            platform_contract: None,
+            hooks: vec![],
+            token_decimals: None,
+            min_apr: config.min_apr.map_or(0, |apr| apr.0),
+            max_apr: config.max_apr.map_or(MAX_APR.raw(), |apr| apr.0),
+            token_verification_pending: verify_token,
+            deposits: LookupMap::new(StorageKey::Deposits),
+            total_deposits: 0,
+            vote_records: LookupMap::new(StorageKey::VoteRecords),
+            vote_record_count: 0,
+            appeal_arbiters: vec![],
+            appeal_bond: DEFAULT_APPEAL_BOND,
+            appeal_window: DEFAULT_APPEAL_WINDOW,
+            reputation_half_life: DEFAULT_REPUTATION_HALF_LIFE,
+            booster_contracts: LookupMap::new(StorageKey::BoosterContracts),
+            lp_pool: None,
+            lp_stakes: LookupMap::new(StorageKey::LpStakes),
+            total_revenue: 0,
+            revenue_epochs: LookupMap::new(StorageKey::RevenueEpochs),
+            max_total_staked: None,
+            queue_entries: LookupMap::new(StorageKey::QueueEntries),
+            queue_order: LookupMap::new(StorageKey::QueueOrder),
+            queue_head: 0,
+            queue_tail: 0,
+            total_queued: 0,
+            roles: LookupSet::new(StorageKey::Roles),
+            action_admins: vec![],
+            confirmation_threshold: 0,
+            pending_actions: LookupMap::new(StorageKey::PendingActions),
+            action_count: 0,
+            action_ttl: DEFAULT_ACTION_TTL,
+            vote_horizon: None,
+            vote_windows: LookupMap::new(StorageKey::VoteWindows),
+            stake_terms: LookupMap::new(StorageKey::StakeTerms),
+            dust_accumulator: 0,
+            badge_tiers: vec![],
+            loyalty_config: None,
+            campaigns: LookupMap::new(StorageKey::Campaigns),
+            campaign_count: 0,
+            campaign_stakes: LookupMap::new(StorageKey::CampaignStakes),
+            account_campaigns: LookupMap::new(StorageKey::AccountCampaigns),
+            total_campaign_reserve: 0,
+            delegation_pools: LookupMap::new(StorageKey::DelegationPools),
+            delegations: LookupMap::new(StorageKey::Delegations),
+            approved_vaults: LookupSet::new(StorageKey::ApprovedVaults),
+            accrual_cap: None,
+            breaker_config: None,
+            outflow_buckets: vec![],
+            breaker_tripped_at: None,
+            course_payment_config: None,
+            protocol_fee_bps: 0,
+            protocol_fee_account: None,
+            protocol_fee_balance: 0,
+            total_protocol_fees: 0,
+            sessions: LookupMap::new(StorageKey::Sessions),
+            session_locked: LookupMap::new(StorageKey::SessionLocked),
+            forfeit_destination: ForfeitDestination::RewardPool,
+            burn_mode: None,
+            burn_penalties: false,
+            pending_burn: 0,
+            total_forfeited: 0,
+            total_burned: 0,
+            config_history: RingBuffer::new(StorageKey::ConfigHistory, MAX_CONFIG_HISTORY),
+            frozen_accounts: LookupMap::new(StorageKey::FrozenAccounts),
+            stake_cancel_window: DEFAULT_STAKE_CANCEL_WINDOW,
+            stakers_by_apr: TreeMap::new(StorageKey::StakersByApr),
+            market_index: MarketIndex::default(),
+            prices: LookupMap::new(StorageKey::Prices),
+            max_price_age: DEFAULT_MAX_PRICE_AGE,
+            groups: LookupMap::new(StorageKey::Groups),
+            group_count: 0,
+            group_members: LookupMap::new(StorageKey::GroupMembers),
+            total_grouped: 0,
+            collateral_lockers: LookupSet::new(StorageKey::CollateralLockers),
+            collateral_liens: LookupMap::new(StorageKey::CollateralLiens),
+            collateral_locked: LookupMap::new(StorageKey::CollateralLocked),
+            deposit_exempt: UnorderedSet::new(StorageKey::DepositExempt),
+            deposit_relayers: LookupSet::new(StorageKey::DepositRelayers),
+            ping_reward: 0,
+            ping_interval: DEFAULT_PING_INTERVAL,
+            last_paid_ping: Timestamp::default(),
+            total_ping_rewards: 0,
+            outbox: LookupMap::new(StorageKey::Outbox),
+            outbox_head: 0,
+            outbox_tail: 0,
+            queued_outflows: 0,
+            outbox_budget: DEFAULT_OUTBOX_BUDGET,
+            outbox_block: 0,
+            outbox_sent: 0,
+            category_relays: LookupMap::new(StorageKey::CategoryRelays),
+            category_weights: UnorderedMap::new(StorageKey::CategoryWeights),
+            governance_token: None,
+            governance_tiers: vec![],
+            micro_claims: LookupMap::new(StorageKey::MicroClaims),
+            partner_pools: LookupMap::new(StorageKey::PartnerPools),
+            partner_pool_count: 0,
+            pool_stakes: LookupMap::new(StorageKey::PoolStakes),
+            total_partner_staked: 0,
+            total_partner_rewards: 0,
+            min_session_duration: Duration::default(),
+            rejected_votes: LookupMap::new(StorageKey::RejectedVotes),
+            total_rejected_votes: 0,
+            vote_fee: 0,
+            state_nodes: LookupMap::new(StorageKey::StateNodes),
+            state_leaf_index: LookupMap::new(StorageKey::StateLeafIndex),
+            state_leaf_accounts: LookupMap::new(StorageKey::StateLeafAccounts),
+            state_leaf_count: 0,
+            state_root: zero_state_root(),
+            promotions: Vec::new(),
+            promotion_count: 0,
+            insurance_policy: None,
+            insurance_claims: LookupMap::new(StorageKey::InsuranceClaims),
+            pause_accrual_on_hold: false,
+            abandon_timeout: None,
+            abandoned_entries: LookupMap::new(StorageKey::AbandonedEntries),
+            abandoned_count: 0,
+            total_abandoned: 0,
+            hidden_from_listings: LookupSet::new(StorageKey::HiddenFromListings),
+            total_inflows: 0,
+            total_outflows: 0,
+            total_reward_funded: 0,
+            total_swept: 0,
+            access_key_accounts: LookupSet::new(StorageKey::AccessKeyAccounts),
+            failed_operations: RingBuffer::new(StorageKey::FailedOperations, MAX_FAILED_OPERATIONS),
+            migration_successors: LookupSet::new(StorageKey::MigrationSuccessors),
+            vote_curve: vec![],
+            sponsored_budgets: LookupMap::new(StorageKey::SponsoredBudgets),
+            total_sponsored: 0,
+            sponsorship_duration: DEFAULT_SPONSORSHIP_DURATION,
+            stake_writes: 0,
+            reconcile_job: None,
+            legacy_pending: false,
+            usd_peg: None,
+            consecutive_transfer_failures: 0,
+            transfer_failure_threshold: DEFAULT_TRANSFER_FAILURE_THRESHOLD,
+            transfers_degraded_since: None,
+            milestones: LookupMap::new(StorageKey::Milestones),
+            milestone_ids: LookupMap::new(StorageKey::MilestoneIds),
+            milestone_budget: 0,
+            hook_queue: LookupMap::new(StorageKey::HookQueue),
+            hook_queue_head: 0,
+            hook_queue_tail: 0,
+            booster_holders: LookupMap::new(StorageKey::BoosterHolders),
+            appeal_weights: LookupMap::new(StorageKey::AppealWeights),
+            appeal_expiry: DEFAULT_APPEAL_EXPIRY,
+            total_skipped_queued: 0,
+            token_balance_refresh_block: 0,
+            stake_positions: LookupMap::new(StorageKey::StakePositions),
+            position_count: 0,
+            storage_deposits: LookupMap::new(StorageKey::StorageDeposits),
+            deferred_session_penalties: LookupMap::new(StorageKey::DeferredSessionPenalties),
+            hooks_deferred: false,
+        };
+        contract.internal_assert_apr_config();
+        if verify_token {
+            contract.paused = true;
         }
+        contract.internal_fetch_token_metadata();
+        contract
     }
 
     // call ft_transfer_call on token contract to do stake_token fn called by token contract
@@ -61,149 +910,446 @@ impl Contract {
         amount: U128,
         msg: String,
     ) -> PromiseOrValue<U128> {
-        let _stake_amount = u128::from(amount);
-        let _account_id = sender_id;
-        require!(_stake_amount > 0, "Stake: Invalid amount!");
-
-        let info = self.stake_info.get(&_account_id);
-        match info {
-            Some(mut unwrap_info) => {
-                unwrap_info.time_staked = Self::now();
-                unwrap_info.amount_staked += _stake_amount;
-                unwrap_info.reward += Self::pending_reward(&self, _account_id.clone());
-
-                self.stake_info.insert(&_account_id, &unwrap_info);
-            }
-            None => {
-                let stake_info = StakeInfo {
-                    time_staked: Self::now(),
-                    amount_staked: _stake_amount,
-                    reward: 0,
-                    apr: DEFAULT_APR,
-                    votes: 0,
-                };
-                self.stake_info.insert(&_account_id, &stake_info);
-                self.total_stakers += 1;
+        let lp_token = LpToken::Ft {
+            contract_id: env::predecessor_account_id(),
+        };
+        if self.internal_is_lp_token(&lp_token) {
+            require!(amount.0 > 0, StakeError::InvalidAmount);
+            if self.paused {
+                env::log_str(&StakeError::Paused);
+                return PromiseOrValue::Value(amount);
             }
+            self.internal_lp_stake(&sender_id, amount.0);
+            return PromiseOrValue::Value(U128(0));
         }
-        self.total_staked += _stake_amount;
-
-        return PromiseOrValue::Value(near_sdk::json_types::U128(0));
-    }
-
-    #[payable]
-    pub fn unstake_token(&mut self, _amount: U128) {
-        assert_one_yocto();
-        let _amount = u128::from(_amount);
-        let _account_id = env::signer_account_id();
-        require!(
-            self.stake_info.contains_key(&_account_id) == true,
-            "Stake: You didn't stake any tokens!"
-        );
-        let mut stake_info = self.stake_info.get(&_account_id).unwrap();
         require!(
-            stake_info.amount_staked > 0,
-            "Stake: You staked less token than amount"
+            env::predecessor_account_id() == self.token_address,
+            StakeError::UnsupportedToken
         );
-        require!(_amount > 0, "Stake: Invalid amount");
-
-        ext_ft_contract::ext(self.token_address.clone())
-            .with_static_gas(FT_TRANSFER_GAS)
-            .with_attached_deposit(ONE_YOCTO)
-            .ft_transfer(env::signer_account_id(), U128::from(_amount), None);
-
-        stake_info.amount_staked -= _amount;
-        stake_info.time_staked = Self::now();
-        stake_info.reward += Self::pending_reward(&self, _account_id.clone());
-
-        self.total_staked -= _amount;
+        let _amount = u128::from(amount);
+        require!(_amount > 0, StakeError::InvalidAmount);
 
-        self.stake_info.insert(&_account_id, &stake_info);
+        match self.internal_on_transfer(sender_id, _amount, &msg) {
+            Ok(result) => result,
+            // nothing was written yet, the token contract hands everything back
+            Err(error) => {
+                env::log_str(&error);
+                PromiseOrValue::Value(amount)
+            }
+        }
     }
 
+    // the principal goes to `receiver_id` right away when it is someone else, otherwise it
+    // becomes withdrawable
     #[payable]
-    pub fn claim_reward(&mut self) {
-        assert_one_yocto();
-        let _account_id = env::signer_account_id();
-        require!(
-            self.stake_info.contains_key(&_account_id) == true,
-            "Stake: You didn't stake any tokens!"
-        );
-        let mut stake_info = self.stake_info.get(&_account_id).unwrap();
+    pub fn unstake_token(
+        &mut self,
+        _amount: U128,
+        course_id: Option<String>,
+        receiver_id: Option<AccountId>,
+    ) -> PromiseOrValue<U128> {
+        self.internal_assert_deposit("unstake_token");
+        match self.internal_unstake_token(_amount.0, course_id, receiver_id) {
+            (received, None) => PromiseOrValue::Value(U128(received)),
+            (_, Some(dispatch)) => dispatch.into(),
+        }
+    }
 
-        let reward = Self::pending_reward(&self, _account_id.clone());
-        require!(reward > 0, "Stake: You have no reward yet!");
+    // a registered booster and a governance tier are checked again before the claim, a
+    // token that moved on loses its boost for everything not settled yet
+    #[payable]
+    pub fn claim_reward(&mut self) -> PromiseOrValue<U128> {
+        self.internal_assert_deposit("claim_reward");
+        self.internal_claim_reward_verified(env::predecessor_account_id())
+    }
 
-        ext_ft_contract::ext(self.token_address.clone())
-            .with_static_gas(FT_TRANSFER_GAS)
-            .with_attached_deposit(ONE_YOCTO)
-            .ft_transfer(env::signer_account_id(), U128::from(reward), None);
+    // api version 1, numbers above 2^53 lose precision in JS
+    #[handle_result]
+    pub fn pending_reward(&self, _account_id: AccountId) -> Result<u128, StakeError> {
+        Self::internal_log_deprecated("pending_reward", "v2_pending_reward");
+        let stake_info = self
+            .internal_get_stake_info(&_account_id)
+            .ok_or(StakeError::NotStaked)?;
+        Ok(self.internal_pending_reward_at(&stake_info, Self::now()))
+    }
 
-        stake_info.time_staked = Self::now();
-        stake_info.reward = 0;
+    // api version 1
+    #[handle_result]
+    pub fn get_staked_amount(&self, _advisor_id: AccountId) -> Result<u128, StakeError> {
+        Self::internal_log_deprecated("get_staked_amount", "v2_get_staked_amount");
+        Ok(self
+            .internal_get_stake_info(&_advisor_id)
+            .ok_or(StakeError::AdvisorNotStaked)?
+            .amount_staked)
+    }
 
-        self.stake_info.insert(&_account_id, &stake_info);
+    // replays of an op_id already seen from the same caller return the first result
+    pub fn update_apr(
+        &mut self,
+        _advisor_id: AccountId,
+        _learner_vote: u8,
+        course_id: Option<String>,
+        op_id: Option<String>,
+        category: Option<String>,
+        metadata: Option<VoteMetadata>,
+    ) -> U128 {
+        let category = self.internal_assert_vote_authority(category);
+        self.internal_assert_vote_fee_off();
+        let voter_id = env::predecessor_account_id();
+        let apr = self.internal_idempotent(op_id, |contract| {
+            let counts = contract.internal_check_vote_tenure(&voter_id, metadata.as_ref());
+            contract.internal_update_apr(
+                &_advisor_id,
+                &voter_id,
+                _learner_vote,
+                course_id,
+                category,
+                counts,
+            )
+        });
+        self.internal_maybe_snapshot();
+        U128(apr)
     }
+}
 
-    pub fn pending_reward(&self, _account_id: AccountId) -> u128 {
-        require!(
-            self.stake_info.contains_key(&_account_id) == true,
-            "Stake: You didn't stake any tokens!"
-        );
-        let stake_info = self.stake_info.get(&_account_id).unwrap();
+impl Contract {
+    pub(crate) fn now() -> Timestamp {
+        contract_clock().now()
+    }
 
-        let time_last = Self::now().sub(stake_info.time_staked);
-        let pending_reward = (stake_info.amount_staked * (time_last as u128) / (31536000 * 100))
-            * stake_info.apr
-            / ONE_NEAR;
-        return pending_reward + stake_info.reward;
+    // unstake_token past the deposit check, returns what was credited and the transfer
+    // when it went to someone else
+    pub(crate) fn internal_unstake_token(
+        &mut self,
+        amount: u128,
+        course_id: Option<String>,
+        receiver_id: Option<AccountId>,
+    ) -> (u128, Option<Dispatch>) {
+        let account_id = env::predecessor_account_id();
+        let receiver_id = receiver_id.unwrap_or_else(|| account_id.clone());
+        let received = self.internal_unstake(&account_id, amount, course_id, &receiver_id, false);
+        self.internal_process_queue(MAX_QUEUE_ACTIVATIONS);
+        self.internal_maybe_snapshot();
+        if receiver_id == account_id {
+            return (received, None);
+        }
+        (
+            received,
+            Some(self.internal_transfer_to(account_id, receiver_id, received)),
+        )
     }
 
-    pub fn get_staked_amount(&self, _advisor_id: AccountId) -> u128 {
-        require!(
-            self.stake_info.contains_key(&_advisor_id) == true,
-            "Stake: Advisor not stake any tokens!"
-        );
-        return self.stake_info.get(&_advisor_id).unwrap().amount_staked;
+    // claim_reward past the deposit check
+    pub(crate) fn internal_claim_reward_verified(
+        &mut self,
+        account_id: AccountId,
+    ) -> PromiseOrValue<U128> {
+        let terms = self
+            .internal_get_stake_info(&account_id)
+            .map(|stake_info| stake_info.terms);
+        let governed = terms
+            .as_ref()
+            .is_some_and(|terms| terms.governance.is_some());
+        if let (true, Some(token_id)) = (governed, self.governance_token.clone()) {
+            return PromiseOrValue::Promise(
+                self.internal_verify_governance(account_id, token_id, true),
+            );
+        }
+        let booster = terms.and_then(|terms| terms.booster);
+        if let Some(booster) = booster {
+            return PromiseOrValue::Promise(self.internal_verify_booster(
+                account_id,
+                booster.nft_contract,
+                booster.token_id,
+                true,
+            ));
+        }
+        let claimed = self.internal_claim_reward(&account_id, None);
+        self.internal_maybe_snapshot();
+        PromiseOrValue::Value(U128(claimed))
     }
 
-    pub fn update_apr(&mut self, _advisor_id: AccountId, _learner_vote: u8) {
-        require!(
-            self.stake_info.contains_key(&_advisor_id) == true,
-            "Stake: Advisor not stake any tokens!"
-        );
-        let mut stake_info = self.stake_info.get(&_advisor_id).unwrap();
-        stake_info.reward = Self::pending_reward(&self, _advisor_id.clone());
-        stake_info.time_staked = Self::now();
-        match _learner_vote {
-            1_u8 => {
-                stake_info.apr -= POINT_ONE_TOKEN * 2;
-                stake_info.votes -= 2;
+    // every check runs before anything is written, so a failed one can refund the whole
+    // amount instead of panicking
+    fn internal_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        amount: u128,
+        msg: &str,
+    ) -> Result<PromiseOrValue<U128>, StakeError> {
+        // a transfer from either contract would be counted twice, once on the books
+        // it already sits on and once as the new stake or funding
+        if sender_id == env::current_account_id() || sender_id == self.token_address {
+            return Err(StakeError::InvalidSender);
+        }
+        let transfer_msg = if msg.is_empty() {
+            TransferMsg::Stake
+        } else {
+            near_sdk::serde_json::from_str(msg).map_err(|_| StakeError::InvalidMsg)?
+        };
+        match transfer_msg {
+            TransferMsg::Stake => {
+                self.internal_check_can_stake(&sender_id)?;
+                if self.internal_needs_attestation(&sender_id) {
+                    return Ok(self.internal_stake_after_attestation(sender_id, U128(amount)));
+                }
+                let accepted = self.internal_accept_up_to_cap(&sender_id, amount);
+                if accepted > 0 {
+                    self.internal_stake(sender_id, accepted);
+                }
+                return Ok(self.internal_finish_stake_transfer(amount, accepted));
+            }
+            TransferMsg::StakeForCourse { course_id } => {
+                self.internal_check_can_stake(&sender_id)?;
+                if self.internal_needs_attestation(&sender_id) {
+                    return Err(StakeError::AttestationRequired);
+                }
+                self.internal_check_course_stake(&sender_id, &course_id)?;
+                let accepted = self.internal_accept_up_to_cap(&sender_id, amount);
+                if accepted > 0 {
+                    self.internal_stake(sender_id.clone(), accepted);
+                    self.internal_add_course_stake(&sender_id, course_id, accepted);
+                }
+                return Ok(self.internal_finish_stake_transfer(amount, accepted));
+            }
+            TransferMsg::ExtendLock {
+                position_id,
+                duration,
+            } => {
+                let lock = TopUpLock::Extend {
+                    position_id,
+                    duration,
+                };
+                return self.internal_stake_with_lock(sender_id, amount, lock);
+            }
+            TransferMsg::NewPosition { duration } => {
+                return self.internal_stake_with_lock(
+                    sender_id,
+                    amount,
+                    TopUpLock::New { duration },
+                );
+            }
+            TransferMsg::RejectIfLocked => {
+                return self.internal_stake_with_lock(sender_id, amount, TopUpLock::RejectIfLocked);
+            }
+            TransferMsg::FundRewardPool => {
+                self.reward_pool += amount;
+                self.total_reward_funded += amount;
             }
-            2_u8 => {
-                stake_info.apr -= POINT_ONE_TOKEN;
-                stake_info.votes -= 1;
+            TransferMsg::FundFaucet => {
+                if !self.internal_has_role(&sender_id, Role::Treasurer) {
+                    return Err(StakeError::MissingRole);
+                }
+                self.faucet_balance += amount;
             }
-            3_u8 => {
-                // do nothing
+            TransferMsg::FundBonus => {
+                if !self.internal_has_role(&sender_id, Role::Treasurer) {
+                    return Err(StakeError::MissingRole);
+                }
+                self.bonus_balance += amount;
             }
-            4_u8 => {
-                stake_info.apr += POINT_ONE_TOKEN;
-                stake_info.votes += 1;
+            TransferMsg::FundIntent => self.internal_fund_intent(&sender_id, amount)?,
+            TransferMsg::FundLpRewards => {
+                if self.lp_pool.is_none() {
+                    return Err(StakeError::NoLpPool);
+                }
+                self.internal_fund_lp_rewards(amount);
             }
-            5_u8 => {
-                stake_info.apr += POINT_ONE_TOKEN * 2;
-                stake_info.votes += 2;
+            TransferMsg::JoinQueue => {
+                self.internal_check_can_stake(&sender_id)?;
+                self.internal_join_queue(&sender_id, amount)?;
             }
-            _ => {
-                require!(1 != 1, "Stake: Invalid vote!");
+            TransferMsg::RevenueShare => self.internal_share_revenue(&sender_id, amount)?,
+            TransferMsg::FundCampaign { campaign_id } => {
+                self.internal_fund_campaign(campaign_id, &sender_id, amount)?
+            }
+            TransferMsg::PayAndRate { advisor, rating } => {
+                self.internal_pay_and_rate(&sender_id, &advisor, rating, amount)?
+            }
+            TransferMsg::StakeInPool { pool_id } => {
+                self.internal_pool_stake(pool_id, &sender_id, amount)?
+            }
+            TransferMsg::FundPool { pool_id } => {
+                self.internal_fund_partner_pool(pool_id, amount)?
+            }
+            TransferMsg::Vote(vote) => self.internal_paid_vote(&sender_id, vote, amount)?,
+            TransferMsg::SponsorAdvisor { advisor } => {
+                self.internal_sponsor_advisor(&sender_id, &advisor, amount)?
+            }
+            TransferMsg::FundMilestones => {
+                if !self.internal_has_role(&sender_id, Role::Treasurer) {
+                    return Err(StakeError::MissingRole);
+                }
+                self.milestone_budget += amount;
+                self.total_reward_funded += amount;
             }
         }
-        self.stake_info.insert(&_advisor_id, &stake_info);
+        self.internal_note_inflow(amount);
+        self.internal_maybe_snapshot();
+        Ok(PromiseOrValue::Value(U128(0)))
     }
 
-    #[private]
-    pub fn now() -> i64 {
-        return env::block_timestamp() as i64;
+    // keeps `accepted` of a staking transfer and hands the rest back to the token
+    pub(crate) fn internal_finish_stake_transfer(
+        &mut self,
+        amount: u128,
+        accepted: u128,
+    ) -> PromiseOrValue<U128> {
+        self.internal_note_inflow(accepted);
+        self.internal_maybe_snapshot();
+        PromiseOrValue::Value(U128(amount - accepted))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    const AMOUNT: u128 = 1_000 * ONE_TOKEN;
+
+    fn assert_nothing_kept(contract: &Contract) {
+        assert_eq!(contract.total_staked, 0);
+        assert_eq!(contract.total_stakers, 0);
+        assert_eq!(contract.total_inflows, 0);
+    }
+
+    #[test]
+    fn a_plain_stake_keeps_everything() {
+        let mut contract = setup();
+        assert_eq!(stake(&mut contract, &account("alice"), AMOUNT), 0);
+        assert_eq!(staked(&contract, &account("alice")), AMOUNT);
+    }
+
+    #[test]
+    fn refunds_everything_for_a_bad_msg() {
+        let mut contract = setup();
+        for msg in [
+            "stake",
+            "{\"Stake\":",
+            "{\"FundCampaign\":{}}",
+            "\"Unknown\"",
+        ] {
+            assert_eq!(
+                transfer_call(&mut contract, &account("alice"), AMOUNT, msg),
+                AMOUNT,
+                "{}",
+                msg
+            );
+        }
+        assert_nothing_kept(&contract);
+    }
+
+    #[test]
+    fn refunds_what_is_over_the_cap() {
+        let mut contract = setup();
+        call_as(&owner());
+        contract.set_max_total_staked(Some(U128(AMOUNT + AMOUNT / 2)));
+
+        assert_eq!(stake(&mut contract, &account("alice"), AMOUNT), 0);
+        assert_eq!(stake(&mut contract, &account("bob"), AMOUNT), AMOUNT / 2);
+        assert_eq!(staked(&contract, &account("bob")), AMOUNT / 2);
+        assert_eq!(stake(&mut contract, &account("carol"), AMOUNT), AMOUNT);
+        assert_eq!(staked(&contract, &account("carol")), 0);
+        assert_eq!(contract.total_staked, AMOUNT + AMOUNT / 2);
+    }
+
+    #[test]
+    fn refunds_everything_for_an_account_missing_from_the_allow_list() {
+        let mut contract = setup();
+        call_as(&owner());
+        contract.set_allow_list_enabled(true);
+        contract.add_to_allow_list(vec![account("bob")]);
+
+        assert_eq!(stake(&mut contract, &account("alice"), AMOUNT), AMOUNT);
+        assert_nothing_kept(&contract);
+        assert_eq!(stake(&mut contract, &account("bob"), AMOUNT), 0);
+    }
+
+    #[test]
+    fn refunds_everything_for_a_denied_account() {
+        let mut contract = setup();
+        call_as(&owner());
+        contract.add_to_deny_list(vec![account("alice")]);
+
+        assert_eq!(stake(&mut contract, &account("alice"), AMOUNT), AMOUNT);
+        assert_eq!(
+            transfer_call(
+                &mut contract,
+                &account("alice"),
+                AMOUNT,
+                "{\"StakeForCourse\":{\"course_id\":\"rust\"}}"
+            ),
+            AMOUNT
+        );
+        assert_eq!(
+            transfer_call(&mut contract, &account("alice"), AMOUNT, "\"JoinQueue\""),
+            AMOUNT
+        );
+        assert_nothing_kept(&contract);
+    }
+
+    #[test]
+    fn refunds_everything_while_paused() {
+        let mut contract = setup();
+        call_as(&owner());
+        contract.set_paused(true);
+
+        assert_eq!(stake(&mut contract, &account("alice"), AMOUNT), AMOUNT);
+        assert_nothing_kept(&contract);
+
+        call_as(&owner());
+        contract.set_paused(false);
+        assert_eq!(stake(&mut contract, &account("alice"), AMOUNT), 0);
+    }
+
+    #[test]
+    fn refunds_everything_sent_by_either_contract() {
+        let mut contract = setup();
+        assert_eq!(stake(&mut contract, &token(), AMOUNT), AMOUNT);
+        assert_eq!(stake(&mut contract, &contract_id(), AMOUNT), AMOUNT);
+        assert_nothing_kept(&contract);
+    }
+
+    #[test]
+    fn refunds_everything_for_funding_without_the_role() {
+        let mut contract = setup();
+        assert_eq!(
+            transfer_call(&mut contract, &account("alice"), AMOUNT, "\"FundFaucet\""),
+            AMOUNT
+        );
+        assert_eq!(
+            transfer_call(
+                &mut contract,
+                &account("alice"),
+                AMOUNT,
+                "\"FundLpRewards\""
+            ),
+            AMOUNT
+        );
+        assert_eq!(contract.faucet_balance, 0);
+        assert_nothing_kept(&contract);
+    }
+
+    // host-call counters with 100 stakers indexed, regression ceilings rather than gas
+    // limits, see host_gas_of
+    const NEW_STAKE_HOST_GAS: u64 = 20 * TGAS;
+    const TOP_UP_HOST_GAS: u64 = 25 * TGAS;
+
+    #[test]
+    fn staking_host_calls_stay_under_their_ceiling() {
+        let mut contract = setup();
+        let stakers = populate(&mut contract, "staker", 100);
+        advance_secs(100);
+
+        let new_stake = host_gas_of(&mut contract, &token(), |contract| {
+            contract.ft_on_transfer(account("alice"), U128(AMOUNT), String::new());
+        });
+        assert!(new_stake <= NEW_STAKE_HOST_GAS, "{} TGas", new_stake / TGAS);
+        let top_up = host_gas_of(&mut contract, &token(), |contract| {
+            contract.ft_on_transfer(stakers[50].clone(), U128(AMOUNT), String::new());
+        });
+        assert!(top_up <= TOP_UP_HOST_GAS, "{} TGas", top_up / TGAS);
+        assert_eq!(staked(&contract, &stakers[50]), 51 * ONE_TOKEN + AMOUNT);
     }
 }
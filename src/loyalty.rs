@@ -0,0 +1,104 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U64;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{near_bindgen, require, AccountId};
+
+use crate::time::{Duration, Timestamp};
+use crate::*;
+
+pub const MAX_LOYALTY_BPS: u32 = 10_000;
+
+// every full `period` of uninterrupted stake adds `bps_per_period` on top of the apr
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct LoyaltyConfig {
+    pub period: Duration,
+    pub bps_per_period: u32,
+    pub max_bps: u32,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TenureView {
+    pub first_staked_at: Timestamp,
+    pub streak_started_at: Option<Timestamp>,
+    pub streak: U64,
+    pub loyalty_bps: u32,
+}
+
+impl StakeInfo {
+    // a full unstake ends the streak, the next stake starts a new one
+    pub(crate) fn sync_streak(&mut self, now: Timestamp) {
+        if self.amount_staked == 0 {
+            self.terms.streak_started_at = None;
+            self.loyalty_bps = 0;
+        } else if self.terms.streak_started_at.is_none() {
+            self.terms.streak_started_at = Some(now);
+        }
+    }
+}
+
+impl Contract {
+    fn internal_loyalty_bps_at(&self, stake_info: &StakeInfo, now: Timestamp) -> u32 {
+        let (config, started_at) = match (&self.loyalty_config, stake_info.terms.streak_started_at)
+        {
+            (Some(config), Some(started_at)) => (config, started_at),
+            _ => return 0,
+        };
+        let periods = Duration::between(started_at, now).as_nanos() / config.period.as_nanos();
+        (periods.min(u32::MAX as u64) as u32)
+            .saturating_mul(config.bps_per_period)
+            .min(config.max_bps)
+    }
+
+    // steps are picked up on touch, until then the position accrues at the lower step
+    pub(crate) fn internal_refresh_loyalty(&mut self, stake_info: &mut StakeInfo) {
+        let loyalty_bps = self.internal_loyalty_bps_at(stake_info, Self::now());
+        if loyalty_bps == stake_info.loyalty_bps {
+            return;
+        }
+        let old_rate = stake_info.boosted(stake_info.apr);
+        stake_info.loyalty_bps = loyalty_bps;
+        self.internal_update_reward_rate(
+            stake_info.amount_staked,
+            old_rate,
+            stake_info.amount_staked,
+            stake_info.boosted(stake_info.apr),
+        );
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn set_loyalty_config(&mut self, loyalty_config: Option<LoyaltyConfig>) {
+        self.internal_assert_role(Role::Admin);
+        if let Some(config) = &loyalty_config {
+            require!(
+                config.period.as_nanos() > 0 && config.max_bps <= MAX_LOYALTY_BPS,
+                StakeError::InvalidLoyaltyConfig
+            );
+        }
+        self.loyalty_config = loyalty_config;
+    }
+
+    pub fn get_loyalty_config(&self) -> Option<LoyaltyConfig> {
+        self.loyalty_config.clone()
+    }
+
+    pub fn get_tenure(&self, account_id: AccountId) -> Option<TenureView> {
+        let now = Self::now();
+        self.internal_get_stake_info(&account_id)
+            .map(|stake_info| TenureView {
+                first_staked_at: stake_info.terms.first_staked_at,
+                streak_started_at: stake_info.terms.streak_started_at,
+                streak: stake_info
+                    .terms
+                    .streak_started_at
+                    .map_or(Duration::default(), |started_at| {
+                        Duration::between(started_at, now)
+                    })
+                    .into(),
+                loyalty_bps: self.internal_loyalty_bps_at(&stake_info, now),
+            })
+    }
+}
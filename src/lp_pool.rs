@@ -0,0 +1,333 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, AccountId, Promise, PromiseError, PromiseOrValue, ONE_YOCTO};
+
+use crate::math::mul_div;
+use crate::time::{Duration, Timestamp, NANOS_PER_SECOND};
+use crate::*;
+
+// scale of acc_reward_per_share
+pub const LP_REWARD_PRECISION: u128 = 1_000_000_000_000_000_000_000_000;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub enum LpToken {
+    Ft {
+        contract_id: AccountId,
+    },
+    Mft {
+        contract_id: AccountId,
+        token_id: String,
+    },
+}
+
+// second pool staking the token's LP shares, it streams stake tokens from its own
+// funded balance at reward_per_second split pro rata, apart from the advisor APR
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct LpPool {
+    pub token: LpToken,
+    pub reward_per_second: U128,
+    pub acc_reward_per_share: U128,
+    pub last_update: Timestamp,
+    pub total_staked: U128,
+    // funded and not paid out yet, `unclaimed` of it is already streamed to stakers
+    pub reward_balance: U128,
+    pub unclaimed: U128,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct LpStake {
+    pub amount: U128,
+    pub reward_per_share_paid: U128,
+    pub reward: U128,
+}
+
+impl LpPool {
+    // streams what accrued since the last update, never more than is funded
+    fn update(&mut self, now: Timestamp) {
+        let elapsed = Duration::between(self.last_update, now).as_nanos() as u128;
+        self.last_update = now;
+        if self.total_staked.0 == 0 {
+            return;
+        }
+        let emitted = mul_div(self.reward_per_second.0, elapsed, NANOS_PER_SECOND as u128)
+            .min(self.reward_balance.0 - self.unclaimed.0);
+        // only what the floored increment credits counts as emitted, the rest stays in
+        // the balance for the next update
+        let increment = mul_div(emitted, LP_REWARD_PRECISION, self.total_staked.0);
+        self.acc_reward_per_share.0 += increment;
+        self.unclaimed.0 += mul_div(increment, self.total_staked.0, LP_REWARD_PRECISION);
+    }
+
+    fn settle(&self, stake: &mut LpStake) {
+        stake.reward.0 += mul_div(
+            stake.amount.0,
+            self.acc_reward_per_share.0 - stake.reward_per_share_paid.0,
+            LP_REWARD_PRECISION,
+        );
+        stake.reward_per_share_paid = self.acc_reward_per_share;
+    }
+}
+
+impl Contract {
+    fn internal_lp_pool(&self) -> LpPool {
+        self.lp_pool
+            .clone()
+            .unwrap_or_else(|| env::panic_str(&StakeError::NoLpPool))
+    }
+
+    // pool updated to now and the account's stake settled against it
+    fn internal_lp_touch(&self, account_id: &AccountId) -> (LpPool, LpStake) {
+        let mut pool = self.internal_lp_pool();
+        pool.update(Self::now());
+        let mut stake = self.lp_stakes.get(account_id).unwrap_or(LpStake {
+            amount: U128(0),
+            reward_per_share_paid: U128(0),
+            reward: U128(0),
+        });
+        pool.settle(&mut stake);
+        (pool, stake)
+    }
+
+    fn internal_lp_save(&mut self, account_id: &AccountId, pool: LpPool, stake: LpStake) {
+        if stake.amount.0 == 0 && stake.reward.0 == 0 {
+            self.lp_stakes.remove(account_id);
+        } else {
+            self.lp_stakes.insert(account_id, &stake);
+        }
+        self.lp_pool = Some(pool);
+    }
+
+    pub(crate) fn internal_is_lp_token(&self, token: &LpToken) -> bool {
+        self.lp_pool
+            .as_ref()
+            .is_some_and(|pool| &pool.token == token)
+    }
+
+    pub(crate) fn internal_lp_stake(&mut self, account_id: &AccountId, amount: u128) {
+        let (mut pool, mut stake) = self.internal_lp_touch(account_id);
+        stake.amount.0 += amount;
+        pool.total_staked.0 += amount;
+        self.internal_lp_save(account_id, pool, stake);
+    }
+
+    pub(crate) fn internal_fund_lp_rewards(&mut self, amount: u128) {
+        let mut pool = self.internal_lp_pool();
+        pool.update(Self::now());
+        pool.reward_balance.0 += amount;
+        self.lp_pool = Some(pool);
+    }
+
+    // LP tokens staked in the pool, when the LP token is `token_id`
+    pub(crate) fn internal_lp_staked_of(&self, token_id: &AccountId) -> u128 {
+        self.lp_pool
+            .as_ref()
+            .filter(|pool| {
+                matches!(&pool.token, LpToken::Ft { contract_id } if contract_id == token_id)
+            })
+            .map_or(0, |pool| pool.total_staked.0)
+    }
+
+    // stake tokens the pool still owes, streamed or not
+    pub(crate) fn internal_lp_reserve(&self) -> u128 {
+        self.lp_pool
+            .as_ref()
+            .map_or(0, |pool| pool.reward_balance.0)
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // the token can only be swapped while nothing is staked in the pool. the accumulator
+    // carries over, so rewards earned on the old token can still be claimed
+    pub fn set_lp_pool(&mut self, token: LpToken, reward_per_second: U128) {
+        self.internal_assert_role(Role::Admin);
+        require!(
+            token
+                != LpToken::Ft {
+                    contract_id: self.token_address.clone()
+                },
+            StakeError::UnsupportedToken
+        );
+        let pool = match self.lp_pool.take() {
+            Some(mut pool) => {
+                require!(pool.total_staked.0 == 0, StakeError::LpPoolInUse);
+                pool.update(Self::now());
+                LpPool {
+                    token,
+                    reward_per_second,
+                    ..pool
+                }
+            }
+            None => LpPool {
+                token,
+                reward_per_second,
+                acc_reward_per_share: U128(0),
+                last_update: Self::now(),
+                total_staked: U128(0),
+                reward_balance: U128(0),
+                unclaimed: U128(0),
+            },
+        };
+        self.lp_pool = Some(pool);
+    }
+
+    pub fn set_lp_reward_rate(&mut self, reward_per_second: U128) {
+        self.internal_assert_role(Role::Admin);
+        let mut pool = self.internal_lp_pool();
+        pool.update(Self::now());
+        pool.reward_per_second = reward_per_second;
+        self.lp_pool = Some(pool);
+    }
+
+    // ref.finance style callback for pool shares sent with mft_transfer_call
+    pub fn mft_on_transfer(
+        &mut self,
+        token_id: String,
+        sender_id: AccountId,
+        amount: U128,
+        #[allow(unused_variables)] msg: String,
+    ) -> PromiseOrValue<U128> {
+        let token = LpToken::Mft {
+            contract_id: env::predecessor_account_id(),
+            token_id,
+        };
+        require!(
+            self.internal_is_lp_token(&token),
+            StakeError::UnsupportedToken
+        );
+        require!(amount.0 > 0, StakeError::InvalidAmount);
+        if self.paused {
+            return PromiseOrValue::Value(amount);
+        }
+        self.internal_lp_stake(&sender_id, amount.0);
+        PromiseOrValue::Value(U128(0))
+    }
+
+    #[payable]
+    pub fn unstake_lp(&mut self, amount: U128) -> Promise {
+        self.internal_assert_deposit("unstake_lp");
+        let account_id = env::predecessor_account_id();
+        let (mut pool, mut stake) = self.internal_lp_touch(&account_id);
+        require!(amount.0 > 0, StakeError::InvalidAmount);
+        require!(amount.0 <= stake.amount.0, StakeError::InsufficientStake);
+        stake.amount.0 -= amount.0;
+        pool.total_staked.0 -= amount.0;
+        let token = pool.token.clone();
+        self.internal_lp_save(&account_id, pool, stake);
+
+        let transfer = match token {
+            LpToken::Ft { contract_id } => ext_ft_contract::ext(contract_id)
+                .with_static_gas(FT_TRANSFER_GAS)
+                .with_attached_deposit(ONE_YOCTO)
+                .ft_transfer(account_id.clone(), amount, None),
+            LpToken::Mft {
+                contract_id,
+                token_id,
+            } => ext_mft::ext(contract_id)
+                .with_static_gas(FT_TRANSFER_GAS)
+                .with_attached_deposit(ONE_YOCTO)
+                .mft_transfer(token_id, account_id.clone(), amount, None),
+        };
+        transfer.then(
+            Self::ext(env::current_account_id())
+                .with_static_gas(WITHDRAW_CALLBACK_GAS)
+                .on_unstake_lp(account_id, amount),
+        )
+    }
+
+    // a failed transfer puts the shares back into the pool
+    #[private]
+    pub fn on_unstake_lp(
+        &mut self,
+        account_id: AccountId,
+        amount: U128,
+        #[callback_result] result: Result<(), PromiseError>,
+    ) -> U128 {
+        self.internal_note_promise_result(
+            "on_unstake_lp",
+            Some(&account_id),
+            amount.0,
+            result.is_ok(),
+        );
+        if result.is_ok() {
+            return amount;
+        }
+        self.internal_lp_stake(&account_id, amount.0);
+        U128(0)
+    }
+
+    #[payable]
+    pub fn claim_lp_reward(&mut self) -> Promise {
+        self.internal_assert_deposit("claim_lp_reward");
+        self.internal_assert_transfers_up();
+        let account_id = env::predecessor_account_id();
+        let (mut pool, mut stake) = self.internal_lp_touch(&account_id);
+        let reward = stake.reward.0;
+        require!(reward > 0, StakeError::NoReward);
+        self.internal_assert_balance_covers();
+        stake.reward.0 = 0;
+        pool.reward_balance.0 -= reward;
+        pool.unclaimed.0 -= reward;
+        self.internal_lp_save(&account_id, pool, stake);
+        self.pending_outflows += reward;
+
+        ext_ft_contract::ext(self.token_address.clone())
+            .with_static_gas(FT_TRANSFER_GAS)
+            .with_attached_deposit(ONE_YOCTO)
+            .ft_transfer(account_id.clone(), U128(reward), None)
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(WITHDRAW_CALLBACK_GAS)
+                    .on_claim_lp_reward(account_id, U128(reward)),
+            )
+    }
+
+    // a failed transfer goes back to the account as unclaimed reward
+    #[private]
+    pub fn on_claim_lp_reward(
+        &mut self,
+        account_id: AccountId,
+        amount: U128,
+        #[callback_result] result: Result<(), PromiseError>,
+    ) -> U128 {
+        self.internal_note_promise_result(
+            "on_claim_lp_reward",
+            Some(&account_id),
+            amount.0,
+            result.is_ok(),
+        );
+        self.internal_note_token_transfer(&account_id, result.is_ok());
+        self.pending_outflows -= amount.0;
+        if result.is_ok() {
+            self.internal_note_outflow(amount.0);
+            return amount;
+        }
+        let (mut pool, mut stake) = self.internal_lp_touch(&account_id);
+        stake.reward.0 += amount.0;
+        pool.reward_balance.0 += amount.0;
+        pool.unclaimed.0 += amount.0;
+        self.internal_lp_save(&account_id, pool, stake);
+        U128(0)
+    }
+
+    pub fn get_lp_pool(&self) -> Option<LpPool> {
+        self.lp_pool.clone().map(|mut pool| {
+            pool.update(Self::now());
+            pool
+        })
+    }
+
+    // reward includes everything streamed up to now
+    pub fn get_lp_stake(&self, account_id: AccountId) -> Option<LpStake> {
+        let mut stake = self.lp_stakes.get(&account_id)?;
+        if let Some(mut pool) = self.lp_pool.clone() {
+            pool.update(Self::now());
+            pool.settle(&mut stake);
+        }
+        Some(stake)
+    }
+}
@@ -0,0 +1,118 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::Serialize;
+use near_sdk::{near_bindgen, AccountId};
+
+use crate::math::{mul_div, Rate, RATE_PRECISION};
+use crate::*;
+
+// kept up to date on every save so the view reads it without scanning. positions
+// opened before the index existed join it the next time they are saved
+#[derive(BorshDeserialize, BorshSerialize, Default)]
+pub struct MarketIndex {
+    // what every indexed position earns a year at its own apr
+    annual_reward: u128,
+    total_stake: u128,
+    // lower median of stakers_by_apr
+    median: Option<(u128, AccountId)>,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MarketIndexView {
+    pub weighted_apr: U128,
+    pub median_apr: Option<U128>,
+    pub min_apr: Option<U128>,
+    pub max_apr: Option<U128>,
+    pub advisors: u64,
+}
+
+impl Contract {
+    pub(crate) fn internal_update_market_index(
+        &mut self,
+        account_id: &AccountId,
+        previous: Option<&StakeInfo>,
+        stake_info: &StakeInfo,
+    ) {
+        let (old_amount, old_apr) = previous.map_or((0, 0), |info| (info.amount_staked, info.apr));
+        if old_amount == stake_info.amount_staked && old_apr == stake_info.apr {
+            return;
+        }
+        if old_amount > 0 {
+            let index = &mut self.market_index;
+            index.annual_reward = index
+                .annual_reward
+                .saturating_sub(Rate::from_raw(old_apr).annual_reward(old_amount));
+            index.total_stake = index.total_stake.saturating_sub(old_amount);
+            self.internal_unindex_apr((old_apr, account_id.clone()));
+        }
+        if stake_info.amount_staked > 0 {
+            let index = &mut self.market_index;
+            index.annual_reward +=
+                Rate::from_raw(stake_info.apr).annual_reward(stake_info.amount_staked);
+            index.total_stake += stake_info.amount_staked;
+            self.internal_index_apr((stake_info.apr, account_id.clone()));
+        }
+    }
+
+    // the median moves at most one step per insert or removal
+    fn internal_index_apr(&mut self, key: (u128, AccountId)) {
+        let before = self.stakers_by_apr.len();
+        if self.stakers_by_apr.insert(&key, &()).is_some() {
+            return;
+        }
+        let odd = before % 2 == 1;
+        self.market_index.median = match self.market_index.median.take() {
+            None => Some(key),
+            Some(median) if key < median && odd => self.stakers_by_apr.lower(&median),
+            Some(median) if key > median && !odd => self.stakers_by_apr.higher(&median),
+            median => median,
+        };
+    }
+
+    fn internal_unindex_apr(&mut self, key: (u128, AccountId)) {
+        let before = self.stakers_by_apr.len();
+        if self.stakers_by_apr.remove(&key).is_none() {
+            return;
+        }
+        let odd = before % 2 == 1;
+        self.market_index.median = match self.market_index.median.take() {
+            Some(median) if key == median && odd => self.stakers_by_apr.lower(&key),
+            Some(median) if key == median => self.stakers_by_apr.higher(&key),
+            Some(median) if key < median && !odd => self.stakers_by_apr.higher(&median),
+            Some(median) if key > median && odd => self.stakers_by_apr.lower(&median),
+            median => median,
+        };
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // base aprs of positions with stake, boosts and modifiers left out. with an even count
+    // the median is the mean of the two middle aprs
+    pub fn get_market_index(&self) -> MarketIndexView {
+        let index = &self.market_index;
+        let advisors = self.stakers_by_apr.len();
+        let median_apr = index.median.as_ref().map(|median| {
+            let upper = match advisors % 2 {
+                0 => self
+                    .stakers_by_apr
+                    .higher(median)
+                    .map_or(median.0, |higher| higher.0),
+                _ => median.0,
+            };
+            U128((median.0 + upper) / 2)
+        });
+        MarketIndexView {
+            weighted_apr: U128(if index.total_stake == 0 {
+                0
+            } else {
+                mul_div(index.annual_reward, 100 * RATE_PRECISION, index.total_stake)
+            }),
+            median_apr,
+            min_apr: self.stakers_by_apr.min().map(|(apr, _)| U128(apr)),
+            max_apr: self.stakers_by_apr.max().map(|(apr, _)| U128(apr)),
+            advisors,
+        }
+    }
+}
@@ -0,0 +1,294 @@
+#[allow(clippy::all)]
+mod u256 {
+    uint::construct_uint! {
+        pub struct U256(4);
+    }
+}
+
+use crate::errors::StakeError;
+use crate::time::{Duration, NANOS_PER_YEAR};
+
+pub use u256::U256;
+
+pub const BPS_DENOMINATOR: u128 = 10_000;
+pub const RATE_PRECISION: u128 = 1_000_000_000_000_000_000_000_000;
+
+// a * b / denominator without overflowing the intermediate product, rounds down and
+// panics if the result itself does not fit
+pub fn mul_div(a: u128, b: u128, denominator: u128) -> u128 {
+    let result = U256::from(a) * U256::from(b) / U256::from(denominator);
+    if result > U256::from(u128::MAX) {
+        near_sdk::env::panic_str(&StakeError::MathOverflow);
+    }
+    result.as_u128()
+}
+
+// yearly percentage scaled by RATE_PRECISION (RATE_PRECISION == 1%), it never mixes
+// with the token's own decimals so the math holds for any token
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Default)]
+pub struct Rate(u128);
+
+impl Rate {
+    pub const fn from_raw(raw: u128) -> Self {
+        Rate(raw)
+    }
+
+    pub const fn percent(percent: u128) -> Self {
+        Rate(percent * RATE_PRECISION)
+    }
+
+    pub const fn raw(&self) -> u128 {
+        self.0
+    }
+
+    // rewards `amount` earns per year
+    pub fn annual_reward(&self, amount: u128) -> u128 {
+        mul_div(amount, self.0, 100 * RATE_PRECISION)
+    }
+
+    // growth of one unit compounded once per `period` for `periods` periods, scaled by
+    // RATE_PRECISION
+    pub fn growth(&self, period: Duration, periods: u32) -> u128 {
+        let rate_per_period = mul_div(
+            self.0,
+            period.as_nanos() as u128,
+            100 * NANOS_PER_YEAR as u128,
+        );
+        pow_fixed(RATE_PRECISION + rate_per_period, periods, RATE_PRECISION)
+    }
+
+    // effective yearly rate when compounded `periods` times a year
+    pub fn compounded(&self, periods: u32) -> Rate {
+        let rate_per_period = self.0 / (100 * periods as u128);
+        let growth = pow_fixed(RATE_PRECISION + rate_per_period, periods, RATE_PRECISION);
+        Rate((growth - RATE_PRECISION) * 100)
+    }
+}
+
+pub fn reward_for_duration(annual_reward: u128, duration: Duration) -> u128 {
+    mul_div(
+        annual_reward,
+        duration.as_nanos() as u128,
+        NANOS_PER_YEAR as u128,
+    )
+}
+
+// base^exp for a fixed point base scaled by `one`
+pub fn pow_fixed(base: u128, exp: u32, one: u128) -> u128 {
+    let mut result = one;
+    let mut base = base;
+    let mut exp = exp;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = mul_div(result, base, one);
+        }
+        base = mul_div(base, base, one);
+        exp >>= 1;
+    }
+    result
+}
+
+// fixed point amount as a decimal string, trailing zeros of the fraction dropped
+pub fn format_decimal(amount: u128, decimals: u8) -> String {
+    let one = 10u128.pow(decimals as u32);
+    let whole = amount / one;
+    let fraction = amount % one;
+    if fraction == 0 {
+        return whole.to_string();
+    }
+    let fraction = format!("{:0width$}", fraction, width = decimals as usize);
+    format!("{}.{}", whole, fraction.trim_end_matches('0'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::Rng;
+    use crate::time::SECONDS_PER_DAY;
+
+    const ONE: u128 = RATE_PRECISION;
+
+    fn assert_close(got: u128, reference: u128, tolerance: u128) {
+        assert!(
+            got.abs_diff(reference) <= tolerance,
+            "{} is more than {} from {}",
+            got,
+            tolerance,
+            reference
+        );
+    }
+
+    #[test]
+    fn pow_fixed_of_exact_bases() {
+        assert_eq!(pow_fixed(2 * ONE, 10, ONE), 1_024 * ONE);
+        assert_eq!(pow_fixed(ONE, 1_000, ONE), ONE);
+        assert_eq!(pow_fixed(7 * ONE, 0, ONE), ONE);
+        assert_eq!(pow_fixed(ONE / 2, 3, ONE), ONE / 8);
+        assert_eq!(pow_fixed(3, 4, 1), 81);
+    }
+
+    #[test]
+    fn pow_fixed_matches_reference_values() {
+        // 1.05^10 = 1.628894626777441406250000
+        assert_eq!(
+            pow_fixed(ONE + 5 * ONE / 100, 10, ONE),
+            1_628_894_626_777_441_406_250_000
+        );
+        // (1 + 0.05/365)^365 = 1.051267496467462550454968...
+        assert_close(
+            pow_fixed(ONE + 5 * ONE / 100 / 365, 365, ONE),
+            1_051_267_496_467_462_550_454_968,
+            ONE / 1_000_000_000_000,
+        );
+    }
+
+    #[test]
+    fn compounded_rate_matches_reference_values() {
+        // monthly 12% is 12.682503013196972066120100% a year
+        assert_eq!(
+            Rate::percent(12).compounded(12).raw(),
+            12_682_503_013_196_972_066_120_100
+        );
+        // quarterly 100% is 144.140625%
+        assert_eq!(
+            Rate::percent(100).compounded(4),
+            Rate::from_raw(144_140_625 * ONE / 1_000_000)
+        );
+        // daily 5% is 5.126749646746255...%, a period that does not divide evenly rounds down
+        assert_close(
+            Rate::percent(5).compounded(365).raw(),
+            5_126_749_646_746_255_045_496_814,
+            ONE / 1_000_000_000_000,
+        );
+        assert_eq!(Rate::percent(10).compounded(1), Rate::percent(10));
+    }
+
+    #[test]
+    fn growth_compounds_once_per_period() {
+        let day = Duration::from_secs(SECONDS_PER_DAY);
+        assert_eq!(Rate::percent(5).growth(day, 0), ONE);
+        assert_eq!(
+            Rate::percent(5).growth(day, 365),
+            1_051_267_496_467_462_550_454_554
+        );
+        assert_close(
+            Rate::percent(5).growth(day, 365),
+            1_051_267_496_467_462_550_454_968,
+            ONE / 1_000_000_000_000,
+        );
+    }
+
+    #[test]
+    fn annual_reward_scales_by_the_percentage() {
+        assert_eq!(Rate::percent(5).annual_reward(1_000), 50);
+        assert_eq!(Rate::percent(0).annual_reward(u128::MAX), 0);
+        assert_eq!(Rate::from_raw(ONE / 2).annual_reward(1_000), 5);
+    }
+
+    const CASES: usize = 2_000;
+
+    fn exact(a: u128, b: u128, denominator: u128) -> U256 {
+        U256::from(a) * U256::from(b) / U256::from(denominator)
+    }
+
+    #[test]
+    fn mul_div_rounds_down_the_exact_quotient() {
+        let mut rng = Rng::new(342);
+        for _ in 0..CASES {
+            let (a, b) = (rng.sized_u128(), rng.sized_u128());
+            let denominator = rng.sized_u128().max(1);
+            if exact(a, b, denominator) > U256::from(u128::MAX) {
+                continue;
+            }
+            let result = mul_div(a, b, denominator);
+            let product = U256::from(a) * U256::from(b);
+            assert!(U256::from(result) * U256::from(denominator) <= product);
+            assert!(product < (U256::from(result) + 1) * U256::from(denominator));
+            if let Some(product) = a.checked_mul(b) {
+                assert_eq!(result, product / denominator);
+            }
+        }
+    }
+
+    #[test]
+    fn mul_div_is_monotone() {
+        let mut rng = Rng::new(3_420);
+        for _ in 0..CASES {
+            let b = rng.sized_u128() >> 64;
+            let denominator = rng.sized_u128().max(1);
+            let (a, larger) = {
+                let (x, y) = (rng.sized_u128() >> 64, rng.sized_u128() >> 64);
+                (x.min(y), x.max(y))
+            };
+            assert!(mul_div(a, b, denominator) <= mul_div(larger, b, denominator));
+            assert!(mul_div(b, a, denominator) <= mul_div(b, larger, denominator));
+            assert!(mul_div(a, b, denominator) >= mul_div(a, b, denominator.saturating_add(1)));
+        }
+    }
+
+    #[test]
+    fn mul_div_stays_within_its_bounds() {
+        let mut rng = Rng::new(34_200);
+        for _ in 0..CASES {
+            let a = rng.sized_u128();
+            let denominator = rng.sized_u128().max(1);
+            let b = rng.below(denominator.saturating_add(1).max(1));
+            assert!(mul_div(a, b, denominator) <= a);
+            assert_eq!(mul_div(a, denominator, denominator), a);
+            assert_eq!(mul_div(a, 0, denominator), 0);
+        }
+    }
+
+    #[test]
+    fn mul_div_keeps_an_intermediate_product_over_u128() {
+        assert_eq!(mul_div(u128::MAX, u128::MAX, u128::MAX), u128::MAX);
+        assert_eq!(mul_div(u128::MAX, 3, 4), u128::MAX / 4 * 3 + 2);
+        assert_eq!(mul_div(u128::MAX, u128::MAX - 1, u128::MAX), u128::MAX - 1);
+    }
+
+    #[test]
+    fn pow_fixed_is_monotone_in_base_and_exponent() {
+        let mut rng = Rng::new(3_421);
+        for _ in 0..CASES {
+            let exp = rng.below(400) as u32;
+            // up to 1% per period keeps 400 periods far from overflow
+            let (base, larger) = {
+                let (x, y) = (rng.below(ONE / 100), rng.below(ONE / 100));
+                (ONE + x.min(y), ONE + x.max(y))
+            };
+            let result = pow_fixed(base, exp, ONE);
+            assert!(result >= ONE);
+            assert!(result <= pow_fixed(larger, exp, ONE));
+            assert!(result <= pow_fixed(base, exp + 1, ONE));
+        }
+    }
+
+    #[test]
+    fn pow_fixed_stays_within_its_bounds() {
+        let mut rng = Rng::new(3_422);
+        for _ in 0..CASES {
+            let exp = rng.below(400) as u32;
+            let x = rng.below(ONE / 100);
+            let result = pow_fixed(ONE + x, exp, ONE);
+            // bernoulli, less a unit of rounding per multiplication
+            assert!(result + 2 * exp as u128 >= ONE + exp as u128 * x);
+            // (1 + x)^n <= e^(nx), and e^(nx) <= 1 / (1 - nx) while nx < 1
+            let growth = exp as u128 * x;
+            if growth < ONE / 2 {
+                assert!(result <= mul_div(ONE, ONE, ONE - growth));
+            }
+            // a base below one only shrinks
+            assert!(pow_fixed(ONE - x, exp, ONE) <= ONE);
+        }
+    }
+
+    #[test]
+    fn compounding_never_earns_less_than_simple_interest() {
+        let mut rng = Rng::new(3_423);
+        for _ in 0..CASES {
+            let rate = Rate::from_raw(rng.below(100 * ONE));
+            let periods = 1 + rng.below(365) as u32;
+            assert!(rate.compounded(periods).raw() + 200 * periods as u128 >= rate.raw());
+        }
+    }
+}
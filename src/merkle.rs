@@ -0,0 +1,115 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{Base58CryptoHash, U128};
+use near_sdk::serde::Serialize;
+use near_sdk::{env, near_bindgen, require, AccountId, CryptoHash};
+
+use crate::*;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MerkleCampaign {
+    pub id: u64,
+    pub root: Base58CryptoHash,
+    pub total: U128,
+    pub claimed: U128,
+}
+
+// leaf = sha256(campaign id as u64 le || account id || amount as u128 le),
+// inner nodes hash the sorted pair so proofs carry no left/right flags
+pub(crate) fn merkle_leaf(campaign_id: u64, account_id: &AccountId, amount: u128) -> CryptoHash {
+    let mut data = campaign_id.to_le_bytes().to_vec();
+    data.extend_from_slice(account_id.as_bytes());
+    data.extend_from_slice(&amount.to_le_bytes());
+    env::sha256_array(&data)
+}
+
+pub(crate) fn merkle_verify(
+    leaf: CryptoHash,
+    proof: &[Base58CryptoHash],
+    root: CryptoHash,
+) -> bool {
+    let computed = proof.iter().fold(leaf, |node, sibling| {
+        let sibling = CryptoHash::from(*sibling);
+        let (left, right) = if node <= sibling {
+            (node, sibling)
+        } else {
+            (sibling, node)
+        };
+        env::sha256_array(&[left, right].concat())
+    });
+    computed == root
+}
+
+impl Contract {
+    // part of the current campaign that can still be claimed
+    pub(crate) fn internal_merkle_reserve(&self) -> u128 {
+        self.merkle_campaign
+            .as_ref()
+            .map_or(0, |campaign| campaign.total.0 - campaign.claimed.0)
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // replaces the current campaign, whatever it left unclaimed goes back to the bonus balance
+    pub fn post_merkle_root(&mut self, root: Base58CryptoHash, total: U128) -> MerkleCampaign {
+        self.internal_assert_role(Role::Treasurer);
+        self.bonus_balance += self.internal_merkle_reserve();
+        require!(
+            total.0 <= self.bonus_balance,
+            StakeError::InsufficientBonusBalance
+        );
+        self.bonus_balance -= total.0;
+        self.merkle_campaigns += 1;
+        let campaign = MerkleCampaign {
+            id: self.merkle_campaigns,
+            root,
+            total,
+            claimed: U128(0),
+        };
+        self.merkle_campaign = Some(campaign.clone());
+        campaign
+    }
+
+    // the bonus is credited to withdrawable like any other payout
+    #[payable]
+    pub fn claim_bonus(&mut self, amount: U128, proof: Vec<Base58CryptoHash>) {
+        self.internal_assert_deposit("claim_bonus");
+        let account_id = env::predecessor_account_id();
+        let mut campaign = self
+            .merkle_campaign
+            .clone()
+            .unwrap_or_else(|| env::panic_str(&StakeError::NoBonusCampaign));
+        let leaf = merkle_leaf(campaign.id, &account_id, amount.0);
+        require!(
+            merkle_verify(leaf, &proof, campaign.root.into()),
+            StakeError::InvalidMerkleProof
+        );
+        require!(
+            self.claimed_leaves.insert(&leaf.to_vec()),
+            StakeError::BonusClaimed
+        );
+        require!(
+            campaign.claimed.0 + amount.0 <= campaign.total.0,
+            StakeError::BonusCampaignExhausted
+        );
+        let mut stake_info = self
+            .internal_touch(&account_id)
+            .unwrap_or_else(|| env::panic_str(&StakeError::NotStaked));
+        Self::internal_assert_no_pending_operation(&stake_info);
+
+        campaign.claimed = U128(campaign.claimed.0 + amount.0);
+        self.merkle_campaign = Some(campaign);
+        self.internal_credit_withdrawable(&mut stake_info, amount.0);
+        self.internal_save_stake_info(&account_id, &stake_info);
+    }
+
+    pub fn get_merkle_campaign(&self) -> Option<MerkleCampaign> {
+        self.merkle_campaign.clone()
+    }
+
+    pub fn is_bonus_claimed(&self, campaign_id: u64, account_id: AccountId, amount: U128) -> bool {
+        self.claimed_leaves
+            .contains(&merkle_leaf(campaign_id, &account_id, amount.0).to_vec())
+    }
+}
@@ -0,0 +1,113 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::Serialize;
+use near_sdk::{env, near_bindgen, AccountId};
+
+use crate::events::{RewardStreamedData, StakeEvent};
+use crate::time::{Duration, Timestamp};
+use crate::*;
+
+// a run of micro-claims is reported once it reaches either
+pub const MICRO_CLAIM_EVENT_CLAIMS: u32 = 50;
+pub const MICRO_CLAIM_EVENT_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+// micro-claims not reported yet
+#[derive(BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MicroClaimTally {
+    pub amount: U128,
+    pub claims: u32,
+    pub since: Timestamp,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ClaimableNow {
+    pub accrued: U128,
+    // zero until the claim cooldown has passed
+    pub claimable: U128,
+    // what the position adds each second at its current rate, net of skim and fees
+    pub per_second: U128,
+    pub next_claim_at: Timestamp,
+    pub as_of: Timestamp,
+}
+
+impl Contract {
+    fn internal_tally_micro_claim(&mut self, account_id: &AccountId, amount: u128) {
+        let now = Self::now();
+        let mut tally = self
+            .micro_claims
+            .get(account_id)
+            .unwrap_or(MicroClaimTally {
+                amount: U128(0),
+                claims: 0,
+                since: now,
+            });
+        tally.amount.0 += amount;
+        tally.claims += 1;
+        if tally.claims < MICRO_CLAIM_EVENT_CLAIMS && now < tally.since + MICRO_CLAIM_EVENT_INTERVAL
+        {
+            self.micro_claims.insert(account_id, &tally);
+            return;
+        }
+        self.micro_claims.remove(account_id);
+        self.internal_record_activity(
+            account_id,
+            Activity::Claim {
+                amount: tally.amount,
+            },
+        );
+        StakeEvent::RewardStreamed(vec![RewardStreamedData {
+            account_id: account_id.clone(),
+            amount: tally.amount,
+            claims: tally.claims,
+            since: tally.since,
+        }])
+        .emit();
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // nanosecond accurate as of the current block, per_second lets a stream extrapolate
+    pub fn claimable_now(&self, account_id: AccountId) -> Option<ClaimableNow> {
+        let stake_info = self.internal_get_stake_info(&account_id)?;
+        let now = Self::now();
+        let accrued = self.internal_pending_reward_at(&stake_info, now);
+        let next_claim_at = self.internal_next_claim_at(&stake_info);
+        let per_second = self
+            .internal_pending_reward_at(&stake_info, now + Duration::from_secs(1))
+            .saturating_sub(accrued);
+        Some(ClaimableNow {
+            accrued: U128(accrued),
+            claimable: U128(if now >= next_claim_at { accrued } else { 0 }),
+            per_second: U128(per_second),
+            next_claim_at,
+            as_of: now,
+        })
+    }
+
+    // claims up to `max_amount` and returns what was claimed, nothing accrued is not an
+    // error. boosters and governance tiers are not re-read, only dropped once stale
+    #[payable]
+    pub fn claim_micro(&mut self, max_amount: U128) -> U128 {
+        self.internal_assert_deposit("claim_micro");
+        let account_id = env::predecessor_account_id();
+        let stake_info = self
+            .internal_get_stake_info(&account_id)
+            .unwrap_or_else(|| env::panic_str(&StakeError::NotStaked));
+        let amount = self
+            .internal_pending_reward_at(&stake_info, Self::now())
+            .min(max_amount.0);
+        if amount == 0 {
+            return U128(0);
+        }
+        let claimed = self.internal_settle_claim(&account_id, Some(amount));
+        self.internal_tally_micro_claim(&account_id, claimed);
+        U128(claimed)
+    }
+
+    pub fn get_micro_claim_tally(&self, account_id: AccountId) -> Option<MicroClaimTally> {
+        self.micro_claims.get(&account_id)
+    }
+}
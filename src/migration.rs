@@ -0,0 +1,128 @@
+use near_sdk::json_types::U128;
+use near_sdk::serde::Serialize;
+use near_sdk::{env, near_bindgen, require, AccountId, Gas, Promise, PromiseError, ONE_YOCTO};
+
+use crate::*;
+
+pub const MIGRATE_CALLBACK_GAS: Gas = Gas(20_000_000_000_000);
+
+// the ft_transfer_call msg, the successor sees this contract as the sender so it
+// learns whose position arrives from here
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MigrationMsg {
+    pub source: AccountId,
+    pub account_id: AccountId,
+    pub principal: U128,
+    // claimed reward and whatever was already withdrawable
+    pub rewards: U128,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+enum MigrationTransferMsg {
+    Migrate(MigrationMsg),
+}
+
+#[near_bindgen]
+impl Contract {
+    // claims, unstakes without the unstake fee and sends everything to the successor in
+    // one transfer. what the successor does not keep comes back, the principal of it
+    // is staked again and the rest stays withdrawable
+    #[payable]
+    pub fn migrate_to(&mut self, successor_account_id: AccountId) -> Promise {
+        self.internal_assert_deposit("migrate_to");
+        self.internal_assert_transfers_up();
+        require!(
+            self.migration_successors.contains(&successor_account_id),
+            StakeError::SuccessorNotApproved
+        );
+        let account_id = env::predecessor_account_id();
+        match self.internal_claim_blocker(&account_id) {
+            None => {
+                self.internal_claim_reward(&account_id, None);
+            }
+            Some(StakeError::NoReward) => {}
+            Some(error) => env::panic_str(&error),
+        }
+        let staked = self
+            .internal_get_stake_info(&account_id)
+            .map_or(0, |stake_info| stake_info.amount_staked);
+        let principal = if staked > 0 {
+            self.internal_unstake(&account_id, staked, None, &successor_account_id, true)
+        } else {
+            0
+        };
+        self.internal_process_queue(MAX_QUEUE_ACTIVATIONS);
+        let amount = self.internal_start_outflow(&account_id);
+        self.internal_maybe_snapshot();
+
+        let msg = MigrationTransferMsg::Migrate(MigrationMsg {
+            source: env::current_account_id(),
+            account_id: account_id.clone(),
+            principal: U128(principal),
+            rewards: U128(amount - principal),
+        });
+        ext_ft_contract::ext(self.token_address.clone())
+            .with_static_gas(FT_TRANSFER_CALL_GAS)
+            .with_attached_deposit(ONE_YOCTO)
+            .ft_transfer_call(
+                successor_account_id,
+                U128(amount),
+                Some("migration".to_string()),
+                near_sdk::serde_json::to_string(&msg).unwrap(),
+            )
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(MIGRATE_CALLBACK_GAS)
+                    .on_migrate(account_id, U128(amount), U128(principal)),
+            )
+    }
+
+    #[private]
+    pub fn on_migrate(
+        &mut self,
+        account_id: AccountId,
+        amount: U128,
+        principal: U128,
+        #[callback_result] result: Result<U128, PromiseError>,
+    ) -> U128 {
+        self.internal_note_promise_result(
+            "on_migrate",
+            Some(&account_id),
+            amount.0,
+            result.is_ok(),
+        );
+        let used = result.map_or(0, |used| used.0.min(amount.0));
+        self.internal_note_outflow(used);
+        let refund = amount.0 - used;
+        self.internal_finish_outflow(&account_id, amount.0, refund);
+        let restake = refund.min(principal.0);
+        if restake > 0 {
+            let mut stake_info = self.internal_load_stake_info(&account_id).unwrap();
+            stake_info.withdrawable -= restake;
+            self.total_withdrawable -= restake;
+            self.internal_save_stake_info(&account_id, &stake_info);
+            self.internal_stake(account_id, restake);
+        }
+        U128(used)
+    }
+
+    pub fn add_migration_successors(&mut self, successor_account_ids: Vec<AccountId>) {
+        self.internal_assert_owner();
+        for successor_account_id in successor_account_ids {
+            self.migration_successors.insert(&successor_account_id);
+        }
+    }
+
+    pub fn remove_migration_successors(&mut self, successor_account_ids: Vec<AccountId>) {
+        self.internal_assert_owner();
+        for successor_account_id in successor_account_ids {
+            self.migration_successors.remove(&successor_account_id);
+        }
+    }
+
+    pub fn is_migration_successor(&self, successor_account_id: AccountId) -> bool {
+        self.migration_successors.contains(&successor_account_id)
+    }
+}
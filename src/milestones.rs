@@ -0,0 +1,122 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::Vector;
+use near_sdk::json_types::U128;
+use near_sdk::serde::Serialize;
+use near_sdk::{env, near_bindgen, require, AccountId};
+
+use crate::events::{MilestoneRecordedData, StakeEvent};
+use crate::time::Timestamp;
+use crate::*;
+
+pub const MAX_MILESTONE_ID_LEN: usize = 64;
+pub const MAX_MILESTONE_PAGE: u64 = 50;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Milestone {
+    pub milestone_id: String,
+    pub bonus: U128,
+    pub recorded_at: Timestamp,
+}
+
+impl Contract {
+    // the bonus joins the advisor's reward like accrual would, so it is claimed, capped
+    // and reported the same way
+    fn internal_credit_milestone(&mut self, advisor_id: &AccountId, bonus: u128) {
+        let mut stake_info = self
+            .internal_touch(advisor_id)
+            .unwrap_or_else(|| env::panic_str(&StakeError::AdvisorNotStaked));
+        self.milestone_budget -= bonus;
+        self.reward_pool += bonus;
+        self.internal_update_global_accrual();
+        self.accrued_rewards += bonus;
+        self.internal_note_accrual(&mut stake_info, bonus);
+        stake_info.reward += bonus;
+        self.internal_record_activity(
+            advisor_id,
+            Activity::RewardAccrued {
+                amount: U128(bonus),
+            },
+        );
+        self.internal_save_stake_info(advisor_id, &stake_info);
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // a milestone id already recorded for the advisor returns the bonus it paid then and
+    // credits nothing
+    pub fn record_milestone(
+        &mut self,
+        advisor: AccountId,
+        milestone_id: String,
+        bonus: U128,
+    ) -> U128 {
+        self.internal_assert_role(Role::MilestoneRecorder);
+        self.internal_assert_not_paused();
+        let key = (advisor.clone(), milestone_id.clone());
+        if let Some(milestone) = self.milestones.get(&key) {
+            return milestone.bonus;
+        }
+        require!(
+            !milestone_id.is_empty() && milestone_id.len() <= MAX_MILESTONE_ID_LEN,
+            StakeError::InvalidMilestoneId
+        );
+        require!(bonus.0 > 0, StakeError::InvalidAmount);
+        require!(
+            bonus.0 <= self.milestone_budget,
+            StakeError::InsufficientMilestoneBudget
+        );
+        self.internal_credit_milestone(&advisor, bonus.0);
+
+        self.milestones.insert(
+            &key,
+            &Milestone {
+                milestone_id: milestone_id.clone(),
+                bonus,
+                recorded_at: Self::now(),
+            },
+        );
+        let mut ids = self
+            .milestone_ids
+            .get(&advisor)
+            .unwrap_or_else(|| Vector::new(StorageKey::milestone_id_entries(&advisor)));
+        ids.push(&milestone_id);
+        self.milestone_ids.insert(&advisor, &ids);
+        StakeEvent::MilestoneRecorded(vec![MilestoneRecordedData {
+            advisor_id: advisor,
+            milestone_id,
+            bonus,
+        }])
+        .emit();
+        self.internal_maybe_snapshot();
+        bonus
+    }
+
+    pub fn get_milestone(&self, advisor: AccountId, milestone_id: String) -> Option<Milestone> {
+        self.milestones.get(&(advisor, milestone_id))
+    }
+
+    // in the order they were recorded
+    pub fn get_milestones(
+        &self,
+        advisor: AccountId,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> Vec<Milestone> {
+        let ids = match self.milestone_ids.get(&advisor) {
+            Some(ids) => ids,
+            None => return vec![],
+        };
+        let from = from_index.unwrap_or(0);
+        let limit = limit.unwrap_or(MAX_MILESTONE_PAGE).min(MAX_MILESTONE_PAGE);
+        (from..ids.len().min(from.saturating_add(limit)))
+            .filter_map(|index| ids.get(index))
+            .filter_map(|milestone_id| self.milestones.get(&(advisor.clone(), milestone_id)))
+            .collect()
+    }
+
+    pub fn get_milestone_budget(&self) -> U128 {
+        U128(self.milestone_budget)
+    }
+}
@@ -0,0 +1,137 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, require, AccountId};
+
+use crate::math::{mul_div, BPS_DENOMINATOR};
+use crate::time::{Duration, Timestamp};
+use crate::*;
+
+pub const NEUTRAL_APR_MODIFIER_BPS: u32 = 10_000;
+pub const MAX_APR_MODIFIER_BPS: u32 = 30_000;
+pub const MAX_CURVE_POINTS: usize = 20;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CurvePoint {
+    pub metric: U128,
+    pub modifier_bps: u32,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AprModifierView {
+    pub oracle_id: Option<AccountId>,
+    pub curve: Vec<CurvePoint>,
+    pub last_metric: Option<U128>,
+    pub modifier_bps: u32,
+}
+
+// piecewise linear between the points, flat outside them
+pub(crate) fn modifier_from_curve(curve: &[CurvePoint], metric: u128) -> u32 {
+    let first = &curve[0];
+    if metric <= first.metric.0 {
+        return first.modifier_bps;
+    }
+    for pair in curve.windows(2) {
+        let (low, high) = (&pair[0], &pair[1]);
+        if metric <= high.metric.0 {
+            let (low_bps, high_bps) = (low.modifier_bps as u128, high.modifier_bps as u128);
+            let span = high.metric.0 - low.metric.0;
+            let offset = metric - low.metric.0;
+            let modifier = if high_bps >= low_bps {
+                low_bps + mul_div(high_bps - low_bps, offset, span)
+            } else {
+                low_bps - mul_div(low_bps - high_bps, offset, span)
+            };
+            return modifier as u32;
+        }
+    }
+    curve[curve.len() - 1].modifier_bps
+}
+
+impl Contract {
+    // nanoseconds weighted by the modifier in bps, a position accrues on the growth of
+    // this index since it was last touched
+    pub(crate) fn internal_apr_index_at(&self, timestamp: Timestamp) -> u128 {
+        let elapsed = Duration::between(self.apr_index_updated_at, timestamp);
+        self.apr_index + self.apr_modifier_bps as u128 * elapsed.as_nanos() as u128
+    }
+
+    // folds the elapsed time into the apr index and the pending reward estimate
+    pub(crate) fn internal_checkpoint_accrual(&mut self) -> u128 {
+        self.internal_update_global_accrual();
+        let now = Self::now();
+        self.apr_index = self.internal_apr_index_at(now);
+        self.apr_index_updated_at = now;
+        self.apr_index
+    }
+
+    pub(crate) fn internal_set_apr_modifier(&mut self, modifier_bps: u32) {
+        self.internal_checkpoint_accrual();
+        self.apr_modifier_bps = modifier_bps;
+    }
+
+    pub(crate) fn internal_apply_apr_modifier(&self, amount: u128) -> u128 {
+        mul_div(amount, self.apr_modifier_bps as u128, BPS_DENOMINATOR)
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn report_metric(&mut self, value: U128) -> u32 {
+        require!(
+            self.oracle_id.as_ref() == Some(&env::predecessor_account_id()),
+            StakeError::NotOracle
+        );
+        self.internal_assert_no_usd_peg();
+        require!(!self.apr_curve.is_empty(), StakeError::AprCurveNotSet);
+        self.last_metric = Some(value);
+        let modifier_bps = modifier_from_curve(&self.apr_curve, value.0);
+        self.internal_set_apr_modifier(modifier_bps);
+        modifier_bps
+    }
+
+    pub fn set_oracle(&mut self, oracle_id: Option<AccountId>) {
+        self.internal_assert_role(Role::Admin);
+        self.oracle_id = oracle_id;
+    }
+
+    // points sorted by metric, an empty curve resets the modifier to neutral
+    pub fn set_apr_curve(&mut self, curve: Vec<CurvePoint>) {
+        self.internal_assert_role(Role::Admin);
+        self.internal_assert_no_usd_peg();
+        require!(
+            curve.len() <= MAX_CURVE_POINTS,
+            StakeError::TooManyCurvePoints
+        );
+        require!(
+            curve
+                .iter()
+                .all(|point| point.modifier_bps <= MAX_APR_MODIFIER_BPS),
+            StakeError::InvalidAprModifier
+        );
+        require!(
+            curve
+                .windows(2)
+                .all(|pair| pair[0].metric.0 < pair[1].metric.0),
+            StakeError::UnsortedCurve
+        );
+        let modifier_bps = match (&self.last_metric, curve.is_empty()) {
+            (_, true) => NEUTRAL_APR_MODIFIER_BPS,
+            (Some(metric), false) => modifier_from_curve(&curve, metric.0),
+            (None, false) => self.apr_modifier_bps,
+        };
+        self.apr_curve = curve;
+        self.internal_set_apr_modifier(modifier_bps);
+    }
+
+    pub fn get_apr_modifier(&self) -> AprModifierView {
+        AprModifierView {
+            oracle_id: self.oracle_id.clone(),
+            curve: self.apr_curve.clone(),
+            last_metric: self.last_metric,
+            modifier_bps: self.apr_modifier_bps,
+        }
+    }
+}
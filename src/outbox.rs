@@ -0,0 +1,208 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::Serialize;
+use near_sdk::{env, near_bindgen, require, AccountId, Gas, Promise, PromiseOrValue, ONE_YOCTO};
+
+use crate::events::{StakeEvent, TransferQueuedData};
+use crate::time::Timestamp;
+use crate::*;
+
+pub const DEFAULT_OUTBOX_BUDGET: u32 = 10;
+pub const MAX_FLUSH_OUTBOX_BATCH: u64 = 20;
+// what one transfer out of the outbox takes, transfer and callback
+pub const OUTBOX_TRANSFER_GAS: Gas = Gas(FT_TRANSFER_GAS.0 + WITHDRAW_CALLBACK_GAS.0);
+// left to flush_outbox for its own bookkeeping
+pub const FLUSH_GAS_RESERVE: Gas = Gas(10_000_000_000_000);
+// left to a withdrawal flushing on its way, for its own transfer and the rest of the call
+pub const INLINE_FLUSH_GAS_RESERVE: Gas = Gas(OUTBOX_TRANSFER_GAS.0 + 30_000_000_000_000);
+
+// a transfer that already left the account's balance, held in pending_outflows with the
+// account's operation pending until it is sent
+#[derive(BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct OutboxTransfer {
+    pub account_id: AccountId,
+    pub receiver_id: AccountId,
+    pub amount: U128,
+    pub memo: Option<String>,
+    pub queued_at: Timestamp,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct OutboxView {
+    pub budget: u32,
+    pub sent_this_block: u32,
+    pub queued: U64,
+    pub queued_amount: U128,
+}
+
+// what became of a transfer handed to the outbox, a promise that is not returned still
+// runs and resolves through on_withdraw
+pub(crate) enum Dispatch {
+    Issued(Promise),
+    Queued(u64),
+}
+
+impl From<Dispatch> for PromiseOrValue<U128> {
+    fn from(dispatch: Dispatch) -> Self {
+        match dispatch {
+            Dispatch::Issued(promise) => PromiseOrValue::Promise(promise),
+            Dispatch::Queued(_) => PromiseOrValue::Value(U128(0)),
+        }
+    }
+}
+
+impl Contract {
+    // ft_transfers issued per block across every call, the rest waits in the outbox
+    fn internal_take_transfer_slot(&mut self) -> bool {
+        let height = env::block_height();
+        if height != self.outbox_block {
+            self.outbox_block = height;
+            self.outbox_sent = 0;
+        }
+        if self.outbox_sent >= self.outbox_budget {
+            return false;
+        }
+        self.outbox_sent += 1;
+        true
+    }
+
+    pub(crate) fn internal_issue_transfer(&self, transfer: OutboxTransfer) -> Promise {
+        ext_ft_contract::ext(self.token_address.clone())
+            .with_static_gas(FT_TRANSFER_GAS)
+            .with_attached_deposit(ONE_YOCTO)
+            .ft_transfer(
+                transfer.receiver_id.clone(),
+                transfer.amount,
+                transfer.memo.clone(),
+            )
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(WITHDRAW_CALLBACK_GAS)
+                    .on_withdraw(
+                        transfer.account_id,
+                        transfer.amount,
+                        transfer.memo,
+                        Some(transfer.receiver_id),
+                    ),
+            )
+    }
+
+    // older transfers go first, so one sent right away means the outbox is empty
+    // tokens sent to this contract would land as untracked balance while still being
+    // taken off the books
+    pub(crate) fn internal_assert_receiver(receiver_id: &AccountId) {
+        require!(
+            receiver_id != &env::current_account_id(),
+            StakeError::InvalidReceiver
+        );
+    }
+
+    pub(crate) fn internal_send_transfer(&mut self, transfer: OutboxTransfer) -> Dispatch {
+        Self::internal_assert_receiver(&transfer.receiver_id);
+        self.internal_flush_outbox(MAX_FLUSH_OUTBOX_BATCH, INLINE_FLUSH_GAS_RESERVE);
+        if self.outbox_head == self.outbox_tail
+            && !self.internal_transfers_degraded()
+            && self.internal_take_transfer_slot()
+        {
+            return Dispatch::Issued(self.internal_issue_transfer(transfer));
+        }
+        Dispatch::Queued(self.internal_enqueue_transfer(transfer))
+    }
+
+    pub(crate) fn internal_enqueue_transfer(&mut self, transfer: OutboxTransfer) -> u64 {
+        let position = self.outbox_tail;
+        self.outbox_tail += 1;
+        self.queued_outflows += transfer.amount.0;
+        StakeEvent::TransferQueued(vec![TransferQueuedData {
+            account_id: transfer.account_id.clone(),
+            receiver_id: transfer.receiver_id.clone(),
+            amount: transfer.amount,
+            position: U64(position),
+        }])
+        .emit();
+        self.outbox.insert(&position, &transfer);
+        position
+    }
+
+    pub(crate) fn internal_pop_outbox(&mut self) -> Option<OutboxTransfer> {
+        if self.outbox_head == self.outbox_tail {
+            return None;
+        }
+        let transfer = self.outbox.remove(&self.outbox_head).unwrap();
+        self.outbox_head += 1;
+        self.queued_outflows -= transfer.amount.0;
+        Some(transfer)
+    }
+
+    // detached promises, each resolves through on_withdraw like a direct transfer.
+    // nothing goes out while transfers are degraded, and only as many as the gas left
+    // above `reserve` pays for
+    pub(crate) fn internal_flush_outbox(&mut self, limit: u64, reserve: Gas) -> u64 {
+        let mut sent = 0;
+        while sent < limit
+            && self.outbox_head < self.outbox_tail
+            && !self.internal_transfers_degraded()
+        {
+            let left = env::prepaid_gas().0.saturating_sub(env::used_gas().0);
+            if left < OUTBOX_TRANSFER_GAS.0 + reserve.0 {
+                break;
+            }
+            if !self.internal_take_transfer_slot() {
+                break;
+            }
+            let transfer = self.internal_pop_outbox().unwrap();
+            self.internal_issue_transfer(transfer);
+            sent += 1;
+        }
+        sent
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // permissionless, for keepers to drain the outbox while nobody else transacts
+    pub fn flush_outbox(&mut self, limit: Option<u64>) -> u64 {
+        let limit = limit
+            .unwrap_or(MAX_FLUSH_OUTBOX_BATCH)
+            .min(MAX_FLUSH_OUTBOX_BATCH);
+        self.internal_flush_outbox(limit, FLUSH_GAS_RESERVE)
+    }
+
+    pub fn set_outbox_budget(&mut self, budget: u32) {
+        self.internal_assert_role(Role::Admin);
+        require!(budget > 0, StakeError::InvalidOutboxBudget);
+        self.internal_record_config_change("outbox_budget", self.outbox_budget, budget);
+        self.outbox_budget = budget;
+    }
+
+    pub fn get_outbox(&self) -> OutboxView {
+        let sent_this_block = if self.outbox_block == env::block_height() {
+            self.outbox_sent
+        } else {
+            0
+        };
+        OutboxView {
+            budget: self.outbox_budget,
+            sent_this_block,
+            queued: U64(self.outbox_tail - self.outbox_head),
+            queued_amount: U128(self.queued_outflows),
+        }
+    }
+
+    // oldest first, `from` counts from the head of the outbox
+    pub fn get_outbox_transfers(
+        &self,
+        from: Option<u64>,
+        limit: Option<u64>,
+    ) -> Vec<OutboxTransfer> {
+        let start = self.outbox_head + from.unwrap_or(0);
+        let end = self
+            .outbox_tail
+            .min(start.saturating_add(limit.unwrap_or(DEFAULT_PAGE_LIMIT)));
+        (start..end)
+            .filter_map(|position| self.outbox.get(&position))
+            .collect()
+    }
+}
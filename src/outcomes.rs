@@ -0,0 +1,138 @@
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::Serialize;
+use near_sdk::{env, near_bindgen, AccountId, PromiseOrValue};
+
+use crate::*;
+
+// where the tokens of a call ended up. a sent transfer can still fail, it then comes
+// back as withdrawable balance
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub enum Delivery {
+    Withdrawable,
+    Sent,
+    // waiting in the outbox at `position`, flush_outbox sends it
+    Queued { position: U64 },
+}
+
+impl From<&Dispatch> for Delivery {
+    fn from(dispatch: &Dispatch) -> Self {
+        match dispatch {
+            Dispatch::Issued(_) => Delivery::Sent,
+            Dispatch::Queued(position) => Delivery::Queued {
+                position: U64(*position),
+            },
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct UnstakeOutcome {
+    pub unstaked: U128,
+    pub fee: U128,
+    // what the unstake credited after the fee
+    pub immediate: U128,
+    pub delivery: Delivery,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ClaimOutcome {
+    pub claimed: U128,
+    // reward still owed after the claim
+    pub remaining: U128,
+    // a booster or governance tier is being checked first, the claim happens in the
+    // callback and claimed stays zero here
+    pub verifying: bool,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct WithdrawOutcome {
+    pub amount: U128,
+    pub delivery: Delivery,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ExitOutcome {
+    pub principal: U128,
+    pub reward: U128,
+    // principal, reward and whatever was already withdrawable
+    pub amount: U128,
+    pub delivery: Delivery,
+}
+
+// api version 3, the same calls answering what happened instead of a bare amount. a
+// transfer they start is not awaited, its result shows up in the events
+#[near_bindgen]
+impl Contract {
+    #[payable]
+    pub fn v3_unstake_token(
+        &mut self,
+        amount: U128,
+        course_id: Option<String>,
+        receiver_id: Option<AccountId>,
+    ) -> UnstakeOutcome {
+        self.internal_assert_deposit("unstake_token");
+        let (received, dispatch) = self.internal_unstake_token(amount.0, course_id, receiver_id);
+        UnstakeOutcome {
+            unstaked: amount,
+            fee: U128(amount.0 - received),
+            immediate: U128(received),
+            delivery: dispatch
+                .as_ref()
+                .map_or(Delivery::Withdrawable, Delivery::from),
+        }
+    }
+
+    #[payable]
+    pub fn v3_claim_reward(&mut self) -> ClaimOutcome {
+        self.internal_assert_deposit("claim_reward");
+        let account_id = env::predecessor_account_id();
+        let (claimed, verifying) = match self.internal_claim_reward_verified(account_id.clone()) {
+            PromiseOrValue::Value(claimed) => (claimed, false),
+            PromiseOrValue::Promise(_) => (U128(0), true),
+        };
+        ClaimOutcome {
+            claimed,
+            remaining: U128(
+                self.internal_get_stake_info(&account_id)
+                    .map_or(0, |stake_info| stake_info.reward),
+            ),
+            verifying,
+        }
+    }
+
+    #[payable]
+    pub fn v3_withdraw(&mut self, memo: Option<String>) -> WithdrawOutcome {
+        self.internal_assert_deposit("withdraw");
+        let account_id = env::predecessor_account_id();
+        let amount = self
+            .internal_get_stake_info(&account_id)
+            .map_or(0, |stake_info| stake_info.withdrawable);
+        let dispatch = self.internal_transfer_withdrawable(account_id, Payout::with_note(memo));
+        self.internal_maybe_snapshot();
+        WithdrawOutcome {
+            amount: U128(amount),
+            delivery: Delivery::from(&dispatch),
+        }
+    }
+
+    #[payable]
+    pub fn v3_exit(&mut self, memo: Option<String>) -> ExitOutcome {
+        self.internal_assert_deposit("exit");
+        let account_id = env::predecessor_account_id();
+        let before = self
+            .internal_get_stake_info(&account_id)
+            .map_or(0, |stake_info| stake_info.withdrawable);
+        let (principal, reward, dispatch) = self.internal_exit(account_id, memo);
+        ExitOutcome {
+            principal: U128(principal),
+            reward: U128(reward),
+            amount: U128(before + principal + reward),
+            delivery: Delivery::from(&dispatch),
+        }
+    }
+}
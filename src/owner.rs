@@ -0,0 +1,12 @@
+use near_sdk::{env, require};
+
+use crate::*;
+
+impl Contract {
+    pub(crate) fn internal_assert_owner(&self) {
+        require!(
+            env::predecessor_account_id() == self.owner_id,
+            StakeError::NotOwner
+        );
+    }
+}
@@ -0,0 +1,327 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::Serialize;
+use near_sdk::{env, near_bindgen, require, AccountId, Promise, PromiseError, ONE_YOCTO};
+
+use crate::apr_config::MAX_APR;
+use crate::math::{mul_div, Rate, RATE_PRECISION};
+use crate::time::{Duration, Timestamp, NANOS_PER_YEAR};
+use crate::*;
+
+pub const MAX_POOL_NAME_LEN: usize = 64;
+
+// a white-labeled staking program of the stake token run for a partner community. its
+// stake, reward pool and admin are its own, nothing in it counts towards the advisor
+// totals and the advisor reward pool never pays it
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PartnerPool {
+    pub pool_id: u64,
+    pub name: String,
+    pub admin_id: AccountId,
+    pub apr: U128,
+    // reward one token has earned since the pool opened, scaled by RATE_PRECISION
+    pub reward_per_token: U128,
+    pub last_update: Timestamp,
+    pub total_staked: U128,
+    pub stakers: u64,
+    // funded and not claimed yet, stakers may be owed more than is left
+    pub reward_pool: U128,
+    // new stakes are refused, claims and unstakes go on
+    pub paused: bool,
+    pub created_at: Timestamp,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PoolStake {
+    pub amount: U128,
+    pub reward_per_token_paid: U128,
+    pub reward: U128,
+}
+
+impl PartnerPool {
+    fn update(&mut self, now: Timestamp) {
+        let elapsed = Duration::between(self.last_update, now).as_nanos() as u128;
+        self.last_update = now;
+        self.reward_per_token.0 += mul_div(self.apr.0, elapsed, 100 * NANOS_PER_YEAR as u128);
+    }
+
+    fn settle(&self, stake: &mut PoolStake) {
+        stake.reward.0 += mul_div(
+            stake.amount.0,
+            self.reward_per_token.0 - stake.reward_per_token_paid.0,
+            RATE_PRECISION,
+        );
+        stake.reward_per_token_paid = self.reward_per_token;
+    }
+}
+
+impl Contract {
+    fn internal_partner_pool(&self, pool_id: u64) -> PartnerPool {
+        self.partner_pools
+            .get(&pool_id)
+            .unwrap_or_else(|| env::panic_str(&StakeError::UnknownPartnerPool))
+    }
+
+    fn internal_assert_pool_admin(&self, pool: &PartnerPool) {
+        let caller = env::predecessor_account_id();
+        require!(
+            caller == pool.admin_id || caller == self.owner_id,
+            StakeError::NotPoolAdmin
+        );
+    }
+
+    // pool updated to now and the account's stake settled against it
+    fn internal_pool_touch(
+        &self,
+        pool_id: u64,
+        account_id: &AccountId,
+    ) -> (PartnerPool, PoolStake) {
+        let mut pool = self.internal_partner_pool(pool_id);
+        pool.update(Self::now());
+        let mut stake = self
+            .pool_stakes
+            .get(&(pool_id, account_id.clone()))
+            .unwrap_or(PoolStake {
+                amount: U128(0),
+                reward_per_token_paid: pool.reward_per_token,
+                reward: U128(0),
+            });
+        pool.settle(&mut stake);
+        (pool, stake)
+    }
+
+    fn internal_pool_save(
+        &mut self,
+        account_id: &AccountId,
+        mut pool: PartnerPool,
+        stake: PoolStake,
+    ) {
+        let key = (pool.pool_id, account_id.clone());
+        let existed = self.pool_stakes.contains_key(&key);
+        if stake.amount.0 == 0 && stake.reward.0 == 0 {
+            if existed {
+                self.pool_stakes.remove(&key);
+                pool.stakers -= 1;
+            }
+        } else {
+            if !existed {
+                pool.stakers += 1;
+            }
+            self.pool_stakes.insert(&key, &stake);
+        }
+        self.partner_pools.insert(&pool.pool_id, &pool);
+    }
+
+    pub(crate) fn internal_pool_stake(
+        &mut self,
+        pool_id: u64,
+        account_id: &AccountId,
+        amount: u128,
+    ) -> Result<(), StakeError> {
+        let pool = self
+            .partner_pools
+            .get(&pool_id)
+            .ok_or(StakeError::UnknownPartnerPool)?;
+        if pool.paused || self.paused {
+            return Err(StakeError::PartnerPoolPaused);
+        }
+        let (mut pool, mut stake) = self.internal_pool_touch(pool_id, account_id);
+        stake.amount.0 += amount;
+        pool.total_staked.0 += amount;
+        self.total_partner_staked += amount;
+        self.internal_pool_save(account_id, pool, stake);
+        Ok(())
+    }
+
+    pub(crate) fn internal_fund_partner_pool(
+        &mut self,
+        pool_id: u64,
+        amount: u128,
+    ) -> Result<(), StakeError> {
+        let mut pool = self
+            .partner_pools
+            .get(&pool_id)
+            .ok_or(StakeError::UnknownPartnerPool)?;
+        pool.reward_pool.0 += amount;
+        self.total_partner_rewards += amount;
+        self.partner_pools.insert(&pool_id, &pool);
+        Ok(())
+    }
+
+    fn internal_pool_transfer(
+        &mut self,
+        pool_id: u64,
+        account_id: AccountId,
+        amount: u128,
+        principal: bool,
+    ) -> Promise {
+        self.internal_assert_transfers_up();
+        self.internal_assert_not_frozen(&account_id);
+        self.internal_assert_breaker_closed();
+        self.internal_assert_balance_covers();
+        self.pending_outflows += amount;
+        ext_ft_contract::ext(self.token_address.clone())
+            .with_static_gas(FT_TRANSFER_GAS)
+            .with_attached_deposit(ONE_YOCTO)
+            .ft_transfer(account_id.clone(), U128(amount), None)
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(WITHDRAW_CALLBACK_GAS)
+                    .on_pool_transfer(pool_id, account_id, U128(amount), principal),
+            )
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn create_partner_pool(&mut self, name: String, admin_id: AccountId, apr: U128) -> U64 {
+        self.internal_assert_owner();
+        require!(
+            !name.is_empty() && name.len() <= MAX_POOL_NAME_LEN,
+            StakeError::InvalidPoolName
+        );
+        require!(
+            Rate::from_raw(apr.0) <= MAX_APR,
+            StakeError::InvalidAprBounds
+        );
+        self.partner_pool_count += 1;
+        let now = Self::now();
+        let pool = PartnerPool {
+            pool_id: self.partner_pool_count,
+            name,
+            admin_id,
+            apr,
+            reward_per_token: U128(0),
+            last_update: now,
+            total_staked: U128(0),
+            stakers: 0,
+            reward_pool: U128(0),
+            paused: false,
+            created_at: now,
+        };
+        self.partner_pools.insert(&pool.pool_id, &pool);
+        U64(pool.pool_id)
+    }
+
+    // only time after the change earns at the new apr
+    pub fn set_partner_pool_apr(&mut self, pool_id: u64, apr: U128) {
+        let mut pool = self.internal_partner_pool(pool_id);
+        self.internal_assert_pool_admin(&pool);
+        require!(
+            Rate::from_raw(apr.0) <= MAX_APR,
+            StakeError::InvalidAprBounds
+        );
+        pool.update(Self::now());
+        pool.apr = apr;
+        self.partner_pools.insert(&pool_id, &pool);
+    }
+
+    pub fn set_partner_pool_paused(&mut self, pool_id: u64, paused: bool) {
+        let mut pool = self.internal_partner_pool(pool_id);
+        self.internal_assert_pool_admin(&pool);
+        pool.paused = paused;
+        self.partner_pools.insert(&pool_id, &pool);
+    }
+
+    pub fn set_partner_pool_admin(&mut self, pool_id: u64, admin_id: AccountId) {
+        let mut pool = self.internal_partner_pool(pool_id);
+        self.internal_assert_pool_admin(&pool);
+        pool.admin_id = admin_id;
+        self.partner_pools.insert(&pool_id, &pool);
+    }
+
+    #[payable]
+    pub fn unstake_from_pool(&mut self, pool_id: u64, amount: U128) -> Promise {
+        self.internal_assert_deposit("unstake_from_pool");
+        let account_id = env::predecessor_account_id();
+        let (mut pool, mut stake) = self.internal_pool_touch(pool_id, &account_id);
+        require!(amount.0 > 0, StakeError::InvalidAmount);
+        require!(amount.0 <= stake.amount.0, StakeError::InsufficientStake);
+        stake.amount.0 -= amount.0;
+        pool.total_staked.0 -= amount.0;
+        self.total_partner_staked -= amount.0;
+        self.internal_pool_save(&account_id, pool, stake);
+        self.internal_pool_transfer(pool_id, account_id, amount.0, true)
+    }
+
+    #[payable]
+    pub fn claim_pool_reward(&mut self, pool_id: u64) -> Promise {
+        self.internal_assert_deposit("claim_pool_reward");
+        let account_id = env::predecessor_account_id();
+        let (mut pool, mut stake) = self.internal_pool_touch(pool_id, &account_id);
+        let reward = stake.reward.0;
+        require!(reward > 0, StakeError::NoReward);
+        require!(
+            reward <= pool.reward_pool.0,
+            StakeError::InsufficientRewardPool
+        );
+        stake.reward.0 = 0;
+        pool.reward_pool.0 -= reward;
+        self.total_partner_rewards -= reward;
+        self.internal_pool_save(&account_id, pool, stake);
+        self.internal_pool_transfer(pool_id, account_id, reward, false)
+    }
+
+    // a failed transfer goes back where it came from, stake or unclaimed reward
+    #[private]
+    pub fn on_pool_transfer(
+        &mut self,
+        pool_id: u64,
+        account_id: AccountId,
+        amount: U128,
+        principal: bool,
+        #[callback_result] result: Result<(), PromiseError>,
+    ) -> U128 {
+        self.internal_note_promise_result(
+            "on_pool_transfer",
+            Some(&account_id),
+            amount.0,
+            result.is_ok(),
+        );
+        self.internal_note_token_transfer(&account_id, result.is_ok());
+        self.pending_outflows -= amount.0;
+        if result.is_ok() {
+            self.internal_note_outflow(amount.0);
+            return amount;
+        }
+        let (mut pool, mut stake) = self.internal_pool_touch(pool_id, &account_id);
+        if principal {
+            stake.amount.0 += amount.0;
+            pool.total_staked.0 += amount.0;
+            self.total_partner_staked += amount.0;
+        } else {
+            stake.reward.0 += amount.0;
+            pool.reward_pool.0 += amount.0;
+            self.total_partner_rewards += amount.0;
+        }
+        self.internal_pool_save(&account_id, pool, stake);
+        U128(0)
+    }
+
+    pub fn get_partner_pool(&self, pool_id: u64) -> Option<PartnerPool> {
+        self.partner_pools.get(&pool_id).map(|mut pool| {
+            pool.update(Self::now());
+            pool
+        })
+    }
+
+    pub fn get_partner_pools(&self, from: Option<u64>, limit: Option<u64>) -> Vec<PartnerPool> {
+        let start = from.unwrap_or(0) + 1;
+        let end = (self.partner_pool_count + 1)
+            .min(start.saturating_add(limit.unwrap_or(DEFAULT_PAGE_LIMIT)));
+        (start..end)
+            .filter_map(|pool_id| self.get_partner_pool(pool_id))
+            .collect()
+    }
+
+    // reward includes everything earned up to now
+    pub fn get_pool_stake(&self, pool_id: u64, account_id: AccountId) -> Option<PoolStake> {
+        let mut stake = self.pool_stakes.get(&(pool_id, account_id))?;
+        let mut pool = self.partner_pools.get(&pool_id)?;
+        pool.update(Self::now());
+        pool.settle(&mut stake);
+        Some(stake)
+    }
+}
@@ -0,0 +1,22 @@
+use near_sdk::near_bindgen;
+
+use crate::*;
+
+impl Contract {
+    pub(crate) fn internal_assert_not_paused(&self) {
+        require!(!self.paused, StakeError::Paused);
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // stops new stakes and votes, exits keep working
+    pub fn set_paused(&mut self, paused: bool) {
+        self.internal_assert_role(Role::Pauser);
+        self.paused = paused;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+}
@@ -0,0 +1,80 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{near_bindgen, require, AccountId};
+
+use crate::math::{mul_div, BPS_DENOMINATOR};
+use crate::*;
+
+// a learner pays the advisor for a course and rates them in the same transfer.
+// `pool_bps` of the payment tops the reward pool, the rest is the advisor's
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CoursePaymentConfig {
+    pub min_payment: U128,
+    pub pool_bps: u16,
+}
+
+impl Contract {
+    // everything is checked up front so a bad rating refunds the payment
+    pub(crate) fn internal_pay_and_rate(
+        &mut self,
+        learner_id: &AccountId,
+        advisor_id: &AccountId,
+        rating: u8,
+        amount: u128,
+    ) -> Result<(), StakeError> {
+        let config = self
+            .course_payment_config
+            .clone()
+            .ok_or(StakeError::CoursePaymentsDisabled)?;
+        if self.paused {
+            return Err(StakeError::Paused);
+        }
+        if amount < config.min_payment.0 {
+            return Err(StakeError::PaymentTooLow);
+        }
+        if learner_id == advisor_id {
+            return Err(StakeError::InvalidVote);
+        }
+        if rating == 0 || rating as usize > self.vote_weights.len() {
+            return Err(StakeError::InvalidVote);
+        }
+        if self.internal_get_stake_info(advisor_id).is_none() {
+            return Err(StakeError::AdvisorNotStaked);
+        }
+        if !self.internal_is_permitted(advisor_id) {
+            return Err(StakeError::AdvisorNotPermitted);
+        }
+
+        let to_pool = mul_div(amount, config.pool_bps as u128, BPS_DENOMINATOR);
+        self.reward_pool += to_pool;
+        // a paid course is its own proof of the interaction
+        self.internal_update_apr(advisor_id, learner_id, rating, None, None, true);
+        let mut stake_info = self
+            .internal_load_stake_info(advisor_id)
+            .ok_or(StakeError::AdvisorNotStaked)?;
+        self.internal_credit_withdrawable(&mut stake_info, amount - to_pool);
+        self.internal_save_stake_info(advisor_id, &stake_info);
+        Ok(())
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // None turns PayAndRate transfers off, they are refunded
+    pub fn set_course_payment_config(&mut self, config: Option<CoursePaymentConfig>) {
+        self.internal_assert_role(Role::Admin);
+        if let Some(config) = &config {
+            require!(
+                config.pool_bps as u128 <= BPS_DENOMINATOR,
+                StakeError::InvalidCoursePaymentConfig
+            );
+        }
+        self.course_payment_config = config;
+    }
+
+    pub fn get_course_payment_config(&self) -> Option<CoursePaymentConfig> {
+        self.course_payment_config.clone()
+    }
+}
@@ -0,0 +1,86 @@
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::Serialize;
+use near_sdk::{env, near_bindgen};
+
+use crate::time::{Duration, Timestamp};
+use crate::*;
+
+pub const DEFAULT_PING_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PingResult {
+    pub apr_index: U128,
+    pub accrued_rewards: U128,
+    // what the caller was credited, zero before the interval passed or when not staked
+    pub reward: U128,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PingConfig {
+    pub ping_reward: U128,
+    pub ping_interval: U64,
+    pub last_paid_ping: Timestamp,
+    pub total_ping_rewards: U128,
+}
+
+impl Contract {
+    // at most one paid ping per interval, so spamming it cannot drain the pool
+    fn internal_pay_ping(&mut self, account_id: &AccountId) -> u128 {
+        let now = Self::now();
+        let reward = self.ping_reward;
+        if reward == 0
+            || reward > self.reward_pool
+            || now < self.last_paid_ping + self.ping_interval
+            || self.frozen_accounts.contains_key(account_id)
+        {
+            return 0;
+        }
+        let mut stake_info = match self.internal_touch(account_id) {
+            Some(stake_info) if !stake_info.pending_operation => stake_info,
+            _ => return 0,
+        };
+        self.reward_pool -= reward;
+        self.total_ping_rewards += reward;
+        self.last_paid_ping = now;
+        self.internal_credit_withdrawable(&mut stake_info, reward);
+        self.internal_save_stake_info(account_id, &stake_info);
+        reward
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // permissionless, keeps views accurate while nobody stakes or claims
+    pub fn ping(&mut self) -> PingResult {
+        let apr_index = self.internal_checkpoint_accrual();
+        let reward = self.internal_pay_ping(&env::predecessor_account_id());
+        PingResult {
+            apr_index: U128(apr_index),
+            accrued_rewards: U128(self.accrued_rewards),
+            reward: U128(reward),
+        }
+    }
+
+    pub fn set_ping_reward(&mut self, ping_reward: U128, ping_interval: U64) {
+        self.internal_assert_role(Role::Admin);
+        self.internal_record_config_change("ping_reward", U128(self.ping_reward), ping_reward);
+        self.internal_record_config_change(
+            "ping_interval",
+            U64(self.ping_interval.as_nanos()),
+            ping_interval,
+        );
+        self.ping_reward = ping_reward.0;
+        self.ping_interval = Duration::from_nanos(ping_interval.0);
+    }
+
+    pub fn get_ping_config(&self) -> PingConfig {
+        PingConfig {
+            ping_reward: U128(self.ping_reward),
+            ping_interval: self.ping_interval.into(),
+            last_paid_ping: self.last_paid_ping,
+            total_ping_rewards: U128(self.total_ping_rewards),
+        }
+    }
+}
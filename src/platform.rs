@@ -0,0 +1,60 @@
+use near_sdk::json_types::U128;
+use near_sdk::{env, near_bindgen, AccountId, Gas, PromiseError};
+
+use crate::events::{RatingPushFailedData, StakeEvent};
+use crate::*;
+
+pub const RATING_PUSH_GAS: Gas = Gas(5_000_000_000_000);
+pub const RATING_PUSH_CALLBACK_GAS: Gas = Gas(3_000_000_000_000);
+
+impl Contract {
+    // fire and forget, a failed delivery only shows up as an event
+    pub(crate) fn internal_push_rating(&self, advisor_id: &AccountId, new_apr: u128, votes: u8) {
+        let platform = match &self.platform_contract {
+            Some(platform) => platform.clone(),
+            None => return,
+        };
+        ext_platform::ext(platform)
+            .with_static_gas(RATING_PUSH_GAS)
+            .on_rating_changed(advisor_id.clone(), U128(new_apr), votes)
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(RATING_PUSH_CALLBACK_GAS)
+                    .on_rating_pushed(advisor_id.clone(), U128(new_apr), votes),
+            );
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    #[private]
+    pub fn on_rating_pushed(
+        &mut self,
+        advisor_id: AccountId,
+        new_apr: U128,
+        votes: u8,
+        #[callback_result] result: Result<(), PromiseError>,
+    ) -> bool {
+        self.internal_note_promise_result(
+            "on_rating_pushed",
+            Some(&advisor_id),
+            new_apr.0,
+            result.is_ok(),
+        );
+        if result.is_err() {
+            StakeEvent::RatingPushFailed(vec![RatingPushFailedData {
+                advisor_id,
+                new_apr,
+                votes,
+            }])
+            .emit();
+            return false;
+        }
+        true
+    }
+
+    pub fn set_platform_contract(&mut self, platform_contract: Option<AccountId>) {
+        self.internal_assert_role(Role::Admin);
+        self.platform_contract = platform_contract;
+    }
+}
@@ -0,0 +1,91 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U64;
+use near_sdk::serde::Serialize;
+use near_sdk::{env, near_bindgen, AccountId};
+
+use crate::time::{Duration, Timestamp};
+use crate::*;
+
+// how long an exiting advisor stays Unbonding, it only drives the position state the
+// platform reads. unstaked principal is withdrawable right away, delegations have their
+// own cooldown
+pub const DEFAULT_UNBOND_PERIOD: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum PositionState {
+    Active,
+    // exiting, becomes Deactivated once the unbond period has passed
+    Unbonding,
+    Deactivated,
+    Slashed,
+}
+
+impl Contract {
+    pub(crate) fn internal_position_state(&self, stake_info: &StakeInfo) -> PositionState {
+        match stake_info.state {
+            PositionState::Unbonding
+                if Self::now() >= stake_info.state_changed_at + self.unbond_period =>
+            {
+                PositionState::Deactivated
+            }
+            state => state,
+        }
+    }
+
+    pub(crate) fn internal_set_position_state(
+        stake_info: &mut StakeInfo,
+        state: PositionState,
+        at: Timestamp,
+    ) {
+        stake_info.state = state;
+        stake_info.state_changed_at = at;
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn deactivate(&mut self) {
+        let account_id = env::predecessor_account_id();
+        self.internal_migrate_legacy(&account_id);
+        let mut stake_info = self
+            .internal_load_stake_info(&account_id)
+            .unwrap_or_else(|| env::panic_str(&StakeError::NotStaked));
+        require!(
+            self.internal_position_state(&stake_info) == PositionState::Active,
+            StakeError::PositionNotActive
+        );
+        Self::internal_set_position_state(&mut stake_info, PositionState::Unbonding, Self::now());
+        self.internal_save_stake_info(&account_id, &stake_info);
+    }
+
+    pub fn reactivate(&mut self) {
+        let account_id = env::predecessor_account_id();
+        self.internal_migrate_legacy(&account_id);
+        let mut stake_info = self
+            .internal_load_stake_info(&account_id)
+            .unwrap_or_else(|| env::panic_str(&StakeError::NotStaked));
+        let state = self.internal_position_state(&stake_info);
+        require!(
+            state == PositionState::Unbonding || state == PositionState::Deactivated,
+            StakeError::PositionNotReactivatable
+        );
+        Self::internal_set_position_state(&mut stake_info, PositionState::Active, Self::now());
+        self.internal_save_stake_info(&account_id, &stake_info);
+    }
+
+    pub fn set_unbond_period(&mut self, unbond_period: U64) {
+        self.internal_assert_role(Role::Admin);
+        self.unbond_period = Duration::from_nanos(unbond_period.0);
+    }
+
+    pub fn is_advisor_active(&self, account_id: AccountId) -> bool {
+        match self.internal_get_stake_info(&account_id) {
+            Some(stake_info) => {
+                stake_info.amount_staked > 0
+                    && self.internal_position_state(&stake_info) == PositionState::Active
+            }
+            None => false,
+        }
+    }
+}
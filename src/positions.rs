@@ -0,0 +1,600 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::Serialize;
+use near_sdk::{env, near_bindgen, require, AccountId, PromiseOrValue};
+
+use crate::math::mul_div;
+use crate::time::{Duration, Timestamp};
+use crate::*;
+
+pub const MAX_POSITIONS_PER_ACCOUNT: usize = 10;
+pub const MAX_LOCK_DURATION: Duration = Duration::from_secs(4 * 365 * 24 * 60 * 60);
+
+// part of amount_staked that cannot leave the position before `locked_until`, the rest
+// of the stake is free. an expired position counts as free and is dropped on the next
+// restructure
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StakePosition {
+    pub id: u64,
+    pub amount: U128,
+    pub locked_until: Timestamp,
+}
+
+// rewards accrue on the whole stake record at one apr, a position is shown its pro-rata
+// share of what is pending, so splitting or merging never moves rewards
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PositionView {
+    pub id: u64,
+    pub amount: U128,
+    pub locked_until: Timestamp,
+    pub locked: bool,
+    pub reward: U128,
+}
+
+// how a staking transfer treats the sender's locks. a plain Stake goes into the free
+// stake and leaves every lock as it is
+pub enum TopUpLock {
+    // joins a running position, which is then held for at least `duration` from now
+    Extend { position_id: u64, duration: U64 },
+    // opens a position of its own
+    New { duration: U64 },
+    // refused whole while any of the sender's locks runs
+    RejectIfLocked,
+}
+
+impl Contract {
+    fn internal_positions(&self, account_id: &AccountId) -> Vec<StakePosition> {
+        self.stake_positions.get(account_id).unwrap_or_default()
+    }
+
+    fn internal_set_positions(&mut self, account_id: &AccountId, positions: &Vec<StakePosition>) {
+        if positions.is_empty() {
+            self.stake_positions.remove(account_id);
+        } else {
+            self.stake_positions.insert(account_id, positions);
+        }
+    }
+
+    pub(crate) fn internal_position_locked(&self, account_id: &AccountId) -> u128 {
+        let now = Self::now();
+        self.internal_positions(account_id)
+            .iter()
+            .filter(|position| now < position.locked_until)
+            .map(|position| position.amount.0)
+            .sum()
+    }
+
+    // the caller's running positions and its stake, expired positions are dropped
+    fn internal_restructure(&mut self, account_id: &AccountId) -> (StakeInfo, Vec<StakePosition>) {
+        self.internal_migrate_legacy(account_id);
+        let stake_info = self
+            .internal_load_stake_info(account_id)
+            .unwrap_or_else(|| env::panic_str(&StakeError::NotStaked));
+        let now = Self::now();
+        let mut positions = self.internal_positions(account_id);
+        positions.retain(|position| now < position.locked_until);
+        (stake_info, positions)
+    }
+
+    fn internal_new_position(&mut self, amount: u128, locked_until: Timestamp) -> StakePosition {
+        self.position_count += 1;
+        StakePosition {
+            id: self.position_count,
+            amount: U128(amount),
+            locked_until,
+        }
+    }
+
+    fn internal_check_lock_duration(duration: U64) -> Result<Duration, StakeError> {
+        let duration = Duration::from_nanos(duration.0);
+        if duration.as_nanos() == 0 || duration > MAX_LOCK_DURATION {
+            return Err(StakeError::InvalidLockDuration);
+        }
+        Ok(duration)
+    }
+
+    fn internal_lock_end(duration: U64) -> Timestamp {
+        match Self::internal_check_lock_duration(duration) {
+            Ok(duration) => Self::now() + duration,
+            Err(error) => env::panic_str(&error),
+        }
+    }
+
+    // runs before anything is written, so a top-up the option refuses is refunded whole
+    fn internal_check_top_up_lock(
+        &self,
+        account_id: &AccountId,
+        lock: &TopUpLock,
+    ) -> Result<(), StakeError> {
+        let now = Self::now();
+        let positions = self.internal_positions(account_id);
+        let mut running = positions
+            .iter()
+            .filter(|position| now < position.locked_until);
+        match lock {
+            TopUpLock::Extend {
+                position_id,
+                duration,
+            } => {
+                Self::internal_check_lock_duration(*duration)?;
+                if !running.any(|position| position.id == *position_id) {
+                    return Err(StakeError::UnknownPosition);
+                }
+            }
+            TopUpLock::New { duration } => {
+                Self::internal_check_lock_duration(*duration)?;
+                if running.count() >= MAX_POSITIONS_PER_ACCOUNT {
+                    return Err(StakeError::TooManyPositions);
+                }
+            }
+            TopUpLock::RejectIfLocked => {
+                if running.next().is_some() {
+                    return Err(StakeError::StakeLocked);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn internal_apply_top_up_lock(
+        &mut self,
+        account_id: &AccountId,
+        lock: TopUpLock,
+        amount: u128,
+    ) {
+        let now = Self::now();
+        let mut positions = self.internal_positions(account_id);
+        positions.retain(|position| now < position.locked_until);
+        match lock {
+            TopUpLock::Extend {
+                position_id,
+                duration,
+            } => {
+                let locked_until = Self::internal_lock_end(duration);
+                let position = positions
+                    .iter_mut()
+                    .find(|position| position.id == position_id)
+                    .unwrap();
+                position.amount = U128(position.amount.0 + amount);
+                position.locked_until = position.locked_until.max(locked_until);
+            }
+            TopUpLock::New { duration } => {
+                let position =
+                    self.internal_new_position(amount, Self::internal_lock_end(duration));
+                positions.push(position);
+            }
+            TopUpLock::RejectIfLocked => {}
+        }
+        self.internal_set_positions(account_id, &positions);
+    }
+
+    // the Stake branch of ft_on_transfer with a lock option, only what the cap accepted
+    // goes into the position
+    pub(crate) fn internal_stake_with_lock(
+        &mut self,
+        sender_id: AccountId,
+        amount: u128,
+        lock: TopUpLock,
+    ) -> Result<PromiseOrValue<U128>, StakeError> {
+        self.internal_check_can_stake(&sender_id)?;
+        if self.internal_needs_attestation(&sender_id) {
+            return Err(StakeError::AttestationRequired);
+        }
+        self.internal_check_top_up_lock(&sender_id, &lock)?;
+        let accepted = self.internal_accept_up_to_cap(&sender_id, amount);
+        if accepted > 0 {
+            self.internal_stake(sender_id.clone(), accepted);
+            self.internal_apply_top_up_lock(&sender_id, lock, accepted);
+        }
+        Ok(self.internal_finish_stake_transfer(amount, accepted))
+    }
+
+    // takes a stake reduction out of the positions in order, like the course stakes
+    pub(crate) fn internal_shrink_positions(
+        &mut self,
+        account_id: &AccountId,
+        amount_staked: u128,
+    ) {
+        let mut positions = match self.stake_positions.get(account_id) {
+            Some(positions) => positions,
+            None => return,
+        };
+        let locked: u128 = positions.iter().map(|position| position.amount.0).sum();
+        let mut excess = locked.saturating_sub(amount_staked);
+        for position in positions.iter_mut() {
+            let cut = excess.min(position.amount.0);
+            position.amount = U128(position.amount.0 - cut);
+            excess -= cut;
+        }
+        positions.retain(|position| position.amount.0 > 0);
+        self.internal_set_positions(account_id, &positions);
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // holds `amount` of the caller's free stake for `duration`
+    pub fn lock_stake(&mut self, amount: U128, duration: U64) -> u64 {
+        let account_id = env::predecessor_account_id();
+        let (stake_info, mut positions) = self.internal_restructure(&account_id);
+        require!(amount.0 > 0, StakeError::InvalidAmount);
+        let locked: u128 = positions.iter().map(|position| position.amount.0).sum();
+        require!(
+            stake_info.amount_staked >= locked + amount.0,
+            StakeError::InsufficientStake
+        );
+        require!(
+            positions.len() < MAX_POSITIONS_PER_ACCOUNT,
+            StakeError::TooManyPositions
+        );
+        let position = self.internal_new_position(amount.0, Self::internal_lock_end(duration));
+        let position_id = position.id;
+        positions.push(position);
+        self.internal_set_positions(&account_id, &positions);
+        position_id
+    }
+
+    // moves `amount` of a position into a new one under the same lock, so the two can be
+    // extended separately
+    pub fn split_position(&mut self, position_id: u64, amount: U128) -> u64 {
+        let account_id = env::predecessor_account_id();
+        let (_, mut positions) = self.internal_restructure(&account_id);
+        require!(
+            positions.len() < MAX_POSITIONS_PER_ACCOUNT,
+            StakeError::TooManyPositions
+        );
+        let position = positions
+            .iter_mut()
+            .find(|position| position.id == position_id)
+            .unwrap_or_else(|| env::panic_str(&StakeError::UnknownPosition));
+        require!(
+            amount.0 > 0 && amount.0 < position.amount.0,
+            StakeError::InvalidAmount
+        );
+        position.amount = U128(position.amount.0 - amount.0);
+        let locked_until = position.locked_until;
+        let split = self.internal_new_position(amount.0, locked_until);
+        let split_id = split.id;
+        positions.push(split);
+        self.internal_set_positions(&account_id, &positions);
+        split_id
+    }
+
+    // folds the positions into the first one, under the longest of their locks
+    pub fn merge_positions(&mut self, position_ids: Vec<u64>) -> u64 {
+        let account_id = env::predecessor_account_id();
+        let (_, positions) = self.internal_restructure(&account_id);
+        let unique = position_ids
+            .iter()
+            .enumerate()
+            .all(|(index, id)| !position_ids[..index].contains(id));
+        require!(
+            position_ids.len() >= 2 && unique,
+            StakeError::InvalidPositionMerge
+        );
+        require!(
+            position_ids
+                .iter()
+                .all(|id| positions.iter().any(|position| position.id == *id)),
+            StakeError::UnknownPosition
+        );
+        let (merged, mut kept): (Vec<StakePosition>, Vec<StakePosition>) = positions
+            .into_iter()
+            .partition(|position| position_ids.contains(&position.id));
+        kept.push(StakePosition {
+            id: position_ids[0],
+            amount: U128(merged.iter().map(|position| position.amount.0).sum()),
+            locked_until: merged
+                .iter()
+                .map(|position| position.locked_until)
+                .max()
+                .unwrap(),
+        });
+        self.internal_set_positions(&account_id, &kept);
+        position_ids[0]
+    }
+
+    // a lock only ever grows
+    pub fn extend_lock(&mut self, position_id: u64, duration: U64) {
+        let account_id = env::predecessor_account_id();
+        let (_, mut positions) = self.internal_restructure(&account_id);
+        let locked_until = Self::internal_lock_end(duration);
+        let position = positions
+            .iter_mut()
+            .find(|position| position.id == position_id)
+            .unwrap_or_else(|| env::panic_str(&StakeError::UnknownPosition));
+        position.locked_until = position.locked_until.max(locked_until);
+        self.internal_set_positions(&account_id, &positions);
+    }
+
+    pub fn get_positions(&self, account_id: AccountId) -> Vec<PositionView> {
+        let now = Self::now();
+        let (pending, staked) =
+            self.internal_get_stake_info(&account_id)
+                .map_or((0, 0), |stake_info| {
+                    (
+                        self.internal_pending_reward_at(&stake_info, now),
+                        stake_info.amount_staked,
+                    )
+                });
+        self.internal_positions(&account_id)
+            .into_iter()
+            .map(|position| PositionView {
+                id: position.id,
+                amount: position.amount,
+                locked_until: position.locked_until,
+                locked: now < position.locked_until,
+                reward: U128(if staked == 0 {
+                    0
+                } else {
+                    mul_div(pending, position.amount.0, staked)
+                }),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    const AMOUNT: u128 = 100 * ONE_TOKEN;
+    const DAY: u64 = 24 * 60 * 60;
+
+    fn days(count: u64) -> U64 {
+        U64(Duration::from_secs(count * DAY).as_nanos())
+    }
+
+    fn setup_locked(locked: u128, lock_days: u64) -> (Contract, u64) {
+        let mut contract = setup();
+        stake(&mut contract, &account("alice"), AMOUNT);
+        call_as(&account("alice"));
+        let position_id = contract.lock_stake(U128(locked), days(lock_days));
+        (contract, position_id)
+    }
+
+    fn unstake(contract: &mut Contract, amount: u128) {
+        call_with_yocto(&account("alice"));
+        contract.unstake_token(U128(amount), None, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_STAKE_LOCKED")]
+    fn locked_stake_cannot_be_unstaked() {
+        let (mut contract, _) = setup_locked(60 * ONE_TOKEN, 30);
+        unstake(&mut contract, 40 * ONE_TOKEN);
+        unstake(&mut contract, 1);
+    }
+
+    #[test]
+    fn an_expired_lock_frees_the_stake() {
+        let (mut contract, _) = setup_locked(60 * ONE_TOKEN, 30);
+        advance_secs(30 * DAY);
+        unstake(&mut contract, AMOUNT);
+        assert_eq!(staked(&contract, &account("alice")), 0);
+    }
+
+    #[test]
+    fn a_split_keeps_the_lock_and_the_rewards() {
+        let (mut contract, position_id) = setup_locked(60 * ONE_TOKEN, 30);
+        advance_secs(10 * DAY);
+        let before = contract.get_positions(account("alice"));
+
+        call_as(&account("alice"));
+        let split_id = contract.split_position(position_id, U128(20 * ONE_TOKEN));
+        let after = contract.get_positions(account("alice"));
+        assert_eq!(after.len(), 2);
+        assert_eq!(after[0].amount.0, 40 * ONE_TOKEN);
+        assert_eq!(after[1].id, split_id);
+        assert_eq!(after[1].amount.0, 20 * ONE_TOKEN);
+        assert_eq!(after[0].locked_until, after[1].locked_until);
+        let reward = after[0].reward.0 + after[1].reward.0;
+        assert!(before[0].reward.0 - reward <= 1);
+        assert_eq!(
+            contract.internal_position_locked(&account("alice")),
+            60 * ONE_TOKEN
+        );
+    }
+
+    #[test]
+    fn a_split_part_can_be_kept_locked_longer() {
+        let (mut contract, position_id) = setup_locked(60 * ONE_TOKEN, 30);
+        call_as(&account("alice"));
+        let split_id = contract.split_position(position_id, U128(20 * ONE_TOKEN));
+        contract.extend_lock(split_id, days(90));
+
+        advance_secs(30 * DAY);
+        unstake(&mut contract, 80 * ONE_TOKEN);
+        assert_eq!(
+            contract.internal_position_locked(&account("alice")),
+            20 * ONE_TOKEN
+        );
+    }
+
+    #[test]
+    fn a_merge_takes_the_longest_lock() {
+        let (mut contract, first_id) = setup_locked(30 * ONE_TOKEN, 30);
+        call_as(&account("alice"));
+        let second_id = contract.lock_stake(U128(50 * ONE_TOKEN), days(60));
+        let merged_id = contract.merge_positions(vec![first_id, second_id]);
+        assert_eq!(merged_id, first_id);
+
+        let positions = contract.get_positions(account("alice"));
+        assert_eq!(positions.len(), 1);
+        assert_eq!(positions[0].amount.0, 80 * ONE_TOKEN);
+        assert_eq!(
+            positions[0].locked_until,
+            Contract::now() + Duration::from_secs(60 * DAY)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INSUFFICIENT_STAKE")]
+    fn only_free_stake_can_be_locked() {
+        let (mut contract, _) = setup_locked(60 * ONE_TOKEN, 30);
+        call_as(&account("alice"));
+        contract.lock_stake(U128(40 * ONE_TOKEN + 1), days(30));
+    }
+
+    #[test]
+    fn a_slash_shrinks_the_positions() {
+        let (mut contract, _) = setup_locked(60 * ONE_TOKEN, 30);
+        call_as(&owner());
+        let proposal_id =
+            contract.propose_slash(account("alice"), U128(70 * ONE_TOKEN), String::new());
+        advance(DEFAULT_CHALLENGE_PERIOD);
+        contract.finalize_slash(proposal_id);
+        let positions = contract.get_positions(account("alice"));
+        assert_eq!(positions[0].amount.0, 30 * ONE_TOKEN);
+    }
+
+    fn top_up(contract: &mut Contract, msg: String) -> u128 {
+        transfer_call(contract, &account("alice"), 10 * ONE_TOKEN, &msg)
+    }
+
+    fn extend_lock(position_id: u64, lock_days: u64) -> String {
+        format!(
+            "{{\"ExtendLock\":{{\"position_id\":{},\"duration\":\"{}\"}}}}",
+            position_id,
+            days(lock_days).0
+        )
+    }
+
+    fn new_position(lock_days: u64) -> String {
+        format!(
+            "{{\"NewPosition\":{{\"duration\":\"{}\"}}}}",
+            days(lock_days).0
+        )
+    }
+
+    const REJECT_IF_LOCKED: &str = "\"RejectIfLocked\"";
+
+    fn amounts(contract: &Contract) -> Vec<u128> {
+        contract
+            .get_positions(account("alice"))
+            .iter()
+            .map(|position| position.amount.0)
+            .collect()
+    }
+
+    #[test]
+    fn a_plain_top_up_leaves_the_locks_alone() {
+        let (mut contract, _) = setup_locked(60 * ONE_TOKEN, 30);
+        let locked_until = contract.get_positions(account("alice"))[0].locked_until;
+        assert_eq!(top_up(&mut contract, String::new()), 0);
+        assert_eq!(
+            staked(&contract, &account("alice")),
+            AMOUNT + 10 * ONE_TOKEN
+        );
+        assert_eq!(amounts(&contract), vec![60 * ONE_TOKEN]);
+        assert_eq!(
+            contract.get_positions(account("alice"))[0].locked_until,
+            locked_until
+        );
+    }
+
+    #[test]
+    fn extend_lock_joins_the_position_and_extends_it() {
+        let (mut contract, position_id) = setup_locked(60 * ONE_TOKEN, 30);
+        assert_eq!(top_up(&mut contract, extend_lock(position_id, 90)), 0);
+        let positions = contract.get_positions(account("alice"));
+        assert_eq!(positions[0].amount.0, 70 * ONE_TOKEN);
+        assert_eq!(
+            positions[0].locked_until,
+            Contract::now() + Duration::from_secs(90 * DAY)
+        );
+    }
+
+    #[test]
+    fn extend_lock_never_shortens_the_lock() {
+        let (mut contract, position_id) = setup_locked(60 * ONE_TOKEN, 30);
+        let locked_until = contract.get_positions(account("alice"))[0].locked_until;
+        assert_eq!(top_up(&mut contract, extend_lock(position_id, 1)), 0);
+        let positions = contract.get_positions(account("alice"));
+        assert_eq!(positions[0].amount.0, 70 * ONE_TOKEN);
+        assert_eq!(positions[0].locked_until, locked_until);
+    }
+
+    #[test]
+    fn extend_lock_refunds_without_a_running_position() {
+        let (mut contract, position_id) = setup_locked(60 * ONE_TOKEN, 30);
+        assert_eq!(
+            top_up(&mut contract, extend_lock(position_id + 1, 30)),
+            10 * ONE_TOKEN
+        );
+        advance_secs(30 * DAY);
+        assert_eq!(
+            top_up(&mut contract, extend_lock(position_id, 30)),
+            10 * ONE_TOKEN
+        );
+        assert_eq!(staked(&contract, &account("alice")), AMOUNT);
+    }
+
+    #[test]
+    fn new_position_opens_a_lock_next_to_the_others() {
+        let (mut contract, _) = setup_locked(60 * ONE_TOKEN, 30);
+        assert_eq!(top_up(&mut contract, new_position(60)), 0);
+        assert_eq!(amounts(&contract), vec![60 * ONE_TOKEN, 10 * ONE_TOKEN]);
+        assert_eq!(
+            contract.internal_position_locked(&account("alice")),
+            70 * ONE_TOKEN
+        );
+    }
+
+    #[test]
+    fn new_position_opens_the_first_lock() {
+        let mut contract = setup();
+        assert_eq!(top_up(&mut contract, new_position(30)), 0);
+        assert_eq!(staked(&contract, &account("alice")), 10 * ONE_TOKEN);
+        assert_eq!(amounts(&contract), vec![10 * ONE_TOKEN]);
+    }
+
+    #[test]
+    fn new_position_holds_only_what_the_cap_accepted() {
+        let mut contract = setup();
+        call_as(&owner());
+        contract.set_max_total_staked(Some(U128(4 * ONE_TOKEN)));
+        assert_eq!(top_up(&mut contract, new_position(30)), 6 * ONE_TOKEN);
+        assert_eq!(amounts(&contract), vec![4 * ONE_TOKEN]);
+    }
+
+    #[test]
+    fn new_position_refunds_an_invalid_duration() {
+        let (mut contract, _) = setup_locked(60 * ONE_TOKEN, 30);
+        assert_eq!(top_up(&mut contract, new_position(0)), 10 * ONE_TOKEN);
+        assert_eq!(top_up(&mut contract, new_position(5 * 365)), 10 * ONE_TOKEN);
+        assert_eq!(amounts(&contract), vec![60 * ONE_TOKEN]);
+    }
+
+    #[test]
+    fn reject_if_locked_refunds_while_a_lock_runs() {
+        let (mut contract, _) = setup_locked(60 * ONE_TOKEN, 30);
+        assert_eq!(
+            top_up(&mut contract, REJECT_IF_LOCKED.to_string()),
+            10 * ONE_TOKEN
+        );
+        assert_eq!(staked(&contract, &account("alice")), AMOUNT);
+    }
+
+    #[test]
+    fn reject_if_locked_stakes_once_the_locks_expired() {
+        let (mut contract, _) = setup_locked(60 * ONE_TOKEN, 30);
+        advance_secs(30 * DAY);
+        assert_eq!(top_up(&mut contract, REJECT_IF_LOCKED.to_string()), 0);
+        assert_eq!(
+            staked(&contract, &account("alice")),
+            AMOUNT + 10 * ONE_TOKEN
+        );
+    }
+
+    #[test]
+    fn reject_if_locked_stakes_without_locks() {
+        let mut contract = setup();
+        assert_eq!(top_up(&mut contract, REJECT_IF_LOCKED.to_string()), 0);
+        assert_eq!(staked(&contract, &account("alice")), 10 * ONE_TOKEN);
+        assert!(amounts(&contract).is_empty());
+    }
+}
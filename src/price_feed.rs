@@ -0,0 +1,103 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::Serialize;
+use near_sdk::{env, near_bindgen, require, AccountId};
+
+use crate::math::{format_decimal, mul_div};
+use crate::time::{Duration, Timestamp};
+use crate::*;
+
+pub const DEFAULT_MAX_PRICE_AGE: Duration = Duration::from_secs(60 * 60);
+pub const MAX_QUOTE_LEN: usize = 16;
+pub const MAX_PRICE_DECIMALS: u8 = 24;
+
+// price of one whole staked token in `quote`, scaled by 10^decimals
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PricePoint {
+    pub price: U128,
+    pub decimals: u8,
+    pub updated_at: Timestamp,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct QuotedReward {
+    pub reward: U128,
+    pub quote: String,
+    // scaled by 10^decimals like the price it came from
+    pub value: U128,
+    pub decimals: u8,
+    pub value_decimal: String,
+    pub price_updated_at: Timestamp,
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn report_price(&mut self, quote: String, price: U128, decimals: u8) {
+        require!(
+            self.oracle_id.as_ref() == Some(&env::predecessor_account_id()),
+            StakeError::NotOracle
+        );
+        require!(
+            !quote.is_empty()
+                && quote.len() <= MAX_QUOTE_LEN
+                && price.0 > 0
+                && decimals <= MAX_PRICE_DECIMALS,
+            StakeError::InvalidPrice
+        );
+        self.prices.insert(
+            &quote,
+            &PricePoint {
+                price,
+                decimals,
+                updated_at: Self::now(),
+            },
+        );
+        self.internal_sync_usd_peg();
+    }
+
+    pub fn set_max_price_age(&mut self, max_price_age: U64) {
+        self.internal_assert_role(Role::Admin);
+        self.internal_record_config_change(
+            "max_price_age",
+            U64(self.max_price_age.as_nanos()),
+            max_price_age,
+        );
+        self.max_price_age = Duration::from_nanos(max_price_age.0);
+    }
+
+    pub fn get_price(&self, quote: String) -> Option<PricePoint> {
+        self.prices.get(&quote)
+    }
+
+    // display only, a price older than max_price_age is refused rather than shown
+    #[handle_result]
+    pub fn pending_reward_in(
+        &self,
+        account_id: AccountId,
+        quote: String,
+    ) -> Result<QuotedReward, StakeError> {
+        let stake_info = self
+            .internal_get_stake_info(&account_id)
+            .ok_or(StakeError::NotStaked)?;
+        let point = self.prices.get(&quote).ok_or(StakeError::UnknownQuote)?;
+        if Self::now() > point.updated_at + self.max_price_age {
+            return Err(StakeError::StalePrice);
+        }
+        let reward = self.internal_pending_reward_at(&stake_info, Self::now());
+        let value = mul_div(
+            reward,
+            point.price.0,
+            10u128.pow(self.internal_token_decimals() as u32),
+        );
+        Ok(QuotedReward {
+            reward: U128(reward),
+            quote,
+            value: U128(value),
+            decimals: point.decimals,
+            value_decimal: format_decimal(value, point.decimals),
+            price_updated_at: point.updated_at,
+        })
+    }
+}
@@ -0,0 +1,110 @@
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::Serialize;
+use near_sdk::{near_bindgen, AccountId};
+
+use crate::math::{format_decimal, reward_for_duration, Rate};
+use crate::time::{Duration, Timestamp, SECONDS_PER_DAY};
+use crate::*;
+
+pub const DEFAULT_COMPOUNDS_PER_YEAR: u32 = 365;
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RewardRate {
+    pub apr: U128,
+    pub per_day: U128,
+    pub per_year: U128,
+    pub per_day_decimal: String,
+    pub per_year_decimal: String,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RewardAsOf {
+    pub pending_reward: U128,
+    pub as_of: Timestamp,
+    // the last settlement of the position or of the apr index, whichever is later.
+    // nothing before it can be reconstructed from current state
+    pub replayable_from: Timestamp,
+}
+
+#[near_bindgen]
+impl Contract {
+    // the accrual a touch at `timestamp` would apply before the accrual cap, for
+    // reproducing a balance at any point since the position last settled
+    #[handle_result]
+    pub fn pending_reward_at(
+        &self,
+        account_id: AccountId,
+        timestamp: U64,
+    ) -> Result<RewardAsOf, StakeError> {
+        let stake_info = self
+            .internal_get_stake_info(&account_id)
+            .ok_or(StakeError::NotStaked)?;
+        let as_of = Timestamp::from(timestamp);
+        let replayable_from = stake_info.time_staked.max(self.apr_index_updated_at);
+        if as_of < replayable_from {
+            return Err(StakeError::TimestampNotReplayable);
+        }
+        Ok(RewardAsOf {
+            pending_reward: U128(self.internal_pending_reward_at(&stake_info, as_of)),
+            as_of,
+            replayable_from,
+        })
+    }
+
+    // reward the account would have at `at_timestamp` if nothing changes until then
+    #[handle_result]
+    pub fn simulate_reward(
+        &self,
+        account_id: AccountId,
+        at_timestamp: U64,
+    ) -> Result<U128, StakeError> {
+        let stake_info = self
+            .internal_get_stake_info(&account_id)
+            .ok_or(StakeError::NotStaked)?;
+        Ok(U128(self.internal_pending_reward_at(
+            &stake_info,
+            at_timestamp.into(),
+        )))
+    }
+
+    // effective yearly yield of the account's boosted APR after the oracle modifier, compounded
+    // `compounds_per_year` times, same scale as apr
+    #[handle_result]
+    pub fn estimate_apy(
+        &self,
+        account_id: AccountId,
+        compounds_per_year: Option<u32>,
+    ) -> Result<U128, StakeError> {
+        let stake_info = self
+            .internal_get_stake_info(&account_id)
+            .ok_or(StakeError::NotStaked)?;
+        let apr = self.internal_apply_apr_modifier(stake_info.boosted(stake_info.apr));
+        let periods = compounds_per_year.unwrap_or(DEFAULT_COMPOUNDS_PER_YEAR);
+        if periods == 0 {
+            return Err(StakeError::InvalidCompoundingPeriods);
+        }
+
+        Ok(U128(Rate::from_raw(apr).compounded(periods).raw()))
+    }
+
+    // simple interest on the current principal at the APR after the oracle modifier,
+    // before the insurance skim
+    #[handle_result]
+    pub fn get_reward_rate(&self, account_id: AccountId) -> Result<RewardRate, StakeError> {
+        let stake_info = self
+            .internal_get_stake_info(&account_id)
+            .ok_or(StakeError::NotStaked)?;
+        let apr = self.internal_apply_apr_modifier(stake_info.boosted(stake_info.apr));
+        let per_year = Rate::from_raw(apr).annual_reward(stake_info.amount_staked);
+        let per_day = reward_for_duration(per_year, Duration::from_secs(SECONDS_PER_DAY));
+        Ok(RewardRate {
+            apr: U128(apr),
+            per_day: U128(per_day),
+            per_year: U128(per_year),
+            per_day_decimal: format_decimal(per_day, self.internal_token_decimals()),
+            per_year_decimal: format_decimal(per_year, self.internal_token_decimals()),
+        })
+    }
+}
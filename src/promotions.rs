@@ -0,0 +1,138 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::Serialize;
+use near_sdk::{env, near_bindgen, require};
+
+use crate::math::{reward_for_duration, Rate};
+use crate::time::{Duration, Timestamp};
+use crate::*;
+
+pub const MAX_PROMOTIONS: usize = 10;
+
+// extra APR paid on top of the position's own rate while the window is open, it
+// needs no transaction to start or stop since accrual checks the window itself
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Promotion {
+    pub id: u64,
+    pub start: Timestamp,
+    pub end: Timestamp,
+    pub bonus_apr: U128,
+    pub filter: CampaignFilter,
+}
+
+impl Promotion {
+    // part of start..end that falls inside from..to
+    fn overlap(&self, from: Timestamp, to: Timestamp) -> Duration {
+        Duration::between(from.max(self.start), to.min(self.end))
+    }
+}
+
+impl Contract {
+    // promotion reward a position earned since it last settled, eligibility is judged
+    // on the position as it is now
+    pub(crate) fn internal_promotion_reward(
+        &self,
+        stake_info: &StakeInfo,
+        timestamp: Timestamp,
+    ) -> u128 {
+        self.promotions
+            .iter()
+            .filter(|promotion| self.internal_promotion_eligible(promotion, stake_info))
+            .map(|promotion| {
+                reward_for_duration(
+                    Rate::from_raw(promotion.bonus_apr.0).annual_reward(stake_info.amount_staked),
+                    promotion.overlap(stake_info.time_staked, timestamp),
+                )
+            })
+            .sum()
+    }
+
+    // for the contract wide estimate, counted as if every position qualified
+    pub(crate) fn internal_promotion_estimate(&self, from: Timestamp, to: Timestamp) -> u128 {
+        self.promotions
+            .iter()
+            .map(|promotion| {
+                reward_for_duration(
+                    Rate::from_raw(promotion.bonus_apr.0).annual_reward(self.total_staked),
+                    promotion.overlap(from, to),
+                )
+            })
+            .sum()
+    }
+
+    fn internal_promotion_eligible(&self, promotion: &Promotion, stake_info: &StakeInfo) -> bool {
+        match &promotion.filter {
+            CampaignFilter::Everyone => true,
+            CampaignFilter::NewStakers => stake_info.terms.first_staked_at >= promotion.start,
+            CampaignFilter::MinStake { amount } => stake_info.amount_staked >= amount.0,
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn schedule_promotion(
+        &mut self,
+        start: Timestamp,
+        end: Timestamp,
+        bonus_apr: U128,
+        filter: Option<CampaignFilter>,
+    ) -> u64 {
+        self.internal_assert_role(Role::Admin);
+        require!(
+            end > start
+                && end > Self::now()
+                && bonus_apr.0 > 0
+                && Rate::from_raw(bonus_apr.0) <= MAX_APR,
+            StakeError::InvalidPromotion
+        );
+        require!(
+            self.promotions.len() < MAX_PROMOTIONS,
+            StakeError::TooManyPromotions
+        );
+        self.internal_update_global_accrual();
+        self.promotion_count += 1;
+        self.promotions.push(Promotion {
+            id: self.promotion_count,
+            start,
+            end,
+            bonus_apr,
+            filter: filter.unwrap_or(CampaignFilter::Everyone),
+        });
+        self.promotion_count
+    }
+
+    // a running promotion is cut short at now so what it already paid stays earned.
+    // removing one that ended drops it from positions that have not settled since
+    pub fn cancel_promotion(&mut self, promotion_id: u64) {
+        self.internal_assert_role(Role::Admin);
+        let now = Self::now();
+        let index = self
+            .promotions
+            .iter()
+            .position(|promotion| promotion.id == promotion_id)
+            .unwrap_or_else(|| env::panic_str(&StakeError::UnknownPromotion));
+        self.internal_update_global_accrual();
+        let promotion = &mut self.promotions[index];
+        if promotion.start < now && now < promotion.end {
+            promotion.end = now;
+        } else {
+            self.promotions.remove(index);
+        }
+    }
+
+    pub fn get_active_promotions(&self) -> Vec<Promotion> {
+        let now = Self::now();
+        self.promotions
+            .iter()
+            .filter(|promotion| promotion.start <= now && now < promotion.end)
+            .cloned()
+            .collect()
+    }
+
+    // scheduled, running and ended ones that were not removed yet
+    pub fn get_promotions(&self) -> Vec<Promotion> {
+        self.promotions.clone()
+    }
+}
@@ -0,0 +1,114 @@
+use near_sdk::json_types::U128;
+use near_sdk::serde::Serialize;
+use near_sdk::{env, near_bindgen, require, AccountId, Gas, Promise, PromiseError, ONE_YOCTO};
+
+use crate::math::{mul_div, BPS_DENOMINATOR};
+use crate::*;
+
+pub const MAX_PROTOCOL_FEE_BPS: u16 = 2_000;
+pub const PROTOCOL_FEE_CALLBACK_GAS: Gas = Gas(20_000_000_000_000);
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ProtocolFeeView {
+    pub fee_bps: u16,
+    pub fee_account: Option<AccountId>,
+    pub unclaimed: U128,
+    pub total_collected: U128,
+}
+
+impl Contract {
+    pub(crate) fn internal_protocol_fee(&self, accrued: u128) -> u128 {
+        mul_div(accrued, self.protocol_fee_bps as u128, BPS_DENOMINATOR)
+    }
+
+    // like the insurance skim, only as much as the reward pool can back
+    pub(crate) fn internal_collect_protocol_fee(&mut self, fee: u128) {
+        let fee = fee.min(self.reward_pool);
+        if fee == 0 {
+            return;
+        }
+        self.internal_update_global_accrual();
+        self.reward_pool -= fee;
+        self.accrued_rewards = self.accrued_rewards.saturating_sub(fee);
+        self.protocol_fee_balance += fee;
+        self.total_protocol_fees += fee;
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn set_protocol_fee(&mut self, fee_bps: u16, fee_account: Option<AccountId>) {
+        self.internal_assert_role(Role::Admin);
+        require!(
+            fee_bps <= MAX_PROTOCOL_FEE_BPS,
+            StakeError::ProtocolFeeTooHigh
+        );
+        self.internal_record_config_change("protocol_fee_bps", self.protocol_fee_bps, fee_bps);
+        self.internal_record_config_change(
+            "protocol_fee_account",
+            self.protocol_fee_account.clone(),
+            fee_account.clone(),
+        );
+        self.protocol_fee_bps = fee_bps;
+        self.protocol_fee_account = fee_account;
+    }
+
+    // sends the collected fees to the fee account, a failed transfer keeps them here
+    pub fn claim_protocol_fees(&mut self) -> Promise {
+        self.internal_assert_role(Role::Treasurer);
+        self.internal_assert_transfers_up();
+        let fee_account = self
+            .protocol_fee_account
+            .clone()
+            .unwrap_or_else(|| env::panic_str(&StakeError::NoProtocolFeeAccount));
+        let amount = self.protocol_fee_balance;
+        require!(amount > 0, StakeError::NoProtocolFees);
+        self.protocol_fee_balance = 0;
+        self.pending_outflows += amount;
+
+        ext_ft_contract::ext(self.token_address.clone())
+            .with_static_gas(FT_TRANSFER_GAS)
+            .with_attached_deposit(ONE_YOCTO)
+            .ft_transfer(fee_account, U128(amount), Some("protocol fees".to_string()))
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(PROTOCOL_FEE_CALLBACK_GAS)
+                    .on_protocol_fees_claimed(U128(amount)),
+            )
+    }
+
+    #[private]
+    pub fn on_protocol_fees_claimed(
+        &mut self,
+        amount: U128,
+        #[callback_result] result: Result<(), PromiseError>,
+    ) -> U128 {
+        self.internal_note_promise_result(
+            "on_protocol_fees_claimed",
+            None,
+            amount.0,
+            result.is_ok(),
+        );
+        if let Some(fee_account) = self.protocol_fee_account.clone() {
+            self.internal_note_token_transfer(&fee_account, result.is_ok());
+        }
+        self.pending_outflows -= amount.0;
+        if result.is_err() {
+            self.protocol_fee_balance += amount.0;
+            return U128(0);
+        }
+        self.internal_note_outflow(amount.0);
+        self.total_swept += amount.0;
+        amount
+    }
+
+    pub fn get_protocol_fee(&self) -> ProtocolFeeView {
+        ProtocolFeeView {
+            fee_bps: self.protocol_fee_bps,
+            fee_account: self.protocol_fee_account.clone(),
+            unclaimed: U128(self.protocol_fee_balance),
+            total_collected: U128(self.total_protocol_fees),
+        }
+    }
+}
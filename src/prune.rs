@@ -0,0 +1,77 @@
+use near_sdk::json_types::U64;
+use near_sdk::{near_bindgen, AccountId};
+
+use crate::*;
+
+pub const MAX_PRUNE_BATCH: u64 = 100;
+
+impl Contract {
+    // nothing owed either way and nothing another record points at. an advisor who
+    // received votes is kept so a full exit cannot wash out their reputation
+    fn internal_is_prunable(&self, account_id: &AccountId, stake_info: &StakeInfo) -> bool {
+        stake_info.amount_staked == 0
+            && stake_info.reward == 0
+            && stake_info.deferred_reward == 0
+            && stake_info.withdrawable == 0
+            && stake_info.delegated == 0
+            && stake_info.grouped == 0
+            && !stake_info.pending_operation
+            && stake_info.votes_received == 0
+            && self
+                .vesting
+                .get(account_id)
+                .is_none_or(|entries| entries.is_empty())
+            && self.internal_session_locked(account_id) == 0
+            && self.internal_collateral_locked(account_id) == 0
+            && !self.open_slashes.contains_key(account_id)
+            && !self.frozen_accounts.contains_key(account_id)
+            && !self.delegations.contains_key(account_id)
+            && !self.delegation_pools.contains_key(account_id)
+            && !self.queue_entries.contains_key(account_id)
+            && !self.account_campaigns.contains_key(account_id)
+    }
+
+    // drops the record and its terms, the freed storage goes back to the contract balance
+    // or to whoever put up a storage deposit for it
+    pub(crate) fn internal_prune(&mut self, account_id: &AccountId) -> bool {
+        let stake_info = match self.internal_load_stake_info(account_id) {
+            Some(stake_info) if self.internal_is_prunable(account_id, &stake_info) => stake_info,
+            _ => return false,
+        };
+        self.stake_info.remove(account_id);
+        self.internal_note_stake_write();
+        self.stake_terms.remove(account_id);
+        self.internal_uncommit_state(account_id);
+        self.staker_ids.remove(account_id);
+        self.course_stakes.remove(account_id);
+        self.stake_positions.remove(account_id);
+        self.total_stakers -= 1;
+        self.total_apr -= stake_info.apr;
+        self.internal_refund_storage(account_id);
+        true
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // the staker index shifts as entries go, a page can be run again until it prunes none
+    pub fn prune_empty(&mut self, from_index: U64, limit: U64) -> U64 {
+        self.internal_assert_owner();
+        let limit = limit.0.min(MAX_PRUNE_BATCH);
+        let accounts: Vec<AccountId> = self
+            .staker_ids
+            .as_vector()
+            .iter()
+            .skip(from_index.0 as usize)
+            .take(limit as usize)
+            .collect();
+
+        let mut pruned = 0;
+        for account_id in accounts {
+            if self.internal_prune(&account_id) {
+                pruned += 1;
+            }
+        }
+        U64(pruned)
+    }
+}
@@ -0,0 +1,101 @@
+use near_sdk::json_types::{U128, U64};
+use near_sdk::near_bindgen;
+use near_sdk::serde::Serialize;
+
+use crate::*;
+
+// flow totals count from the release that introduced them, a contract deployed
+// earlier starts them at zero
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Inflows {
+    pub staked: U128,
+    pub reward_funding: U128,
+    // every stake token transfer this contract accepted
+    pub total: U128,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Outflows {
+    // moved from stake into withdrawable, before it left
+    pub unstaked: U128,
+    pub claimed: U128,
+    // protocol fee claims and surplus sweeps
+    pub swept: U128,
+    pub burned: U128,
+    // every stake token transfer out that succeeded
+    pub total: U128,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Liabilities {
+    pub principal: U128,
+    pub accrued_rewards: U128,
+    // withdrawable, in flight or waiting in the outbox
+    pub pending_unbonds: U128,
+    pub reward_pool: U128,
+    // faucet, vesting, insurance, fees, pools and the other earmarked balances
+    pub other: U128,
+    pub total: U128,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Reconciliation {
+    pub inflows: Inflows,
+    pub outflows: Outflows,
+    pub liabilities: Liabilities,
+    // liabilities plus whatever accrued rewards the reward pool and sponsored budgets
+    // can't back
+    pub required_balance: U128,
+    // the last fetched token balance moved along with the transfers since, compare
+    // it with ft_balance_of after refresh_token_balance
+    pub token_balance: Option<U128>,
+    pub token_balance_checked_at: U64,
+    pub surplus: Option<U128>,
+    pub shortfall: Option<U128>,
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn get_reconciliation(&self) -> Reconciliation {
+        let accrued = self.accrued_rewards + self.internal_accrued_since_update(Self::now());
+        let principal = self.total_staked + self.total_partner_staked;
+        let pending_unbonds = self.total_withdrawable + self.pending_outflows;
+        let total = self.internal_liabilities();
+        let required = total + accrued.saturating_sub(self.reward_pool + self.total_sponsored);
+        Reconciliation {
+            inflows: Inflows {
+                staked: U128(self.total_volume_staked),
+                reward_funding: U128(self.total_reward_funded),
+                total: U128(self.total_inflows),
+            },
+            outflows: Outflows {
+                unstaked: U128(self.total_volume_unstaked),
+                claimed: U128(self.total_rewards_paid),
+                swept: U128(self.total_swept),
+                burned: U128(self.total_burned),
+                total: U128(self.total_outflows),
+            },
+            liabilities: Liabilities {
+                principal: U128(principal),
+                accrued_rewards: U128(accrued),
+                pending_unbonds: U128(pending_unbonds),
+                reward_pool: U128(self.reward_pool),
+                other: U128(total - principal - pending_unbonds - self.reward_pool),
+                total: U128(total),
+            },
+            required_balance: U128(required),
+            token_balance: self.token_balance.map(U128),
+            token_balance_checked_at: self.token_balance_checked_at.into(),
+            surplus: self
+                .token_balance
+                .map(|balance| U128(balance.saturating_sub(required))),
+            shortfall: self
+                .token_balance
+                .map(|balance| U128(required.saturating_sub(balance))),
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use near_sdk::json_types::{I64, U128, U64};
+use near_sdk::serde::Serialize;
+use near_sdk::{near_bindgen, require, AccountId};
+
+use crate::math::{mul_div, BPS_DENOMINATOR};
+use crate::time::{Duration, Timestamp};
+use crate::*;
+
+// reputation a vote of weight 1 adds, apr moves one vote step per this many points
+pub const REPUTATION_PER_WEIGHT: i64 = 1_000;
+pub const DEFAULT_REPUTATION_HALF_LIFE: Duration = Duration::from_secs(180 * 24 * 60 * 60);
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ReputationView {
+    pub score: I64,
+    pub apr: U128,
+    pub updated_at: U64,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct VotePreview {
+    pub apr_before: U128,
+    pub apr_after: U128,
+    pub votes_before: u8,
+    pub votes_after: u8,
+    pub score_before: I64,
+    pub score_after: I64,
+    // votes inside the grace period are counted but do not move the score
+    pub in_grace: bool,
+    pub at_apr_bound: bool,
+}
+
+impl Contract {
+    // halves every half life, linear in between. a zero half life turns decay off
+    pub(crate) fn internal_decayed_reputation(
+        &self,
+        stake_info: &StakeInfo,
+        now: Timestamp,
+    ) -> i64 {
+        let half_life = self.reputation_half_life.as_nanos();
+        if half_life == 0 {
+            return stake_info.reputation;
+        }
+        let elapsed = Duration::between(stake_info.reputation_updated_at, now).as_nanos();
+        let halvings = elapsed / half_life;
+        if halvings >= 63 {
+            return 0;
+        }
+        let halved = stake_info.reputation / (1i64 << halvings);
+        let remainder = (elapsed % half_life) as i128;
+        halved - (halved as i128 * remainder / (2 * half_life as i128)) as i64
+    }
+
+    // under a vote horizon the record was synced on touch and expiry replaces decay
+    pub(crate) fn internal_add_reputation(&self, stake_info: &mut StakeInfo, delta: i64) {
+        let now = Self::now();
+        let current = if self.vote_horizon.is_some() {
+            stake_info.reputation
+        } else {
+            self.internal_decayed_reputation(stake_info, now)
+        };
+        stake_info.reputation = current.saturating_add(delta);
+        stake_info.reputation_updated_at = now;
+    }
+
+    // an advisor no one votes for any more still decays, the apr follows the score as of
+    // now. the stored score is left alone so decay doesn't compound over touches. under a
+    // vote horizon expiry does this instead
+    pub(crate) fn internal_decay_apr(
+        &mut self,
+        advisor_id: &AccountId,
+        stake_info: &mut StakeInfo,
+    ) {
+        let old_apr = stake_info.apr;
+        stake_info.apr = self
+            .internal_decayed_apr(stake_info, Self::now())
+            .unwrap_or(old_apr);
+        if stake_info.apr != old_apr {
+            self.internal_update_reward_rate(
+                stake_info.amount_staked,
+                stake_info.boosted(old_apr),
+                stake_info.amount_staked,
+                stake_info.boosted(stake_info.apr),
+            );
+            self.internal_record_apr_change(
+                advisor_id,
+                old_apr,
+                stake_info.apr,
+                AprChangeCause::ReputationDecayed,
+            );
+        }
+    }
+
+    // what the apr decays to by `timestamp`, None when nothing decays
+    pub(crate) fn internal_decayed_apr(
+        &self,
+        stake_info: &StakeInfo,
+        timestamp: Timestamp,
+    ) -> Option<u128> {
+        if self.vote_horizon.is_some()
+            || self.reputation_half_life.as_nanos() == 0
+            || stake_info.reputation == 0
+        {
+            return None;
+        }
+        let mut decayed = stake_info.clone();
+        decayed.reputation = self.internal_decayed_reputation(stake_info, timestamp);
+        Some(self.internal_apr_for_reputation(&decayed))
+    }
+
+    // the apr is derived from the score and never stored as the source of truth, so a new
+    // formula applies to every advisor the next time their score moves
+    pub(crate) fn internal_apr_for_reputation(&self, stake_info: &StakeInfo) -> u128 {
+        let delta = self.internal_curved_deviation(mul_div(
+            stake_info.reputation.unsigned_abs() as u128,
+            self.vote_step,
+            REPUTATION_PER_WEIGHT as u128,
+        ));
+        let apr = if stake_info.reputation >= 0 {
+            stake_info.terms.base_apr.saturating_add(delta)
+        } else {
+            stake_info.terms.base_apr.saturating_sub(delta)
+        };
+        apr.clamp(self.min_apr, self.max_apr)
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // score decayed to now, apr is what the score maps to under the current formula
+    pub fn get_reputation(&self, advisor_id: AccountId) -> Option<ReputationView> {
+        self.internal_get_stake_info(&advisor_id)
+            .map(|mut stake_info| {
+                let now = Self::now();
+                stake_info.reputation = self
+                    .internal_windowed_reputation(&advisor_id, now)
+                    .unwrap_or_else(|| self.internal_decayed_reputation(&stake_info, now));
+                ReputationView {
+                    score: I64(stake_info.reputation),
+                    apr: U128(self.internal_apr_for_reputation(&stake_info)),
+                    updated_at: stake_info.reputation_updated_at.into(),
+                }
+            })
+    }
+
+    // what update_apr would leave the advisor at, nothing is written
+    pub fn preview_vote(&self, advisor_id: AccountId, vote: u8) -> Option<VotePreview> {
+        require!(
+            vote >= 1 && vote as usize <= self.vote_weights.len(),
+            StakeError::InvalidVote
+        );
+        let mut stake_info = self.internal_get_stake_info(&advisor_id)?;
+        let now = Self::now();
+        stake_info.reputation = self
+            .internal_windowed_reputation(&advisor_id, now)
+            .unwrap_or_else(|| self.internal_decayed_reputation(&stake_info, now));
+        stake_info.reputation_updated_at = now;
+        stake_info.apr = self.internal_apr_for_reputation(&stake_info);
+        let (apr_before, votes_before, score_before) =
+            (stake_info.apr, stake_info.votes, stake_info.reputation);
+
+        let in_grace = self.internal_in_vote_grace(&stake_info);
+        if !in_grace {
+            self.internal_apply_vote(&mut stake_info, vote, BPS_DENOMINATOR as u16);
+        }
+        Some(VotePreview {
+            apr_before: U128(apr_before),
+            apr_after: U128(stake_info.apr),
+            votes_before,
+            votes_after: stake_info.votes,
+            score_before: I64(score_before),
+            score_after: I64(stake_info.reputation),
+            in_grace,
+            at_apr_bound: stake_info.apr == self.min_apr || stake_info.apr == self.max_apr,
+        })
+    }
+
+    pub fn set_reputation_half_life(&mut self, half_life: U64) {
+        self.internal_assert_role(Role::Admin);
+        self.reputation_half_life = Duration::from_nanos(half_life.0);
+    }
+}
@@ -0,0 +1,60 @@
+use near_sdk::json_types::U128;
+use near_sdk::{env, near_bindgen, require, AccountId, Promise, ONE_YOCTO};
+
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    // claim_and_call into an approved vault for the claimed reward only, principal and
+    // course payments stay withdrawable. with vesting on only the instant part of the claim
+    // goes, the rest vests as usual. the vault sees this contract as the sender, `msg` is
+    // the account to credit. a failed or refunded deposit lands back in withdrawable
+    #[payable]
+    pub fn restake_into(&mut self, vault_account_id: AccountId) -> Promise {
+        self.internal_assert_deposit("restake_into");
+        self.internal_assert_transfers_up();
+        require!(
+            self.approved_vaults.contains(&vault_account_id),
+            StakeError::VaultNotApproved
+        );
+        let account_id = env::predecessor_account_id();
+        let withdrawable = |contract: &Self| {
+            contract
+                .internal_get_stake_info(&account_id)
+                .map_or(0, |stake_info| stake_info.withdrawable)
+        };
+        let before = withdrawable(self);
+        self.internal_claim_reward(&account_id, None);
+        let released = withdrawable(self) - before;
+        let amount = self.internal_start_outflow_of(&account_id, Some(released));
+        self.internal_maybe_snapshot();
+
+        ext_ft_contract::ext(self.token_address.clone())
+            .with_static_gas(FT_TRANSFER_CALL_GAS)
+            .with_attached_deposit(ONE_YOCTO)
+            .ft_transfer_call(vault_account_id, U128(amount), None, account_id.to_string())
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(CLAIM_AND_CALL_CALLBACK_GAS)
+                    .on_claim_and_call(account_id, U128(amount)),
+            )
+    }
+
+    pub fn add_approved_vaults(&mut self, vault_account_ids: Vec<AccountId>) {
+        self.internal_assert_owner();
+        for vault_account_id in vault_account_ids {
+            self.approved_vaults.insert(&vault_account_id);
+        }
+    }
+
+    pub fn remove_approved_vaults(&mut self, vault_account_ids: Vec<AccountId>) {
+        self.internal_assert_owner();
+        for vault_account_id in vault_account_ids {
+            self.approved_vaults.remove(&vault_account_id);
+        }
+    }
+
+    pub fn is_approved_vault(&self, vault_account_id: AccountId) -> bool {
+        self.approved_vaults.contains(&vault_account_id)
+    }
+}
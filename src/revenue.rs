@@ -0,0 +1,90 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::Serialize;
+use near_sdk::{env, near_bindgen, AccountId};
+
+use crate::events::{RevenueSharedData, StakeEvent};
+use crate::*;
+
+pub const MAX_REVENUE_EPOCHS: u64 = 100;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RevenueEpoch {
+    pub epoch_height: U64,
+    pub amount: U128,
+    pub deposits: u64,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RevenueStats {
+    pub total_revenue: U128,
+    pub current_epoch: RevenueEpoch,
+}
+
+impl Contract {
+    // platform revenue is paid out pro rata through the bonus accumulator, the same way
+    // as a bonus, returns false when there is nobody to pay
+    pub(crate) fn internal_share_revenue(
+        &mut self,
+        sender_id: &AccountId,
+        amount: u128,
+    ) -> Result<(), StakeError> {
+        if self.platform_contract.as_ref() != Some(sender_id) {
+            return Err(StakeError::NotPlatform);
+        }
+        if self.total_staked == 0 {
+            return Err(StakeError::NothingStaked);
+        }
+        self.internal_distribute_to_stakers(amount);
+        self.total_revenue += amount;
+
+        let epoch_height = env::epoch_height();
+        let mut epoch = self
+            .revenue_epochs
+            .get(&epoch_height)
+            .unwrap_or(RevenueEpoch {
+                epoch_height: U64(epoch_height),
+                amount: U128(0),
+                deposits: 0,
+            });
+        epoch.amount.0 += amount;
+        epoch.deposits += 1;
+        self.revenue_epochs.insert(&epoch_height, &epoch);
+
+        StakeEvent::RevenueShared(vec![RevenueSharedData {
+            epoch_height: U64(epoch_height),
+            amount: U128(amount),
+            total_staked: U128(self.total_staked),
+        }])
+        .emit();
+        Ok(())
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn get_revenue_stats(&self) -> RevenueStats {
+        let epoch_height = env::epoch_height();
+        RevenueStats {
+            total_revenue: U128(self.total_revenue),
+            current_epoch: self
+                .revenue_epochs
+                .get(&epoch_height)
+                .unwrap_or(RevenueEpoch {
+                    epoch_height: U64(epoch_height),
+                    amount: U128(0),
+                    deposits: 0,
+                }),
+        }
+    }
+
+    // epochs in [from_epoch, from_epoch + limit) that received revenue, oldest first
+    pub fn get_revenue_epochs(&self, from_epoch: U64, limit: Option<u64>) -> Vec<RevenueEpoch> {
+        let limit = limit.unwrap_or(MAX_REVENUE_EPOCHS).min(MAX_REVENUE_EPOCHS);
+        (from_epoch.0..from_epoch.0.saturating_add(limit))
+            .filter_map(|epoch_height| self.revenue_epochs.get(&epoch_height))
+            .collect()
+    }
+}
@@ -0,0 +1,79 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, require, AccountId};
+
+use crate::events::{RewardAdjustedData, StakeEvent};
+use crate::*;
+
+pub const MAX_ADJUST_BATCH: usize = 50;
+pub const MAX_ADJUST_REASON_LEN: usize = 256;
+
+// sets what a position is owed in total as of now, accrual since the last touch included
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RewardAdjustment {
+    pub account_id: AccountId,
+    pub new_reward: U128,
+    pub reason: String,
+}
+
+impl Contract {
+    pub(crate) fn internal_adjust_rewards(&mut self, adjustments: Vec<RewardAdjustment>) {
+        require!(
+            adjustments.len() <= MAX_ADJUST_BATCH,
+            StakeError::BatchTooLarge
+        );
+        let mut events = vec![];
+        for adjustment in adjustments {
+            require!(
+                adjustment.reason.len() <= MAX_ADJUST_REASON_LEN,
+                StakeError::AdjustReasonTooLong
+            );
+            let mut stake_info = self
+                .internal_touch(&adjustment.account_id)
+                .unwrap_or_else(|| env::panic_str(&StakeError::NotStaked));
+            let old_reward = stake_info.reward;
+            let new_reward = adjustment.new_reward.0;
+            // the global estimate tracks the sum of what positions are owed
+            self.internal_update_global_accrual();
+            if new_reward < old_reward {
+                self.accrued_rewards = self.accrued_rewards.saturating_sub(old_reward - new_reward);
+            } else {
+                self.accrued_rewards += new_reward - old_reward;
+            }
+            stake_info.reward = new_reward;
+            self.internal_save_stake_info(&adjustment.account_id, &stake_info);
+            events.push(RewardAdjustedData {
+                account_id: adjustment.account_id,
+                old_reward: U128(old_reward),
+                new_reward: U128(new_reward),
+                reason: adjustment.reason,
+            });
+        }
+        if !events.is_empty() {
+            StakeEvent::RewardAdjusted(events).emit();
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // for remediating mis-accrued rewards, goes through AdminAction::AdjustRewards once an
+    // admin set is configured
+    pub fn adjust_reward(&mut self, account_id: AccountId, new_value: U128, reason: String) {
+        self.internal_assert_owner();
+        self.internal_assert_no_confirmations();
+        self.internal_adjust_rewards(vec![RewardAdjustment {
+            account_id,
+            new_reward: new_value,
+            reason,
+        }]);
+    }
+
+    pub fn bulk_adjust(&mut self, adjustments: Vec<RewardAdjustment>) {
+        self.internal_assert_owner();
+        self.internal_assert_no_confirmations();
+        self.internal_adjust_rewards(adjustments);
+    }
+}
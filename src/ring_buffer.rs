@@ -0,0 +1,67 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::Vector;
+use near_sdk::IntoStorageKey;
+
+// fixed capacity log, once full the oldest entry gets overwritten
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct RingBuffer<T> {
+    entries: Vector<T>,
+    capacity: u64,
+    next: u64,
+}
+
+impl<T> RingBuffer<T>
+where
+    T: BorshSerialize + BorshDeserialize,
+{
+    pub fn new<S: IntoStorageKey>(prefix: S, capacity: u64) -> Self {
+        RingBuffer {
+            entries: Vector::new(prefix),
+            capacity,
+            next: 0,
+        }
+    }
+
+    // returns the entry that got overwritten, if any
+    pub fn push(&mut self, entry: &T) -> Option<T> {
+        let evicted = if self.entries.len() < self.capacity {
+            self.entries.push(entry);
+            None
+        } else {
+            Some(self.entries.replace(self.next % self.capacity, entry))
+        };
+        self.next += 1;
+        evicted
+    }
+
+    // every entry ever pushed, overwritten ones included
+    pub fn pushed(&self) -> u64 {
+        self.next
+    }
+
+    pub fn len(&self) -> u64 {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // index 0 is the oldest entry still kept
+    pub fn get(&self, index: u64) -> Option<T> {
+        if index >= self.len() {
+            return None;
+        }
+        if self.len() < self.capacity {
+            self.entries.get(index)
+        } else {
+            self.entries.get((self.next + index) % self.capacity)
+        }
+    }
+
+    pub fn page(&self, from: u64, limit: u64) -> Vec<T> {
+        (from..self.len().min(from.saturating_add(limit)))
+            .filter_map(|index| self.get(index))
+            .collect()
+    }
+}
@@ -0,0 +1,56 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, require, AccountId};
+
+use crate::*;
+
+// narrow operational keys, the owner implicitly holds every role and alone grants them
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub enum Role {
+    // configuration setters
+    Admin,
+    Pauser,
+    // proposes and cancels slashes
+    Slasher,
+    // submits learner votes
+    VoterRelay,
+    // moves contract funds: sweeps, bonus, merkle campaigns, insurance payouts, deposits
+    Treasurer,
+    // legal holds on single accounts
+    Compliance,
+    // claims on behalf of advisors, the reward still goes to the advisor
+    ClaimRelayer,
+    // records course completion milestones, usually the course registry
+    MilestoneRecorder,
+}
+
+impl Contract {
+    pub(crate) fn internal_has_role(&self, account_id: &AccountId, role: Role) -> bool {
+        account_id == &self.owner_id || self.roles.contains(&(role, account_id.clone()))
+    }
+
+    pub(crate) fn internal_assert_role(&self, role: Role) {
+        require!(
+            self.internal_has_role(&env::predecessor_account_id(), role),
+            StakeError::MissingRole
+        );
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn grant_role(&mut self, account_id: AccountId, role: Role) -> bool {
+        self.internal_assert_owner();
+        self.roles.insert(&(role, account_id))
+    }
+
+    pub fn revoke_role(&mut self, account_id: AccountId, role: Role) -> bool {
+        self.internal_assert_owner();
+        self.roles.remove(&(role, account_id))
+    }
+
+    pub fn has_role(&self, account_id: AccountId, role: Role) -> bool {
+        self.internal_has_role(&account_id, role)
+    }
+}
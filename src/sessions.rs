@@ -0,0 +1,359 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, require, AccountId};
+
+use crate::math::{mul_div, BPS_DENOMINATOR};
+use crate::time::Timestamp;
+use crate::*;
+
+pub const MAX_SESSION_ID_LEN: usize = 64;
+
+// stake the platform earmarked as collateral for one mentorship session, it keeps
+// earning but cannot be unstaked until the session is released
+#[derive(BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Session {
+    pub session_id: String,
+    pub advisor_id: AccountId,
+    pub amount: U128,
+    pub locked_at: Timestamp,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub enum SessionOutcome {
+    Completed,
+    // proposes a slash of `slash_bps` of the collateral, it goes through the usual
+    // challenge period
+    Failed { slash_bps: u16 },
+}
+
+// what a failed session's penalty turned into
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub enum SessionPenalty {
+    // a slash proposal in its challenge period
+    Proposed { proposal_id: u64 },
+    // a slash the action admins still have to confirm
+    AwaitingConfirmation { action_id: U64 },
+    // the advisor already has a slash open, this one is proposed once that closes
+    Deferred { open_proposal_id: u64 },
+}
+
+// penalties of sessions that failed while their advisor had a slash open, merged into
+// one proposal for when it closes
+#[derive(BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DeferredSessionPenalty {
+    pub amount: U128,
+    // the sessions that fit in MAX_EVIDENCE_URI_LEN, the amount covers all of them
+    pub evidence_uri: String,
+}
+
+impl Contract {
+    fn internal_assert_platform(&self) {
+        require!(
+            self.platform_contract.as_ref() == Some(&env::predecessor_account_id()),
+            StakeError::NotPlatformCaller
+        );
+    }
+
+    // once an admin set exists the penalty waits for the same confirmations a slash from
+    // propose_action would. an advisor has one open slash at a time, so a penalty for
+    // someone who already has one waits for it instead of failing the release
+    fn internal_apply_session_penalty(
+        &mut self,
+        advisor: AccountId,
+        amount: u128,
+        evidence_uri: String,
+    ) -> SessionPenalty {
+        if !self.action_admins.is_empty() {
+            let action_id = self.internal_queue_action(AdminAction::Slash {
+                advisor,
+                amount: U128(amount),
+                evidence_uri,
+            });
+            return SessionPenalty::AwaitingConfirmation {
+                action_id: U64(action_id),
+            };
+        }
+        if let Some(open_proposal_id) = self.open_slashes.get(&advisor) {
+            let deferred = match self.deferred_session_penalties.get(&advisor) {
+                Some(mut deferred) => {
+                    deferred.amount = U128(deferred.amount.0 + amount);
+                    if deferred.evidence_uri.len() + 1 + evidence_uri.len() <= MAX_EVIDENCE_URI_LEN
+                    {
+                        deferred.evidence_uri =
+                            format!("{},{}", deferred.evidence_uri, evidence_uri);
+                    }
+                    deferred
+                }
+                None => DeferredSessionPenalty {
+                    amount: U128(amount),
+                    evidence_uri,
+                },
+            };
+            self.deferred_session_penalties.insert(&advisor, &deferred);
+            return SessionPenalty::Deferred { open_proposal_id };
+        }
+        SessionPenalty::Proposed {
+            proposal_id: self.internal_propose_slash(advisor, U128(amount), evidence_uri),
+        }
+    }
+
+    // called when the advisor's open slash closed, whichever way
+    pub(crate) fn internal_release_deferred_penalty(&mut self, advisor: &AccountId) {
+        let deferred = match self.deferred_session_penalties.remove(advisor) {
+            Some(deferred) => deferred,
+            None => return,
+        };
+        if self.internal_get_stake_info(advisor).is_some() {
+            self.internal_apply_session_penalty(
+                advisor.clone(),
+                deferred.amount.0,
+                deferred.evidence_uri,
+            );
+        }
+    }
+
+    pub(crate) fn internal_session_locked(&self, account_id: &AccountId) -> u128 {
+        self.session_locked.get(account_id).unwrap_or(0)
+    }
+
+    // stake that leaves the position has to come out of what no session, lien or lock holds
+    pub(crate) fn internal_assert_stake_free(
+        &self,
+        account_id: &AccountId,
+        stake_info: &StakeInfo,
+        amount: u128,
+    ) {
+        let remaining = stake_info.amount_staked - amount;
+        let session_locked = self.internal_session_locked(account_id);
+        require!(
+            remaining >= session_locked,
+            StakeError::StakeLockedInSession
+        );
+        require!(
+            remaining >= session_locked + self.internal_collateral_locked(account_id),
+            StakeError::StakeLockedAsCollateral
+        );
+        // a time lock may overlap the earmarks above, it holds the same stake
+        require!(
+            remaining >= self.internal_position_locked(account_id),
+            StakeError::StakeLocked
+        );
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn lock_for_session(&mut self, advisor: AccountId, amount: U128, session_id: String) {
+        self.internal_assert_platform();
+        require!(amount.0 > 0, StakeError::InvalidAmount);
+        require!(
+            session_id.len() <= MAX_SESSION_ID_LEN,
+            StakeError::InvalidSessionId
+        );
+        require!(
+            !self.sessions.contains_key(&session_id),
+            StakeError::SessionExists
+        );
+        let stake_info = self
+            .internal_get_stake_info(&advisor)
+            .unwrap_or_else(|| env::panic_str(&StakeError::AdvisorNotStaked));
+        let locked = self.internal_session_locked(&advisor);
+        require!(
+            stake_info.amount_staked
+                >= locked + self.internal_collateral_locked(&advisor) + amount.0,
+            StakeError::InsufficientStake
+        );
+        self.session_locked.insert(&advisor, &(locked + amount.0));
+        self.sessions.insert(
+            &session_id,
+            &Session {
+                session_id: session_id.clone(),
+                advisor_id: advisor,
+                amount,
+                locked_at: Self::now(),
+            },
+        );
+    }
+
+    // the collateral is freed whatever becomes of the penalty
+    pub fn release_session(
+        &mut self,
+        session_id: String,
+        outcome: SessionOutcome,
+    ) -> Option<SessionPenalty> {
+        self.internal_assert_platform();
+        let session = self
+            .sessions
+            .remove(&session_id)
+            .unwrap_or_else(|| env::panic_str(&StakeError::UnknownSession));
+        let locked = self.internal_session_locked(&session.advisor_id) - session.amount.0;
+        if locked == 0 {
+            self.session_locked.remove(&session.advisor_id);
+        } else {
+            self.session_locked.insert(&session.advisor_id, &locked);
+        }
+        match outcome {
+            SessionOutcome::Completed => None,
+            SessionOutcome::Failed { slash_bps } => {
+                require!(
+                    slash_bps as u128 <= BPS_DENOMINATOR,
+                    StakeError::InvalidSessionOutcome
+                );
+                let amount = mul_div(session.amount.0, slash_bps as u128, BPS_DENOMINATOR);
+                if amount == 0 {
+                    return None;
+                }
+                let evidence_uri = format!("session:{}", session.session_id);
+                Some(self.internal_apply_session_penalty(session.advisor_id, amount, evidence_uri))
+            }
+        }
+    }
+
+    pub fn get_session(&self, session_id: String) -> Option<Session> {
+        self.sessions.get(&session_id)
+    }
+
+    pub fn get_session_locked(&self, advisor_id: AccountId) -> U128 {
+        U128(self.internal_session_locked(&advisor_id))
+    }
+
+    pub fn get_deferred_session_penalty(
+        &self,
+        advisor_id: AccountId,
+    ) -> Option<DeferredSessionPenalty> {
+        self.deferred_session_penalties.get(&advisor_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    fn platform() -> AccountId {
+        account("platform")
+    }
+
+    fn setup_session(amount: u128) -> Contract {
+        let mut contract = setup();
+        call_as(&owner());
+        contract.set_platform_contract(Some(platform()));
+        stake(&mut contract, &account("alice"), 10 * ONE_TOKEN);
+        call_as(&platform());
+        contract.lock_for_session(account("alice"), U128(amount), "s1".to_string());
+        contract
+    }
+
+    fn fail(contract: &mut Contract) -> Option<SessionPenalty> {
+        call_as(&platform());
+        contract.release_session(
+            "s1".to_string(),
+            SessionOutcome::Failed { slash_bps: 5_000 },
+        )
+    }
+
+    #[test]
+    fn a_failed_session_proposes_a_slash() {
+        let mut contract = setup_session(4 * ONE_TOKEN);
+        match fail(&mut contract) {
+            Some(SessionPenalty::Proposed { proposal_id }) => {
+                let proposal = contract.get_slash_proposal(proposal_id).unwrap();
+                assert_eq!(proposal.amount.0, 2 * ONE_TOKEN);
+            }
+            _ => panic!("expected a slash proposal"),
+        }
+        assert_eq!(contract.get_session_locked(account("alice")).0, 0);
+    }
+
+    #[test]
+    fn with_admins_a_failed_session_waits_for_confirmations() {
+        let mut contract = setup_session(4 * ONE_TOKEN);
+        call_as(&owner());
+        contract.set_action_admins(vec![account("admin1"), account("admin2")], 2);
+
+        let action_id = match fail(&mut contract) {
+            Some(SessionPenalty::AwaitingConfirmation { action_id }) => action_id,
+            _ => panic!("expected a pending action"),
+        };
+        assert_eq!(contract.get_session_locked(account("alice")).0, 0);
+        assert!(contract.get_open_slash(account("alice")).is_none());
+        assert!(contract
+            .get_pending_action(action_id)
+            .unwrap()
+            .confirmations
+            .is_empty());
+
+        call_with_yocto(&account("admin1"));
+        let _ = contract.confirm_action(action_id);
+        assert!(contract.get_open_slash(account("alice")).is_none());
+        call_with_yocto(&account("admin2"));
+        let _ = contract.confirm_action(action_id);
+        let proposal = contract.get_open_slash(account("alice")).unwrap();
+        assert_eq!(proposal.amount.0, 2 * ONE_TOKEN);
+    }
+
+    fn open_slash(contract: &mut Contract) -> u64 {
+        call_as(&owner());
+        contract.propose_slash(
+            account("alice"),
+            U128(ONE_TOKEN),
+            "ipfs://misconduct".into(),
+        )
+    }
+
+    #[test]
+    fn a_failed_session_behind_an_open_slash_is_deferred() {
+        let mut contract = setup_session(4 * ONE_TOKEN);
+        call_as(&platform());
+        contract.lock_for_session(account("alice"), U128(2 * ONE_TOKEN), "s2".to_string());
+        let open = open_slash(&mut contract);
+
+        match fail(&mut contract) {
+            Some(SessionPenalty::Deferred { open_proposal_id }) => {
+                assert_eq!(open_proposal_id, open)
+            }
+            _ => panic!("expected a deferred penalty"),
+        }
+        call_as(&platform());
+        contract.release_session(
+            "s2".to_string(),
+            SessionOutcome::Failed { slash_bps: 10_000 },
+        );
+        assert_eq!(contract.get_session_locked(account("alice")).0, 0);
+        let deferred = contract
+            .get_deferred_session_penalty(account("alice"))
+            .unwrap();
+        assert_eq!(deferred.amount.0, 4 * ONE_TOKEN);
+        assert_eq!(deferred.evidence_uri, "session:s1,session:s2");
+        assert_eq!(contract.get_open_slash(account("alice")).unwrap().id, open);
+
+        call_as(&owner());
+        contract.cancel_slash(open);
+        let proposal = contract.get_open_slash(account("alice")).unwrap();
+        assert_ne!(proposal.id, open);
+        assert_eq!(proposal.amount.0, 4 * ONE_TOKEN);
+        assert_eq!(proposal.evidence_uri, "session:s1,session:s2");
+        assert!(contract
+            .get_deferred_session_penalty(account("alice"))
+            .is_none());
+    }
+
+    #[test]
+    fn a_deferred_penalty_follows_an_executed_slash() {
+        let mut contract = setup_session(4 * ONE_TOKEN);
+        let open = open_slash(&mut contract);
+        fail(&mut contract);
+
+        advance(DEFAULT_CHALLENGE_PERIOD);
+        call_as(&account("anyone"));
+        assert_eq!(contract.finalize_slash(open).0, ONE_TOKEN);
+        let proposal = contract.get_open_slash(account("alice")).unwrap();
+        assert_eq!(proposal.amount.0, 2 * ONE_TOKEN);
+        assert_eq!(proposal.evidence_uri, "session:s1");
+    }
+}
@@ -0,0 +1,337 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::Serialize;
+use near_sdk::{env, near_bindgen, require, AccountId};
+
+use crate::events::{SlashData, StakeEvent};
+use crate::time::{Duration, Timestamp};
+use crate::*;
+
+pub const DEFAULT_CHALLENGE_PERIOD: Duration = Duration::from_secs(3 * 24 * 60 * 60);
+// the advisor always gets a day to contest
+pub const MIN_CHALLENGE_PERIOD: Duration = Duration::from_secs(24 * 60 * 60);
+pub const MAX_EVIDENCE_URI_LEN: usize = 256;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub enum SlashStatus {
+    // executable by anyone once the challenge window passed
+    Pending,
+    // waiting for the arbiter
+    Contested,
+    Rejected,
+    Executed,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SlashProposal {
+    pub id: u64,
+    // the group's admin for a group slash, it contests on the members' behalf
+    pub advisor_id: AccountId,
+    pub group_id: Option<u64>,
+    pub amount: U128,
+    pub evidence_uri: String,
+    pub proposed_at: Timestamp,
+    pub challenge_ends_at: Timestamp,
+    pub status: SlashStatus,
+}
+
+impl Contract {
+    fn internal_get_slash_proposal(&self, proposal_id: u64) -> SlashProposal {
+        self.slash_proposals
+            .get(&proposal_id)
+            .unwrap_or_else(|| env::panic_str(&StakeError::UnknownSlashProposal))
+    }
+
+    pub(crate) fn internal_assert_no_open_slash(&self, account_id: &AccountId) {
+        require!(
+            !self.open_slashes.contains_key(account_id),
+            StakeError::OpenSlashProposal
+        );
+    }
+
+    fn internal_close_slash(&mut self, mut proposal: SlashProposal, status: SlashStatus) {
+        proposal.status = status;
+        match proposal.group_id {
+            Some(group_id) => self.internal_set_group_open_slash(group_id, None),
+            None => {
+                self.open_slashes.remove(&proposal.advisor_id);
+                self.internal_sync_accrual_pause(&proposal.advisor_id);
+            }
+        }
+        self.slash_proposals.insert(&proposal.id, &proposal);
+        if proposal.group_id.is_none() {
+            self.internal_release_deferred_penalty(&proposal.advisor_id);
+        }
+    }
+
+    // slashed stake moves into the reward pool, or is burned with burn_penalties on
+    fn internal_execute_slash(&mut self, proposal: SlashProposal) -> u128 {
+        let amount = match proposal.group_id {
+            Some(group_id) => self.internal_slash_group(group_id, proposal.amount.0),
+            None => {
+                let amount = self.internal_slash_advisor(&proposal.advisor_id, proposal.amount.0);
+                self.internal_open_insurance_claim(
+                    &proposal.advisor_id,
+                    proposal.id,
+                    proposal.proposed_at,
+                    amount,
+                );
+                amount
+            }
+        };
+        StakeEvent::SlashExecuted(vec![SlashData {
+            proposal_id: proposal.id,
+            advisor_id: proposal.advisor_id.clone(),
+            amount: U128(amount),
+        }])
+        .emit();
+        self.internal_close_slash(proposal, SlashStatus::Executed);
+        self.internal_process_queue(MAX_QUEUE_ACTIVATIONS);
+        self.internal_maybe_snapshot();
+        amount
+    }
+
+    fn internal_slash_advisor(&mut self, advisor_id: &AccountId, amount: u128) -> u128 {
+        let mut stake_info = self
+            .internal_touch(advisor_id)
+            .unwrap_or_else(|| env::panic_str(&StakeError::AdvisorNotStaked));
+        let from_stake = amount.min(stake_info.amount_staked);
+        self.internal_update_reward_rate(
+            stake_info.amount_staked,
+            stake_info.boosted(stake_info.apr),
+            stake_info.amount_staked - from_stake,
+            stake_info.boosted(stake_info.apr),
+        );
+        stake_info.amount_staked -= from_stake;
+        // delegating or undelegating does not put stake out of a slash's reach
+        let from_delegation = self.internal_slash_delegation(advisor_id, amount - from_stake);
+        stake_info.delegated -= from_delegation;
+        let amount = from_stake + from_delegation;
+        stake_info.lifetime_unstaked += amount;
+        stake_info.sync_streak(Self::now());
+        self.internal_shrink_course_stakes(advisor_id, stake_info.amount_staked);
+        self.internal_shrink_positions(advisor_id, stake_info.amount_staked);
+        Self::internal_set_position_state(&mut stake_info, PositionState::Slashed, Self::now());
+        self.total_staked -= amount;
+        if !self.internal_collect_penalty(amount) {
+            self.reward_pool += amount;
+        }
+        self.total_slashed += amount;
+        self.internal_save_stake_info(advisor_id, &stake_info);
+        self.internal_record_activity(
+            advisor_id,
+            Activity::Slashed {
+                amount: U128(amount),
+            },
+        );
+        self.internal_notify_hooks(HookEvent::Slash, advisor_id, amount);
+        amount
+    }
+
+    pub(crate) fn internal_propose_slash(
+        &mut self,
+        advisor: AccountId,
+        amount: U128,
+        evidence_uri: String,
+    ) -> u64 {
+        require!(
+            self.internal_get_stake_info(&advisor).is_some(),
+            StakeError::AdvisorNotStaked
+        );
+        self.internal_assert_no_open_slash(&advisor);
+        let proposal_id =
+            self.internal_open_slash_proposal(advisor.clone(), None, amount, evidence_uri);
+        self.open_slashes.insert(&advisor, &proposal_id);
+        self.internal_sync_accrual_pause(&advisor);
+        proposal_id
+    }
+
+    fn internal_open_slash_proposal(
+        &mut self,
+        advisor: AccountId,
+        group_id: Option<u64>,
+        amount: U128,
+        evidence_uri: String,
+    ) -> u64 {
+        require!(amount.0 > 0, StakeError::InvalidAmount);
+        require!(
+            evidence_uri.len() <= MAX_EVIDENCE_URI_LEN,
+            StakeError::EvidenceUriTooLong
+        );
+
+        self.slash_proposal_count += 1;
+        let now = Self::now();
+        let proposal = SlashProposal {
+            id: self.slash_proposal_count,
+            advisor_id: advisor.clone(),
+            group_id,
+            amount,
+            evidence_uri,
+            proposed_at: now,
+            challenge_ends_at: now + self.slash_challenge_period,
+            status: SlashStatus::Pending,
+        };
+        self.slash_proposals.insert(&proposal.id, &proposal);
+        StakeEvent::SlashProposed(vec![SlashData {
+            proposal_id: proposal.id,
+            advisor_id: advisor,
+            amount,
+        }])
+        .emit();
+        proposal.id
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn propose_slash(&mut self, advisor: AccountId, amount: U128, evidence_uri: String) -> u64 {
+        self.internal_assert_role(Role::Slasher);
+        self.internal_assert_no_confirmations();
+        self.internal_propose_slash(advisor, amount, evidence_uri)
+    }
+
+    // lands on every member pro-rata through the group's share price, members cannot
+    // leave while it is open
+    pub fn propose_group_slash(
+        &mut self,
+        group_id: u64,
+        amount: U128,
+        evidence_uri: String,
+    ) -> u64 {
+        self.internal_assert_role(Role::Slasher);
+        self.internal_assert_no_confirmations();
+        let group = self.internal_get_group(group_id);
+        require!(group.open_slash.is_none(), StakeError::OpenSlashProposal);
+        let proposal_id =
+            self.internal_open_slash_proposal(group.admin_id, Some(group_id), amount, evidence_uri);
+        self.internal_set_group_open_slash(group_id, Some(proposal_id));
+        proposal_id
+    }
+
+    // the advisor disputes a pending proposal, the arbiter decides from then on
+    pub fn contest(&mut self, proposal_id: u64) {
+        let mut proposal = self.internal_get_slash_proposal(proposal_id);
+        require!(
+            proposal.advisor_id == env::predecessor_account_id(),
+            StakeError::NotSlashedAdvisor
+        );
+        require!(
+            proposal.status == SlashStatus::Pending,
+            StakeError::ProposalNotPending
+        );
+        require!(
+            Self::now() < proposal.challenge_ends_at,
+            StakeError::ChallengeWindowClosed
+        );
+        proposal.status = SlashStatus::Contested;
+        self.slash_proposals.insert(&proposal_id, &proposal);
+    }
+
+    pub fn resolve_slash(&mut self, proposal_id: u64, uphold: bool) -> U128 {
+        require!(
+            self.arbiter_id.as_ref() == Some(&env::predecessor_account_id()),
+            StakeError::NotArbiter
+        );
+        let proposal = self.internal_get_slash_proposal(proposal_id);
+        require!(
+            proposal.status == SlashStatus::Contested,
+            StakeError::ProposalNotContested
+        );
+        if uphold {
+            U128(self.internal_execute_slash(proposal))
+        } else {
+            self.internal_close_slash(proposal, SlashStatus::Rejected);
+            U128(0)
+        }
+    }
+
+    pub fn finalize_slash(&mut self, proposal_id: u64) -> U128 {
+        let proposal = self.internal_get_slash_proposal(proposal_id);
+        require!(
+            proposal.status == SlashStatus::Pending,
+            StakeError::ProposalNotPending
+        );
+        require!(
+            Self::now() >= proposal.challenge_ends_at,
+            StakeError::ChallengeWindowOpen
+        );
+        U128(self.internal_execute_slash(proposal))
+    }
+
+    // owner can withdraw a proposal that has not been executed
+    pub fn cancel_slash(&mut self, proposal_id: u64) {
+        self.internal_assert_role(Role::Slasher);
+        let proposal = self.internal_get_slash_proposal(proposal_id);
+        require!(
+            matches!(
+                proposal.status,
+                SlashStatus::Pending | SlashStatus::Contested
+            ),
+            StakeError::ProposalClosed
+        );
+        self.internal_close_slash(proposal, SlashStatus::Rejected);
+    }
+
+    pub fn set_arbiter(&mut self, arbiter_id: Option<AccountId>) {
+        self.internal_assert_role(Role::Admin);
+        self.internal_assert_no_confirmations();
+        self.arbiter_id = arbiter_id;
+    }
+
+    pub fn set_slash_challenge_period(&mut self, challenge_period: U64) {
+        self.internal_assert_role(Role::Admin);
+        let challenge_period = Duration::from_nanos(challenge_period.0);
+        require!(
+            challenge_period >= MIN_CHALLENGE_PERIOD,
+            StakeError::InvalidChallengePeriod
+        );
+        self.internal_record_config_change(
+            "slash_challenge_period",
+            self.slash_challenge_period,
+            challenge_period,
+        );
+        self.slash_challenge_period = challenge_period;
+    }
+
+    pub fn get_slash_proposal(&self, proposal_id: u64) -> Option<SlashProposal> {
+        self.slash_proposals.get(&proposal_id)
+    }
+
+    pub fn get_open_slash(&self, account_id: AccountId) -> Option<SlashProposal> {
+        self.open_slashes
+            .get(&account_id)
+            .and_then(|proposal_id| self.slash_proposals.get(&proposal_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    #[should_panic(expected = "ERR_CONFIRMATION_REQUIRED")]
+    fn set_arbiter_needs_confirmations_once_admins_exist() {
+        let mut contract = setup();
+        call_as(&owner());
+        contract.set_action_admins(vec![account("admin1"), account("admin2")], 2);
+        contract.set_arbiter(Some(account("arbiter")));
+    }
+
+    #[test]
+    fn the_admins_set_the_arbiter() {
+        let mut contract = setup();
+        call_as(&owner());
+        contract.set_action_admins(vec![account("admin1"), account("admin2")], 2);
+        call_with_yocto(&account("admin1"));
+        let action_id = contract.propose_action(AdminAction::SetArbiter {
+            arbiter_id: Some(account("arbiter")),
+        });
+        assert_eq!(contract.arbiter_id, None);
+        call_with_yocto(&account("admin2"));
+        let _ = contract.confirm_action(action_id);
+        assert_eq!(contract.arbiter_id, Some(account("arbiter")));
+    }
+}
@@ -0,0 +1,51 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{U128, U64};
+use near_sdk::near_bindgen;
+use near_sdk::serde::Serialize;
+
+use crate::time::{Duration, Timestamp};
+use crate::*;
+
+pub const MAX_SNAPSHOTS: u64 = 720;
+pub const DEFAULT_SNAPSHOT_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Snapshot {
+    pub timestamp: Timestamp,
+    pub total_staked: U128,
+    pub total_stakers: U128,
+    pub reward_pool: U128,
+}
+
+impl Contract {
+    // called at the end of mutating methods, writes at most once per interval
+    pub(crate) fn internal_maybe_snapshot(&mut self) {
+        let now = Self::now();
+        self.last_mutation_at = now;
+        if !self.snapshots.is_empty() && now < self.last_snapshot_at + self.snapshot_interval {
+            return;
+        }
+        self.snapshots.push(&Snapshot {
+            timestamp: now,
+            total_staked: U128(self.total_staked),
+            total_stakers: U128(self.total_stakers),
+            reward_pool: U128(self.reward_pool),
+        });
+        self.last_snapshot_at = now;
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // oldest first
+    pub fn get_snapshots(&self, from: Option<u64>, limit: Option<u64>) -> Vec<Snapshot> {
+        self.snapshots
+            .page(from.unwrap_or(0), limit.unwrap_or(DEFAULT_PAGE_LIMIT))
+    }
+
+    pub fn set_snapshot_interval(&mut self, snapshot_interval: U64) {
+        self.internal_assert_role(Role::Admin);
+        self.snapshot_interval = Duration::from_nanos(snapshot_interval.0);
+    }
+}
@@ -0,0 +1,129 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::Serialize;
+use near_sdk::{near_bindgen, require, AccountId};
+
+use crate::time::{Duration, Timestamp};
+use crate::*;
+
+pub const DEFAULT_SPONSORSHIP_DURATION: Duration = Duration::from_secs(180 * 24 * 60 * 60);
+pub const MAX_EXPIRE_BATCH: usize = 50;
+
+// enrollment funding earmarked for one advisor, their claims draw on it before the
+// reward pool. every top up pushes the expiry out, what is left at expiry joins the
+// reward pool
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SponsoredBudget {
+    pub amount: U128,
+    pub total_sponsored: U128,
+    pub total_used: U128,
+    pub expires_at: Timestamp,
+}
+
+impl Contract {
+    pub(crate) fn internal_sponsor_advisor(
+        &mut self,
+        sender_id: &AccountId,
+        advisor_id: &AccountId,
+        amount: u128,
+    ) -> Result<(), StakeError> {
+        if !self.internal_has_role(sender_id, Role::Treasurer) {
+            return Err(StakeError::MissingRole);
+        }
+        if self.internal_get_stake_info(advisor_id).is_none() {
+            return Err(StakeError::AdvisorNotStaked);
+        }
+        self.internal_expire_budget(advisor_id);
+        let mut budget = self
+            .sponsored_budgets
+            .get(advisor_id)
+            .unwrap_or(SponsoredBudget {
+                amount: U128(0),
+                total_sponsored: U128(0),
+                total_used: U128(0),
+                expires_at: Self::now(),
+            });
+        budget.amount.0 += amount;
+        budget.total_sponsored.0 += amount;
+        budget.expires_at = Self::now() + self.sponsorship_duration;
+        self.sponsored_budgets.insert(advisor_id, &budget);
+        self.total_sponsored += amount;
+        self.total_reward_funded += amount;
+        Ok(())
+    }
+
+    // moves an expired budget's leftover into the reward pool, returns the amount moved
+    fn internal_expire_budget(&mut self, advisor_id: &AccountId) -> u128 {
+        let mut budget = match self.sponsored_budgets.get(advisor_id) {
+            Some(budget) if Self::now() >= budget.expires_at && budget.amount.0 > 0 => budget,
+            _ => return 0,
+        };
+        let amount = budget.amount.0;
+        budget.amount = U128(0);
+        self.sponsored_budgets.insert(advisor_id, &budget);
+        self.total_sponsored -= amount;
+        self.reward_pool += amount;
+        amount
+    }
+
+    // internal_pay_reward drawing on the advisor's budget first
+    pub(crate) fn internal_pay_sponsored_reward(&mut self, advisor_id: &AccountId, amount: u128) {
+        self.internal_expire_budget(advisor_id);
+        let mut budget = match self.sponsored_budgets.get(advisor_id) {
+            Some(budget) if budget.amount.0 > 0 => budget,
+            _ => return self.internal_pay_reward(amount),
+        };
+        let sponsored = budget.amount.0.min(amount);
+        let rest = amount - sponsored;
+        require!(rest <= self.reward_pool, StakeError::InsufficientRewardPool);
+        budget.amount.0 -= sponsored;
+        budget.total_used.0 += sponsored;
+        self.sponsored_budgets.insert(advisor_id, &budget);
+        self.total_sponsored -= sponsored;
+        // the sponsored part goes through the pool so the accrual books stay the same
+        self.reward_pool += sponsored;
+        self.internal_pay_reward(amount);
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // only budgets topped up after the change expire on the new duration
+    pub fn set_sponsorship_duration(&mut self, duration: U64) {
+        self.internal_assert_role(Role::Admin);
+        let duration = Duration::from_nanos(duration.0);
+        require!(
+            duration.as_nanos() > 0,
+            StakeError::InvalidSponsorshipDuration
+        );
+        self.internal_record_config_change(
+            "sponsorship_duration",
+            self.sponsorship_duration,
+            duration,
+        );
+        self.sponsorship_duration = duration;
+    }
+
+    // permissionless, returns what went back to the reward pool
+    pub fn expire_sponsored_budgets(&mut self, advisor_ids: Vec<AccountId>) -> U128 {
+        require!(
+            advisor_ids.len() <= MAX_EXPIRE_BATCH,
+            StakeError::BatchTooLarge
+        );
+        U128(
+            advisor_ids
+                .iter()
+                .map(|advisor_id| self.internal_expire_budget(advisor_id))
+                .sum(),
+        )
+    }
+
+    pub fn get_sponsored_budget(&self, advisor_id: AccountId) -> Option<SponsoredBudget> {
+        self.sponsored_budgets.get(&advisor_id)
+    }
+
+    pub fn get_total_sponsored(&self) -> U128 {
+        U128(self.total_sponsored)
+    }
+}
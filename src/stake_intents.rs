@@ -0,0 +1,134 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::Serialize;
+use near_sdk::{env, near_bindgen, require, AccountId};
+
+use crate::time::{Duration, Timestamp};
+use crate::*;
+
+pub const MAX_INTENT_BATCH: usize = 50;
+
+// recurring top-up paid out of tokens the account escrowed with a `fund_intent` transfer,
+// tokens can't be pulled from an account so the keeper only moves escrow into stake
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StakeIntent {
+    pub amount_per_period: U128,
+    pub period: Duration,
+    pub next_at: Timestamp,
+    pub balance: U128,
+}
+
+impl Contract {
+    pub(crate) fn internal_fund_intent(
+        &mut self,
+        account_id: &AccountId,
+        amount: u128,
+    ) -> Result<(), StakeError> {
+        let mut intent = self
+            .stake_intents
+            .get(account_id)
+            .ok_or(StakeError::NoStakeIntent)?;
+        intent.balance = U128(intent.balance.0 + amount);
+        self.stake_intents.insert(account_id, &intent);
+        self.total_intent_balance += amount;
+        Ok(())
+    }
+
+    // stakes one period if it is due and funded, returns the amount staked
+    fn internal_execute_intent(&mut self, account_id: AccountId) -> u128 {
+        let mut intent = match self.stake_intents.get(&account_id) {
+            Some(intent) => intent,
+            None => return 0,
+        };
+        let amount = intent.amount_per_period.0;
+        let now = Self::now();
+        if now < intent.next_at
+            || intent.balance.0 < amount
+            || amount > self.internal_open_capacity()
+            || !self.internal_is_permitted(&account_id)
+        {
+            return 0;
+        }
+        intent.balance = U128(intent.balance.0 - amount);
+        intent.next_at = now + intent.period;
+        self.stake_intents.insert(&account_id, &intent);
+        self.total_intent_balance -= amount;
+        self.internal_stake(account_id, amount);
+        amount
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // replaces an existing intent, its escrow carries over
+    pub fn create_stake_intent(&mut self, amount_per_period: U128, period: U64) -> StakeIntent {
+        let account_id = env::predecessor_account_id();
+        require!(amount_per_period.0 > 0, StakeError::InvalidAmount);
+        require!(period.0 > 0, StakeError::InvalidIntentPeriod);
+        require!(
+            self.internal_get_stake_info(&account_id).is_some(),
+            StakeError::NotStaked
+        );
+        let balance = self
+            .stake_intents
+            .get(&account_id)
+            .map_or(U128(0), |intent| intent.balance);
+        let intent = StakeIntent {
+            amount_per_period,
+            period: Duration::from_nanos(period.0),
+            next_at: Self::now(),
+            balance,
+        };
+        self.stake_intents.insert(&account_id, &intent);
+        intent
+    }
+
+    // the unused escrow becomes withdrawable
+    #[payable]
+    pub fn cancel_stake_intent(&mut self) -> U128 {
+        self.internal_assert_deposit("cancel_stake_intent");
+        let account_id = env::predecessor_account_id();
+        let intent = self
+            .stake_intents
+            .remove(&account_id)
+            .unwrap_or_else(|| env::panic_str(&StakeError::NoStakeIntent));
+        self.internal_migrate_legacy(&account_id);
+        let mut stake_info = self
+            .internal_load_stake_info(&account_id)
+            .unwrap_or_else(|| env::panic_str(&StakeError::NotStaked));
+        self.total_intent_balance -= intent.balance.0;
+        self.internal_credit_withdrawable(&mut stake_info, intent.balance.0);
+        self.internal_save_stake_info(&account_id, &stake_info);
+        intent.balance
+    }
+
+    pub fn execute_intents(&mut self, accounts: Vec<AccountId>) -> Vec<U128> {
+        require!(
+            self.keeper_id.as_ref() == Some(&env::predecessor_account_id()),
+            StakeError::NotKeeper
+        );
+        self.internal_assert_not_paused();
+        require!(
+            accounts.len() <= MAX_INTENT_BATCH,
+            StakeError::BatchTooLarge
+        );
+        let staked = self.internal_with_hooks_deferred(|contract| {
+            accounts
+                .into_iter()
+                .map(|account_id| U128(contract.internal_execute_intent(account_id)))
+                .collect()
+        });
+        self.internal_maybe_snapshot();
+        staked
+    }
+
+    pub fn set_keeper(&mut self, keeper_id: Option<AccountId>) {
+        self.internal_assert_role(Role::Admin);
+        self.keeper_id = keeper_id;
+    }
+
+    pub fn get_stake_intent(&self, account_id: AccountId) -> Option<StakeIntent> {
+        self.stake_intents.get(&account_id)
+    }
+}
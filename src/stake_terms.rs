@@ -0,0 +1,171 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::AccountId;
+
+use crate::time::Timestamp;
+use crate::*;
+
+// the part of a position that only changes on open, compounding toggles and booster
+// registration. kept in its own map so the accrual fields written on every call don't
+// drag it along
+#[derive(BorshDeserialize, BorshSerialize, Clone, PartialEq, Default)]
+pub struct StakeTerms {
+    pub(crate) opened_at: Timestamp,
+    // apr the position opened at, the reputation formula builds on it
+    pub(crate) base_apr: u128,
+    pub(crate) compounding: bool,
+    pub(crate) booster: Option<Booster>,
+    pub(crate) first_staked_at: Timestamp,
+    // None while nothing is staked
+    pub(crate) streak_started_at: Option<Timestamp>,
+    pub(crate) governance: Option<GovernanceBoost>,
+    // set while the position pays the insurance premium
+    pub(crate) insured_since: Option<Timestamp>,
+}
+
+impl Contract {
+    pub(crate) fn internal_load_stake_info(&self, account_id: &AccountId) -> Option<StakeInfo> {
+        let mut stake_info = self.stake_info.get(account_id)?;
+        stake_info.terms = self.stake_terms.get(account_id).unwrap_or_default();
+        stake_info.stored_terms = Some(stake_info.terms.clone());
+        Some(stake_info)
+    }
+
+    // the accrual record alone for scans that only read amounts, apr and votes. the terms
+    // are left at their defaults, so what this returns must never be saved back
+    pub(crate) fn internal_peek_stake_info(&self, account_id: &AccountId) -> Option<StakeInfo> {
+        self.stake_info.get(account_id)
+    }
+
+    // the terms record is only rewritten when something in it changed
+    pub(crate) fn internal_store_stake_terms(
+        &mut self,
+        account_id: &AccountId,
+        stake_info: &StakeInfo,
+    ) {
+        if stake_info.stored_terms.as_ref() != Some(&stake_info.terms) {
+            self.stake_terms.insert(account_id, &stake_info.terms);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::env;
+
+    use super::*;
+    use crate::test_utils::*;
+
+    // the record as it was stored before the split, accrual fields and terms together
+    const COMBINED_PREFIX: &[u8] = b"combined";
+
+    fn combined_key(account_id: &AccountId) -> Vec<u8> {
+        [COMBINED_PREFIX, account_id.as_bytes()].concat()
+    }
+
+    fn combined_bytes(stake_info: &StakeInfo) -> Vec<u8> {
+        (stake_info, &stake_info.terms).try_to_vec().unwrap()
+    }
+
+    fn measured(call: impl FnOnce()) -> u64 {
+        call_as(&relay());
+        let before = env::used_gas().0;
+        call();
+        env::used_gas().0 - before
+    }
+
+    // a booster and a governance tier, the terms a boosted position carries
+    fn boosted_position(contract: &mut Contract, account_id: &AccountId) -> StakeInfo {
+        stake(contract, account_id, 1_000 * ONE_TOKEN);
+        let mut stake_info = contract.internal_load_stake_info(account_id).unwrap();
+        stake_info.terms.booster = Some(Booster {
+            nft_contract: account("booster-collection"),
+            token_id: "booster-token-0001".to_string(),
+            boost_bps: 500,
+            verified_at: Contract::now(),
+        });
+        stake_info.terms.governance = Some(GovernanceBoost {
+            balance: U128(ONE_TOKEN),
+            bonus_bps: 200,
+            verified_at: Contract::now(),
+        });
+        contract.internal_save_stake_info(account_id, &stake_info);
+        env::storage_write(&combined_key(account_id), &combined_bytes(&stake_info));
+        contract.internal_load_stake_info(account_id).unwrap()
+    }
+
+    fn split_write(contract: &mut Contract, account_id: &AccountId, stake_info: &StakeInfo) {
+        contract.stake_info.insert(account_id, stake_info);
+        contract.internal_store_stake_terms(account_id, stake_info);
+    }
+
+    #[test]
+    fn an_accrual_write_skips_the_terms() {
+        let mut contract = setup();
+        let alice = account("alice");
+        let stake_info = boosted_position(&mut contract, &alice);
+        assert_eq!(stake_info.terms.try_to_vec().unwrap().len(), 134);
+
+        let split = measured(|| split_write(&mut contract, &alice, &stake_info));
+        let combined = measured(|| {
+            env::storage_write(&combined_key(&alice), &combined_bytes(&stake_info));
+        });
+        // the 134 bytes of terms are neither written nor evicted, about 3 Ggas
+        assert!(split < combined, "split {} combined {}", split, combined);
+    }
+
+    #[test]
+    fn a_scan_reads_only_the_accrual_record() {
+        let mut contract = setup();
+        let alice = account("alice");
+        boosted_position(&mut contract, &alice);
+
+        let peek = measured(|| {
+            contract.internal_peek_stake_info(&alice);
+        });
+        let combined = measured(|| {
+            env::storage_read(&combined_key(&alice));
+        });
+        assert!(peek < combined, "peek {} combined {}", peek, combined);
+    }
+
+    // a touch loads both records, so the split costs one storage read more than the write
+    // saves. about 70 Ggas on a touch of about 5 TGas, most of which goes to the indexes
+    #[test]
+    fn a_touch_pays_one_extra_read() {
+        let mut contract = setup();
+        let alice = account("alice");
+        let stake_info = boosted_position(&mut contract, &alice);
+
+        let split = measured(|| {
+            let stake_info = contract.internal_load_stake_info(&alice).unwrap();
+            split_write(&mut contract, &alice, &stake_info);
+        });
+        let combined = measured(|| {
+            env::storage_read(&combined_key(&alice));
+            env::storage_write(&combined_key(&alice), &combined_bytes(&stake_info));
+        });
+        assert!(split > combined);
+        assert!(
+            split - combined < 100_000_000_000,
+            "split {} combined {}",
+            split,
+            combined
+        );
+    }
+
+    #[test]
+    fn a_terms_change_is_written() {
+        let mut contract = setup();
+        let alice = account("alice");
+        let mut stake_info = boosted_position(&mut contract, &alice);
+        stake_info.terms.compounding = true;
+        contract.internal_save_stake_info(&alice, &stake_info);
+        assert!(
+            contract
+                .internal_load_stake_info(&alice)
+                .unwrap()
+                .terms
+                .compounding
+        );
+    }
+}
@@ -0,0 +1,70 @@
+use near_sdk::borsh::BorshSerialize;
+use near_sdk::json_types::{Base64VecU8, U128, U64};
+use near_sdk::serde::Serialize;
+use near_sdk::{env, near_bindgen};
+
+use crate::*;
+
+pub const MAX_EXPORT_PAGE: u64 = 50;
+
+// each record is borsh of (account_id, stake_info, stake_terms) as stored, the checksum
+// is sha256 over the page's records in order
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StateExportPage {
+    pub from_index: U64,
+    pub records: Vec<Base64VecU8>,
+    pub next_index: Option<U64>,
+    pub checksum: Base64VecU8,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StateChecksum {
+    // sha256 of the root state, everything outside the collections
+    pub root_hash: Base64VecU8,
+    pub indexed_stakers: U64,
+    pub total_staked: U128,
+    pub block_height: U64,
+}
+
+#[near_bindgen]
+impl Contract {
+    // a view cannot tell who calls it, and contract state is public anyway, so any
+    // auditor can page through. the staker index only reorders when records are pruned
+    pub fn export_state(&self, from_index: Option<U64>, limit: Option<U64>) -> StateExportPage {
+        let from = from_index.map_or(0, |index| index.0);
+        let limit = limit.map_or(MAX_EXPORT_PAGE, |limit| limit.0.min(MAX_EXPORT_PAGE));
+        let staker_ids = self.staker_ids.as_vector();
+        let end = staker_ids.len().min(from.saturating_add(limit));
+
+        let mut records = vec![];
+        let mut page = vec![];
+        for index in from..end {
+            let account_id = staker_ids.get(index).unwrap();
+            let stake_info = match self.stake_info.get(&account_id) {
+                Some(stake_info) => stake_info,
+                None => continue,
+            };
+            let terms = self.stake_terms.get(&account_id).unwrap_or_default();
+            let record = (account_id, stake_info, terms).try_to_vec().unwrap();
+            page.extend_from_slice(&record);
+            records.push(Base64VecU8(record));
+        }
+        StateExportPage {
+            from_index: U64(from),
+            records,
+            next_index: (end < staker_ids.len()).then_some(U64(end)),
+            checksum: Base64VecU8(env::sha256(&page)),
+        }
+    }
+
+    pub fn get_state_checksum(&self) -> StateChecksum {
+        StateChecksum {
+            root_hash: Base64VecU8(env::sha256(&self.try_to_vec().unwrap())),
+            indexed_stakers: U64(self.staker_ids.len()),
+            total_staked: U128(self.total_staked),
+            block_height: U64(env::block_height()),
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use near_sdk::borsh::BorshSerialize;
+use near_sdk::json_types::{Base64VecU8, U64};
+use near_sdk::serde::Serialize;
+use near_sdk::{env, near_bindgen, require, AccountId, CryptoHash};
+
+use crate::*;
+
+// room for 2^20 positions
+pub const STATE_TREE_DEPTH: u8 = 20;
+pub const MAX_STATE_ROOT_REBUILD: u64 = 50;
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StateRootView {
+    pub root: Base64VecU8,
+    pub leaves: U64,
+    pub depth: u8,
+}
+
+// siblings from the leaf up, a sibling goes on the left when the path bit at its level is 1
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StateProof {
+    pub leaf_index: U64,
+    pub leaf: Base64VecU8,
+    pub siblings: Vec<Base64VecU8>,
+    pub root: Base64VecU8,
+}
+
+fn hash_pair(left: &CryptoHash, right: &CryptoHash) -> CryptoHash {
+    let mut bytes = left.to_vec();
+    bytes.extend_from_slice(right);
+    env::sha256_array(&bytes)
+}
+
+// roots of the empty subtree at every level, an empty leaf is all zeroes
+fn zero_hashes() -> Vec<CryptoHash> {
+    let mut zeroes = vec![[0; 32]];
+    for level in 0..STATE_TREE_DEPTH as usize {
+        zeroes.push(hash_pair(&zeroes[level], &zeroes[level]));
+    }
+    zeroes
+}
+
+pub(crate) fn zero_state_root() -> CryptoHash {
+    zero_hashes()[STATE_TREE_DEPTH as usize]
+}
+
+// sha256 of the same bytes export_state hands out for the record
+pub(crate) fn state_leaf(account_id: &AccountId, stake_info: &StakeInfo) -> CryptoHash {
+    let mut bytes = account_id.try_to_vec().unwrap();
+    bytes.extend(stake_info.try_to_vec().unwrap());
+    bytes.extend(stake_info.terms.try_to_vec().unwrap());
+    env::sha256_array(&bytes)
+}
+
+// a sparse merkle tree over the positions, each holds a leaf slot from its first save
+// until it is pruned. only non-empty nodes are stored
+impl Contract {
+    fn internal_state_node(&self, level: u8, index: u64, zeroes: &[CryptoHash]) -> CryptoHash {
+        self.state_nodes
+            .get(&(level, index))
+            .unwrap_or(zeroes[level as usize])
+    }
+
+    fn internal_set_state_leaf(&mut self, mut index: u64, leaf: CryptoHash) {
+        let zeroes = zero_hashes();
+        let mut node = leaf;
+        for level in 0..=STATE_TREE_DEPTH {
+            if node == zeroes[level as usize] {
+                self.state_nodes.remove(&(level, index));
+            } else {
+                self.state_nodes.insert(&(level, index), &node);
+            }
+            if level == STATE_TREE_DEPTH {
+                break;
+            }
+            let sibling = self.internal_state_node(level, index ^ 1, &zeroes);
+            node = if index & 1 == 0 {
+                hash_pair(&node, &sibling)
+            } else {
+                hash_pair(&sibling, &node)
+            };
+            index >>= 1;
+        }
+        self.state_root = node;
+    }
+
+    pub(crate) fn internal_commit_state(&mut self, account_id: &AccountId, stake_info: &StakeInfo) {
+        let index = match self.state_leaf_index.get(account_id) {
+            Some(index) => index,
+            None => {
+                let index = self.state_leaf_count;
+                require!(index < 1 << STATE_TREE_DEPTH, StakeError::StateTreeFull);
+                self.state_leaf_count += 1;
+                self.state_leaf_index.insert(account_id, &index);
+                self.state_leaf_accounts.insert(&index, account_id);
+                index
+            }
+        };
+        self.internal_set_state_leaf(index, state_leaf(account_id, stake_info));
+    }
+
+    // the last leaf moves into the freed slot so the leaves stay contiguous
+    pub(crate) fn internal_uncommit_state(&mut self, account_id: &AccountId) {
+        let index = match self.state_leaf_index.remove(account_id) {
+            Some(index) => index,
+            None => return,
+        };
+        let last = self.state_leaf_count - 1;
+        if index != last {
+            let moved = self.state_leaf_accounts.get(&last).unwrap();
+            let leaf = self.internal_state_node(0, last, &zero_hashes());
+            self.internal_set_state_leaf(index, leaf);
+            self.state_leaf_index.insert(&moved, &index);
+            self.state_leaf_accounts.insert(&index, &moved);
+        }
+        self.internal_set_state_leaf(last, [0; 32]);
+        self.state_leaf_accounts.remove(&last);
+        self.state_leaf_count = last;
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // records last written before the tree existed only join it on their next save, this
+    // commits a page of the staker index right away
+    pub fn rebuild_state_root(&mut self, from_index: U64, limit: U64) -> U64 {
+        self.internal_assert_owner();
+        let limit = limit.0.min(MAX_STATE_ROOT_REBUILD);
+        let accounts: Vec<AccountId> = self
+            .staker_ids
+            .as_vector()
+            .iter()
+            .skip(from_index.0 as usize)
+            .take(limit as usize)
+            .collect();
+        let mut committed = 0;
+        for account_id in accounts {
+            if let Some(stake_info) = self.internal_load_stake_info(&account_id) {
+                self.internal_commit_state(&account_id, &stake_info);
+                committed += 1;
+            }
+        }
+        U64(committed)
+    }
+
+    pub fn get_state_root(&self) -> StateRootView {
+        StateRootView {
+            root: Base64VecU8(self.state_root.to_vec()),
+            leaves: U64(self.state_leaf_count),
+            depth: STATE_TREE_DEPTH,
+        }
+    }
+
+    pub fn get_state_proof(&self, account_id: AccountId) -> Option<StateProof> {
+        let leaf_index = self.state_leaf_index.get(&account_id)?;
+        let zeroes = zero_hashes();
+        let mut index = leaf_index;
+        let mut siblings = vec![];
+        for level in 0..STATE_TREE_DEPTH {
+            siblings.push(Base64VecU8(
+                self.internal_state_node(level, index ^ 1, &zeroes).to_vec(),
+            ));
+            index >>= 1;
+        }
+        Some(StateProof {
+            leaf_index: U64(leaf_index),
+            leaf: Base64VecU8(self.internal_state_node(0, leaf_index, &zeroes).to_vec()),
+            siblings,
+            root: Base64VecU8(self.state_root.to_vec()),
+        })
+    }
+}
@@ -0,0 +1,59 @@
+use near_sdk::json_types::U128;
+use near_sdk::serde::Serialize;
+use near_sdk::{near_bindgen, AccountId};
+
+use crate::*;
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AccountStats {
+    pub lifetime_staked: U128,
+    pub lifetime_unstaked: U128,
+    pub lifetime_rewards_accrued: U128,
+    pub lifetime_rewards_claimed: U128,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct GlobalStats {
+    pub total_volume_staked: U128,
+    pub total_volume_unstaked: U128,
+    pub total_rewards_accrued: U128,
+    pub total_rewards_paid: U128,
+    pub total_unstake_fees: U128,
+    pub total_slashed: U128,
+}
+
+impl Contract {
+    pub(crate) fn internal_note_accrual(&mut self, stake_info: &mut StakeInfo, accrued: u128) {
+        stake_info.lifetime_rewards_accrued += accrued;
+        self.total_rewards_accrued += accrued;
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    #[handle_result]
+    pub fn get_account_stats(&self, account_id: AccountId) -> Result<AccountStats, StakeError> {
+        let stake_info = self
+            .internal_get_stake_info(&account_id)
+            .ok_or(StakeError::NotStaked)?;
+        Ok(AccountStats {
+            lifetime_staked: U128(stake_info.lifetime_staked),
+            lifetime_unstaked: U128(stake_info.lifetime_unstaked),
+            lifetime_rewards_accrued: U128(stake_info.lifetime_rewards_accrued),
+            lifetime_rewards_claimed: U128(stake_info.lifetime_rewards_claimed),
+        })
+    }
+
+    pub fn get_global_stats(&self) -> GlobalStats {
+        GlobalStats {
+            total_volume_staked: U128(self.total_volume_staked),
+            total_volume_unstaked: U128(self.total_volume_unstaked),
+            total_rewards_accrued: U128(self.total_rewards_accrued),
+            total_rewards_paid: U128(self.total_rewards_paid),
+            total_unstake_fees: U128(self.total_unstake_fees),
+            total_slashed: U128(self.total_slashed),
+        }
+    }
+}
@@ -0,0 +1,228 @@
+use near_contract_standards::storage_management::StorageBalance;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::Serialize;
+use near_sdk::{env, near_bindgen, require, AccountId, Balance, Promise};
+
+use crate::events::{StakeEvent, StorageDepositData};
+use crate::*;
+
+// NEAR put up for one account's records, it goes back to `payer_id` once they are pruned.
+// an account that stakes without one has its storage paid by the contract as before
+#[derive(BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StorageDeposit {
+    pub payer_id: AccountId,
+    pub amount: U128,
+    // what creating the account's stake record took, 0 until it is created
+    pub bytes: U64,
+}
+
+impl Contract {
+    // called with the bytes the call that created the account's record wrote
+    pub(crate) fn internal_note_storage_created(&mut self, account_id: &AccountId, bytes: u64) {
+        if let Some(mut deposit) = self.storage_deposits.get(account_id) {
+            deposit.bytes = U64(bytes);
+            self.storage_deposits.insert(account_id, &deposit);
+        }
+    }
+
+    // the records are gone, so whoever paid for them gets the whole deposit back
+    pub(crate) fn internal_refund_storage(&mut self, account_id: &AccountId) {
+        let deposit = match self.storage_deposits.remove(account_id) {
+            Some(deposit) => deposit,
+            None => return,
+        };
+        Promise::new(deposit.payer_id.clone()).transfer(deposit.amount.0);
+        StakeEvent::StorageRefunded(vec![StorageDepositData {
+            account_id: account_id.clone(),
+            payer_id: deposit.payer_id,
+            amount: deposit.amount,
+            bytes: deposit.bytes,
+        }])
+        .emit();
+    }
+
+    fn internal_storage_balance(deposit: &StorageDeposit) -> StorageBalance {
+        let used = deposit.bytes.0 as Balance * env::storage_byte_cost();
+        StorageBalance {
+            total: deposit.amount,
+            available: U128(deposit.amount.0.saturating_sub(used)),
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // one payer per account, its top-ups add to the deposit
+    #[payable]
+    pub fn storage_deposit(&mut self, account_id: Option<AccountId>) -> StorageBalance {
+        let amount = env::attached_deposit();
+        require!(amount > 0, StakeError::InvalidAmount);
+        let payer_id = env::predecessor_account_id();
+        let account_id = account_id.unwrap_or_else(|| payer_id.clone());
+        let deposit = match self.storage_deposits.get(&account_id) {
+            Some(mut deposit) => {
+                require!(
+                    deposit.payer_id == payer_id,
+                    StakeError::StoragePayerMismatch
+                );
+                deposit.amount = U128(deposit.amount.0 + amount);
+                deposit
+            }
+            None => StorageDeposit {
+                payer_id: payer_id.clone(),
+                amount: U128(amount),
+                bytes: U64(0),
+            },
+        };
+        self.storage_deposits.insert(&account_id, &deposit);
+        StakeEvent::StorageDeposited(vec![StorageDepositData {
+            account_id,
+            payer_id,
+            amount: U128(amount),
+            bytes: deposit.bytes,
+        }])
+        .emit();
+        Self::internal_storage_balance(&deposit)
+    }
+
+    // prunes the caller's record if nothing is left in it and refunds the deposit, false
+    // when there was no deposit to refund
+    #[payable]
+    pub fn storage_unregister(&mut self) -> bool {
+        self.internal_assert_deposit("storage_unregister");
+        let account_id = env::predecessor_account_id();
+        if !self.storage_deposits.contains_key(&account_id) {
+            return false;
+        }
+        self.internal_migrate_legacy(&account_id);
+        if self.internal_load_stake_info(&account_id).is_some() {
+            require!(self.internal_prune(&account_id), StakeError::StorageInUse);
+            return true;
+        }
+        self.internal_refund_storage(&account_id);
+        true
+    }
+
+    pub fn storage_balance_of(&self, account_id: AccountId) -> Option<StorageBalance> {
+        self.storage_deposits
+            .get(&account_id)
+            .map(|deposit| Self::internal_storage_balance(&deposit))
+    }
+
+    pub fn get_storage_deposit(&self, account_id: AccountId) -> Option<StorageDeposit> {
+        self.storage_deposits.get(&account_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::mock::VmAction;
+    use near_sdk::test_utils::get_created_receipts;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::testing_env;
+
+    use super::*;
+    use crate::test_utils::*;
+    use crate::time::Clock;
+    use crate::time::MockClock;
+
+    const AMOUNT: u128 = 100 * ONE_TOKEN;
+    const DEPOSIT: Balance = 100_000_000_000_000_000_000_000;
+
+    fn deposit_as(contract: &mut Contract, payer_id: &AccountId, account_id: &AccountId) {
+        let mut builder = VMContextBuilder::new();
+        builder
+            .current_account_id(contract_id())
+            .predecessor_account_id(payer_id.clone())
+            .block_timestamp(MockClock.now().as_nanos())
+            .attached_deposit(DEPOSIT);
+        testing_env!(builder.build());
+        contract.storage_deposit(Some(account_id.clone()));
+    }
+
+    // receiver and amount of every NEAR transfer the last receipt created
+    fn near_transfers() -> Vec<(AccountId, Balance)> {
+        get_created_receipts()
+            .into_iter()
+            .flat_map(|receipt| {
+                let receiver_id = receipt.receiver_id;
+                receipt
+                    .actions
+                    .into_iter()
+                    .filter_map(move |action| match action {
+                        VmAction::Transfer { deposit } => Some((receiver_id.clone(), deposit)),
+                        _ => None,
+                    })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn creating_the_record_charges_the_deposit() {
+        let mut contract = setup();
+        let alice = account("alice");
+        deposit_as(&mut contract, &account("sponsor"), &alice);
+        stake(&mut contract, &alice, AMOUNT);
+
+        let deposit = contract.get_storage_deposit(alice.clone()).unwrap();
+        assert_eq!(deposit.payer_id, account("sponsor"));
+        assert!(deposit.bytes.0 > 0);
+        let balance = contract.storage_balance_of(alice).unwrap();
+        assert_eq!(balance.total.0, DEPOSIT);
+        assert_eq!(
+            balance.available.0,
+            DEPOSIT - deposit.bytes.0 as Balance * env::storage_byte_cost()
+        );
+    }
+
+    #[test]
+    fn a_full_exit_refunds_the_payer() {
+        let mut contract = setup();
+        let alice = account("alice");
+        deposit_as(&mut contract, &account("sponsor"), &alice);
+        stake(&mut contract, &alice, AMOUNT);
+        call_with_yocto(&alice);
+        contract.unstake_token(U128(AMOUNT), None, None);
+        call_with_yocto(&alice);
+        let _ = contract.withdraw(None);
+
+        call_as(&contract_id());
+        contract.on_withdraw(alice.clone(), U128(AMOUNT), None, None, Ok(()));
+        assert!(contract.get_storage_deposit(alice.clone()).is_none());
+        assert!(contract.internal_get_stake_info(&alice).is_none());
+        assert_eq!(near_transfers(), vec![(account("sponsor"), DEPOSIT)]);
+    }
+
+    #[test]
+    fn unregister_refunds_an_account_that_never_staked() {
+        let mut contract = setup();
+        let alice = account("alice");
+        deposit_as(&mut contract, &alice, &alice);
+        call_with_yocto(&alice);
+        assert!(contract.storage_unregister());
+        assert_eq!(near_transfers(), vec![(alice.clone(), DEPOSIT)]);
+        assert!(contract.storage_balance_of(alice).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_STORAGE_IN_USE")]
+    fn unregister_keeps_a_record_in_use() {
+        let mut contract = setup();
+        let alice = account("alice");
+        deposit_as(&mut contract, &alice, &alice);
+        stake(&mut contract, &alice, AMOUNT);
+        call_with_yocto(&alice);
+        contract.storage_unregister();
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_STORAGE_PAYER_MISMATCH")]
+    fn a_second_payer_cannot_top_up() {
+        let mut contract = setup();
+        let alice = account("alice");
+        deposit_as(&mut contract, &account("sponsor"), &alice);
+        deposit_as(&mut contract, &alice, &alice);
+    }
+}
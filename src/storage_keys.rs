@@ -0,0 +1,380 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::{env, AccountId, BorshStorageKey};
+
+// every collection lives under its own variant, the variant's borsh tag is the first
+// byte of all its keys so two variants can never share a key. variants are only ever
+// appended, reordering one moves a live collection to another prefix. a collection
+// created per account takes a variant with the account's hash as sub-key instead of
+// reusing a flat one
+#[derive(BorshDeserialize, BorshSerialize, BorshStorageKey)]
+pub enum StorageKey {
+    StakeInfoKey,
+    AprHistory,
+    AprHistoryEntries { account_hash: Vec<u8> },
+    StakeInfoV2,
+    FaucetLastClaim,
+    DenyList,
+    AllowList,
+    Vesting,
+    FeeExempt,
+    ImportedAccounts,
+    StakerIds,
+    Snapshots,
+    StakersByAmount,
+    StakersByVotes,
+    ClaimedLeaves,
+    ClaimKeys,
+    ClaimNonces,
+    CourseStakes,
+    SlashProposals,
+    OpenSlashes,
+    AccountHistory,
+    AccountHistoryEntries { account_hash: Vec<u8> },
+    ProcessedOps,
+    ProcessedOpIds,
+    StakeIntents,
+    Deposits,
+    VoteRecords,
+    BoosterContracts,
+    LpStakes,
+    RevenueEpochs,
+    QueueEntries,
+    QueueOrder,
+    Roles,
+    PendingActions,
+    VoteWindows,
+    VoteWindowEntries { account_hash: Vec<u8> },
+    StakeTerms,
+    Campaigns,
+    CampaignStakes,
+    AccountCampaigns,
+    DelegationPools,
+    Delegations,
+    ApprovedVaults,
+    Sessions,
+    SessionLocked,
+    ConfigHistory,
+    FrozenAccounts,
+    StakersByApr,
+    Prices,
+    Groups,
+    GroupMembers,
+    CollateralLockers,
+    CollateralLiens,
+    CollateralLocked,
+    DepositExempt,
+    DepositRelayers,
+    Outbox,
+    CategoryRelays,
+    CategoryWeights,
+    MicroClaims,
+    PartnerPools,
+    PoolStakes,
+    RejectedVotes,
+    StateNodes,
+    StateLeafIndex,
+    StateLeafAccounts,
+    InsuranceClaims,
+    AbandonedEntries,
+    HiddenFromListings,
+    AccessKeyAccounts,
+    FailedOperations,
+    MigrationSuccessors,
+    SponsoredBudgets,
+    Milestones,
+    MilestoneIds,
+    MilestoneIdEntries { account_hash: Vec<u8> },
+    HookQueue,
+    BoosterHolders,
+    AppealWeights,
+    StakePositions,
+    StorageDeposits,
+    DeferredSessionPenalties,
+}
+
+impl StorageKey {
+    // fixed length, so one account's sub-key can't be a prefix of another's
+    pub(crate) fn account_hash(account_id: &AccountId) -> Vec<u8> {
+        env::sha256(account_id.as_bytes())
+    }
+
+    pub(crate) fn apr_history_entries(account_id: &AccountId) -> Self {
+        StorageKey::AprHistoryEntries {
+            account_hash: Self::account_hash(account_id),
+        }
+    }
+
+    pub(crate) fn account_history_entries(account_id: &AccountId) -> Self {
+        StorageKey::AccountHistoryEntries {
+            account_hash: Self::account_hash(account_id),
+        }
+    }
+
+    pub(crate) fn vote_window_entries(account_id: &AccountId) -> Self {
+        StorageKey::VoteWindowEntries {
+            account_hash: Self::account_hash(account_id),
+        }
+    }
+
+    pub(crate) fn milestone_id_entries(account_id: &AccountId) -> Self {
+        StorageKey::MilestoneIdEntries {
+            account_hash: Self::account_hash(account_id),
+        }
+    }
+
+    // what the collection's keys start with
+    pub fn prefix(&self) -> Vec<u8> {
+        self.try_to_vec().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // no wildcard arm, a new variant does not compile until it is listed here with the
+    // tag it was appended under
+    fn tag(key: &StorageKey) -> u8 {
+        match key {
+            StorageKey::StakeInfoKey => 0,
+            StorageKey::AprHistory => 1,
+            StorageKey::AprHistoryEntries { .. } => 2,
+            StorageKey::StakeInfoV2 => 3,
+            StorageKey::FaucetLastClaim => 4,
+            StorageKey::DenyList => 5,
+            StorageKey::AllowList => 6,
+            StorageKey::Vesting => 7,
+            StorageKey::FeeExempt => 8,
+            StorageKey::ImportedAccounts => 9,
+            StorageKey::StakerIds => 10,
+            StorageKey::Snapshots => 11,
+            StorageKey::StakersByAmount => 12,
+            StorageKey::StakersByVotes => 13,
+            StorageKey::ClaimedLeaves => 14,
+            StorageKey::ClaimKeys => 15,
+            StorageKey::ClaimNonces => 16,
+            StorageKey::CourseStakes => 17,
+            StorageKey::SlashProposals => 18,
+            StorageKey::OpenSlashes => 19,
+            StorageKey::AccountHistory => 20,
+            StorageKey::AccountHistoryEntries { .. } => 21,
+            StorageKey::ProcessedOps => 22,
+            StorageKey::ProcessedOpIds => 23,
+            StorageKey::StakeIntents => 24,
+            StorageKey::Deposits => 25,
+            StorageKey::VoteRecords => 26,
+            StorageKey::BoosterContracts => 27,
+            StorageKey::LpStakes => 28,
+            StorageKey::RevenueEpochs => 29,
+            StorageKey::QueueEntries => 30,
+            StorageKey::QueueOrder => 31,
+            StorageKey::Roles => 32,
+            StorageKey::PendingActions => 33,
+            StorageKey::VoteWindows => 34,
+            StorageKey::VoteWindowEntries { .. } => 35,
+            StorageKey::StakeTerms => 36,
+            StorageKey::Campaigns => 37,
+            StorageKey::CampaignStakes => 38,
+            StorageKey::AccountCampaigns => 39,
+            StorageKey::DelegationPools => 40,
+            StorageKey::Delegations => 41,
+            StorageKey::ApprovedVaults => 42,
+            StorageKey::Sessions => 43,
+            StorageKey::SessionLocked => 44,
+            StorageKey::ConfigHistory => 45,
+            StorageKey::FrozenAccounts => 46,
+            StorageKey::StakersByApr => 47,
+            StorageKey::Prices => 48,
+            StorageKey::Groups => 49,
+            StorageKey::GroupMembers => 50,
+            StorageKey::CollateralLockers => 51,
+            StorageKey::CollateralLiens => 52,
+            StorageKey::CollateralLocked => 53,
+            StorageKey::DepositExempt => 54,
+            StorageKey::DepositRelayers => 55,
+            StorageKey::Outbox => 56,
+            StorageKey::CategoryRelays => 57,
+            StorageKey::CategoryWeights => 58,
+            StorageKey::MicroClaims => 59,
+            StorageKey::PartnerPools => 60,
+            StorageKey::PoolStakes => 61,
+            StorageKey::RejectedVotes => 62,
+            StorageKey::StateNodes => 63,
+            StorageKey::StateLeafIndex => 64,
+            StorageKey::StateLeafAccounts => 65,
+            StorageKey::InsuranceClaims => 66,
+            StorageKey::AbandonedEntries => 67,
+            StorageKey::HiddenFromListings => 68,
+            StorageKey::AccessKeyAccounts => 69,
+            StorageKey::FailedOperations => 70,
+            StorageKey::MigrationSuccessors => 71,
+            StorageKey::SponsoredBudgets => 72,
+            StorageKey::Milestones => 73,
+            StorageKey::MilestoneIds => 74,
+            StorageKey::MilestoneIdEntries { .. } => 75,
+            StorageKey::HookQueue => 76,
+            StorageKey::BoosterHolders => 77,
+            StorageKey::AppealWeights => 78,
+            StorageKey::StakePositions => 79,
+            StorageKey::StorageDeposits => 80,
+            StorageKey::DeferredSessionPenalties => 81,
+        }
+    }
+
+    fn every_key(alice: &AccountId) -> Vec<StorageKey> {
+        let alice = alice.clone();
+        vec![
+            StorageKey::StakeInfoKey,
+            StorageKey::AprHistory,
+            StorageKey::AprHistoryEntries {
+                account_hash: StorageKey::account_hash(&alice),
+            },
+            StorageKey::StakeInfoV2,
+            StorageKey::FaucetLastClaim,
+            StorageKey::DenyList,
+            StorageKey::AllowList,
+            StorageKey::Vesting,
+            StorageKey::FeeExempt,
+            StorageKey::ImportedAccounts,
+            StorageKey::StakerIds,
+            StorageKey::Snapshots,
+            StorageKey::StakersByAmount,
+            StorageKey::StakersByVotes,
+            StorageKey::ClaimedLeaves,
+            StorageKey::ClaimKeys,
+            StorageKey::ClaimNonces,
+            StorageKey::CourseStakes,
+            StorageKey::SlashProposals,
+            StorageKey::OpenSlashes,
+            StorageKey::AccountHistory,
+            StorageKey::AccountHistoryEntries {
+                account_hash: StorageKey::account_hash(&alice),
+            },
+            StorageKey::ProcessedOps,
+            StorageKey::ProcessedOpIds,
+            StorageKey::StakeIntents,
+            StorageKey::Deposits,
+            StorageKey::VoteRecords,
+            StorageKey::BoosterContracts,
+            StorageKey::LpStakes,
+            StorageKey::RevenueEpochs,
+            StorageKey::QueueEntries,
+            StorageKey::QueueOrder,
+            StorageKey::Roles,
+            StorageKey::PendingActions,
+            StorageKey::VoteWindows,
+            StorageKey::VoteWindowEntries {
+                account_hash: StorageKey::account_hash(&alice),
+            },
+            StorageKey::StakeTerms,
+            StorageKey::Campaigns,
+            StorageKey::CampaignStakes,
+            StorageKey::AccountCampaigns,
+            StorageKey::DelegationPools,
+            StorageKey::Delegations,
+            StorageKey::ApprovedVaults,
+            StorageKey::Sessions,
+            StorageKey::SessionLocked,
+            StorageKey::ConfigHistory,
+            StorageKey::FrozenAccounts,
+            StorageKey::StakersByApr,
+            StorageKey::Prices,
+            StorageKey::Groups,
+            StorageKey::GroupMembers,
+            StorageKey::CollateralLockers,
+            StorageKey::CollateralLiens,
+            StorageKey::CollateralLocked,
+            StorageKey::DepositExempt,
+            StorageKey::DepositRelayers,
+            StorageKey::Outbox,
+            StorageKey::CategoryRelays,
+            StorageKey::CategoryWeights,
+            StorageKey::MicroClaims,
+            StorageKey::PartnerPools,
+            StorageKey::PoolStakes,
+            StorageKey::RejectedVotes,
+            StorageKey::StateNodes,
+            StorageKey::StateLeafIndex,
+            StorageKey::StateLeafAccounts,
+            StorageKey::InsuranceClaims,
+            StorageKey::AbandonedEntries,
+            StorageKey::HiddenFromListings,
+            StorageKey::AccessKeyAccounts,
+            StorageKey::FailedOperations,
+            StorageKey::MigrationSuccessors,
+            StorageKey::SponsoredBudgets,
+            StorageKey::Milestones,
+            StorageKey::MilestoneIds,
+            StorageKey::MilestoneIdEntries {
+                account_hash: StorageKey::account_hash(&alice),
+            },
+            StorageKey::HookQueue,
+            StorageKey::BoosterHolders,
+            StorageKey::AppealWeights,
+            StorageKey::StakePositions,
+            StorageKey::StorageDeposits,
+            StorageKey::DeferredSessionPenalties,
+        ]
+    }
+
+    fn helper_keys(account_id: &AccountId) -> Vec<StorageKey> {
+        vec![
+            StorageKey::apr_history_entries(account_id),
+            StorageKey::account_history_entries(account_id),
+            StorageKey::vote_window_entries(account_id),
+            StorageKey::milestone_id_entries(account_id),
+        ]
+    }
+
+    fn assert_prefix_free(prefixes: &[Vec<u8>]) {
+        for (i, a) in prefixes.iter().enumerate() {
+            for (j, b) in prefixes.iter().enumerate() {
+                assert!(
+                    i == j || !b.starts_with(a),
+                    "{:?} is a prefix of {:?}",
+                    a,
+                    b
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn variants_keep_the_tag_they_were_appended_under() {
+        let keys = every_key(&"alice.near".parse().unwrap());
+        for (index, key) in keys.iter().enumerate() {
+            assert_eq!(tag(key) as usize, index);
+            assert_eq!(key.prefix()[0], tag(key));
+        }
+    }
+
+    #[test]
+    fn no_prefix_collides_with_or_contains_another() {
+        let accounts: Vec<AccountId> = ["alice.near", "bob.near", "ab", "alice.near.near"]
+            .iter()
+            .map(|account_id| account_id.parse().unwrap())
+            .collect();
+        let mut prefixes: Vec<Vec<u8>> = every_key(&accounts[0])
+            .iter()
+            .map(StorageKey::prefix)
+            .collect();
+        for account_id in &accounts {
+            prefixes.extend(helper_keys(account_id).iter().map(StorageKey::prefix));
+        }
+        prefixes.sort();
+        prefixes.dedup();
+        // every flat variant once, every per-account variant once per account
+        assert_eq!(prefixes.len(), 82 - 4 + 4 * accounts.len());
+        assert_prefix_free(&prefixes);
+    }
+
+    #[test]
+    fn helpers_build_the_variant_they_are_named_after() {
+        let alice: AccountId = "alice.near".parse().unwrap();
+        let keys = every_key(&alice);
+        for helper in helper_keys(&alice) {
+            assert!(keys.iter().any(|key| key.prefix() == helper.prefix()));
+        }
+        assert_eq!(StorageKey::account_hash(&alice).len(), 32);
+    }
+}
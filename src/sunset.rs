@@ -0,0 +1,71 @@
+use near_sdk::json_types::{U128, U64};
+use near_sdk::near_bindgen;
+
+use crate::*;
+
+pub const MAX_FORCE_RETURN_BATCH: u64 = 10;
+
+impl Contract {
+    // unstakes the whole position without a fee, returns false when the account has to be skipped.
+    // hooks are not notified here, a full batch would not leave them enough gas
+    fn internal_force_unstake(&mut self, account_id: &AccountId) -> bool {
+        if self.open_slashes.contains_key(account_id) {
+            return false;
+        }
+        let mut stake_info = match self.internal_touch(account_id) {
+            Some(stake_info) => stake_info,
+            None => return false,
+        };
+        if stake_info.pending_operation {
+            return false;
+        }
+        let amount = stake_info.amount_staked;
+        if amount > 0 {
+            self.internal_update_reward_rate(amount, stake_info.boosted(stake_info.apr), 0, 0);
+            stake_info.amount_staked = 0;
+            stake_info.lifetime_unstaked += amount;
+            stake_info.sync_streak(Self::now());
+            self.internal_credit_withdrawable(&mut stake_info, amount);
+            self.total_staked -= amount;
+            self.total_volume_unstaked += amount;
+            self.course_stakes.remove(account_id);
+            self.internal_record_activity(
+                account_id,
+                Activity::Unstake {
+                    amount: U128(amount),
+                },
+            );
+        }
+        let has_withdrawable = stake_info.withdrawable > 0;
+        self.internal_save_stake_info(account_id, &stake_info);
+        has_withdrawable
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // winds the contract down a page of the staker index at a time, transfers that fail
+    // stay withdrawable and are picked up again by the next run over the same page
+    pub fn force_return_stakes(&mut self, from_index: U64, limit: U64) -> U64 {
+        self.internal_assert_owner();
+        require!(self.paused, StakeError::NotPaused);
+        let limit = limit.0.min(MAX_FORCE_RETURN_BATCH);
+        let accounts: Vec<AccountId> = self
+            .staker_ids
+            .as_vector()
+            .iter()
+            .skip(from_index.0 as usize)
+            .take(limit as usize)
+            .collect();
+
+        let mut started = 0;
+        for account_id in accounts {
+            if self.internal_force_unstake(&account_id) {
+                self.internal_transfer_withdrawable(account_id, Payout::default());
+                started += 1;
+            }
+        }
+        self.internal_maybe_snapshot();
+        U64(started)
+    }
+}
@@ -0,0 +1,144 @@
+use near_sdk::json_types::U128;
+use near_sdk::{env, near_bindgen, AccountId, Gas, Promise, PromiseError, ONE_YOCTO};
+
+use crate::*;
+
+pub const FT_BALANCE_OF_GAS: Gas = Gas(5_000_000_000_000);
+pub const SWEEP_CALLBACK_GAS: Gas = Gas(30_000_000_000_000);
+
+impl Contract {
+    // stake tokens the contract owes to stakers, the reward pool and the faucet
+    pub(crate) fn internal_liabilities(&self) -> u128 {
+        self.total_staked
+            + self.reward_pool
+            + self.total_withdrawable
+            + self.faucet_balance
+            + self.total_vesting
+            + self.pending_outflows
+            + self.bonus_balance
+            + self.insurance_balance
+            + self.total_intent_balance
+            + self.total_deposits
+            + self.internal_lp_reserve()
+            + self.total_queued
+            + self.internal_merkle_reserve()
+            + self.dust_accumulator
+            + self.total_campaign_reserve
+            + self.protocol_fee_balance
+            + self.pending_burn
+            + self.total_partner_staked
+            + self.total_partner_rewards
+            + self.total_sponsored
+            + self.milestone_budget
+    }
+
+    // moves tokens that are not accounted for anywhere, e.g. sent with plain ft_transfer
+    pub(crate) fn internal_sweep(
+        &mut self,
+        token_id: AccountId,
+        amount: U128,
+        receiver_id: AccountId,
+    ) -> Promise {
+        require!(amount.0 > 0, StakeError::InvalidAmount);
+        Self::internal_assert_receiver(&receiver_id);
+
+        if self.internal_lp_staked_of(&token_id) > 0 {
+            return ext_ft_contract::ext(token_id.clone())
+                .with_static_gas(FT_BALANCE_OF_GAS)
+                .ft_balance_of(env::current_account_id())
+                .then(
+                    Self::ext(env::current_account_id())
+                        .with_static_gas(SWEEP_CALLBACK_GAS)
+                        .on_sweep_lp_balance(token_id, amount, receiver_id),
+                );
+        }
+        if token_id != self.token_address {
+            return ext_ft_contract::ext(token_id)
+                .with_static_gas(FT_TRANSFER_GAS)
+                .with_attached_deposit(ONE_YOCTO)
+                .ft_transfer(receiver_id, amount, None);
+        }
+        ext_ft_contract::ext(self.token_address.clone())
+            .with_static_gas(FT_BALANCE_OF_GAS)
+            .ft_balance_of(env::current_account_id())
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(SWEEP_CALLBACK_GAS)
+                    .on_sweep_balance(amount, receiver_id),
+            )
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    #[payable]
+    pub fn sweep(&mut self, token_id: AccountId, amount: U128, receiver_id: AccountId) -> Promise {
+        self.internal_assert_deposit("sweep");
+        self.internal_assert_role(Role::Treasurer);
+        self.internal_assert_no_confirmations();
+        self.internal_sweep(token_id, amount, receiver_id)
+    }
+
+    #[private]
+    pub fn on_sweep_balance(
+        &mut self,
+        amount: U128,
+        receiver_id: AccountId,
+        #[callback_result] balance: Result<U128, PromiseError>,
+    ) -> Promise {
+        let balance = balance
+            .unwrap_or_else(|_| env::panic_str(&StakeError::BalanceFetchFailed))
+            .0;
+        self.internal_set_token_balance(balance);
+        let surplus = balance.saturating_sub(self.internal_liabilities());
+        require!(amount.0 <= surplus, StakeError::AmountExceedsSurplus);
+
+        self.pending_outflows += amount.0;
+        ext_ft_contract::ext(self.token_address.clone())
+            .with_static_gas(FT_TRANSFER_GAS)
+            .with_attached_deposit(ONE_YOCTO)
+            .ft_transfer(receiver_id, amount, None)
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(WITHDRAW_CALLBACK_GAS)
+                    .on_sweep_transfer(amount),
+            )
+    }
+
+    // LP tokens staked in the pool stay, only what is above them can be swept
+    #[private]
+    pub fn on_sweep_lp_balance(
+        &mut self,
+        token_id: AccountId,
+        amount: U128,
+        receiver_id: AccountId,
+        #[callback_result] balance: Result<U128, PromiseError>,
+    ) -> Promise {
+        let balance = balance
+            .unwrap_or_else(|_| env::panic_str(&StakeError::BalanceFetchFailed))
+            .0;
+        let surplus = balance.saturating_sub(self.internal_lp_staked_of(&token_id));
+        require!(amount.0 <= surplus, StakeError::AmountExceedsSurplus);
+        ext_ft_contract::ext(token_id)
+            .with_static_gas(FT_TRANSFER_GAS)
+            .with_attached_deposit(ONE_YOCTO)
+            .ft_transfer(receiver_id, amount, None)
+    }
+
+    #[private]
+    pub fn on_sweep_transfer(
+        &mut self,
+        amount: U128,
+        #[callback_result] result: Result<(), PromiseError>,
+    ) -> U128 {
+        self.internal_note_promise_result("on_sweep_transfer", None, amount.0, result.is_ok());
+        self.pending_outflows -= amount.0;
+        if result.is_ok() {
+            self.internal_note_outflow(amount.0);
+            self.total_swept += amount.0;
+            amount
+        } else {
+            U128(0)
+        }
+    }
+}
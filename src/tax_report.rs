@@ -0,0 +1,80 @@
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::Serialize;
+use near_sdk::{near_bindgen, AccountId};
+
+use crate::*;
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub enum RewardEventKind {
+    Accrual,
+    Claim,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RewardEvent {
+    pub timestamp: U64,
+    pub kind: RewardEventKind,
+    pub amount: U128,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TaxReport {
+    pub account_id: AccountId,
+    pub from_ts: U64,
+    pub to_ts: U64,
+    pub events: Vec<RewardEvent>,
+    pub total_accrued: U128,
+    pub total_claimed: U128,
+    // the activity log is bounded, entries before this were already dropped
+    pub history_starts_at: Option<U64>,
+}
+
+#[near_bindgen]
+impl Contract {
+    // reward accruals and claims from the activity log with from_ts <= timestamp < to_ts
+    pub fn get_tax_report(&self, account_id: AccountId, from_ts: U64, to_ts: U64) -> TaxReport {
+        let entries = self
+            .account_history
+            .get(&account_id)
+            .map_or(vec![], |history| history.page(0, MAX_ACCOUNT_HISTORY));
+        let history_starts_at = entries.first().map(|entry| entry.timestamp);
+
+        let mut total_accrued = 0;
+        let mut total_claimed = 0;
+        let events = entries
+            .into_iter()
+            .filter(|entry| entry.timestamp.0 >= from_ts.0 && entry.timestamp.0 < to_ts.0)
+            .filter_map(|entry| {
+                let (kind, amount) = match entry.activity {
+                    Activity::RewardAccrued { amount } => {
+                        total_accrued += amount.0;
+                        (RewardEventKind::Accrual, amount)
+                    }
+                    Activity::Claim { amount } => {
+                        total_claimed += amount.0;
+                        (RewardEventKind::Claim, amount)
+                    }
+                    _ => return None,
+                };
+                Some(RewardEvent {
+                    timestamp: entry.timestamp,
+                    kind,
+                    amount,
+                })
+            })
+            .collect();
+
+        TaxReport {
+            account_id,
+            from_ts,
+            to_ts,
+            events,
+            total_accrued: U128(total_accrued),
+            total_claimed: U128(total_claimed),
+            history_starts_at,
+        }
+    }
+}
@@ -0,0 +1,224 @@
+use near_sdk::json_types::U128;
+use near_sdk::mock::VmAction;
+use near_sdk::test_utils::{get_created_receipts, VMContextBuilder};
+use near_sdk::{env, testing_env, AccountId, Gas, MockedBlockchain, PromiseOrValue};
+use near_sdk::{RuntimeFeesConfig, VMConfig, ONE_YOCTO};
+
+use crate::time::{Clock, Duration, MockClock, Timestamp};
+use crate::{Contract, InitConfig, Role};
+
+pub const START: u64 = 1_600_000_000 * crate::time::NANOS_PER_SECOND;
+pub const ONE_TOKEN: u128 = 1_000_000_000_000_000_000;
+pub const TGAS: u64 = 1_000_000_000_000;
+
+pub fn contract_id() -> AccountId {
+    "stake.near".parse().unwrap()
+}
+
+pub fn owner() -> AccountId {
+    "owner.near".parse().unwrap()
+}
+
+pub fn token() -> AccountId {
+    "token.near".parse().unwrap()
+}
+
+pub fn relay() -> AccountId {
+    "relay.near".parse().unwrap()
+}
+
+pub fn account(name: &str) -> AccountId {
+    format!("{}.near", name).parse().unwrap()
+}
+
+fn context(predecessor: &AccountId, deposit: u128) -> VMContextBuilder {
+    let mut builder = VMContextBuilder::new();
+    builder
+        .current_account_id(contract_id())
+        .signer_account_id(predecessor.clone())
+        .predecessor_account_id(predecessor.clone())
+        .block_timestamp(MockClock.now().as_nanos())
+        .attached_deposit(deposit)
+        .prepaid_gas(Gas(300 * TGAS));
+    builder
+}
+
+// a fresh receipt from `predecessor`, storage carries over and used gas starts at 0
+pub fn call_as(predecessor: &AccountId) {
+    testing_env!(context(predecessor, 0).build());
+}
+
+pub fn call_with_yocto(predecessor: &AccountId) {
+    testing_env!(context(predecessor, ONE_YOCTO).build());
+}
+
+// host-call gas of `call` from `predecessor` as a single receipt, the state read and
+// write near_bindgen wraps around a method included. the mocked vm only charges host
+// functions (storage, hashing, logs, promises), wasm execution is not part of the figure,
+// so it counts host work for regression checks and says nothing about whether a call
+// fits its prepaid gas on chain. that needs the compiled wasm in a sandbox
+pub fn host_gas_of(
+    contract: &mut Contract,
+    predecessor: &AccountId,
+    call: impl FnOnce(&mut Contract),
+) -> u64 {
+    env::state_write(contract);
+    call_as(predecessor);
+    let mut loaded: Contract = env::state_read().unwrap();
+    call(&mut loaded);
+    env::state_write(&loaded);
+    *contract = loaded;
+    env::used_gas().0
+}
+
+pub fn advance(duration: Duration) {
+    MockClock::advance(duration);
+}
+
+pub fn advance_secs(secs: u64) {
+    advance(Duration::from_secs(secs));
+}
+
+// owner.near owns a contract for token.near at START, relay.near may vote
+pub fn setup_with(config: InitConfig) -> Contract {
+    MockClock::reset();
+    MockClock::set(Timestamp::from_nanos(START));
+    // empty storage, so one test can set up several contracts
+    env::set_blockchain_interface(MockedBlockchain::new(
+        context(&owner(), 0).build(),
+        VMConfig::test(),
+        RuntimeFeesConfig::test(),
+        vec![],
+        Default::default(),
+        Default::default(),
+        None,
+    ));
+    let mut contract = Contract::new(
+        token(),
+        Some(InitConfig {
+            owner_id: Some(owner()),
+            ..config
+        }),
+    );
+    contract.grant_role(relay(), Role::VoterRelay);
+    // as if refresh_token_balance ran on the fresh account, transfer_call keeps it in step
+    contract.internal_set_token_balance(0);
+    contract
+}
+
+pub fn setup() -> Contract {
+    setup_with(InitConfig::default())
+}
+
+// ft_transfer_call from `sender_id` as the token contract sees it, returns the refund
+pub fn transfer_call(
+    contract: &mut Contract,
+    sender_id: &AccountId,
+    amount: u128,
+    msg: &str,
+) -> u128 {
+    call_as(&token());
+    match contract.ft_on_transfer(sender_id.clone(), U128(amount), msg.to_string()) {
+        PromiseOrValue::Value(refund) => refund.0,
+        PromiseOrValue::Promise(_) => panic!("ft_on_transfer returned a promise"),
+    }
+}
+
+pub fn stake(contract: &mut Contract, account_id: &AccountId, amount: u128) -> u128 {
+    transfer_call(contract, account_id, amount, "")
+}
+
+pub fn fund_reward_pool(contract: &mut Contract, amount: u128) {
+    assert_eq!(
+        transfer_call(contract, &owner(), amount, "\"FundRewardPool\""),
+        0
+    );
+}
+
+pub fn vote(contract: &mut Contract, advisor_id: &AccountId, rating: u8) -> u128 {
+    call_as(&relay());
+    contract
+        .update_apr(advisor_id.clone(), rating, None, None, None, None)
+        .0
+}
+
+pub fn pending(contract: &Contract, account_id: &AccountId) -> u128 {
+    let stake_info = contract.internal_get_stake_info(account_id).unwrap();
+    contract.internal_pending_reward_at(&stake_info, Contract::now())
+}
+
+pub fn staked(contract: &Contract, account_id: &AccountId) -> u128 {
+    contract
+        .internal_get_stake_info(account_id)
+        .map_or(0, |stake_info| stake_info.amount_staked)
+}
+
+// xorshift64*, deterministic per seed so a failing case can be replayed
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng(seed.max(1))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    pub fn next_u128(&mut self) -> u128 {
+        ((self.next_u64() as u128) << 64) | self.next_u64() as u128
+    }
+
+    // in [0, bound), bound > 0
+    pub fn below(&mut self, bound: u128) -> u128 {
+        self.next_u128() % bound
+    }
+
+    // uniform bit length first so small and large values are drawn equally often
+    pub fn sized_u128(&mut self) -> u128 {
+        let bits = self.below(129) as u32;
+        if bits == 0 {
+            return 0;
+        }
+        self.next_u128() >> (128 - bits)
+    }
+}
+
+// stakers so the indexes have realistic depth, `name0` holds the smallest stake
+pub fn populate(contract: &mut Contract, name: &str, count: u32) -> Vec<AccountId> {
+    (0..count)
+        .map(|index| {
+            let account_id = account(&format!("{}{}", name, index));
+            stake(contract, &account_id, (index as u128 + 1) * ONE_TOKEN);
+            account_id
+        })
+        .collect()
+}
+
+// receiver, method and json args of every function call the last receipt created
+pub fn function_calls() -> Vec<(AccountId, String, near_sdk::serde_json::Value)> {
+    get_created_receipts()
+        .into_iter()
+        .flat_map(|receipt| {
+            let receiver_id = receipt.receiver_id;
+            receipt
+                .actions
+                .into_iter()
+                .filter_map(move |action| match action {
+                    VmAction::FunctionCall {
+                        function_name,
+                        args,
+                        ..
+                    } => Some((
+                        receiver_id.clone(),
+                        function_name,
+                        near_sdk::serde_json::from_slice(&args).unwrap_or_default(),
+                    )),
+                    _ => None,
+                })
+        })
+        .collect()
+}
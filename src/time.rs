@@ -0,0 +1,169 @@
+use std::ops::Add;
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::env;
+use near_sdk::json_types::U64;
+use near_sdk::serde::{Deserialize, Serialize};
+
+pub const NANOS_PER_SECOND: u64 = 1_000_000_000;
+pub const SECONDS_PER_YEAR: u64 = 31_536_000;
+pub const NANOS_PER_YEAR: u64 = SECONDS_PER_YEAR * NANOS_PER_SECOND;
+pub const SECONDS_PER_DAY: u64 = 86_400;
+
+// span of block time, always in nanoseconds like env::block_timestamp(),
+// json uses the same string form as U64
+#[derive(
+    BorshDeserialize,
+    BorshSerialize,
+    Serialize,
+    Deserialize,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Debug,
+    Default,
+)]
+#[serde(crate = "near_sdk::serde", from = "U64", into = "U64")]
+pub struct Duration(u64);
+
+impl Duration {
+    pub const fn from_nanos(nanos: u64) -> Self {
+        Duration(nanos)
+    }
+
+    pub const fn from_secs(secs: u64) -> Self {
+        Duration(secs * NANOS_PER_SECOND)
+    }
+
+    pub const fn as_nanos(&self) -> u64 {
+        self.0
+    }
+
+    // zero when `end` is not after `start`
+    pub fn between(start: Timestamp, end: Timestamp) -> Self {
+        Duration(end.0.saturating_sub(start.0))
+    }
+}
+
+// point in block time, nanoseconds since the unix epoch
+#[derive(
+    BorshDeserialize,
+    BorshSerialize,
+    Serialize,
+    Deserialize,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Debug,
+    Default,
+)]
+#[serde(crate = "near_sdk::serde", from = "U64", into = "U64")]
+pub struct Timestamp(u64);
+
+impl Timestamp {
+    pub const fn from_nanos(nanos: u64) -> Self {
+        Timestamp(nanos)
+    }
+
+    pub const fn as_nanos(&self) -> u64 {
+        self.0
+    }
+}
+
+impl Add<Duration> for Timestamp {
+    type Output = Timestamp;
+
+    fn add(self, duration: Duration) -> Timestamp {
+        Timestamp(self.0 + duration.0)
+    }
+}
+
+impl From<Timestamp> for U64 {
+    fn from(timestamp: Timestamp) -> U64 {
+        U64(timestamp.0)
+    }
+}
+
+impl From<U64> for Timestamp {
+    fn from(timestamp: U64) -> Timestamp {
+        Timestamp(timestamp.0)
+    }
+}
+
+impl From<Duration> for U64 {
+    fn from(duration: Duration) -> U64 {
+        U64(duration.0)
+    }
+}
+
+impl From<U64> for Duration {
+    fn from(duration: U64) -> Duration {
+        Duration(duration.0)
+    }
+}
+
+// source of the current time, all accrual math takes the timestamp it produces
+// instead of reading the block directly
+pub trait Clock {
+    fn now(&self) -> Timestamp;
+}
+
+pub struct BlockClock;
+
+impl Clock for BlockClock {
+    fn now(&self) -> Timestamp {
+        Timestamp(env::block_timestamp())
+    }
+}
+
+// what Contract::now() reads, unit tests drive time through MockClock
+#[cfg(not(test))]
+pub fn contract_clock() -> impl Clock {
+    BlockClock
+}
+
+#[cfg(test)]
+pub fn contract_clock() -> impl Clock {
+    MockClock
+}
+
+#[cfg(test)]
+thread_local! {
+    static MOCK_NOW: std::cell::Cell<Option<Timestamp>> = const { std::cell::Cell::new(None) };
+}
+
+// falls back to the block timestamp of the testing_env until a time is set, per test
+// thread so parallel tests don't see each other's clock
+#[cfg(test)]
+pub struct MockClock;
+
+#[cfg(test)]
+impl MockClock {
+    pub fn set(now: Timestamp) {
+        MOCK_NOW.with(|mock| mock.set(Some(now)));
+    }
+
+    pub fn advance(duration: Duration) {
+        let now = MockClock.now();
+        Self::set(now + duration);
+    }
+
+    pub fn reset() {
+        MOCK_NOW.with(|mock| mock.set(None));
+    }
+}
+
+#[cfg(test)]
+impl Clock for MockClock {
+    fn now(&self) -> Timestamp {
+        MOCK_NOW
+            .with(|mock| mock.get())
+            .unwrap_or_else(|| BlockClock.now())
+    }
+}
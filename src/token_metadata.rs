@@ -0,0 +1,63 @@
+use near_contract_standards::fungible_token::metadata::FungibleTokenMetadata;
+use near_sdk::{env, near_bindgen, Gas, Promise, PromiseError};
+
+use crate::*;
+
+// assumed until the token answered ft_metadata
+pub const DEFAULT_TOKEN_DECIMALS: u8 = 24;
+pub const FT_METADATA_GAS: Gas = Gas(5_000_000_000_000);
+pub const METADATA_CALLBACK_GAS: Gas = Gas(5_000_000_000_000);
+
+impl Contract {
+    pub(crate) fn internal_fetch_token_metadata(&self) -> Promise {
+        ext_ft_contract::ext(self.token_address.clone())
+            .with_static_gas(FT_METADATA_GAS)
+            .ft_metadata()
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(METADATA_CALLBACK_GAS)
+                    .on_token_metadata(),
+            )
+    }
+
+    // only display values depend on this, reward math works on raw amounts
+    pub(crate) fn internal_token_decimals(&self) -> u8 {
+        self.token_decimals.unwrap_or(DEFAULT_TOKEN_DECIMALS)
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // retry in case the call scheduled by `new` failed
+    pub fn refresh_token_metadata(&mut self) -> Promise {
+        self.internal_assert_role(Role::Admin);
+        self.internal_fetch_token_metadata()
+    }
+
+    #[private]
+    pub fn on_token_metadata(
+        &mut self,
+        #[callback_result] metadata: Result<FungibleTokenMetadata, PromiseError>,
+    ) -> Option<u8> {
+        let decimals = match metadata {
+            Ok(metadata) => metadata.decimals,
+            Err(_) => {
+                if self.token_verification_pending {
+                    env::log_str("token did not answer ft_metadata, staking stays paused");
+                }
+                return None;
+            }
+        };
+        self.token_decimals = Some(decimals);
+        // a verified token lifts the pause `new` started with
+        if self.token_verification_pending {
+            self.token_verification_pending = false;
+            self.paused = false;
+        }
+        Some(decimals)
+    }
+
+    pub fn get_token_decimals(&self) -> Option<u8> {
+        self.token_decimals
+    }
+}
@@ -0,0 +1,56 @@
+use near_sdk::json_types::U128;
+use near_sdk::{near_bindgen, AccountId};
+
+use crate::events::{StakeEvent, StakePartiallyAcceptedData};
+use crate::*;
+
+impl Contract {
+    // what can still be staked before total_staked hits the ceiling
+    pub(crate) fn internal_stake_capacity(&self) -> u128 {
+        self.max_total_staked
+            .map_or(u128::MAX, |cap| cap.saturating_sub(self.total_staked))
+    }
+
+    // room for stake that doesn't come from the entry queue, none while anyone is waiting.
+    // entries the head skipped don't wait for capacity any more
+    pub(crate) fn internal_open_capacity(&self) -> u128 {
+        if self.total_queued > self.total_skipped_queued {
+            return 0;
+        }
+        self.internal_stake_capacity()
+    }
+
+    // part of `amount` that fits under the ceiling, the caller refunds the rest
+    pub(crate) fn internal_accept_up_to_cap(&self, account_id: &AccountId, amount: u128) -> u128 {
+        let accepted = amount.min(self.internal_open_capacity());
+        if accepted < amount {
+            StakeEvent::StakePartiallyAccepted(vec![StakePartiallyAcceptedData {
+                account_id: account_id.clone(),
+                accepted: U128(accepted),
+                refunded: U128(amount - accepted),
+            }])
+            .emit();
+        }
+        accepted
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // lowering it below total_staked only blocks new stake, nobody is forced out
+    pub fn set_max_total_staked(&mut self, max_total_staked: Option<U128>) {
+        self.internal_assert_role(Role::Admin);
+        self.internal_record_config_change(
+            "max_total_staked",
+            self.max_total_staked.map(U128),
+            max_total_staked,
+        );
+        self.max_total_staked = max_total_staked.map(|cap| cap.0);
+        self.internal_process_queue(MAX_QUEUE_ACTIVATIONS);
+    }
+
+    pub fn get_stake_capacity(&self) -> Option<U128> {
+        self.max_total_staked
+            .map(|_| U128(self.internal_open_capacity()))
+    }
+}
@@ -0,0 +1,77 @@
+use near_sdk::json_types::U128;
+use near_sdk::serde::Serialize;
+use near_sdk::{near_bindgen, AccountId};
+
+use crate::math::{mul_div, BPS_DENOMINATOR};
+use crate::*;
+
+pub const MAX_UNSTAKE_FEE_BPS: u16 = 1_000;
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct UnstakePreview {
+    pub amount: U128,
+    pub fee: U128,
+    pub received: U128,
+}
+
+impl Contract {
+    pub(crate) fn internal_unstake_fee(&self, account_id: &AccountId, amount: u128) -> u128 {
+        if self.fee_exempt.contains(account_id) {
+            return 0;
+        }
+        mul_div(amount, self.unstake_fee_bps as u128, BPS_DENOMINATOR)
+    }
+
+    // an early exit penalty, forfeited like everything else a position gives up
+    pub(crate) fn internal_collect_unstake_fee(&mut self, fee: u128) {
+        if !self.internal_collect_penalty(fee) {
+            self.internal_forfeit(fee);
+        }
+        self.total_unstake_fees += fee;
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn preview_unstake(&self, account_id: AccountId, amount: U128) -> UnstakePreview {
+        let fee = self.internal_unstake_fee(&account_id, amount.0);
+        UnstakePreview {
+            amount,
+            fee: U128(fee),
+            received: U128(amount.0 - fee),
+        }
+    }
+
+    pub fn set_unstake_fee_bps(&mut self, unstake_fee_bps: u16) {
+        self.internal_assert_role(Role::Admin);
+        require!(
+            unstake_fee_bps <= MAX_UNSTAKE_FEE_BPS,
+            StakeError::UnstakeFeeTooHigh
+        );
+        self.internal_record_config_change(
+            "unstake_fee_bps",
+            self.unstake_fee_bps,
+            unstake_fee_bps,
+        );
+        self.unstake_fee_bps = unstake_fee_bps;
+    }
+
+    pub fn add_fee_exempt(&mut self, account_ids: Vec<AccountId>) {
+        self.internal_assert_role(Role::Admin);
+        for account_id in account_ids {
+            self.fee_exempt.insert(&account_id);
+        }
+    }
+
+    pub fn remove_fee_exempt(&mut self, account_ids: Vec<AccountId>) {
+        self.internal_assert_role(Role::Admin);
+        for account_id in account_ids {
+            self.fee_exempt.remove(&account_id);
+        }
+    }
+
+    pub fn is_fee_exempt(&self, account_id: AccountId) -> bool {
+        self.fee_exempt.contains(&account_id)
+    }
+}
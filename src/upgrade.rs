@@ -0,0 +1,138 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{Base58CryptoHash, Base64VecU8, U64};
+use near_sdk::serde::Serialize;
+use near_sdk::{env, near_bindgen, Gas, GasWeight, Promise};
+
+use crate::time::{Duration, Timestamp};
+use crate::*;
+
+pub const MIGRATE_GAS: Gas = Gas(20_000_000_000_000);
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StagedUpgrade {
+    pub code_hash: Base58CryptoHash,
+    pub staged_at: Timestamp,
+    pub executable_at: Timestamp,
+}
+
+impl Contract {
+    // only the hash is stored, the code itself is passed again to deploy_upgrade
+    pub(crate) fn internal_stage_upgrade(&mut self, code_hash: Base58CryptoHash) -> StagedUpgrade {
+        let now = Self::now();
+        let staged = StagedUpgrade {
+            code_hash,
+            staged_at: now,
+            executable_at: now + self.upgrade_delay,
+        };
+        self.staged_upgrade = Some(staged.clone());
+        staged
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn stage_upgrade(&mut self, code_hash: Base58CryptoHash) -> StagedUpgrade {
+        self.internal_assert_owner();
+        self.internal_assert_no_confirmations();
+        self.internal_stage_upgrade(code_hash)
+    }
+
+    pub fn cancel_upgrade(&mut self) {
+        self.internal_assert_owner();
+        self.staged_upgrade = None;
+    }
+
+    #[payable]
+    pub fn deploy_upgrade(&mut self, code: Base64VecU8, migrate: bool) -> Promise {
+        self.internal_assert_deposit("deploy_upgrade");
+        self.internal_assert_owner();
+        let staged = self
+            .staged_upgrade
+            .take()
+            .unwrap_or_else(|| env::panic_str(&StakeError::NoStagedUpgrade));
+        require!(
+            Self::now() >= staged.executable_at,
+            StakeError::UpgradeTimelocked
+        );
+        let code: Vec<u8> = code.into();
+        let code_hash: [u8; 32] = env::sha256(&code).try_into().unwrap();
+        require!(
+            Base58CryptoHash::from(code_hash) == staged.code_hash,
+            StakeError::CodeHashMismatch
+        );
+
+        let promise = Promise::new(env::current_account_id()).deploy_contract(code);
+        if !migrate {
+            return promise;
+        }
+        promise.function_call_weight(
+            "migrate_state".to_string(),
+            vec![],
+            0,
+            MIGRATE_GAS,
+            GasWeight(1),
+        )
+    }
+
+    // runs right after deploy_upgrade and reads the layout the running contract wrote.
+    // a release that changes the layout reads its predecessor here, `migrate` only
+    // understands the first deployment
+    #[private]
+    #[init(ignore_state)]
+    pub fn migrate_state() -> Self {
+        env::state_read().unwrap_or_else(|| env::panic_str(&StakeError::NoStateToMigrate))
+    }
+
+    // the delay can only grow so a compromised owner key can't skip it
+    pub fn set_upgrade_delay(&mut self, upgrade_delay: U64) {
+        self.internal_assert_owner();
+        let upgrade_delay = Duration::from_nanos(upgrade_delay.0);
+        require!(
+            upgrade_delay >= self.upgrade_delay,
+            StakeError::UpgradeDelayDecrease
+        );
+        self.upgrade_delay = upgrade_delay;
+    }
+
+    pub fn get_staged_upgrade(&self) -> Option<StagedUpgrade> {
+        self.staged_upgrade.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn migrate_state_keeps_the_current_state() {
+        let mut contract = setup();
+        stake(&mut contract, &account("alice"), 10 * ONE_TOKEN);
+        fund_reward_pool(&mut contract, 5 * ONE_TOKEN);
+        env::state_write(&contract);
+
+        call_as(&contract_id());
+        let migrated = Contract::migrate_state();
+        assert_eq!(migrated.owner_id, owner());
+        assert_eq!(migrated.total_staked, 10 * ONE_TOKEN);
+        assert_eq!(migrated.reward_pool, 5 * ONE_TOKEN);
+        assert_eq!(staked(&migrated, &account("alice")), 10 * ONE_TOKEN);
+    }
+
+    #[test]
+    fn deploy_upgrade_chains_into_migrate_state() {
+        let mut contract = setup();
+        let code = b"new code".to_vec();
+        let code_hash: [u8; 32] = env::sha256(&code).try_into().unwrap();
+        call_as(&owner());
+        contract.stage_upgrade(Base58CryptoHash::from(code_hash));
+
+        call_with_yocto(&owner());
+        contract.deploy_upgrade(Base64VecU8::from(code), true);
+        let calls = function_calls();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].0, contract_id());
+        assert_eq!(calls[0].1, "migrate_state");
+    }
+}
@@ -0,0 +1,115 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{near_bindgen, require};
+
+use crate::math::{mul_div, BPS_DENOMINATOR};
+use crate::*;
+
+// APRs read as a yield in `quote`: rewards are scaled by reference_price over the pushed
+// price, so a falling token pays out more tokens. the scale stays within
+// max_deviation_bps of neutral, and a stale price falls back to neutral
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct UsdPeg {
+    pub quote: String,
+    // price of one whole token the APRs were set against, scaled by 10^decimals
+    pub reference_price: U128,
+    pub decimals: u8,
+    pub max_deviation_bps: u16,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct UsdPegView {
+    pub peg: Option<UsdPeg>,
+    pub price: Option<PricePoint>,
+    pub stale: bool,
+    pub modifier_bps: u32,
+}
+
+impl Contract {
+    fn internal_peg_price(&self, peg: &UsdPeg) -> Option<PricePoint> {
+        self.prices
+            .get(&peg.quote)
+            .filter(|point| Self::now() <= point.updated_at + self.max_price_age)
+    }
+
+    fn internal_peg_modifier(&self, peg: &UsdPeg) -> u32 {
+        let point = match self.internal_peg_price(peg) {
+            Some(point) => point,
+            None => return NEUTRAL_APR_MODIFIER_BPS,
+        };
+        let reference = mul_div(
+            peg.reference_price.0,
+            10u128.pow(point.decimals as u32),
+            10u128.pow(peg.decimals as u32),
+        );
+        let neutral = NEUTRAL_APR_MODIFIER_BPS as u128;
+        let deviation = peg.max_deviation_bps as u128;
+        mul_div(BPS_DENOMINATOR, reference, point.price.0)
+            .clamp(neutral - deviation, neutral + deviation) as u32
+    }
+
+    // recomputed after every price push, a push for another quote changes nothing
+    pub(crate) fn internal_sync_usd_peg(&mut self) {
+        if let Some(peg) = self.usd_peg.clone() {
+            let modifier_bps = self.internal_peg_modifier(&peg);
+            if modifier_bps != self.apr_modifier_bps {
+                self.internal_set_apr_modifier(modifier_bps);
+            }
+        }
+    }
+
+    pub(crate) fn internal_assert_no_usd_peg(&self) {
+        require!(self.usd_peg.is_none(), StakeError::UsdPegActive);
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // takes the APR modifier over from the metric curve, None hands it back at neutral
+    pub fn set_usd_peg(&mut self, peg: Option<UsdPeg>) {
+        self.internal_assert_role(Role::Admin);
+        if let Some(peg) = &peg {
+            require!(self.apr_curve.is_empty(), StakeError::AprCurveSet);
+            require!(
+                !peg.quote.is_empty()
+                    && peg.quote.len() <= MAX_QUOTE_LEN
+                    && peg.reference_price.0 > 0
+                    && peg.decimals <= MAX_PRICE_DECIMALS
+                    && (peg.max_deviation_bps as u128) < BPS_DENOMINATOR,
+                StakeError::InvalidUsdPeg
+            );
+        }
+        self.internal_record_config_change("usd_peg", self.usd_peg.clone(), peg.clone());
+        self.usd_peg = peg;
+        if self.usd_peg.is_some() {
+            self.internal_sync_usd_peg();
+        } else {
+            self.internal_set_apr_modifier(NEUTRAL_APR_MODIFIER_BPS);
+        }
+    }
+
+    // permissionless, drops the modifier back to neutral once the price went stale
+    pub fn sync_usd_peg(&mut self) -> u32 {
+        self.internal_sync_usd_peg();
+        self.apr_modifier_bps
+    }
+
+    pub fn get_usd_peg(&self) -> UsdPegView {
+        let price = self
+            .usd_peg
+            .as_ref()
+            .and_then(|peg| self.prices.get(&peg.quote));
+        UsdPegView {
+            stale: self
+                .usd_peg
+                .as_ref()
+                .is_some_and(|peg| self.internal_peg_price(peg).is_none()),
+            peg: self.usd_peg.clone(),
+            price,
+            modifier_bps: self.apr_modifier_bps,
+        }
+    }
+}
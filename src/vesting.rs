@@ -0,0 +1,156 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, AccountId};
+
+use crate::math::{mul_div, BPS_DENOMINATOR};
+use crate::time::{Duration, Timestamp};
+use crate::*;
+
+pub const MAX_VESTING_ENTRIES: usize = 20;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct VestingConfig {
+    // part of every claim that is withdrawable right away
+    pub instant_bps: u16,
+    pub duration: Duration,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct VestingEntry {
+    pub amount: U128,
+    pub released: U128,
+    pub start: Timestamp,
+    pub end: Timestamp,
+}
+
+impl VestingEntry {
+    fn unlocked_at(&self, timestamp: Timestamp) -> u128 {
+        if timestamp >= self.end {
+            return self.amount.0;
+        }
+        let elapsed = Duration::between(self.start, timestamp);
+        let total = Duration::between(self.start, self.end);
+        mul_div(
+            self.amount.0,
+            elapsed.as_nanos() as u128,
+            total.as_nanos() as u128,
+        )
+    }
+
+    fn releasable_at(&self, timestamp: Timestamp) -> u128 {
+        self.unlocked_at(timestamp) - self.released.0
+    }
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct VestingView {
+    pub entries: Vec<VestingEntry>,
+    pub locked: U128,
+    pub claimable: U128,
+}
+
+impl Contract {
+    // claimed rewards go through here, with vesting on only part of them is withdrawable now
+    pub(crate) fn internal_release_reward(
+        &mut self,
+        account_id: &AccountId,
+        stake_info: &mut StakeInfo,
+        reward: u128,
+    ) {
+        let config = match self.vesting_config.clone() {
+            Some(config) => config,
+            None => {
+                self.internal_credit_withdrawable(stake_info, reward);
+                return;
+            }
+        };
+        let instant = mul_div(reward, config.instant_bps as u128, BPS_DENOMINATOR);
+        self.internal_credit_withdrawable(stake_info, instant);
+
+        let vested = reward - instant;
+        if vested == 0 {
+            return;
+        }
+        let mut entries = self.vesting.get(account_id).unwrap_or_default();
+        require!(
+            entries.len() < MAX_VESTING_ENTRIES,
+            StakeError::TooManyVestingEntries
+        );
+        let now = Self::now();
+        entries.push(VestingEntry {
+            amount: U128(vested),
+            released: U128(0),
+            start: now,
+            end: now + config.duration,
+        });
+        self.vesting.insert(account_id, &entries);
+        self.total_vesting += vested;
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    #[payable]
+    pub fn claim_vested(&mut self) -> U128 {
+        self.internal_assert_deposit("claim_vested");
+        let account_id = env::predecessor_account_id();
+        let mut entries = self
+            .vesting
+            .get(&account_id)
+            .unwrap_or_else(|| env::panic_str(&StakeError::NotVesting));
+        let mut stake_info = self
+            .internal_touch(&account_id)
+            .unwrap_or_else(|| env::panic_str(&StakeError::NotStaked));
+        Self::internal_assert_no_pending_operation(&stake_info);
+
+        let now = Self::now();
+        let mut released = 0;
+        for entry in entries.iter_mut() {
+            let amount = entry.releasable_at(now);
+            entry.released = U128(entry.released.0 + amount);
+            released += amount;
+        }
+        require!(released > 0, StakeError::NothingVested);
+        entries.retain(|entry| entry.released.0 < entry.amount.0);
+        if entries.is_empty() {
+            self.vesting.remove(&account_id);
+        } else {
+            self.vesting.insert(&account_id, &entries);
+        }
+
+        self.total_vesting -= released;
+        self.internal_credit_withdrawable(&mut stake_info, released);
+        self.internal_save_stake_info(&account_id, &stake_info);
+        U128(released)
+    }
+
+    pub fn set_vesting_config(&mut self, config: Option<VestingConfig>) {
+        self.internal_assert_role(Role::Admin);
+        if let Some(config) = &config {
+            require!(
+                config.instant_bps as u128 <= BPS_DENOMINATOR,
+                StakeError::InvalidVestingConfig
+            );
+        }
+        self.vesting_config = config;
+    }
+
+    pub fn get_vesting(&self, account_id: AccountId) -> VestingView {
+        let entries = self.vesting.get(&account_id).unwrap_or_default();
+        let now = Self::now();
+        VestingView {
+            locked: U128(
+                entries
+                    .iter()
+                    .map(|entry| entry.amount.0 - entry.unlocked_at(now))
+                    .sum(),
+            ),
+            claimable: U128(entries.iter().map(|entry| entry.releasable_at(now)).sum()),
+            entries,
+        }
+    }
+}
@@ -0,0 +1,199 @@
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::Serialize;
+use near_sdk::{near_bindgen, AccountId};
+
+use crate::*;
+
+pub const MAX_BATCH_ACCOUNTS: usize = 100;
+pub const CONTRACT_SPEC: &str = "spk-stake-1.0.0";
+pub const SUPPORTED_STANDARDS: &[&str] = &["nep141-receiver", "nep297"];
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ContractMetadata {
+    pub name: String,
+    pub version: String,
+    pub spec: String,
+    pub standards: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StakeInfoJson {
+    pub account_id: AccountId,
+    pub time_staked: U64,
+    pub amount_staked: U128,
+    pub reward: U128,
+    pub pending_reward: U128,
+    pub apr: U128,
+    pub votes: u8,
+    pub withdrawable: U128,
+    pub state: PositionState,
+    pub state_changed_at: U64,
+    pub opened_at: U64,
+    pub votes_received: U64,
+    pub rating_counts: [u32; 5],
+    pub paused_accrual_since: Option<U64>,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ConfigView {
+    pub owner_id: AccountId,
+    pub token_address: AccountId,
+    pub token_decimals: Option<u8>,
+    pub default_apr: U128,
+    pub min_apr: U128,
+    pub max_apr: U128,
+    pub vote_step: U128,
+    pub vote_weights: [i64; 5],
+    pub vote_curve: Vec<CurveSegment>,
+    pub vote_grace_period: U64,
+    pub vote_grace_votes: u64,
+    pub reputation_half_life: U64,
+    pub vote_horizon: Option<U64>,
+    pub faucet_amount: U128,
+    pub faucet_cooldown: U64,
+    pub allow_list_enabled: bool,
+    pub attestation_registry: Option<AccountId>,
+    pub unbond_period: U64,
+    pub min_advisor_stake: U128,
+    pub vesting_config: Option<VestingConfig>,
+    pub claim_cooldown: U64,
+    pub unstake_fee_bps: u16,
+    pub upgrade_delay: U64,
+    pub snapshot_interval: U64,
+    pub max_total_staked: Option<U128>,
+    pub stake_cancel_window: U64,
+    pub pause_accrual_on_hold: bool,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Summary {
+    pub version: String,
+    pub total_staked: U128,
+    pub total_stakers: U128,
+    pub total_rewards_paid: U128,
+    pub total_pending_rewards: U128,
+    pub reward_pool: U128,
+    pub protocol_fees_collected: U128,
+    pub protocol_fees_unclaimed: U128,
+    pub config: ConfigView,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Totals {
+    pub total_staked: U128,
+    pub total_stakers: U128,
+}
+
+impl Contract {
+    pub(crate) fn internal_stake_info_json(
+        &self,
+        account_id: AccountId,
+        stake_info: StakeInfo,
+    ) -> StakeInfoJson {
+        StakeInfoJson {
+            account_id,
+            time_staked: stake_info.time_staked.into(),
+            amount_staked: U128(stake_info.amount_staked),
+            reward: U128(stake_info.reward),
+            pending_reward: U128(self.internal_pending_reward_at(&stake_info, Self::now())),
+            apr: U128(stake_info.apr),
+            votes: stake_info.votes,
+            withdrawable: U128(stake_info.withdrawable),
+            state: self.internal_position_state(&stake_info),
+            state_changed_at: stake_info.state_changed_at.into(),
+            opened_at: stake_info.terms.opened_at.into(),
+            votes_received: U64(stake_info.votes_received),
+            rating_counts: stake_info.rating_counts,
+            paused_accrual_since: stake_info.paused_accrual_since.map(Into::into),
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn get_stake_info(&self, account_id: AccountId) -> Option<StakeInfoJson> {
+        self.internal_get_stake_info(&account_id)
+            .map(|stake_info| self.internal_stake_info_json(account_id, stake_info))
+    }
+
+    // unknown accounts come back as null instead of failing the whole call
+    pub fn get_accounts(&self, account_ids: Vec<AccountId>) -> Vec<Option<StakeInfoJson>> {
+        require!(
+            account_ids.len() <= MAX_BATCH_ACCOUNTS,
+            StakeError::TooManyAccounts
+        );
+        account_ids
+            .into_iter()
+            .map(|account_id| self.get_stake_info(account_id))
+            .collect()
+    }
+
+    pub fn contract_metadata(&self) -> ContractMetadata {
+        ContractMetadata {
+            name: env!("CARGO_PKG_NAME").to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            spec: CONTRACT_SPEC.to_string(),
+            standards: SUPPORTED_STANDARDS.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    pub fn get_config(&self) -> ConfigView {
+        ConfigView {
+            owner_id: self.owner_id.clone(),
+            token_address: self.token_address.clone(),
+            token_decimals: self.token_decimals,
+            default_apr: U128(self.default_apr),
+            min_apr: U128(self.min_apr),
+            max_apr: U128(self.max_apr),
+            vote_step: U128(self.vote_step),
+            vote_weights: self.vote_weights,
+            vote_curve: self.vote_curve.clone(),
+            vote_grace_period: self.vote_grace_period.as_nanos().into(),
+            vote_grace_votes: self.vote_grace_votes,
+            reputation_half_life: self.reputation_half_life.into(),
+            vote_horizon: self.vote_horizon.map(U64::from),
+            faucet_amount: U128(self.faucet_amount),
+            faucet_cooldown: U64(self.faucet_cooldown.as_nanos()),
+            allow_list_enabled: self.allow_list_enabled,
+            attestation_registry: self.attestation_registry.clone(),
+            unbond_period: U64(self.unbond_period.as_nanos()),
+            min_advisor_stake: U128(self.min_advisor_stake),
+            vesting_config: self.vesting_config.clone(),
+            claim_cooldown: self.claim_cooldown.into(),
+            unstake_fee_bps: self.unstake_fee_bps,
+            upgrade_delay: self.upgrade_delay.into(),
+            snapshot_interval: self.snapshot_interval.into(),
+            max_total_staked: self.max_total_staked.map(U128),
+            stake_cancel_window: self.stake_cancel_window.into(),
+            pause_accrual_on_hold: self.pause_accrual_on_hold,
+        }
+    }
+
+    pub fn get_totals(&self) -> Totals {
+        Totals {
+            total_staked: U128(self.total_staked),
+            total_stakers: U128(self.total_stakers),
+        }
+    }
+
+    pub fn get_summary(&self) -> Summary {
+        Summary {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            total_staked: U128(self.total_staked),
+            total_stakers: U128(self.total_stakers),
+            total_rewards_paid: U128(self.total_rewards_paid),
+            total_pending_rewards: U128(
+                self.accrued_rewards + self.internal_accrued_since_update(Self::now()),
+            ),
+            reward_pool: U128(self.reward_pool),
+            protocol_fees_collected: U128(self.total_protocol_fees),
+            protocol_fees_unclaimed: U128(self.protocol_fee_balance),
+            config: self.get_config(),
+        }
+    }
+}
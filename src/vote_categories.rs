@@ -0,0 +1,111 @@
+use near_sdk::serde::Serialize;
+use near_sdk::{env, near_bindgen, require, AccountId};
+
+use crate::math::BPS_DENOMINATOR;
+use crate::*;
+
+pub const MAX_CATEGORY_LEN: usize = 64;
+// a category can at most double what its votes move reputation by
+pub const MAX_CATEGORY_WEIGHT_BPS: u16 = 20_000;
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct VoteCategoryView {
+    pub category: String,
+    pub weight_bps: u16,
+}
+
+impl Contract {
+    // a VoterRelay may tag any category, a relay scoped to a course category or region
+    // only its own, and its untagged votes count as its own. returns the category in effect
+    pub(crate) fn internal_assert_vote_authority(
+        &self,
+        category: Option<String>,
+    ) -> Option<String> {
+        self.internal_vote_authority(&env::predecessor_account_id(), category)
+            .unwrap_or_else(|error| env::panic_str(&error))
+    }
+
+    // the category a vote from `relay_id` counts under
+    pub(crate) fn internal_vote_authority(
+        &self,
+        relay_id: &AccountId,
+        category: Option<String>,
+    ) -> Result<Option<String>, StakeError> {
+        if self.internal_has_role(relay_id, Role::VoterRelay) {
+            return Ok(category);
+        }
+        let scope = self
+            .category_relays
+            .get(relay_id)
+            .ok_or(StakeError::MissingRole)?;
+        if category.as_ref().is_some_and(|category| category != &scope) {
+            return Err(StakeError::CategoryOutOfScope);
+        }
+        Ok(Some(scope))
+    }
+
+    // categories without a weight count in full
+    pub(crate) fn internal_category_weight_bps(&self, category: Option<&String>) -> u16 {
+        category
+            .and_then(|category| self.category_weights.get(category))
+            .unwrap_or(BPS_DENOMINATOR as u16)
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn register_category_relay(&mut self, relay_id: AccountId, category: String) {
+        self.internal_assert_owner();
+        require!(
+            !category.is_empty() && category.len() <= MAX_CATEGORY_LEN,
+            StakeError::InvalidCategory
+        );
+        self.category_relays.insert(&relay_id, &category);
+    }
+
+    pub fn remove_category_relay(&mut self, relay_id: AccountId) -> Option<String> {
+        self.internal_assert_owner();
+        self.category_relays.remove(&relay_id)
+    }
+
+    pub fn set_category_weight(&mut self, category: String, weight_bps: Option<u16>) {
+        self.internal_assert_role(Role::Admin);
+        require!(
+            !category.is_empty() && category.len() <= MAX_CATEGORY_LEN,
+            StakeError::InvalidCategory
+        );
+        let old = self.internal_category_weight_bps(Some(&category));
+        match weight_bps {
+            Some(weight_bps) => {
+                require!(
+                    weight_bps <= MAX_CATEGORY_WEIGHT_BPS,
+                    StakeError::InvalidCategoryWeight
+                );
+                self.category_weights.insert(&category, &weight_bps);
+            }
+            None => {
+                self.category_weights.remove(&category);
+            }
+        }
+        self.internal_record_config_change(
+            &format!("category_weight:{}", category),
+            old,
+            self.internal_category_weight_bps(Some(&category)),
+        );
+    }
+
+    pub fn get_relay_category(&self, relay_id: AccountId) -> Option<String> {
+        self.category_relays.get(&relay_id)
+    }
+
+    pub fn get_category_weights(&self) -> Vec<VoteCategoryView> {
+        self.category_weights
+            .iter()
+            .map(|(category, weight_bps)| VoteCategoryView {
+                category,
+                weight_bps,
+            })
+            .collect()
+    }
+}
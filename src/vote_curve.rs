@@ -0,0 +1,68 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{near_bindgen, require};
+
+use crate::math::{mul_div, BPS_DENOMINATOR};
+use crate::*;
+
+pub const MAX_CURVE_SEGMENTS: usize = 8;
+
+// inside a band of deviation from the base APR each vote step only moves the APR by
+// `scale_bps` of a step. past the last band the last scale keeps applying
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CurveSegment {
+    pub until: U128,
+    pub scale_bps: u16,
+}
+
+impl Contract {
+    // maps the deviation the flat steps would give onto the curve, an empty curve is
+    // the flat steps
+    pub(crate) fn internal_curved_deviation(&self, linear: u128) -> u128 {
+        let mut remaining = linear;
+        let mut deviation = 0;
+        let mut scale_bps = BPS_DENOMINATOR;
+        for segment in &self.vote_curve {
+            scale_bps = segment.scale_bps as u128;
+            let width = segment.until.0 - deviation;
+            let cost = mul_div(width, BPS_DENOMINATOR, scale_bps);
+            if remaining < cost {
+                return deviation + mul_div(remaining, scale_bps, BPS_DENOMINATOR);
+            }
+            remaining -= cost;
+            deviation = segment.until.0;
+        }
+        deviation + mul_div(remaining, scale_bps, BPS_DENOMINATOR)
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // bands must widen and can only damp a step, existing advisors move onto the new
+    // curve the next time their score moves
+    pub fn set_vote_curve(&mut self, curve: Vec<CurveSegment>) {
+        self.internal_assert_role(Role::Admin);
+        require!(
+            curve.len() <= MAX_CURVE_SEGMENTS,
+            StakeError::InvalidVoteCurve
+        );
+        let mut previous = 0;
+        for segment in &curve {
+            require!(
+                segment.until.0 > previous
+                    && segment.scale_bps > 0
+                    && segment.scale_bps as u128 <= BPS_DENOMINATOR,
+                StakeError::InvalidVoteCurve
+            );
+            previous = segment.until.0;
+        }
+        self.internal_record_config_change("vote_curve", self.vote_curve.clone(), curve.clone());
+        self.vote_curve = curve;
+    }
+
+    pub fn get_vote_curve(&self) -> Vec<CurveSegment> {
+        self.vote_curve.clone()
+    }
+}
@@ -0,0 +1,95 @@
+use near_sdk::json_types::U128;
+use near_sdk::serde::Deserialize;
+use near_sdk::{near_bindgen, require, AccountId};
+
+use crate::*;
+
+// the fields of update_apr, carried in the msg of a paid vote
+#[derive(Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PaidVote {
+    pub advisor: AccountId,
+    pub rating: u8,
+    pub course_id: Option<String>,
+    pub op_id: Option<String>,
+    pub category: Option<String>,
+    pub metadata: Option<VoteMetadata>,
+}
+
+impl Contract {
+    // while a fee is set relayed votes have to arrive through the token with it
+    pub(crate) fn internal_assert_vote_fee_off(&self) {
+        require!(self.vote_fee == 0, StakeError::VoteFeeRequired);
+    }
+
+    // update_apr with the fee on top, the relay sending the transfer is authorized and
+    // its vote tenure checked the same way. the whole transfer goes into the reward pool.
+    // every check runs before anything is written so a refused vote is refunded, a
+    // replayed op_id included
+    pub(crate) fn internal_paid_vote(
+        &mut self,
+        relay_id: &AccountId,
+        vote: PaidVote,
+        amount: u128,
+    ) -> Result<(), StakeError> {
+        let PaidVote {
+            advisor,
+            rating,
+            course_id,
+            op_id,
+            category,
+            metadata,
+        } = vote;
+        let advisor_id = &advisor;
+        if self.vote_fee == 0 {
+            return Err(StakeError::VoteFeeDisabled);
+        }
+        if amount < self.vote_fee {
+            return Err(StakeError::VoteFeeTooLow);
+        }
+        if self.paused {
+            return Err(StakeError::Paused);
+        }
+        let category = self.internal_vote_authority(relay_id, category)?;
+        if relay_id == advisor_id || rating == 0 || rating as usize > self.vote_weights.len() {
+            return Err(StakeError::InvalidVote);
+        }
+        if let Some(op_id) = &op_id {
+            if op_id.len() > MAX_OP_ID_LEN {
+                return Err(StakeError::InvalidOpId);
+            }
+            if self
+                .processed_ops
+                .contains_key(&(relay_id.clone(), op_id.clone()))
+            {
+                return Err(StakeError::OpAlreadyProcessed);
+            }
+        }
+        if self.internal_get_stake_info(advisor_id).is_none() {
+            return Err(StakeError::AdvisorNotStaked);
+        }
+        if !self.internal_is_permitted(advisor_id) {
+            return Err(StakeError::AdvisorNotPermitted);
+        }
+        self.reward_pool += amount;
+        self.internal_idempotent_for(relay_id, op_id, |contract| {
+            let counts = contract.internal_check_vote_tenure(relay_id, metadata.as_ref());
+            contract.internal_update_apr(advisor_id, relay_id, rating, course_id, category, counts)
+        });
+        Ok(())
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // zero turns paid votes off and relayed votes back on
+    pub fn set_vote_fee(&mut self, vote_fee: U128) {
+        self.internal_assert_role(Role::Admin);
+        self.internal_record_config_change("vote_fee", U128(self.vote_fee), vote_fee);
+        self.vote_fee = vote_fee.0;
+    }
+
+    pub fn get_vote_fee(&self) -> U128 {
+        U128(self.vote_fee)
+    }
+}
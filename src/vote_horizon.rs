@@ -0,0 +1,172 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::LookupMap;
+use near_sdk::json_types::U64;
+use near_sdk::{near_bindgen, AccountId};
+
+use crate::time::{Duration, Timestamp};
+use crate::*;
+
+// expired votes dropped per touch, a long backlog catches up over the next calls
+pub const MAX_VOTE_EXPIRIES: u64 = 50;
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct VoteDelta {
+    pub vote_id: u64,
+    pub cast_at: Timestamp,
+    pub delta: i64,
+}
+
+// every reputation delta an advisor received, oldest at `head`. `sum` covers head..tail
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct VoteWindow {
+    entries: LookupMap<u64, VoteDelta>,
+    head: u64,
+    tail: u64,
+    sum: i64,
+}
+
+impl VoteWindow {
+    fn new(advisor_id: &AccountId) -> Self {
+        VoteWindow {
+            entries: LookupMap::new(StorageKey::vote_window_entries(advisor_id)),
+            head: 0,
+            tail: 0,
+            sum: 0,
+        }
+    }
+
+    // sum and head once the deltas cast at or before `cutoff` are gone
+    fn expire(&self, cutoff: Timestamp) -> (u64, i64) {
+        let (mut head, mut sum) = (self.head, self.sum);
+        while head < self.tail && head - self.head < MAX_VOTE_EXPIRIES {
+            match self.entries.get(&head) {
+                Some(entry) if entry.cast_at > cutoff => break,
+                Some(entry) => sum -= entry.delta,
+                None => {}
+            }
+            head += 1;
+        }
+        (head, sum)
+    }
+}
+
+impl Contract {
+    fn internal_vote_cutoff(&self, now: Timestamp) -> Option<Timestamp> {
+        self.vote_horizon
+            .map(|horizon| Timestamp::from_nanos(now.as_nanos().saturating_sub(horizon.as_nanos())))
+    }
+
+    pub(crate) fn internal_push_vote_delta(
+        &mut self,
+        advisor_id: &AccountId,
+        vote_id: u64,
+        delta: i64,
+    ) {
+        let mut window = self
+            .vote_windows
+            .get(advisor_id)
+            .unwrap_or_else(|| VoteWindow::new(advisor_id));
+        window.entries.insert(
+            &window.tail,
+            &VoteDelta {
+                vote_id,
+                cast_at: Self::now(),
+                delta,
+            },
+        );
+        window.tail += 1;
+        window.sum += delta;
+        self.vote_windows.insert(advisor_id, &window);
+    }
+
+    // zeroes a vote's delta, false when it already expired out of the window
+    pub(crate) fn internal_forget_vote_delta(
+        &mut self,
+        advisor_id: &AccountId,
+        vote_id: u64,
+    ) -> bool {
+        let mut window = match self.vote_windows.get(advisor_id) {
+            Some(window) => window,
+            None => return false,
+        };
+        // from the newest end, appeals only reach back over the appeal window
+        for index in (window.head..window.tail).rev() {
+            if let Some(mut entry) = window.entries.get(&index) {
+                if entry.vote_id == vote_id {
+                    window.sum -= entry.delta;
+                    entry.delta = 0;
+                    window.entries.insert(&index, &entry);
+                    self.vote_windows.insert(advisor_id, &window);
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    // with a horizon set the score is just the sum of deltas younger than it, synced into
+    // the record on every touch so the apr formula keeps reading `reputation`
+    pub(crate) fn internal_windowed_reputation(
+        &self,
+        advisor_id: &AccountId,
+        now: Timestamp,
+    ) -> Option<i64> {
+        let cutoff = self.internal_vote_cutoff(now)?;
+        Some(
+            self.vote_windows
+                .get(advisor_id)
+                .map_or(0, |window| window.expire(cutoff).1),
+        )
+    }
+
+    pub(crate) fn internal_expire_votes(
+        &mut self,
+        advisor_id: &AccountId,
+        stake_info: &mut StakeInfo,
+    ) {
+        let now = Self::now();
+        let cutoff = match self.internal_vote_cutoff(now) {
+            Some(cutoff) => cutoff,
+            None => return,
+        };
+        let mut score = 0;
+        if let Some(mut window) = self.vote_windows.get(advisor_id) {
+            let (head, sum) = window.expire(cutoff);
+            for index in window.head..head {
+                window.entries.remove(&index);
+            }
+            window.head = head;
+            window.sum = sum;
+            score = sum;
+            self.vote_windows.insert(advisor_id, &window);
+        }
+        stake_info.reputation = score;
+        stake_info.reputation_updated_at = now;
+
+        let old_apr = stake_info.apr;
+        stake_info.apr = self.internal_apr_for_reputation(stake_info);
+        if stake_info.apr != old_apr {
+            self.internal_update_reward_rate(
+                stake_info.amount_staked,
+                stake_info.boosted(old_apr),
+                stake_info.amount_staked,
+                stake_info.boosted(stake_info.apr),
+            );
+            self.internal_record_apr_change(
+                advisor_id,
+                old_apr,
+                stake_info.apr,
+                AprChangeCause::VotesExpired,
+            );
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // None goes back to the half life decay from whatever score was synced last
+    pub fn set_vote_horizon(&mut self, vote_horizon: Option<U64>) {
+        self.internal_assert_role(Role::Admin);
+        self.vote_horizon = vote_horizon.map(|horizon| Duration::from_nanos(horizon.0));
+    }
+}
@@ -0,0 +1,94 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U64;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{near_bindgen, require, AccountId};
+
+use crate::time::{Duration, Timestamp};
+use crate::*;
+
+pub const MAX_LEARNER_ID_LEN: usize = 64;
+
+// what the relay knows about the interaction a vote comes from
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct VoteMetadata {
+    // the platform's learner identity, only kept for the trust team
+    pub learner_id: Option<String>,
+    pub interaction_started_at: Timestamp,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Default)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RejectedVoteStats {
+    pub rejected: u64,
+    pub last_rejected_at: Timestamp,
+    pub last_learner_id: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct VoteTenureView {
+    pub min_session_duration: U64,
+    pub total_rejected: U64,
+}
+
+impl Contract {
+    // a relayed vote only moves apr once its interaction is min_session_duration old, a
+    // younger or undated one is recorded like a grace vote and counted against the relay
+    pub(crate) fn internal_check_vote_tenure(
+        &mut self,
+        relay_id: &AccountId,
+        metadata: Option<&VoteMetadata>,
+    ) -> bool {
+        if let Some(learner_id) = metadata.and_then(|metadata| metadata.learner_id.as_ref()) {
+            require!(
+                learner_id.len() <= MAX_LEARNER_ID_LEN,
+                StakeError::InvalidVoteMetadata
+            );
+        }
+        if self.min_session_duration.as_nanos() == 0 {
+            return true;
+        }
+        let now = Self::now();
+        let old_enough = metadata.is_some_and(|metadata| {
+            metadata.interaction_started_at <= now
+                && Duration::between(metadata.interaction_started_at, now)
+                    >= self.min_session_duration
+        });
+        if old_enough {
+            return true;
+        }
+        let mut stats = self.rejected_votes.get(relay_id).unwrap_or_default();
+        stats.rejected += 1;
+        stats.last_rejected_at = now;
+        stats.last_learner_id = metadata.and_then(|metadata| metadata.learner_id.clone());
+        self.rejected_votes.insert(relay_id, &stats);
+        self.total_rejected_votes += 1;
+        false
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // zero turns the check off
+    pub fn set_min_session_duration(&mut self, min_session_duration: U64) {
+        self.internal_assert_role(Role::Admin);
+        self.internal_record_config_change(
+            "min_session_duration",
+            U64(self.min_session_duration.as_nanos()),
+            min_session_duration,
+        );
+        self.min_session_duration = Duration::from_nanos(min_session_duration.0);
+    }
+
+    pub fn get_vote_tenure(&self) -> VoteTenureView {
+        VoteTenureView {
+            min_session_duration: self.min_session_duration.into(),
+            total_rejected: U64(self.total_rejected_votes),
+        }
+    }
+
+    pub fn get_rejected_vote_stats(&self, relay_id: AccountId) -> Option<RejectedVoteStats> {
+        self.rejected_votes.get(&relay_id)
+    }
+}
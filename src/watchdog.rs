@@ -0,0 +1,135 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::Serialize;
+use near_sdk::{near_bindgen, require};
+
+use crate::events::{CounterPatchedData, StakeEvent};
+use crate::*;
+
+pub const MAX_RECONCILE_BATCH: u64 = 100;
+
+// running sums of a reconcile pass. any position written while it runs restarts it, so
+// a finished pass saw one consistent state
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct ReconcileJob {
+    pub next_index: u64,
+    pub stake_writes: u64,
+    pub stakers: u128,
+    pub staked: u128,
+    pub apr: u128,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ReconcileReport {
+    // where the next call picks up, None once the pass finished
+    pub next_index: Option<U64>,
+    pub restarted: bool,
+    pub patched: Vec<CounterPatchedData>,
+}
+
+impl Contract {
+    // every position write and removal counts, a pass compares it before each page
+    pub(crate) fn internal_note_stake_write(&mut self) {
+        self.stake_writes += 1;
+    }
+
+    fn internal_patch_counter(
+        patched: &mut Vec<CounterPatchedData>,
+        counter: &str,
+        current: &mut u128,
+        actual: u128,
+    ) {
+        if *current != actual {
+            patched.push(CounterPatchedData {
+                counter: counter.to_string(),
+                old: U128(*current),
+                new: U128(actual),
+            });
+            *current = actual;
+        }
+    }
+
+    fn internal_finish_reconcile(&mut self, job: ReconcileJob) -> Vec<CounterPatchedData> {
+        let mut patched = vec![];
+        // stake moved into groups is counted once, at what the groups hold
+        let staked = job.staked + self.total_grouped;
+        Self::internal_patch_counter(
+            &mut patched,
+            "total_stakers",
+            &mut self.total_stakers,
+            job.stakers,
+        );
+        Self::internal_patch_counter(&mut patched, "total_staked", &mut self.total_staked, staked);
+        Self::internal_patch_counter(&mut patched, "total_apr", &mut self.total_apr, job.apr);
+        if !patched.is_empty() {
+            StakeEvent::CountersPatched(patched.clone()).emit();
+        }
+        patched
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // permissionless so a keeper can run it page by page, from_index 0 starts a new pass.
+    // counters are only patched once a pass covered the whole staker index
+    pub fn reconcile(&mut self, from_index: U64, limit: U64) -> ReconcileReport {
+        require!(!self.legacy_pending, StakeError::LegacyPending);
+        let limit = limit.0.min(MAX_RECONCILE_BATCH);
+        let mut job = match self.reconcile_job.take() {
+            Some(job)
+                if job.next_index == from_index.0 && job.stake_writes == self.stake_writes =>
+            {
+                job
+            }
+            Some(job) if job.next_index == from_index.0 => {
+                return ReconcileReport {
+                    next_index: Some(U64(0)),
+                    restarted: true,
+                    patched: vec![],
+                };
+            }
+            _ => {
+                require!(from_index.0 == 0, StakeError::ReconcileOutOfOrder);
+                ReconcileJob {
+                    next_index: 0,
+                    stake_writes: self.stake_writes,
+                    stakers: 0,
+                    staked: 0,
+                    apr: 0,
+                }
+            }
+        };
+
+        let staker_ids = self.staker_ids.as_vector();
+        let end = staker_ids.len().min(job.next_index.saturating_add(limit));
+        for index in job.next_index..end {
+            let account_id = staker_ids.get(index).unwrap();
+            if let Some(stake_info) = self.internal_peek_stake_info(&account_id) {
+                job.stakers += 1;
+                job.staked += stake_info.amount_staked + stake_info.delegated;
+                job.apr += stake_info.apr;
+            }
+        }
+        job.next_index = end;
+        if end < staker_ids.len() {
+            self.reconcile_job = Some(job);
+            return ReconcileReport {
+                next_index: Some(U64(end)),
+                restarted: false,
+                patched: vec![],
+            };
+        }
+        ReconcileReport {
+            next_index: None,
+            restarted: false,
+            patched: self.internal_finish_reconcile(job),
+        }
+    }
+
+    // every record the pre-migration layout left has been touched, and so is indexed
+    pub fn mark_legacy_migrated(&mut self) {
+        self.internal_assert_owner();
+        self.legacy_pending = false;
+    }
+}
@@ -0,0 +1,422 @@
+use near_sdk::json_types::U128;
+use near_sdk::{env, near_bindgen, AccountId, PromiseError, PromiseOrValue};
+
+use crate::events::{StakeEvent, WithdrawnData};
+use crate::*;
+
+// bytes, callers tag payouts with invoice ids and the like
+pub const MAX_MEMO_LEN: usize = 128;
+
+// what one transfer to a receiver is made of. amounts owed within a call are summed so
+// they leave in a single ft_transfer, the memo lists the parts
+#[derive(Default)]
+pub struct Payout {
+    parts: Vec<(&'static str, u128)>,
+    note: Option<String>,
+}
+
+impl Payout {
+    pub fn with_note(note: Option<String>) -> Self {
+        if let Some(note) = &note {
+            require!(note.len() <= MAX_MEMO_LEN, StakeError::MemoTooLong);
+        }
+        Payout {
+            parts: vec![],
+            note,
+        }
+    }
+
+    pub fn add(&mut self, label: &'static str, amount: u128) {
+        if amount == 0 {
+            return;
+        }
+        match self.parts.iter_mut().find(|(part, _)| *part == label) {
+            Some((_, total)) => *total += amount,
+            None => self.parts.push((label, amount)),
+        }
+    }
+
+    pub fn part(&self, label: &str) -> u128 {
+        self.parts
+            .iter()
+            .find(|(part, _)| *part == label)
+            .map_or(0, |(_, amount)| *amount)
+    }
+
+    pub fn total(&self) -> u128 {
+        self.parts.iter().map(|(_, amount)| amount).sum()
+    }
+
+    // a plain withdraw keeps sending without a memo, the caller's note comes first
+    pub fn memo(&self) -> Option<String> {
+        let parts = (self.parts.len() >= 2).then(|| {
+            let parts: Vec<String> = self
+                .parts
+                .iter()
+                .map(|(label, amount)| format!("{}:{}", label, amount))
+                .collect();
+            parts.join(",")
+        });
+        match (&self.note, parts) {
+            (Some(note), Some(parts)) => Some(format!("{} {}", note, parts)),
+            (Some(note), None) => Some(note.clone()),
+            (None, parts) => parts,
+        }
+    }
+}
+
+impl Contract {
+    // payouts only ever credit here, tokens leave through internal_start_outflow
+    pub(crate) fn internal_credit_withdrawable(
+        &mut self,
+        stake_info: &mut StakeInfo,
+        amount: u128,
+    ) {
+        stake_info.withdrawable += amount;
+        self.total_withdrawable += amount;
+    }
+
+    pub(crate) fn internal_assert_no_pending_operation(stake_info: &StakeInfo) {
+        require!(!stake_info.pending_operation, StakeError::OperationPending);
+    }
+
+    // moves the whole withdrawable balance into pending_outflows until the transfer resolves
+    pub(crate) fn internal_start_outflow(&mut self, account_id: &AccountId) -> u128 {
+        self.internal_start_outflow_of(account_id, None)
+    }
+
+    // like internal_start_outflow for only `amount` of the withdrawable balance
+    pub(crate) fn internal_start_outflow_of(
+        &mut self,
+        account_id: &AccountId,
+        amount: Option<u128>,
+    ) -> u128 {
+        self.internal_migrate_legacy(account_id);
+        let mut stake_info = self
+            .internal_load_stake_info(account_id)
+            .unwrap_or_else(|| env::panic_str(&StakeError::NotStaked));
+        Self::internal_assert_no_pending_operation(&stake_info);
+        self.internal_assert_not_frozen(account_id);
+        self.internal_assert_breaker_closed();
+        let amount = amount.unwrap_or(stake_info.withdrawable);
+        require!(
+            amount > 0 && amount <= stake_info.withdrawable,
+            StakeError::NothingToWithdraw
+        );
+
+        stake_info.withdrawable -= amount;
+        stake_info.pending_operation = true;
+        self.total_withdrawable -= amount;
+        self.pending_outflows += amount;
+        self.internal_save_stake_info(account_id, &stake_info);
+        amount
+    }
+
+    // `refund` is the part of `amount` that did not leave the contract
+    pub(crate) fn internal_finish_outflow(
+        &mut self,
+        account_id: &AccountId,
+        amount: u128,
+        refund: u128,
+    ) {
+        self.pending_outflows -= amount;
+        if let Some(mut stake_info) = self.internal_load_stake_info(account_id) {
+            stake_info.pending_operation = false;
+            self.internal_credit_withdrawable(&mut stake_info, refund);
+            self.internal_save_stake_info(account_id, &stake_info);
+            // a full exit leaves nothing behind
+            self.internal_prune(account_id);
+        }
+    }
+
+    // sends the whole withdrawable balance, whatever `payout` doesn't name was already
+    // withdrawable before this call
+    pub(crate) fn internal_transfer_withdrawable(
+        &mut self,
+        account_id: AccountId,
+        mut payout: Payout,
+    ) -> Dispatch {
+        let amount = self.internal_start_outflow(&account_id);
+        payout.add("withdrawable", amount - payout.total());
+        self.internal_send_transfer(OutboxTransfer {
+            account_id: account_id.clone(),
+            receiver_id: account_id,
+            amount: U128(amount),
+            memo: payout.memo(),
+            queued_at: Self::now(),
+        })
+    }
+
+    // sends `amount` of the account's withdrawable balance to someone else, a failed
+    // transfer leaves it withdrawable by the account
+    pub(crate) fn internal_transfer_to(
+        &mut self,
+        account_id: AccountId,
+        receiver_id: AccountId,
+        amount: u128,
+    ) -> Dispatch {
+        let amount = self.internal_start_outflow_of(&account_id, Some(amount));
+        self.internal_send_transfer(OutboxTransfer {
+            account_id,
+            receiver_id,
+            amount: U128(amount),
+            memo: None,
+            queued_at: Self::now(),
+        })
+    }
+
+    // exit past the deposit check, returns the principal and reward it sent along
+    pub(crate) fn internal_exit(
+        &mut self,
+        account_id: AccountId,
+        memo: Option<String>,
+    ) -> (u128, u128, Dispatch) {
+        let staked = self
+            .internal_get_stake_info(&account_id)
+            .unwrap_or_else(|| env::panic_str(&StakeError::NotStaked))
+            .amount_staked;
+        let mut payout = Payout::with_note(memo);
+        if staked > 0 {
+            self.internal_shrink_course_stakes(&account_id, 0);
+            payout.add(
+                "principal",
+                self.internal_unstake(&account_id, staked, None, &account_id, false),
+            );
+        }
+
+        let stake_info = self.internal_get_stake_info(&account_id).unwrap();
+        if stake_info.reward > 0
+            && stake_info.reward <= self.reward_pool
+            && Self::now() >= self.internal_next_claim_at(&stake_info)
+        {
+            self.internal_claim_reward(&account_id, None);
+            let withdrawable = self
+                .internal_get_stake_info(&account_id)
+                .unwrap()
+                .withdrawable;
+            payout.add("reward", withdrawable - stake_info.withdrawable);
+        }
+
+        let (principal, reward) = (payout.part("principal"), payout.part("reward"));
+        let dispatch = self.internal_transfer_withdrawable(account_id, payout);
+        self.internal_process_queue(MAX_QUEUE_ACTIVATIONS);
+        self.internal_maybe_snapshot();
+        (principal, reward, dispatch)
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // `memo` is forwarded to ft_transfer and echoed in the withdrawn event
+    #[payable]
+    pub fn withdraw(&mut self, memo: Option<String>) -> PromiseOrValue<U128> {
+        self.internal_assert_deposit("withdraw");
+        let account_id = env::predecessor_account_id();
+        let dispatch = self.internal_transfer_withdrawable(account_id, Payout::with_note(memo));
+        self.internal_maybe_snapshot();
+        dispatch.into()
+    }
+
+    // unstakes everything, claims the reward when it can be claimed and sends it all in one
+    // transfer. a reward still in cooldown or not covered by the pool stays for later
+    #[payable]
+    pub fn exit(&mut self, memo: Option<String>) -> PromiseOrValue<U128> {
+        self.internal_assert_deposit("exit");
+        let (_, _, dispatch) = self.internal_exit(env::predecessor_account_id(), memo);
+        dispatch.into()
+    }
+
+    // returns the amount that actually left the contract
+    #[private]
+    pub fn on_withdraw(
+        &mut self,
+        account_id: AccountId,
+        amount: U128,
+        memo: Option<String>,
+        receiver_id: Option<AccountId>,
+        #[callback_result] result: Result<(), PromiseError>,
+    ) -> U128 {
+        self.internal_note_promise_result(
+            "on_withdraw",
+            Some(&account_id),
+            amount.0,
+            result.is_ok(),
+        );
+        self.internal_note_token_transfer(
+            receiver_id.as_ref().unwrap_or(&account_id),
+            result.is_ok(),
+        );
+        let sent = if result.is_ok() { amount.0 } else { 0 };
+        self.internal_note_outflow(sent);
+        self.internal_finish_outflow(&account_id, amount.0, amount.0 - sent);
+        if sent > 0 {
+            StakeEvent::Withdrawn(vec![WithdrawnData {
+                account_id,
+                amount: U128(sent),
+                memo,
+            }])
+            .emit();
+        }
+        U128(sent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_contract_standards::storage_management::StorageBalance;
+    use near_sdk::serde_json::json;
+    use near_sdk::{PromiseError, PromiseOrValue};
+
+    use super::*;
+    use crate::test_utils::*;
+    use crate::time::SECONDS_PER_YEAR;
+
+    const AMOUNT: u128 = 1_000 * ONE_TOKEN;
+
+    fn withdraw(contract: &mut Contract, account_id: &AccountId) -> u128 {
+        call_with_yocto(account_id);
+        assert!(matches!(
+            contract.withdraw(None),
+            PromiseOrValue::Promise(_)
+        ));
+        let transfer = function_calls()
+            .into_iter()
+            .find(|(receiver_id, method, _)| receiver_id == &token() && method == "ft_transfer")
+            .expect("no ft_transfer issued");
+        assert_eq!(transfer.2["receiver_id"], json!(account_id));
+        transfer.2["amount"].as_str().unwrap().parse().unwrap()
+    }
+
+    fn resolve(contract: &mut Contract, account_id: &AccountId, amount: u128, ok: bool) -> u128 {
+        call_as(&contract_id());
+        let result = if ok {
+            Ok(())
+        } else {
+            Err(PromiseError::Failed)
+        };
+        contract
+            .on_withdraw(
+                account_id.clone(),
+                U128(amount),
+                None,
+                Some(account_id.clone()),
+                result,
+            )
+            .0
+    }
+
+    fn withdrawable(contract: &Contract, account_id: &AccountId) -> u128 {
+        contract
+            .internal_get_stake_info(account_id)
+            .map_or(0, |stake_info| stake_info.withdrawable)
+    }
+
+    #[test]
+    fn stake_vote_claim_unstake_withdraw() {
+        let mut contract = setup();
+        fund_reward_pool(&mut contract, AMOUNT);
+        let alice = account("alice");
+        assert_eq!(stake(&mut contract, &alice, AMOUNT), 0);
+        let apr = vote(&mut contract, &alice, 5);
+        assert_eq!(apr, DEFAULT_APR + 2 * DEFAULT_VOTE_STEP);
+
+        advance_secs(SECONDS_PER_YEAR);
+        let reward = pending(&contract, &alice);
+        assert!(reward > 50 * ONE_TOKEN && reward < 52 * ONE_TOKEN);
+        call_with_yocto(&alice);
+        match contract.claim_reward() {
+            PromiseOrValue::Value(claimed) => assert_eq!(claimed.0, reward),
+            PromiseOrValue::Promise(_) => panic!("claim without a booster went async"),
+        }
+        assert_eq!(withdrawable(&contract, &alice), reward);
+
+        call_with_yocto(&alice);
+        contract.unstake_token(U128(AMOUNT), None, None);
+        assert_eq!(staked(&contract, &alice), 0);
+
+        let amount = withdraw(&mut contract, &alice);
+        assert_eq!(amount, AMOUNT + reward);
+        assert_eq!(contract.pending_outflows, amount);
+        assert_eq!(resolve(&mut contract, &alice, amount, true), amount);
+        assert_eq!(contract.pending_outflows, 0);
+        assert_eq!(contract.total_withdrawable, 0);
+        assert_eq!(contract.total_staked, 0);
+        assert_eq!(contract.reward_pool, AMOUNT - reward);
+    }
+
+    #[test]
+    fn a_failed_transfer_leaves_the_balance_withdrawable() {
+        let mut contract = setup();
+        let alice = account("alice");
+        stake(&mut contract, &alice, AMOUNT);
+        call_with_yocto(&alice);
+        contract.unstake_token(U128(AMOUNT), None, None);
+
+        let amount = withdraw(&mut contract, &alice);
+        assert!(
+            contract
+                .internal_get_stake_info(&alice)
+                .unwrap()
+                .pending_operation
+        );
+        assert_eq!(resolve(&mut contract, &alice, amount, false), 0);
+        assert_eq!(withdrawable(&contract, &alice), AMOUNT);
+        assert_eq!(contract.pending_outflows, 0);
+        assert_eq!(contract.total_withdrawable, AMOUNT);
+        // the failure is only counted once the token says the receiver is registered
+        assert!(function_calls()
+            .iter()
+            .any(|(receiver_id, method, _)| receiver_id == &token()
+                && method == "storage_balance_of"));
+
+        call_as(&contract_id());
+        contract.on_transfer_failure_checked(alice.clone(), Ok(None));
+        assert_eq!(contract.consecutive_transfer_failures, 0);
+        contract.on_transfer_failure_checked(
+            alice.clone(),
+            Ok(Some(StorageBalance {
+                total: U128(ONE_TOKEN / 100),
+                available: U128(0),
+            })),
+        );
+        assert_eq!(contract.consecutive_transfer_failures, 1);
+
+        // the next attempt goes through
+        let amount = withdraw(&mut contract, &alice);
+        assert_eq!(resolve(&mut contract, &alice, amount, true), AMOUNT);
+        assert_eq!(contract.total_withdrawable, 0);
+    }
+
+    // the unbond period only moves the position state, it never holds principal back
+    #[test]
+    fn an_unbonding_advisor_withdraws_right_away() {
+        let mut contract = setup();
+        let alice = account("alice");
+        stake(&mut contract, &alice, AMOUNT);
+        call_as(&alice);
+        contract.deactivate();
+        call_with_yocto(&alice);
+        contract.unstake_token(U128(AMOUNT), None, None);
+        assert_eq!(
+            contract.internal_position_state(&contract.internal_get_stake_info(&alice).unwrap()),
+            PositionState::Unbonding
+        );
+
+        let amount = withdraw(&mut contract, &alice);
+        assert_eq!(resolve(&mut contract, &alice, amount, true), AMOUNT);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_OPERATION_PENDING")]
+    fn a_withdraw_in_flight_blocks_a_second_one() {
+        let mut contract = setup();
+        let alice = account("alice");
+        stake(&mut contract, &alice, AMOUNT);
+        call_with_yocto(&alice);
+        contract.unstake_token(U128(AMOUNT / 2), None, None);
+        withdraw(&mut contract, &alice);
+
+        call_with_yocto(&alice);
+        contract.unstake_token(U128(AMOUNT / 2), None, None);
+    }
+}
@@ -0,0 +1,162 @@
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::Serialize;
+use near_sdk::{env, near_bindgen, AccountId};
+
+use crate::time::{Duration, Timestamp};
+use crate::*;
+
+// used to turn outbox blocks into time, NEAR blocks come about once a second
+pub const ASSUMED_BLOCK_TIME: Duration = Duration::from_secs(1);
+pub const MAX_ETA_OUTBOX_SCAN: u64 = 100;
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub enum UnbondKind {
+    Delegation,
+    // already left the balance, waiting for a transfer slot
+    Outbox,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PendingUnbond {
+    pub kind: UnbondKind,
+    pub amount: U128,
+    // may be in the past, the unbond is then ready to withdraw
+    pub matures_at: Timestamp,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct WithdrawalEta {
+    pub withdrawable: U128,
+    pub pending: Vec<PendingUnbond>,
+    // how long a transfer started now would wait in the outbox, assuming a keeper
+    // flushes it every block
+    pub outbox_delay: U64,
+    pub outbox_queued: U64,
+    pub as_of: Timestamp,
+}
+
+impl Contract {
+    // until a transfer with `ahead` others in front of it gets a slot
+    fn internal_outbox_wait(&self, ahead: u64) -> Duration {
+        let blocks = ahead / self.outbox_budget as u64;
+        Duration::from_nanos(blocks * ASSUMED_BLOCK_TIME.as_nanos())
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    // only amounts that turn withdrawable on their own are listed. outbox entries are
+    // looked for among the first MAX_ETA_OUTBOX_SCAN only
+    pub fn get_withdrawal_eta(&self, account_id: AccountId) -> WithdrawalEta {
+        let now = Self::now();
+        let mut pending = vec![];
+        let stake_info = self.internal_get_stake_info(&account_id);
+        // an Unbonding position's stake still has to be unstaked, it only becomes
+        // withdrawable then and without a wait, so it is not listed
+        if let Some(delegation) = self.get_delegation(account_id.clone()) {
+            if let Some(unbonded_at) = delegation.unbonded_at {
+                pending.push(PendingUnbond {
+                    kind: UnbondKind::Delegation,
+                    amount: delegation.unbonding,
+                    matures_at: unbonded_at,
+                });
+            }
+        }
+        let scan_end = self
+            .outbox_tail
+            .min(self.outbox_head.saturating_add(MAX_ETA_OUTBOX_SCAN));
+        for position in self.outbox_head..scan_end {
+            if let Some(transfer) = self.outbox.get(&position) {
+                if transfer.account_id == account_id {
+                    pending.push(PendingUnbond {
+                        kind: UnbondKind::Outbox,
+                        amount: transfer.amount,
+                        matures_at: now + self.internal_outbox_wait(position - self.outbox_head),
+                    });
+                }
+            }
+        }
+
+        let queued = self.outbox_tail - self.outbox_head;
+        let slot_free =
+            self.outbox_block != env::block_height() || self.outbox_sent < self.outbox_budget;
+        // a full slot budget this block pushes even an empty outbox to the next one
+        let outbox_delay = if queued == 0 && slot_free {
+            Duration::from_nanos(0)
+        } else {
+            self.internal_outbox_wait(queued + self.outbox_budget as u64)
+        };
+        WithdrawalEta {
+            withdrawable: U128(stake_info.map_or(0, |stake_info| stake_info.withdrawable)),
+            pending,
+            outbox_delay: U64(outbox_delay.as_nanos()),
+            outbox_queued: U64(queued),
+            as_of: now,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use crate::time::MockClock;
+
+    const AMOUNT: u128 = 100 * ONE_TOKEN;
+
+    // bob delegates AMOUNT to alice's pool and undelegates it at START
+    fn setup_undelegated() -> Contract {
+        let mut contract = setup();
+        stake(&mut contract, &account("alice"), AMOUNT);
+        call_as(&account("alice"));
+        contract.open_delegation_pool(1_000);
+        stake(&mut contract, &account("bob"), AMOUNT);
+        call_with_yocto(&account("bob"));
+        contract.delegate(account("alice"), U128(AMOUNT));
+        call_with_yocto(&account("bob"));
+        contract.undelegate(U128(AMOUNT));
+        contract
+    }
+
+    #[test]
+    fn the_eta_matches_withdraw_undelegated() {
+        let mut contract = setup_undelegated();
+        let eta = contract.get_withdrawal_eta(account("bob"));
+        assert_eq!(eta.pending.len(), 1);
+        let unbond = &eta.pending[0];
+        assert!(matches!(unbond.kind, UnbondKind::Delegation));
+        assert_eq!(unbond.amount.0, AMOUNT);
+        assert_eq!(unbond.matures_at, Contract::now() + DEFAULT_UNBOND_PERIOD);
+
+        MockClock::set(unbond.matures_at);
+        call_with_yocto(&account("bob"));
+        assert_eq!(contract.withdraw_undelegated().0, AMOUNT);
+        let eta = contract.get_withdrawal_eta(account("bob"));
+        assert!(eta.pending.is_empty());
+        assert_eq!(eta.withdrawable.0, AMOUNT);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_STILL_UNBONDING")]
+    fn nothing_is_withdrawn_before_the_eta() {
+        let mut contract = setup_undelegated();
+        let matures_at = contract.get_withdrawal_eta(account("bob")).pending[0].matures_at;
+        MockClock::set(Timestamp::from_nanos(matures_at.as_nanos() - 1));
+        call_with_yocto(&account("bob"));
+        contract.withdraw_undelegated();
+    }
+
+    #[test]
+    fn an_unbonding_position_is_not_listed() {
+        let mut contract = setup();
+        stake(&mut contract, &account("alice"), AMOUNT);
+        call_as(&account("alice"));
+        contract.deactivate();
+        let eta = contract.get_withdrawal_eta(account("alice"));
+        assert!(eta.pending.is_empty());
+        assert_eq!(eta.withdrawable.0, 0);
+    }
+}
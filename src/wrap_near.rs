@@ -0,0 +1,72 @@
+use near_sdk::json_types::U128;
+use near_sdk::{env, near_bindgen, require, AccountId, Gas, Promise, PromiseError};
+
+use crate::*;
+
+pub const NEAR_DEPOSIT_GAS: Gas = Gas(10_000_000_000_000);
+pub const NEAR_DEPOSIT_CALLBACK_GAS: Gas = Gas(20_000_000_000_000);
+pub const WRAP_NEAR_ACCOUNTS: &[&str] = &["wrap.near", "wrap.testnet"];
+
+#[near_bindgen]
+impl Contract {
+    // wraps the attached NEAR into the stake token and stakes it for the caller,
+    // first time stakers behind the attestation registry still go through ft_transfer_call
+    #[payable]
+    pub fn deposit_and_stake(&mut self) -> Promise {
+        require!(
+            WRAP_NEAR_ACCOUNTS.contains(&self.token_address.as_str()),
+            StakeError::NotWrappedNear
+        );
+        self.internal_assert_not_paused();
+        let account_id = env::predecessor_account_id();
+        let amount = env::attached_deposit();
+        require!(amount > 0, StakeError::InvalidAmount);
+        // checked up front only, a concurrent stake can still overshoot the cap slightly
+        require!(
+            amount <= self.internal_open_capacity(),
+            StakeError::TvlCapReached
+        );
+        require!(
+            self.internal_is_permitted(&account_id),
+            StakeError::AccountNotPermitted
+        );
+        require!(
+            !self.internal_needs_attestation(&account_id),
+            StakeError::AttestationRequired
+        );
+
+        ext_wrap_near::ext(self.token_address.clone())
+            .with_static_gas(NEAR_DEPOSIT_GAS)
+            .with_attached_deposit(amount)
+            .near_deposit()
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(NEAR_DEPOSIT_CALLBACK_GAS)
+                    .on_near_deposit(account_id, U128(amount)),
+            )
+    }
+
+    // the attached NEAR goes back to the account if wrapping failed
+    #[private]
+    pub fn on_near_deposit(
+        &mut self,
+        account_id: AccountId,
+        amount: U128,
+        #[callback_result] result: Result<(), PromiseError>,
+    ) -> U128 {
+        self.internal_note_promise_result(
+            "on_near_deposit",
+            Some(&account_id),
+            amount.0,
+            result.is_ok(),
+        );
+        if result.is_err() {
+            Promise::new(account_id).transfer(amount.0);
+            return U128(0);
+        }
+        self.internal_stake(account_id, amount.0);
+        self.internal_note_inflow(amount.0);
+        self.internal_maybe_snapshot();
+        amount
+    }
+}
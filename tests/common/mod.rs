@@ -0,0 +1,583 @@
+// receipt runner for the end-to-end tests. the contract runs on near-sdk's mocked vm
+// and a NEP-141 token is modelled in rust next to it. every receipt the contract creates
+// is executed, and its result is fed to the callback chained on it.
+//
+// this is not the near-workspaces suite: the wasm is not compiled or run, gas only
+// counts host calls, unused gas is split evenly instead of by weight, and the mock does
+// not expose receipt dependencies, so a call back into the contract is taken to depend
+// on the receipt created right before it, which is how every `a.then(b)` in the
+// contract is built. a panic from `require!` fails the receipt and rolls its state
+// back. `env::panic_str` aborts the process on the mocked vm, so the scenarios stay on
+// paths that fail through `require!` or not at all
+#![allow(dead_code)]
+
+use std::collections::{HashMap, HashSet};
+use std::panic::{self, AssertUnwindSafe};
+
+use near_contract_standards::fungible_token::metadata::{FungibleTokenMetadata, FT_METADATA_SPEC};
+use near_contract_standards::storage_management::StorageBalance;
+use near_sdk::json_types::U128;
+use near_sdk::mock::{MockedBlockchain, VmAction};
+use near_sdk::serde::de::DeserializeOwned;
+use near_sdk::serde::Serialize;
+use near_sdk::serde_json::{self, json, Value};
+use near_sdk::test_utils::{get_created_receipts, get_logs, VMContextBuilder};
+use near_sdk::{
+    env, AccountId, Balance, Gas, PromiseError, PromiseOrValue, PromiseResult, RuntimeFeesConfig,
+    VMConfig, ONE_YOCTO,
+};
+use spk_stake::{Contract, InitConfig, Role};
+
+pub const ONE_TOKEN: u128 = 1_000_000_000_000_000_000;
+pub const START: u64 = 1_600_000_000_000_000_000;
+pub const SECONDS_PER_YEAR: u64 = 365 * 24 * 60 * 60;
+const TGAS: u64 = 1_000_000_000_000;
+const STORAGE_BALANCE: u128 = 1_250_000_000_000_000_000_000;
+
+pub fn contract_id() -> AccountId {
+    "stake.near".parse().unwrap()
+}
+
+pub fn owner() -> AccountId {
+    "owner.near".parse().unwrap()
+}
+
+pub fn token() -> AccountId {
+    "token.near".parse().unwrap()
+}
+
+pub fn relay() -> AccountId {
+    "relay.near".parse().unwrap()
+}
+
+pub fn account(name: &str) -> AccountId {
+    format!("{}.near", name).parse().unwrap()
+}
+
+// what a receipt ended with, `Value` holds the json the method returned
+#[derive(Clone, Debug)]
+pub enum Outcome {
+    Value(Vec<u8>),
+    Failed(String),
+}
+
+impl Outcome {
+    pub fn is_ok(&self) -> bool {
+        matches!(self, Outcome::Value(_))
+    }
+
+    pub fn json<T: DeserializeOwned>(&self) -> T {
+        match self {
+            Outcome::Value(bytes) => serde_json::from_slice(bytes).unwrap(),
+            Outcome::Failed(error) => panic!("receipt failed: {}", error),
+        }
+    }
+
+    fn callback<T: DeserializeOwned>(&self) -> Result<T, PromiseError> {
+        match self {
+            Outcome::Value(bytes) => {
+                serde_json::from_slice(bytes).map_err(|_| PromiseError::Failed)
+            }
+            Outcome::Failed(_) => Err(PromiseError::Failed),
+        }
+    }
+
+    fn unit(&self) -> Result<(), PromiseError> {
+        match self {
+            Outcome::Value(_) => Ok(()),
+            Outcome::Failed(_) => Err(PromiseError::Failed),
+        }
+    }
+
+    fn promise_result(&self) -> PromiseResult {
+        match self {
+            Outcome::Value(bytes) => PromiseResult::Successful(bytes.clone()),
+            Outcome::Failed(_) => PromiseResult::Failed,
+        }
+    }
+}
+
+// what a contract method handed back to the runtime, a promise resolves to the outcome
+// of the last receipt it created
+pub enum Return {
+    Value(Vec<u8>),
+    Promise,
+}
+
+pub trait IntoReturn {
+    fn into_return(self) -> Return;
+}
+
+impl<T: Serialize> IntoReturn for PromiseOrValue<T> {
+    fn into_return(self) -> Return {
+        match self {
+            PromiseOrValue::Promise(_) => Return::Promise,
+            PromiseOrValue::Value(value) => Return::Value(serde_json::to_vec(&value).unwrap()),
+        }
+    }
+}
+
+impl IntoReturn for near_sdk::Promise {
+    fn into_return(self) -> Return {
+        Return::Promise
+    }
+}
+
+macro_rules! value_returns {
+    ($($ty:ty),*) => {
+        $(impl IntoReturn for $ty {
+            fn into_return(self) -> Return {
+                Return::Value(serde_json::to_vec(&self).unwrap())
+            }
+        })*
+    };
+}
+
+value_returns!(U128, bool, u64, Option<u8>, Option<U128>);
+
+impl IntoReturn for () {
+    fn into_return(self) -> Return {
+        Return::Value(vec![])
+    }
+}
+
+struct Receipt {
+    predecessor_id: AccountId,
+    receiver_id: AccountId,
+    method: String,
+    args: Vec<u8>,
+    deposit: Balance,
+    gas: Gas,
+}
+
+// the reference token: balances, NEP-145 registration and a switch that makes every
+// plain ft_transfer fail on the token's side
+#[derive(Default)]
+pub struct MockToken {
+    pub balances: HashMap<AccountId, u128>,
+    pub registered: HashSet<AccountId>,
+    pub fail_transfers: bool,
+}
+
+impl MockToken {
+    pub fn register(&mut self, account_id: &AccountId) {
+        self.registered.insert(account_id.clone());
+    }
+
+    pub fn unregister(&mut self, account_id: &AccountId) {
+        self.registered.remove(account_id);
+    }
+
+    pub fn mint(&mut self, account_id: &AccountId, amount: u128) {
+        self.register(account_id);
+        *self.balances.entry(account_id.clone()).or_default() += amount;
+    }
+
+    pub fn balance_of(&self, account_id: &AccountId) -> u128 {
+        self.balances.get(account_id).copied().unwrap_or(0)
+    }
+
+    fn transfer(
+        &mut self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        amount: u128,
+    ) -> Outcome {
+        if !self.registered.contains(receiver_id) {
+            return Outcome::Failed(format!("The account {} is not registered", receiver_id));
+        }
+        let balance = self.balance_of(sender_id);
+        if balance < amount {
+            return Outcome::Failed("The account doesn't have enough balance".to_string());
+        }
+        self.balances.insert(sender_id.clone(), balance - amount);
+        *self.balances.entry(receiver_id.clone()).or_default() += amount;
+        Outcome::Value(vec![])
+    }
+}
+
+pub struct Sandbox {
+    storage: HashMap<Vec<u8>, Vec<u8>>,
+    pub token: MockToken,
+    pub now: u64,
+    height: u64,
+    pub logs: Vec<String>,
+    // NEAR sent by the contract, e.g. storage refunds
+    pub near_transfers: Vec<(AccountId, Balance)>,
+}
+
+impl Sandbox {
+    // deploys the stake contract for the token with owner.near as owner and relay.near
+    // as a voter relay, and runs the metadata fetch `new` schedules
+    pub fn new(config: InitConfig) -> Self {
+        let mut sandbox = Sandbox {
+            storage: HashMap::new(),
+            token: MockToken::default(),
+            now: START,
+            height: 1,
+            logs: vec![],
+            near_transfers: vec![],
+        };
+        sandbox.token.register(&contract_id());
+        let config = InitConfig {
+            owner_id: Some(owner()),
+            ..config
+        };
+        let outcome = sandbox.run_contract(&owner(), 0, Gas(300 * TGAS), None, move || {
+            env::state_write(&Contract::new(token(), Some(config)));
+            Return::Value(vec![])
+        });
+        assert!(outcome.is_ok(), "deploy failed: {:?}", outcome);
+        let outcome = sandbox.call(&owner(), 0, |contract| {
+            contract.grant_role(relay(), Role::VoterRelay)
+        });
+        assert!(outcome.is_ok());
+        sandbox
+    }
+
+    pub fn advance_secs(&mut self, secs: u64) {
+        self.now += secs * 1_000_000_000;
+    }
+
+    // a transaction from `predecessor_id` to the stake contract
+    pub fn call<R: IntoReturn>(
+        &mut self,
+        predecessor_id: &AccountId,
+        deposit: Balance,
+        method: impl FnOnce(&mut Contract) -> R,
+    ) -> Outcome {
+        self.run_contract(predecessor_id, deposit, Gas(300 * TGAS), None, move || {
+            let mut contract: Contract = env::state_read().expect("contract is not deployed");
+            let result = method(&mut contract).into_return();
+            env::state_write(&contract);
+            result
+        })
+    }
+
+    pub fn call_with_yocto<R: IntoReturn>(
+        &mut self,
+        predecessor_id: &AccountId,
+        method: impl FnOnce(&mut Contract) -> R,
+    ) -> Outcome {
+        self.call(predecessor_id, ONE_YOCTO, method)
+    }
+
+    pub fn view<R>(&mut self, view: impl FnOnce(&Contract) -> R) -> R {
+        self.install(&contract_id(), 0, Gas(300 * TGAS), vec![]);
+        let contract: Contract = env::state_read().expect("contract is not deployed");
+        view(&contract)
+    }
+
+    // a transaction from `sender_id` calling ft_transfer_call on the token, returns how
+    // much of `amount` the token kept on the contract after ft_resolve_transfer
+    pub fn ft_transfer_call(&mut self, sender_id: &AccountId, amount: u128, msg: &str) -> u128 {
+        let outcome = self.token_call(
+            sender_id,
+            "ft_transfer_call",
+            &json!({
+                "receiver_id": contract_id(),
+                "amount": U128(amount),
+                "msg": msg,
+            }),
+            ONE_YOCTO,
+        );
+        outcome.json::<U128>().0
+    }
+
+    pub fn logged(&self, needle: &str) -> bool {
+        self.logs.iter().any(|log| log.contains(needle))
+    }
+
+    fn install(
+        &mut self,
+        predecessor_id: &AccountId,
+        deposit: Balance,
+        gas: Gas,
+        promise_results: Vec<PromiseResult>,
+    ) {
+        self.height += 1;
+        let mut builder = VMContextBuilder::new();
+        builder
+            .current_account_id(contract_id())
+            .signer_account_id(predecessor_id.clone())
+            .predecessor_account_id(predecessor_id.clone())
+            .block_index(self.height)
+            .block_timestamp(self.now)
+            .attached_deposit(deposit)
+            .prepaid_gas(gas);
+        env::set_blockchain_interface(MockedBlockchain::new(
+            builder.build(),
+            VMConfig::test(),
+            RuntimeFeesConfig::test(),
+            promise_results,
+            self.storage.clone(),
+            Default::default(),
+            None,
+        ));
+    }
+
+    // one receipt on the stake contract. its state only persists when it succeeds, the
+    // receipts it created run right after it
+    fn run_contract(
+        &mut self,
+        predecessor_id: &AccountId,
+        deposit: Balance,
+        gas: Gas,
+        input: Option<&Outcome>,
+        method: impl FnOnce() -> Return,
+    ) -> Outcome {
+        let promise_results = input.map(|input| vec![input.promise_result()]);
+        self.install(
+            predecessor_id,
+            deposit,
+            gas,
+            promise_results.unwrap_or_default(),
+        );
+        let result = match panic::catch_unwind(AssertUnwindSafe(method)) {
+            Ok(result) => result,
+            Err(error) => {
+                let message = error
+                    .downcast_ref::<String>()
+                    .cloned()
+                    .or_else(|| {
+                        error
+                            .downcast_ref::<&str>()
+                            .map(|message| message.to_string())
+                    })
+                    .unwrap_or_default();
+                return Outcome::Failed(message);
+            }
+        };
+        self.logs.extend(get_logs());
+        let receipts = get_created_receipts();
+        let calls = receipts
+            .iter()
+            .flat_map(|receipt| &receipt.actions)
+            .filter_map(|action| match action {
+                VmAction::FunctionCall { gas, .. } => Some(gas.0),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        // the runtime hands the gas left over to the calls by their weight, the mock
+        // does not record weights so every call gets an equal share
+        let unused = env::prepaid_gas()
+            .0
+            .saturating_sub(env::used_gas().0 + calls.iter().sum::<u64>());
+        let share = unused / calls.len().max(1) as u64;
+        self.storage = near_sdk::mock::with_mocked_blockchain(|b| b.take_storage());
+
+        let mut last: Option<Outcome> = None;
+        for receipt in receipts {
+            let receiver_id = receipt.receiver_id;
+            for action in receipt.actions {
+                match action {
+                    VmAction::FunctionCall {
+                        function_name,
+                        args,
+                        gas,
+                        deposit,
+                    } => {
+                        let receipt = Receipt {
+                            predecessor_id: contract_id(),
+                            receiver_id: receiver_id.clone(),
+                            method: function_name,
+                            args,
+                            deposit,
+                            gas: Gas(gas.0 + share),
+                        };
+                        // a callback takes the result of the receipt it was chained on
+                        let input = match receipt.receiver_id == contract_id() {
+                            true => last.take(),
+                            false => None,
+                        };
+                        last = Some(self.execute(receipt, input));
+                    }
+                    VmAction::Transfer { deposit } => {
+                        self.near_transfers.push((receiver_id.clone(), deposit));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        match result {
+            Return::Value(bytes) => Outcome::Value(bytes),
+            Return::Promise => last.expect("returned a promise without creating a receipt"),
+        }
+    }
+
+    fn execute(&mut self, receipt: Receipt, input: Option<Outcome>) -> Outcome {
+        let args: Value = serde_json::from_slice(&receipt.args).unwrap_or(Value::Null);
+        if receipt.receiver_id == token() {
+            return self.token_call(
+                &receipt.predecessor_id,
+                &receipt.method,
+                &args,
+                receipt.deposit,
+            );
+        }
+        if receipt.receiver_id != contract_id() {
+            return Outcome::Failed(format!("no contract on {}", receipt.receiver_id));
+        }
+        let callback = callback(&receipt.method)
+            .unwrap_or_else(|| panic!("the runner has no dispatch for {}", receipt.method));
+        let method_input = input.clone();
+        self.run_contract(
+            &receipt.predecessor_id,
+            receipt.deposit,
+            receipt.gas,
+            input.as_ref(),
+            move || {
+                let mut contract: Contract = env::state_read().unwrap();
+                let result = callback(&mut contract, &args, method_input.as_ref());
+                env::state_write(&contract);
+                result
+            },
+        )
+    }
+
+    fn token_call(
+        &mut self,
+        predecessor_id: &AccountId,
+        method: &str,
+        args: &Value,
+        deposit: Balance,
+    ) -> Outcome {
+        match method {
+            "ft_transfer" => {
+                if deposit != ONE_YOCTO {
+                    return Outcome::Failed(
+                        "Requires attached deposit of exactly 1 yoctoNEAR".into(),
+                    );
+                }
+                if self.token.fail_transfers {
+                    return Outcome::Failed("transfers are failing".into());
+                }
+                let receiver_id: AccountId = arg(args, "receiver_id");
+                let amount: U128 = arg(args, "amount");
+                self.token.transfer(predecessor_id, &receiver_id, amount.0)
+            }
+            "ft_transfer_call" => {
+                let receiver_id: AccountId = arg(args, "receiver_id");
+                let amount: U128 = arg(args, "amount");
+                let msg: String = arg(args, "msg");
+                let sent = self.token.transfer(predecessor_id, &receiver_id, amount.0);
+                if !sent.is_ok() {
+                    return sent;
+                }
+                let on_transfer = self.execute(
+                    Receipt {
+                        predecessor_id: token(),
+                        receiver_id: receiver_id.clone(),
+                        method: "ft_on_transfer".to_string(),
+                        args: serde_json::to_vec(&json!({
+                            "sender_id": predecessor_id,
+                            "amount": amount,
+                            "msg": msg,
+                        }))
+                        .unwrap(),
+                        deposit: 0,
+                        gas: Gas(100 * TGAS),
+                    },
+                    None,
+                );
+                // ft_resolve_transfer: a failed ft_on_transfer refunds everything
+                let unused = on_transfer
+                    .callback::<U128>()
+                    .map_or(amount.0, |unused| unused.0);
+                let refund = unused
+                    .min(amount.0)
+                    .min(self.token.balance_of(&receiver_id));
+                if refund > 0 {
+                    let refunded = self.token.transfer(&receiver_id, predecessor_id, refund);
+                    assert!(refunded.is_ok());
+                }
+                Outcome::Value(serde_json::to_vec(&U128(amount.0 - refund)).unwrap())
+            }
+            "ft_balance_of" => {
+                let account_id: AccountId = arg(args, "account_id");
+                Outcome::Value(
+                    serde_json::to_vec(&U128(self.token.balance_of(&account_id))).unwrap(),
+                )
+            }
+            "storage_balance_of" => {
+                let account_id: AccountId = arg(args, "account_id");
+                let balance =
+                    self.token
+                        .registered
+                        .contains(&account_id)
+                        .then_some(StorageBalance {
+                            total: U128(STORAGE_BALANCE),
+                            available: U128(0),
+                        });
+                Outcome::Value(serde_json::to_vec(&balance).unwrap())
+            }
+            "ft_metadata" => Outcome::Value(
+                serde_json::to_vec(&FungibleTokenMetadata {
+                    spec: FT_METADATA_SPEC.to_string(),
+                    name: "Spark".to_string(),
+                    symbol: "SPK".to_string(),
+                    icon: None,
+                    reference: None,
+                    reference_hash: None,
+                    decimals: 18,
+                })
+                .unwrap(),
+            ),
+            _ => Outcome::Failed(format!("MethodNotFound {}", method)),
+        }
+    }
+}
+
+fn arg<T: DeserializeOwned>(args: &Value, name: &str) -> T {
+    serde_json::from_value(args.get(name).cloned().unwrap_or(Value::Null))
+        .unwrap_or_else(|error| panic!("bad argument {}: {}", name, error))
+}
+
+type Callback = fn(&mut Contract, &Value, Option<&Outcome>) -> Return;
+
+// what near_bindgen's wrappers do for the methods the contract and the token call on
+// the contract: decode the json arguments and the promise result
+fn callback(method: &str) -> Option<Callback> {
+    fn result(input: Option<&Outcome>) -> &Outcome {
+        input.expect("callback without a promise result")
+    }
+    let callback: Callback = match method {
+        "ft_on_transfer" => |contract, args, _| {
+            contract
+                .ft_on_transfer(
+                    arg(args, "sender_id"),
+                    arg(args, "amount"),
+                    arg(args, "msg"),
+                )
+                .into_return()
+        },
+        "on_withdraw" => |contract, args, input| {
+            contract
+                .on_withdraw(
+                    arg(args, "account_id"),
+                    arg(args, "amount"),
+                    arg(args, "memo"),
+                    arg(args, "receiver_id"),
+                    result(input).unit(),
+                )
+                .into_return()
+        },
+        "on_transfer_failure_checked" => |contract, args, input| {
+            contract
+                .on_transfer_failure_checked(arg(args, "receiver_id"), result(input).callback());
+            Return::Value(vec![])
+        },
+        "on_token_metadata" => |contract, _, input| {
+            contract
+                .on_token_metadata(result(input).callback())
+                .into_return()
+        },
+        "on_token_balance" => |contract, _, input| {
+            contract
+                .on_token_balance(result(input).callback())
+                .into_return()
+        },
+        _ => return None,
+    };
+    Some(callback)
+}
@@ -0,0 +1,178 @@
+// stake, vote, claim, unstake and withdraw against a reference NEP-141 token, with every
+// promise the contract creates executed by the runner in `common`
+mod common;
+
+use near_sdk::json_types::U128;
+use spk_stake::{InitConfig, DEFAULT_APR, DEFAULT_VOTE_STEP};
+
+use crate::common::*;
+
+const AMOUNT: u128 = 1_000 * ONE_TOKEN;
+
+fn sandbox() -> Sandbox {
+    let mut sandbox = Sandbox::new(InitConfig::default());
+    // payouts fail closed until the contract has seen its token balance once
+    assert!(sandbox
+        .call(&owner(), 0, |contract| contract.refresh_token_balance())
+        .is_ok());
+    sandbox.token.mint(&owner(), 10 * AMOUNT);
+    sandbox.token.mint(&account("alice"), AMOUNT);
+    assert_eq!(
+        sandbox.ft_transfer_call(&owner(), AMOUNT, "\"FundRewardPool\""),
+        AMOUNT
+    );
+    sandbox
+}
+
+fn withdrawable(sandbox: &mut Sandbox, account_id: &near_sdk::AccountId) -> u128 {
+    sandbox.view(|contract| {
+        contract
+            .get_stake_info(account_id.clone())
+            .map_or(0, |stake_info| stake_info.withdrawable.0)
+    })
+}
+
+fn unstake_all(sandbox: &mut Sandbox, account_id: &near_sdk::AccountId) {
+    let outcome = sandbox.call_with_yocto(account_id, |contract| {
+        contract.unstake_token(U128(AMOUNT), None, None)
+    });
+    assert_eq!(outcome.json::<U128>().0, AMOUNT);
+}
+
+#[test]
+fn the_token_metadata_is_fetched_on_deploy() {
+    let mut sandbox = sandbox();
+    let decimals = sandbox.view(|contract| contract.get_config().token_decimals);
+    assert_eq!(decimals, Some(18));
+}
+
+#[test]
+fn stake_vote_claim_unstake_withdraw() {
+    let mut sandbox = sandbox();
+    let alice = account("alice");
+    assert_eq!(sandbox.ft_transfer_call(&alice, AMOUNT, ""), AMOUNT);
+    assert_eq!(sandbox.token.balance_of(&alice), 0);
+    assert_eq!(sandbox.token.balance_of(&contract_id()), 2 * AMOUNT);
+
+    let apr = sandbox.call(&relay(), 0, |contract| {
+        contract.update_apr(alice.clone(), 5, None, None, None, None)
+    });
+    assert_eq!(apr.json::<U128>().0, DEFAULT_APR + 2 * DEFAULT_VOTE_STEP);
+
+    sandbox.advance_secs(SECONDS_PER_YEAR);
+    let reward = sandbox
+        .call_with_yocto(&alice, |contract| contract.claim_reward())
+        .json::<U128>()
+        .0;
+    assert!(reward > 50 * ONE_TOKEN && reward < 52 * ONE_TOKEN);
+
+    unstake_all(&mut sandbox, &alice);
+    let sent = sandbox.call_with_yocto(&alice, |contract| contract.withdraw(None));
+    assert_eq!(sent.json::<U128>().0, AMOUNT + reward);
+    assert_eq!(sandbox.token.balance_of(&alice), AMOUNT + reward);
+    assert_eq!(
+        sandbox.token.balance_of(&contract_id()),
+        2 * AMOUNT - AMOUNT - reward
+    );
+    assert_eq!(withdrawable(&mut sandbox, &alice), 0);
+    let (pending_outflows, total_withdrawable) =
+        sandbox.view(|contract| (contract.pending_outflows, contract.total_withdrawable));
+    assert_eq!((pending_outflows, total_withdrawable), (0, 0));
+}
+
+#[test]
+fn the_token_refunds_a_stake_made_while_paused() {
+    let mut sandbox = sandbox();
+    let alice = account("alice");
+    assert!(sandbox
+        .call(&owner(), 0, |contract| contract.set_paused(true))
+        .is_ok());
+    assert_eq!(sandbox.ft_transfer_call(&alice, AMOUNT, ""), 0);
+    assert_eq!(sandbox.token.balance_of(&alice), AMOUNT);
+    assert_eq!(sandbox.token.balance_of(&contract_id()), AMOUNT);
+    assert!(sandbox.view(|contract| contract.get_stake_info(alice.clone()).is_none()));
+}
+
+#[test]
+fn a_transfer_to_an_unregistered_receiver_is_not_counted() {
+    let mut sandbox = sandbox();
+    let alice = account("alice");
+    sandbox.ft_transfer_call(&alice, AMOUNT, "");
+    unstake_all(&mut sandbox, &alice);
+    sandbox.token.unregister(&alice);
+
+    let sent = sandbox.call_with_yocto(&alice, |contract| contract.withdraw(None));
+    assert_eq!(sent.json::<U128>().0, 0);
+    assert!(sandbox.logged("transfer to unregistered alice.near not counted"));
+    assert_eq!(withdrawable(&mut sandbox, &alice), AMOUNT);
+    let failures = sandbox.view(|contract| contract.consecutive_transfer_failures);
+    assert_eq!(failures, 0);
+
+    sandbox.token.register(&alice);
+    let sent = sandbox.call_with_yocto(&alice, |contract| contract.withdraw(None));
+    assert_eq!(sent.json::<U128>().0, AMOUNT);
+    assert_eq!(sandbox.token.balance_of(&alice), AMOUNT);
+    assert_eq!(withdrawable(&mut sandbox, &alice), 0);
+}
+
+#[test]
+fn failed_transfers_to_a_registered_receiver_degrade_transfers() {
+    let mut sandbox = sandbox();
+    let alice = account("alice");
+    sandbox.ft_transfer_call(&alice, AMOUNT, "");
+    unstake_all(&mut sandbox, &alice);
+    sandbox.token.fail_transfers = true;
+
+    for failures in 1..=3 {
+        let sent = sandbox.call_with_yocto(&alice, |contract| contract.withdraw(None));
+        assert_eq!(sent.json::<U128>().0, 0);
+        let counted = sandbox.view(|contract| contract.consecutive_transfer_failures);
+        assert_eq!(counted, failures);
+    }
+    let degraded = sandbox.view(|contract| contract.transfers_degraded_since.is_some());
+    assert!(degraded);
+    assert_eq!(withdrawable(&mut sandbox, &alice), AMOUNT);
+    assert_eq!(sandbox.token.balance_of(&alice), 0);
+}
+
+#[test]
+fn a_failed_stake_leaves_the_token_balances_alone() {
+    let mut sandbox = sandbox();
+    let alice = account("alice");
+    // ft_on_transfer hands an unreadable msg back as unused, the token refunds it
+    assert_eq!(sandbox.ft_transfer_call(&alice, AMOUNT, "not json"), 0);
+    assert_eq!(sandbox.token.balance_of(&alice), AMOUNT);
+    assert!(sandbox.view(|contract| contract.get_stake_info(alice.clone()).is_none()));
+}
+
+#[test]
+fn a_failed_receipt_changes_nothing() {
+    let mut sandbox = sandbox();
+    let alice = account("alice");
+    sandbox.ft_transfer_call(&alice, AMOUNT, "");
+    unstake_all(&mut sandbox, &alice);
+    let outcome = sandbox.call(&alice, 0, |contract| contract.withdraw(None));
+    assert!(!outcome.is_ok());
+    assert_eq!(withdrawable(&mut sandbox, &alice), AMOUNT);
+    assert_eq!(sandbox.token.balance_of(&alice), 0);
+}
+
+#[test]
+fn payouts_wait_for_the_first_balance_refresh() {
+    let mut sandbox = Sandbox::new(InitConfig::default());
+    let alice = account("alice");
+    sandbox.token.mint(&alice, AMOUNT);
+    sandbox.ft_transfer_call(&alice, AMOUNT, "");
+    let outcome = sandbox.call_with_yocto(&alice, |contract| {
+        contract.unstake_token(U128(AMOUNT), None, None)
+    });
+    assert!(
+        matches!(outcome, Outcome::Failed(error) if error.contains("ERR_TOKEN_BALANCE_UNKNOWN"))
+    );
+
+    let balance = sandbox.call(&alice, 0, |contract| contract.refresh_token_balance());
+    assert_eq!(balance.json::<Option<U128>>(), Some(U128(AMOUNT)));
+    unstake_all(&mut sandbox, &alice);
+    let sent = sandbox.call_with_yocto(&alice, |contract| contract.withdraw(None));
+    assert_eq!(sent.json::<U128>().0, AMOUNT);
+}